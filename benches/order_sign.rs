@@ -1,9 +1,10 @@
 use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
 use paradex::{
-    message::sign_order,
+    message::{market_felt, sign_order},
     structs::{OrderRequest, OrderType, Side},
 };
 use rust_decimal::{Decimal, prelude::FromPrimitive};
+use starknet_core::utils::cairo_short_string_to_felt;
 use starknet_crypto::Felt;
 use starknet_signers::SigningKey;
 
@@ -46,5 +47,14 @@ pub fn order_benchmark(c: &mut Criterion) {
     );
 }
 
-criterion_group!(benches, order_benchmark);
+pub fn market_felt_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("market felt");
+    group.bench_function("uncached", |b| {
+        b.iter(|| cairo_short_string_to_felt("BTC-USD-PERP"))
+    });
+    group.bench_function("cached", |b| b.iter(|| market_felt("BTC-USD-PERP")));
+    group.finish();
+}
+
+criterion_group!(benches, order_benchmark, market_felt_benchmark);
 criterion_main!(benches);
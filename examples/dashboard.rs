@@ -0,0 +1,281 @@
+//! Terminal dashboard of an account: its margin, positions, balances and recent balance events
+//! next to the market summaries of the markets it trades, all kept up to date from websocket
+//! subscriptions sharing one connection.
+//!
+//! Runs on testnet unless `--production` is given. Press `q` or `Esc` to quit.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use clap::Parser;
+use paradex::prelude::*;
+use paradex::structs::{AccountInformation, BalanceEvent, MarketSummary, Position, PositionStatus};
+use paradex::ws::{
+    AccountSubscription, BalanceEventsSubscription, Health, MarketSummarySubscription,
+    PositionSubscription,
+};
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table};
+
+/// Balance events kept on screen
+const RECENT_EVENTS: usize = 20;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(long, action)]
+    production: bool,
+
+    #[arg(long)]
+    private_keyfile: String,
+
+    /// Markets shown in addition to those with an open position
+    #[arg(long, default_values_t = ["BTC-USD-PERP".to_string(), "ETH-USD-PERP".to_string()])]
+    market: Vec<String>,
+}
+
+/// Latest state of the account, written by the subscription callbacks
+#[derive(Default)]
+struct Dashboard {
+    account: Option<AccountInformation>,
+    positions: BTreeMap<String, Position>,
+    balances: BTreeMap<String, f64>,
+    balance_events: VecDeque<BalanceEvent>,
+    summaries: BTreeMap<String, MarketSummary>,
+}
+
+impl Dashboard {
+    fn on_position(&mut self, position: &Position) {
+        if position.status == PositionStatus::CLOSED || position.size == 0. {
+            self.positions.remove(&position.market);
+        } else {
+            self.positions
+                .insert(position.market.clone(), position.clone());
+        }
+    }
+
+    fn on_balance_event(&mut self, event: &BalanceEvent, settlement_asset: &str) {
+        self.balances.insert(
+            settlement_asset.to_string(),
+            event.settlement_asset_balance_after,
+        );
+        self.balance_events.push_front(event.clone());
+        self.balance_events.truncate(RECENT_EVENTS);
+    }
+}
+
+fn draw(frame: &mut Frame, dashboard: &Dashboard, markets: &[String], health: &Health) {
+    let [header, body, events, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Fill(1),
+        Constraint::Length(RECENT_EVENTS as u16 / 2 + 2),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [left, right] =
+        Layout::horizontal([Constraint::Percentage(55), Constraint::Fill(1)]).areas(body);
+    let [positions, balances] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(6)]).areas(left);
+
+    let account = match &dashboard.account {
+        Some(account) => format!(
+            "value {:.2}  free collateral {:.2}  initial margin {:.2}  maintenance margin {:.2}  cushion {:.2}  {:?}",
+            account.account_value,
+            account.free_collateral,
+            account.initial_margin_requirement,
+            account.maintenance_margin_requirement,
+            account.margin_cushion,
+            account.status,
+        ),
+        None => "waiting for the account".to_string(),
+    };
+    frame.render_widget(
+        Paragraph::new(account).block(Block::bordered().title("Account")),
+        header,
+    );
+
+    let rows = dashboard.positions.values().map(|position| {
+        let pnl = position.unrealized_pnl;
+        Row::new([
+            position.market.clone(),
+            format!("{:?}", position.side),
+            format!("{}", position.size),
+            format!("{:.2}", position.average_entry_price),
+            format!("{:.2}", position.liquidation_price),
+            format!("{pnl:.2}"),
+        ])
+        .style(Style::new().fg(if pnl < 0. { Color::Red } else { Color::Green }))
+    });
+    frame.render_widget(
+        Table::new(rows, [Constraint::Fill(1); 6])
+            .header(Row::new(["Market", "Side", "Size", "Entry", "Liquidation", "uPnL"]).bold())
+            .block(Block::bordered().title("Positions")),
+        positions,
+    );
+
+    let rows = dashboard
+        .balances
+        .iter()
+        .map(|(token, size)| Row::new([token.clone(), format!("{size:.4}")]));
+    frame.render_widget(
+        Table::new(rows, [Constraint::Fill(1); 2])
+            .header(Row::new(["Token", "Balance"]).bold())
+            .block(Block::bordered().title("Balances")),
+        balances,
+    );
+
+    let shown = markets
+        .iter()
+        .chain(dashboard.positions.keys())
+        .collect::<std::collections::BTreeSet<_>>();
+    let rows = shown
+        .into_iter()
+        .map(|market| match dashboard.summaries.get(market) {
+            Some(summary) => Row::new([
+                market.clone(),
+                format!("{}", summary.bid),
+                format!("{}", summary.ask),
+                format!("{}", summary.mark_price),
+                format!("{:.4}%", summary.funding_rate * 100.),
+                format!("{:+.2}%", summary.price_change_rate_24h * 100.),
+            ]),
+            None => Row::new([market.clone()]),
+        });
+    frame.render_widget(
+        Table::new(rows, [Constraint::Fill(1); 6])
+            .header(Row::new(["Market", "Bid", "Ask", "Mark", "Funding", "24h"]).bold())
+            .block(Block::bordered().title("Markets")),
+        right,
+    );
+
+    let lines = dashboard.balance_events.iter().map(|event| {
+        Line::from(format!(
+            "{} {} {} fees {:.4} realized {:.4} funding {:.4} balance {:.4}",
+            event.created_at,
+            event.market,
+            event.status,
+            event.fees,
+            event.realized_pnl,
+            event.realized_funding,
+            event.settlement_asset_balance_after,
+        ))
+    });
+    frame.render_widget(
+        Paragraph::new(lines.collect::<Vec<_>>()).block(Block::bordered().title("Balance events")),
+        events,
+    );
+
+    let messages: u64 = health.channels.values().map(|stats| stats.messages).sum();
+    frame.render_widget(
+        Line::from(format!(
+            "{:?}  ping {:?}  reconnects {}  channels {}  messages {}  (q to quit)",
+            health.state,
+            health.ping_latency,
+            health.reconnects,
+            health.channels.len(),
+            messages,
+        ))
+        .dim(),
+        footer,
+    );
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let url = if args.production {
+        URL::Production
+    } else {
+        URL::Testnet
+    };
+    let private_key = std::fs::read_to_string(&args.private_keyfile)
+        .expect("Failed to read private key file")
+        .trim()
+        .to_string();
+    let client = Client::new(url.clone(), Some(private_key)).await.unwrap();
+
+    // Start from the REST snapshots, which the subscriptions then keep current
+    let dashboard = Arc::new(Mutex::new(Dashboard::default()));
+    let account = client.account_information().await.unwrap();
+    let settlement_asset = account.settlement_asset.clone();
+    let positions = client.positions().await.unwrap().results;
+    let balances = client.balance().await.unwrap().results;
+    {
+        let mut dashboard = dashboard.lock().unwrap();
+        dashboard.account = Some(account);
+        for position in &positions {
+            dashboard.on_position(position);
+        }
+        for balance in balances {
+            dashboard.balances.insert(balance.token, balance.size);
+        }
+    }
+
+    let manager = WebsocketManager::new(url, Some(client)).await;
+    let state = Arc::clone(&dashboard);
+    manager
+        .subscribe_typed(AccountSubscription, move |event| {
+            if let ChannelEvent::Data(account) = event {
+                state.lock().unwrap().account = Some(account.clone());
+            }
+        })
+        .await
+        .unwrap();
+    let state = Arc::clone(&dashboard);
+    manager
+        .subscribe_typed(PositionSubscription, move |event| {
+            if let ChannelEvent::Data(position) = event {
+                state.lock().unwrap().on_position(position);
+            }
+        })
+        .await
+        .unwrap();
+    let state = Arc::clone(&dashboard);
+    manager
+        .subscribe_typed(BalanceEventsSubscription, move |event| {
+            if let ChannelEvent::Data(balance_event) = event {
+                state
+                    .lock()
+                    .unwrap()
+                    .on_balance_event(balance_event, &settlement_asset);
+            }
+        })
+        .await
+        .unwrap();
+    let state = Arc::clone(&dashboard);
+    manager
+        .subscribe_typed(MarketSummarySubscription, move |event| {
+            if let ChannelEvent::Data(summary) = event {
+                state
+                    .lock()
+                    .unwrap()
+                    .summaries
+                    .insert(summary.symbol.clone(), summary.clone());
+            }
+        })
+        .await
+        .unwrap();
+
+    let mut terminal = ratatui::init();
+    loop {
+        let health = manager.health();
+        terminal
+            .draw(|frame| draw(frame, &dashboard.lock().unwrap(), &args.market, &health))
+            .unwrap();
+        // Redraw at least four times a second while waiting for a key
+        if event::poll(Duration::from_millis(250)).unwrap()
+            && let Event::Key(key) = event::read().unwrap()
+            && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+        {
+            break;
+        }
+    }
+    ratatui::restore();
+
+    manager.stop().await.unwrap();
+}
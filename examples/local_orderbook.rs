@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use log::info;
+use paradex::orderbook::OrderBookEngine;
+use paradex::rest::Client;
+use paradex::url::URL;
+use paradex::ws::WebsocketManager;
+
+#[tokio::main]
+async fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    let symbol: String = "BTC-USD-PERP".into();
+    let url = URL::Testnet;
+    let client = Client::new(url.clone(), None).await.unwrap();
+    let manager = WebsocketManager::new(url, None).await;
+    let engine = OrderBookEngine::new(client);
+
+    engine
+        .track(&manager, symbol.clone(), |book| {
+            info!(
+                "{} seq_no={:?} bid={:?} ask={:?}",
+                book.market(),
+                book.seq_no(),
+                book.best_bid(),
+                book.best_ask()
+            );
+        })
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_secs(30)).await;
+    info!(
+        "Top of book {:?}",
+        engine.with_book(&symbol, |book| book.depth(5))
+    );
+
+    engine.untrack(&manager, &symbol).await.unwrap();
+    manager.stop().await.unwrap();
+}
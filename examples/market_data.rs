@@ -4,7 +4,8 @@ use log::{info, warn};
 use paradex::url::URL;
 use paradex::ws::{
     BboSubscription, ChannelEvent, FundingDataSubscription, MarketSummarySubscription,
-    OrderBookDeltasSubscription, OrderBookSubscription, TradesSubscription, WebsocketManager,
+    OrderBookDeltasSubscription, OrderBookSubscription, RefreshRate, TradesSubscription,
+    WebsocketManager,
 };
 
 #[tokio::main]
@@ -31,12 +32,7 @@ async fn main() {
         })
         .await
         .unwrap();
-    let orderbook_spec = OrderBookSubscription {
-        market_symbol: symbol.clone(),
-        channel_name: None,
-        refresh_rate: "50ms".into(),
-        price_tick: None,
-    };
+    let orderbook_spec = OrderBookSubscription::new(symbol.clone()).refresh_rate(RefreshRate::Ms50);
     let orderbook_id = manager
         .subscribe_typed(orderbook_spec, |event| {
             log_channel_event("Order book", event);
@@ -0,0 +1,175 @@
+//! Quotes a bid and an ask around the mid of the local order book, skewing both towards
+//! reducing the inventory built up since the start and pulling the side which would grow it
+//! past `--max-inventory`.
+//!
+//! Runs on testnet unless `--production` is given, until interrupted with ctrl-c, after which
+//! its quotes are cancelled.
+
+use std::time::Duration;
+
+use clap::Parser;
+use log::{info, warn};
+use paradex::decimal::parse_decimal;
+use paradex::exchange::LiveExchange;
+use paradex::orderbook::OrderBookEngine;
+use paradex::prelude::*;
+use paradex::quoter::{Quote, Quoter};
+use paradex::rest::{OrderEvent, OrderGuard};
+use paradex::tracker::OrderTracker;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+
+const CLIENT_ID_PREFIX: &str = "mm-";
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(long, action)]
+    production: bool,
+
+    #[arg(long)]
+    private_keyfile: String,
+
+    #[arg(long, default_value = "BTC-USD-PERP")]
+    market: String,
+
+    /// Size of each quote
+    #[arg(long, default_value = "0.001")]
+    size: String,
+
+    /// Distance of each quote from the fair price, in basis points
+    #[arg(long, default_value_t = 10.)]
+    half_spread_bps: f64,
+
+    /// Largest inventory, long or short, the bot may build up
+    #[arg(long, default_value = "0.01")]
+    max_inventory: String,
+
+    /// Seconds between requotes
+    #[arg(long, default_value_t = 2)]
+    interval: u64,
+}
+
+/// Net size bought by the bot's orders, sells counting negative
+fn inventory(tracker: &OrderTracker, market: &str) -> Decimal {
+    tracker
+        .orders_for_market(market)
+        .iter()
+        .filter(|order| order.update.client_id.starts_with(CLIENT_ID_PREFIX))
+        .map(|order| match order.update.side {
+            Side::BUY => order.filled_size(),
+            Side::SELL => -order.filled_size(),
+        })
+        .sum()
+}
+
+/// Target quotes around `mid`, None for a side which would take the inventory past the limit
+fn quotes(
+    args: &Args,
+    market: &MarketSummaryStatic,
+    mid: f64,
+    inventory: Decimal,
+    size: Decimal,
+    max_inventory: Decimal,
+) -> (Option<Quote>, Option<Quote>) {
+    let half_spread = mid * args.half_spread_bps / 10_000.;
+    // Lean the fair price against the inventory, up to a full half spread at the limit
+    let skew = (inventory / max_inventory)
+        .to_f64()
+        .unwrap_or(0.)
+        .clamp(-1., 1.);
+    let fair = mid - skew * half_spread;
+    // Round on the tick grid in decimals, away from the mid
+    let tick = Decimal::from_f64(market.price_tick_size).unwrap_or(Decimal::ONE);
+    let quote = |price: f64, round: fn(Decimal) -> Decimal| {
+        Decimal::from_f64(price).map(|price| Quote::new(round(price / tick) * tick, size))
+    };
+    let bid = quote(fair - half_spread, |ticks| ticks.floor())
+        .filter(|_| inventory + size <= max_inventory);
+    let ask = quote(fair + half_spread, |ticks| ticks.ceil())
+        .filter(|_| inventory - size >= -max_inventory);
+    (bid, ask)
+}
+
+#[tokio::main]
+async fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+
+    let args = Args::parse();
+    let url = if args.production {
+        URL::Production
+    } else {
+        URL::Testnet
+    };
+    let size = parse_decimal(&args.size).expect("Invalid size");
+    let max_inventory = parse_decimal(&args.max_inventory).expect("Invalid max inventory");
+
+    let private_key = std::fs::read_to_string(&args.private_keyfile)
+        .expect("Failed to read private key file")
+        .trim()
+        .to_string();
+    let client = Client::new(url.clone(), Some(private_key))
+        .await
+        .unwrap()
+        .with_order_guard(OrderGuard::new().max_orders_per_minute(120))
+        .with_order_log(|event: &OrderEvent| {
+            info!(
+                "{:?} {} {:?} {:?}@{:?}",
+                event.action,
+                event.order_id.as_deref().unwrap_or("-"),
+                event.side,
+                event.size,
+                event.price
+            );
+        });
+    // Order creations and modifications fail locally while the venue is in maintenance
+    let _status_poller = client.poll_system_state(Duration::from_secs(30));
+
+    let market = client
+        .markets()
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|market| market.symbol == args.market)
+        .expect("Unknown market");
+    let manager = WebsocketManager::new(url, Some(client.clone())).await;
+    let exchange = LiveExchange::new(client.clone(), manager.clone());
+
+    let tracker = OrderTracker::new();
+    tracker.attach(&exchange).await.unwrap();
+    tracker.sync(&exchange).await.unwrap();
+    let engine = OrderBookEngine::new(client.clone());
+    engine
+        .track(&manager, market.symbol.clone(), |_| {})
+        .await
+        .unwrap();
+    let quoter = Quoter::new(client.clone(), tracker.clone(), market.clone())
+        .client_id_prefix(CLIENT_ID_PREFIX);
+
+    let mut requote = tokio::time::interval(Duration::from_secs(args.interval));
+    loop {
+        tokio::select! {
+            _ = requote.tick() => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+        let Some(mid) = engine
+            .with_book(&market.symbol, |book| {
+                Some((book.best_bid()?.0 + book.best_ask()?.0) / 2.)
+            })
+            .flatten()
+        else {
+            info!("Waiting for the order book of {}", market.symbol);
+            continue;
+        };
+        let inventory = inventory(&tracker, &market.symbol);
+        let (bid, ask) = quotes(&args, &market, mid, inventory, size, max_inventory);
+        info!("mid={mid} inventory={inventory} bid={bid:?} ask={ask:?}");
+        if let Err(e) = quoter.quote(bid, ask).await {
+            warn!("Could not quote: {e}");
+        }
+    }
+
+    info!("Cancelling quotes");
+    quoter.cancel_all().await.unwrap();
+    engine.untrack(&manager, &market.symbol).await.unwrap();
+    manager.stop().await.unwrap();
+}
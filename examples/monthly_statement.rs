@@ -0,0 +1,80 @@
+//! Writes the statement of an account for a calendar month as CSV files: the totals per
+//! currency with opening and closing balances, and the fills, funding payments and transfers
+//! they are made of.
+//!
+//! Runs on testnet unless `--production` is given.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use log::info;
+use paradex::prelude::*;
+use paradex::reports::{Period, Statement};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(long, action)]
+    production: bool,
+
+    #[arg(long)]
+    private_keyfile: String,
+
+    #[arg(long)]
+    year: i32,
+
+    /// Month of the year, from 1
+    #[arg(long)]
+    month: u32,
+
+    /// Directory the CSV files are written to
+    #[arg(long, default_value = ".")]
+    output: PathBuf,
+}
+
+#[tokio::main]
+async fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+
+    let args = Args::parse();
+    let url = if args.production {
+        URL::Production
+    } else {
+        URL::Testnet
+    };
+    let period = Period::month(args.year, args.month).expect("Invalid month");
+
+    let private_key = std::fs::read_to_string(&args.private_keyfile)
+        .expect("Failed to read private key file")
+        .trim()
+        .to_string();
+    let client = Client::new(url, Some(private_key)).await.unwrap();
+    let statement = Statement::fetch(&client, period).await.unwrap();
+
+    for totals in &statement.totals {
+        info!(
+            "{}: opening {} realized {} fees {} funding {} deposits {} withdrawals {} closing {}",
+            totals.currency,
+            totals.opening_balance,
+            totals.realized_pnl,
+            totals.fees,
+            totals.funding,
+            totals.deposits,
+            totals.withdrawals,
+            totals.closing_balance,
+        );
+    }
+
+    let prefix = format!("statement-{}-{:02}", args.year, args.month);
+    std::fs::create_dir_all(&args.output).expect("Failed to create the output directory");
+    for (section, csv) in [
+        ("totals", statement.totals_csv()),
+        ("fills", statement.fills_csv()),
+        ("funding", statement.funding_csv()),
+        ("transfers", statement.transfers_csv()),
+    ] {
+        let path = args.output.join(format!("{prefix}-{section}.csv"));
+        std::fs::write(&path, csv).expect("Failed to write the statement");
+        info!("Wrote {}", path.display());
+    }
+}
@@ -41,7 +41,9 @@ async fn main() {
         .expect("Failed to read private key file")
         .trim()
         .to_string();
-    let client_private = Client::new(url, Some(private_key.clone())).await.unwrap();
+    let client_private = Client::new(url.clone(), Some(private_key.clone()))
+        .await
+        .unwrap();
 
     info!(
         "Account Information {:?}",
@@ -51,7 +53,7 @@ async fn main() {
     info!("Positions {:?}", client_private.positions().await);
 
     let manager = WebsocketManager::new(
-        URL::Testnet,
+        url.clone(),
         Some(Client::new(url, Some(private_key)).await.unwrap()),
     )
     .await;
@@ -122,7 +124,11 @@ async fn main() {
         order_type: OrderType::LIMIT,
     };
     info!("Sending modify order {modify_request:?}");
-    let result = client_private.modify_order(modify_request).await.unwrap();
+    let result = client_private
+        .modify_order(modify_request)
+        .await
+        .unwrap()
+        .into_order();
     info!("Modify order result {result:?}");
 
     tokio::time::sleep(Duration::from_secs(5)).await;
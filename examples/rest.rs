@@ -1,5 +1,9 @@
 use log::info;
-use paradex::{rest::Client, structs::OrderBookParams, url::URL};
+use paradex::{
+    rest::Client,
+    structs::{FillsFilter, FundingPaymentsFilter, OrderBookParams},
+    url::URL,
+};
 
 #[tokio::main]
 async fn main() {
@@ -8,7 +12,7 @@ async fn main() {
     let symbol: String = "BTC-USD-PERP".into();
 
     let url = URL::Testnet;
-    let client = Client::new(url, None).await.unwrap();
+    let client = Client::new(url.clone(), None).await.unwrap();
     info!("system_config {:?}", client.system_config().await);
     info!("system_time {:?}", client.system_time().await);
     info!("system_state {:?}", client.system_state().await);
@@ -24,9 +28,10 @@ async fn main() {
         "Fills {:?}",
         client_private
             .fills(
-                Some("BTC-USD-PERP".to_string()),
-                Some(chrono::Utc::now() - chrono::Duration::days(2)),
-                Some(chrono::Utc::now())
+                FillsFilter::new()
+                    .market("BTC-USD-PERP")
+                    .start(chrono::Utc::now() - chrono::Duration::days(2))
+                    .end(chrono::Utc::now())
             )
             .await
             .unwrap()
@@ -35,7 +40,7 @@ async fn main() {
     info!(
         "Funding {:?}",
         client_private
-            .funding_payments(None, None, None)
+            .funding_payments(FundingPaymentsFilter::new())
             .await
             .unwrap()
     );
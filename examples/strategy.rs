@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use log::info;
+use paradex::error::Error;
+use paradex::exchange::{Exchange, LiveExchange};
+use paradex::rest::Client;
+use paradex::strategy::{Strategy, StrategyRunner};
+use paradex::url::URL;
+use paradex::ws::{Channel, Message, WebsocketManager};
+
+/// Logs the spread of each BBO update and how many updates arrived per timer interval
+#[derive(Default)]
+struct SpreadLogger {
+    updates: usize,
+}
+
+impl<E: Exchange> Strategy<E> for SpreadLogger {
+    async fn on_tick(&mut self, _exchange: &E, message: &Message) -> Result<(), Error> {
+        if let Message::BBO(bbo) = message {
+            self.updates += 1;
+            info!("{} spread {}", bbo.market, bbo.ask - bbo.bid);
+        }
+        Ok(())
+    }
+
+    async fn on_timer(&mut self, _exchange: &E) -> Result<(), Error> {
+        info!("{} BBO updates since the last timer", self.updates);
+        self.updates = 0;
+        Ok(())
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    let url = URL::Testnet;
+    let client = Client::new(url.clone(), None).await.unwrap();
+    let manager = WebsocketManager::new(url, None).await;
+    let exchange = LiveExchange::new(client, manager.clone());
+
+    let strategy = StrategyRunner::new(exchange)
+        .subscribe(Channel::BBO {
+            market_symbol: "BTC-USD-PERP".into(),
+        })
+        .timer(Duration::from_secs(10))
+        .run(SpreadLogger::default(), async {
+            tokio::signal::ctrl_c().await.unwrap();
+        })
+        .await
+        .unwrap();
+    info!("Stopped with {} unreported updates", strategy.updates);
+
+    manager.stop().await.unwrap();
+}
@@ -0,0 +1,257 @@
+//! Command line client for common Paradex operations.
+//!
+//! Results are printed as JSON. Private commands read the Stark private key from
+//! `--private-key-file` or the `PARADEX_PRIVATE_KEY` environment variable.
+//!
+//! ```text
+//! paradex-cli md bbo BTC-USD-PERP
+//! paradex-cli --private-key-file key.txt balance
+//! paradex-cli --private-key-file key.txt order place BTC-USD-PERP buy 0.001 --price 30000 --post-only
+//! paradex-cli --private-key-file key.txt orders ls --market BTC-USD-PERP
+//! ```
+
+use std::error::Error;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use paradex::order_builder::OrderBuilder;
+use paradex::rest::{Client, OrderGuard};
+use paradex::rust_decimal::Decimal;
+use paradex::structs::{OrderInstruction, OrderType, Side};
+use paradex::url::URL;
+use serde::Serialize;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Command line client for common Paradex operations", long_about = None)]
+struct Args {
+    /// Use production instead of testnet endpoints
+    #[arg(long, action)]
+    production: bool,
+
+    /// File containing the hex-encoded Stark private key of the account
+    #[arg(long, global = true)]
+    private_key_file: Option<String>,
+
+    /// Hex-encoded Stark private key of the account
+    #[arg(
+        long,
+        env = "PARADEX_PRIVATE_KEY",
+        hide_env_values = true,
+        global = true
+    )]
+    private_key: Option<String>,
+
+    /// Refuse to place orders whose price times size exceeds this
+    #[arg(long, env = "PARADEX_MAX_NOTIONAL", global = true)]
+    max_notional: Option<Decimal>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Balances of the account
+    Balance,
+    /// Open positions of the account
+    Positions,
+    /// Place and cancel orders
+    #[command(subcommand)]
+    Order(OrderCommand),
+    /// List orders
+    #[command(subcommand)]
+    Orders(OrdersCommand),
+    /// Public market data
+    #[command(subcommand)]
+    Md(MarketDataCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum OrderCommand {
+    /// Validate an order against its market and place it
+    Place {
+        market: String,
+        /// buy or sell
+        side: Side,
+        size: Decimal,
+        /// Limit price, a market order is placed without one
+        #[arg(long)]
+        price: Option<Decimal>,
+        /// Order type, defaults to limit with a price and market without one
+        #[arg(long = "type")]
+        order_type: Option<OrderType>,
+        /// Time in force, e.g. gtc, ioc or post_only
+        #[arg(long)]
+        instruction: Option<OrderInstruction>,
+        #[arg(long, action)]
+        post_only: bool,
+        #[arg(long, action)]
+        reduce_only: bool,
+        #[arg(long)]
+        trigger_price: Option<Decimal>,
+        #[arg(long)]
+        client_id: Option<String>,
+        /// Round price and size onto the market's grid instead of rejecting the order
+        #[arg(long, action)]
+        round: bool,
+    },
+    /// Cancel an order by id, or by client id with --client-id
+    Cancel {
+        id: String,
+        #[arg(long, action)]
+        client_id: bool,
+    },
+    /// Cancel all open orders, or those of one market
+    CancelAll {
+        #[arg(long)]
+        market: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum OrdersCommand {
+    /// Open orders of the account
+    Ls {
+        #[arg(long)]
+        market: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum MarketDataCommand {
+    /// Best bid and offer of a market
+    Bbo { market: String },
+    /// Static details of every market
+    Markets,
+}
+
+fn print<T: Serialize>(value: &T) -> Result<(), Box<dyn Error>> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+async fn client(args: &Args, url: URL) -> Result<Client, Box<dyn Error>> {
+    let private_key = match (&args.private_key_file, &args.private_key) {
+        (Some(path), _) => std::fs::read_to_string(path)?.trim().to_string(),
+        (None, Some(private_key)) => private_key.clone(),
+        (None, None) => {
+            return Err("this command requires --private-key-file or PARADEX_PRIVATE_KEY".into());
+        }
+    };
+    let client = Client::new(url, Some(private_key)).await?;
+    Ok(match args.max_notional {
+        Some(max_notional) => client.with_order_guard(OrderGuard::new().max_notional(max_notional)),
+        None => client,
+    })
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let url = if args.production {
+        URL::Production
+    } else {
+        URL::Testnet
+    };
+    match &args.command {
+        Command::Md(MarketDataCommand::Bbo { market }) => {
+            print(&Client::new(url, None).await?.bbo(market.clone()).await?)
+        }
+        Command::Md(MarketDataCommand::Markets) => {
+            print(&Client::new(url, None).await?.markets().await?)
+        }
+        Command::Balance => print(&client(&args, url).await?.balance().await?.results),
+        Command::Positions => print(&client(&args, url).await?.positions().await?.results),
+        Command::Orders(OrdersCommand::Ls { market }) => {
+            let orders = client(&args, url).await?.open_orders().await?.results;
+            let orders: Vec<_> = orders
+                .into_iter()
+                .filter(|order| market.as_ref().is_none_or(|market| order.market == *market))
+                .collect();
+            print(&orders)
+        }
+        Command::Order(OrderCommand::Cancel { id, client_id }) => {
+            let client = client(&args, url).await?;
+            if *client_id {
+                client.cancel_order_by_client_id(id.clone()).await?;
+            } else {
+                client.cancel_order(id.clone()).await?;
+            }
+            print(&id)
+        }
+        Command::Order(OrderCommand::CancelAll { market }) => {
+            let client = client(&args, url).await?;
+            match market {
+                Some(market) => print(&client.cancel_all_orders_for_market(market.clone()).await?),
+                None => print(&client.cancel_all_orders().await?),
+            }
+        }
+        Command::Order(OrderCommand::Place {
+            market,
+            side,
+            size,
+            price,
+            order_type,
+            instruction,
+            post_only,
+            reduce_only,
+            trigger_price,
+            client_id,
+            round,
+        }) => {
+            let client = client(&args, url).await?;
+            let markets = client.markets().await?;
+            let summary = markets
+                .iter()
+                .find(|summary| summary.symbol == *market)
+                .ok_or_else(|| format!("unknown market {market}"))?;
+            let order_type = order_type.unwrap_or(match price {
+                Some(_) => OrderType::LIMIT,
+                None => OrderType::MARKET,
+            });
+            let mut builder = match (order_type, price) {
+                (OrderType::MARKET, None) => OrderBuilder::market(summary, *side, *size),
+                (order_type, price) => {
+                    let builder = OrderBuilder::new(summary, *side, order_type, *size);
+                    match price {
+                        Some(price) => builder.price(*price),
+                        None => builder,
+                    }
+                }
+            }
+            .round_to_market(*round);
+            if let Some(instruction) = instruction {
+                builder = builder.instruction(instruction.clone());
+            }
+            if *post_only {
+                builder = builder.post_only();
+            }
+            if *reduce_only {
+                builder = builder.reduce_only();
+            }
+            if let Some(trigger_price) = trigger_price {
+                builder = builder.trigger_price(*trigger_price);
+            }
+            if let Some(client_id) = client_id {
+                builder = builder.client_id(client_id.clone());
+            }
+            let request = builder.build().map_err(|errors| {
+                errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })?;
+            print(&client.create_order(request).await?)
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Args::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
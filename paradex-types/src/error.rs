@@ -0,0 +1,278 @@
+use std::time::Duration;
+
+use http::StatusCode;
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use crate::structs::{AccountMarginUpdateResponse, SystemStatus};
+
+#[derive(Error, Debug, Clone)]
+pub enum Error {
+    #[error("Websocket Send Error: {0:?}")]
+    WebSocketSend(String),
+    #[error("Websocket Connect Error: {0:?}")]
+    WebSocketConnectError(String),
+    #[error("Websocket Request Timeout: {0:?}")]
+    WebSocketRequestTimeout(String),
+    #[error("Websocket Auth Error: {0:?}")]
+    WebSocketAuthError(String),
+    /// The read loop did not exit within the shutdown timeout and was aborted
+    #[error("Websocket Shutdown Timeout: read loop aborted after {0:?}")]
+    WebSocketShutdownTimeout(Duration),
+    #[error("Authentication Required For Channel: {0:?}")]
+    AuthenticationRequired(String),
+    #[error("Invalid Subscription: {0:?}")]
+    InvalidSubscription(String),
+    #[error("Websocket Subscription Error: channel={channel:?} code={code:?} message={message:?}")]
+    WebSocketSubscriptionError {
+        channel: String,
+        code: i32,
+        message: String,
+    },
+    #[error(
+        "Order Book Sequence Gap: market={market:?} expected={expected:?} received={received:?}"
+    )]
+    OrderBookSequenceGap {
+        market: String,
+        expected: Option<u64>,
+        received: u64,
+    },
+    #[error("Paper Trading Error: {0:?}")]
+    PaperTradingError(String),
+    #[error("Parse Error: {0:?}")]
+    JsonParseError(String),
+    #[error("Rest Error: {0:?}")]
+    RestError(String),
+    #[error("Rest Empty Response")]
+    RestEmptyResponse,
+    #[error("Deserialization Error: {0:?}")]
+    DeserializationError(String),
+    #[error("Starknet Error: {0:?}")]
+    StarknetError(String),
+    #[error("Ethereum Error: {0:?}")]
+    EthereumError(String),
+    #[error("Type Conversion Error: {0:?}")]
+    TypeConversionError(String),
+    #[error("Time Error: {0:?}")]
+    TimeError(String),
+    #[error("State Store Error: {0:?}")]
+    StateStoreError(String),
+    #[error("IO Error: {0:?}")]
+    IoError(String),
+    #[error("Invalid Configuration: {0:?}")]
+    InvalidConfiguration(String),
+    #[error("Invalid Order: {0:?}")]
+    InvalidOrder(String),
+    #[error("Invalid Margin Update: {0:?}")]
+    InvalidMarginUpdate(String),
+    #[error(
+        "Margin Update Mismatch: requested {leverage}x {margin_type} for {market:?}, applied={applied:?}"
+    )]
+    MarginUpdateMismatch {
+        market: String,
+        leverage: u64,
+        margin_type: String,
+        /// The configuration the exchange applied instead, which is in effect
+        applied: Box<AccountMarginUpdateResponse>,
+    },
+    #[error("Order Guard Violation: {0}")]
+    OrderGuardViolation(GuardViolation),
+    #[error("Operation Not Approved: {0}")]
+    OperationNotApproved(String),
+    #[error("Token Not Bridged: {0:?}")]
+    TokenNotBridged(String),
+    #[error("Withdrawal Not Whitelisted: {0:?}")]
+    WithdrawalNotWhitelisted(String),
+    #[error("Order Flow Suspended: system status is {0:?}")]
+    OrderFlowSuspended(SystemStatus),
+    #[error("Deadline Exceeded: {0:?}")]
+    DeadlineExceeded(Duration),
+    #[error(
+        "Order Submission Timed Out: client_id={client_id:?} deadline={deadline:?} cancelled={cancelled:?}"
+    )]
+    OrderSubmissionTimedOut {
+        client_id: String,
+        deadline: Duration,
+        /// Whether the follow-up cancel of the order was accepted
+        ///
+        /// The abandoned request may still reach the exchange after the cancel, so the order
+        /// should be looked up by its client id before it is known not to rest.
+        cancelled: bool,
+    },
+    #[error("Feature Disabled: paradex was built without the {0:?} feature")]
+    FeatureDisabled(String),
+    #[error("Missing Private Key")]
+    MissingPrivateKey,
+    #[error("Missing API Token")]
+    MissingApiToken,
+    #[error("Missing Market Cache")]
+    MissingMarketCache,
+    #[error("Unknown Market: {0:?}")]
+    UnknownMarket(String),
+    #[error("Paradex Error: status_code={status_code:?} error={error:?}, message={message:?}")]
+    ParadexError {
+        status_code: StatusCode,
+        error: Option<ParadexErrorCode>,
+        message: String,
+    },
+    #[error("HTTP Error: status_code={status_code:?}")]
+    HTTPError { status_code: StatusCode },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Client side order guard rejecting a request before it was sent
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum GuardViolation {
+    #[error("notional {notional} of order on {market} exceeds the limit of {limit}")]
+    MaxNotional {
+        market: String,
+        notional: Decimal,
+        limit: Decimal,
+    },
+    #[error("more than {limit} orders within a minute")]
+    MaxOrdersPerMinute { limit: u32 },
+    #[error("order on {market} has no price to check the notional limit against")]
+    Unpriced { market: String },
+}
+
+/// Error codes returned by the Paradex API in the `error` field of failed requests
+///
+/// Codes not known to this version of the SDK are kept as `Unknown`, and may get their own
+/// variant in a later version. The enum is non-exhaustive so that adding one is not a breaking
+/// change: matches need a wildcard arm, and should use `as_str` rather than `Unknown` to
+/// recognise codes without a variant.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ParadexErrorCode {
+    VALIDATION_ERROR,
+    BINDING_ERROR,
+    INTERNAL_ERROR,
+    NOT_FOUND,
+    SERVICE_UNAVAILABLE,
+    INVALID_REQUEST_PARAMETER,
+    RATE_LIMIT_EXCEEDED,
+    ORDER_NOT_FOUND,
+    ORDER_ID_NOT_FOUND,
+    CLIENT_ORDER_ID_NOT_FOUND,
+    ORDER_IS_CLOSED,
+    ORDER_IS_NOT_OPEN_YET,
+    DUPLICATED_CLIENT_ID,
+    INSUFFICIENT_MARGIN,
+    INVALID_PRICE_PRECISION,
+    INVALID_ORDER_SIGNATURE,
+    INVALID_STARKNET_SIGNATURE,
+    INVALID_TIMESTAMP,
+    INVALID_TOKEN,
+    NOT_ONBOARDED,
+    ACCOUNT_NOT_FOUND,
+    MARKET_NOT_FOUND,
+    MARKET_NOT_OPEN,
+    Unknown(String),
+}
+
+impl ParadexErrorCode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ParadexErrorCode::VALIDATION_ERROR => "VALIDATION_ERROR",
+            ParadexErrorCode::BINDING_ERROR => "BINDING_ERROR",
+            ParadexErrorCode::INTERNAL_ERROR => "INTERNAL_ERROR",
+            ParadexErrorCode::NOT_FOUND => "NOT_FOUND",
+            ParadexErrorCode::SERVICE_UNAVAILABLE => "SERVICE_UNAVAILABLE",
+            ParadexErrorCode::INVALID_REQUEST_PARAMETER => "INVALID_REQUEST_PARAMETER",
+            ParadexErrorCode::RATE_LIMIT_EXCEEDED => "RATE_LIMIT_EXCEEDED",
+            ParadexErrorCode::ORDER_NOT_FOUND => "ORDER_NOT_FOUND",
+            ParadexErrorCode::ORDER_ID_NOT_FOUND => "ORDER_ID_NOT_FOUND",
+            ParadexErrorCode::CLIENT_ORDER_ID_NOT_FOUND => "CLIENT_ORDER_ID_NOT_FOUND",
+            ParadexErrorCode::ORDER_IS_CLOSED => "ORDER_IS_CLOSED",
+            ParadexErrorCode::ORDER_IS_NOT_OPEN_YET => "ORDER_IS_NOT_OPEN_YET",
+            ParadexErrorCode::DUPLICATED_CLIENT_ID => "DUPLICATED_CLIENT_ID",
+            ParadexErrorCode::INSUFFICIENT_MARGIN => "INSUFFICIENT_MARGIN",
+            ParadexErrorCode::INVALID_PRICE_PRECISION => "INVALID_PRICE_PRECISION",
+            ParadexErrorCode::INVALID_ORDER_SIGNATURE => "INVALID_ORDER_SIGNATURE",
+            ParadexErrorCode::INVALID_STARKNET_SIGNATURE => "INVALID_STARKNET_SIGNATURE",
+            ParadexErrorCode::INVALID_TIMESTAMP => "INVALID_TIMESTAMP",
+            ParadexErrorCode::INVALID_TOKEN => "INVALID_TOKEN",
+            ParadexErrorCode::NOT_ONBOARDED => "NOT_ONBOARDED",
+            ParadexErrorCode::ACCOUNT_NOT_FOUND => "ACCOUNT_NOT_FOUND",
+            ParadexErrorCode::MARKET_NOT_FOUND => "MARKET_NOT_FOUND",
+            ParadexErrorCode::MARKET_NOT_OPEN => "MARKET_NOT_OPEN",
+            ParadexErrorCode::Unknown(code) => code,
+        }
+    }
+
+    /// Whether the order referenced by the request does not exist
+    pub fn is_order_not_found(&self) -> bool {
+        matches!(
+            self,
+            ParadexErrorCode::ORDER_NOT_FOUND
+                | ParadexErrorCode::ORDER_ID_NOT_FOUND
+                | ParadexErrorCode::CLIENT_ORDER_ID_NOT_FOUND
+        )
+    }
+}
+
+impl From<&str> for ParadexErrorCode {
+    fn from(code: &str) -> Self {
+        match code {
+            "VALIDATION_ERROR" => ParadexErrorCode::VALIDATION_ERROR,
+            "BINDING_ERROR" => ParadexErrorCode::BINDING_ERROR,
+            "INTERNAL_ERROR" => ParadexErrorCode::INTERNAL_ERROR,
+            "NOT_FOUND" => ParadexErrorCode::NOT_FOUND,
+            "SERVICE_UNAVAILABLE" => ParadexErrorCode::SERVICE_UNAVAILABLE,
+            "INVALID_REQUEST_PARAMETER" => ParadexErrorCode::INVALID_REQUEST_PARAMETER,
+            "RATE_LIMIT_EXCEEDED" => ParadexErrorCode::RATE_LIMIT_EXCEEDED,
+            "ORDER_NOT_FOUND" => ParadexErrorCode::ORDER_NOT_FOUND,
+            "ORDER_ID_NOT_FOUND" => ParadexErrorCode::ORDER_ID_NOT_FOUND,
+            "CLIENT_ORDER_ID_NOT_FOUND" => ParadexErrorCode::CLIENT_ORDER_ID_NOT_FOUND,
+            "ORDER_IS_CLOSED" => ParadexErrorCode::ORDER_IS_CLOSED,
+            "ORDER_IS_NOT_OPEN_YET" => ParadexErrorCode::ORDER_IS_NOT_OPEN_YET,
+            "DUPLICATED_CLIENT_ID" => ParadexErrorCode::DUPLICATED_CLIENT_ID,
+            "INSUFFICIENT_MARGIN" => ParadexErrorCode::INSUFFICIENT_MARGIN,
+            "INVALID_PRICE_PRECISION" => ParadexErrorCode::INVALID_PRICE_PRECISION,
+            "INVALID_ORDER_SIGNATURE" => ParadexErrorCode::INVALID_ORDER_SIGNATURE,
+            "INVALID_STARKNET_SIGNATURE" => ParadexErrorCode::INVALID_STARKNET_SIGNATURE,
+            "INVALID_TIMESTAMP" => ParadexErrorCode::INVALID_TIMESTAMP,
+            "INVALID_TOKEN" => ParadexErrorCode::INVALID_TOKEN,
+            "NOT_ONBOARDED" => ParadexErrorCode::NOT_ONBOARDED,
+            "ACCOUNT_NOT_FOUND" => ParadexErrorCode::ACCOUNT_NOT_FOUND,
+            "MARKET_NOT_FOUND" => ParadexErrorCode::MARKET_NOT_FOUND,
+            "MARKET_NOT_OPEN" => ParadexErrorCode::MARKET_NOT_OPEN,
+            other => ParadexErrorCode::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for ParadexErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Error {
+    /// API error code of a failed REST request, if the response carried one
+    pub fn paradex_error_code(&self) -> Option<&ParadexErrorCode> {
+        match self {
+            Error::ParadexError { error, .. } => error.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_codes() {
+        for code in ["ORDER_ID_NOT_FOUND", "INSUFFICIENT_MARGIN", "NEW_CODE"] {
+            assert_eq!(ParadexErrorCode::from(code).as_str(), code);
+        }
+        assert!(ParadexErrorCode::from("CLIENT_ORDER_ID_NOT_FOUND").is_order_not_found());
+        assert_eq!(
+            ParadexErrorCode::from("NEW_CODE"),
+            ParadexErrorCode::Unknown("NEW_CODE".into())
+        );
+    }
+}
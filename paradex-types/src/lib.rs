@@ -0,0 +1,12 @@
+//! Payload types of the Paradex API.
+//!
+//! The structs, errors and endpoints of the `paradex` client without its networking and
+//! signing dependencies, for services which only deserialize Paradex payloads, e.g. consumers
+//! of recorded market data. The `paradex` crate re-exports these modules under the same names.
+
+pub mod error;
+pub mod structs;
+pub mod url;
+
+pub use rust_decimal;
+pub use starknet_types_core::felt::Felt;
@@ -0,0 +1,2147 @@
+use crate::error::{Error, Result};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_tuple::{Deserialize_tuple, Serialize_tuple};
+use serde_with::{DisplayFromStr, PickFirst, serde_as};
+use starknet_types_core::felt::Felt;
+use std::str::FromStr;
+
+/// Implement `as_str`, `Display` and a case-insensitive `FromStr` for an enum whose variants are
+/// named as on the wire, so logs, config files and command lines can use `buy` as well as `BUY`
+macro_rules! wire_names {
+    ($name:ident { $($variant:ident),+ $(,)? }) => {
+        impl $name {
+            /// Name of the variant in API payloads
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => stringify!($variant),)+
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Error;
+
+            fn from_str(value: &str) -> Result<Self> {
+                $(
+                    if value.eq_ignore_ascii_case(stringify!($variant)) {
+                        return Ok($name::$variant);
+                    }
+                )+
+                Err(Error::TypeConversionError(format!(
+                    "Unknown {} {value:?}",
+                    stringify!($name)
+                )))
+            }
+        }
+    };
+}
+
+fn deserialize_string_to_f64<'de, D>(deserializer: D) -> std::result::Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: String = String::deserialize(deserializer)?;
+    if s.is_empty() {
+        Ok(f64::NAN)
+    } else {
+        f64::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+fn deserialize_optional_string_to_f64<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    // First deserialize to an Option<String>
+    let opt_str = Option::<String>::deserialize(deserializer)?;
+
+    // Handle the Option
+    match opt_str {
+        None => Ok(None),
+        Some(s) if s.is_empty() => Ok(None),
+        Some(s) => f64::from_str(&s)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+fn serialize_f64_as_string<S>(value: &f64, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if value.is_nan() {
+        serializer.serialize_str("")
+    } else {
+        serializer.serialize_str(&value.to_string())
+    }
+}
+
+fn serialize_optional_f64_as_string<S>(
+    value: &Option<f64>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        None => Ok(serializer.serialize_unit())?,
+        Some(float) => serializer.serialize_str(&float.to_string()),
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResultsContainer<T> {
+    pub results: T,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BridgedToken {
+    pub decimals: u32,
+    pub l1_bridge_address: String,
+    pub l1_token_address: String,
+    pub l2_bridge_address: String,
+    pub l2_token_address: String,
+    pub name: String,
+    pub symbol: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SystemConfig {
+    pub block_explorer_url: String,
+    pub bridged_tokens: Vec<BridgedToken>,
+    pub environment: String,
+    pub l1_chain_id: String,
+    pub l1_core_contract_address: String,
+    pub l1_operator_address: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub liquidation_fee: f64,
+    pub oracle_address: String,
+    pub paraclear_account_hash: String,
+    pub paraclear_account_proxy_hash: String,
+    pub paraclear_address: String,
+    pub paraclear_decimals: u32,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub partial_liquidation_buffer: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub partial_liquidation_share_increment: f64,
+    pub starknet_chain_id: String,
+    pub starknet_fullnode_rpc_url: String,
+    pub starknet_gateway_url: String,
+    pub universal_deployer_address: String,
+}
+
+impl SystemConfig {
+    /// Bridge configuration of a token, matched by symbol, e.g. `USDC`
+    pub fn bridged_token(&self, symbol: &str) -> Option<&BridgedToken> {
+        self.bridged_tokens
+            .iter()
+            .find(|token| token.symbol.eq_ignore_ascii_case(symbol))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemStatus {
+    Ok,
+    Maintenance,
+    CancelOnly,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SystemState {
+    pub status: SystemStatus,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SystemTimeResponse {
+    #[serde_as(as = "DisplayFromStr")]
+    pub server_time: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JWTToken {
+    pub jwt_token: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct OnboardingUtm {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub campaign: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub medium: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// Metadata submitted when onboarding an account
+///
+/// Available without the `onboarding` feature so code building requests compiles either way,
+/// but only a client built with the feature can submit it.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct OnboardingRequest {
+    pub public_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marketing_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub referral_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utm: Option<OnboardingUtm>,
+}
+
+impl OnboardingRequest {
+    pub fn new(public_key_hex: impl Into<String>) -> Self {
+        Self {
+            public_key: public_key_hex.into(),
+            marketing_code: None,
+            referral_code: None,
+            utm: None,
+        }
+    }
+
+    pub fn with_marketing_code(mut self, code: impl Into<String>) -> Self {
+        self.marketing_code = Some(code.into());
+        self
+    }
+
+    pub fn with_referral_code(mut self, code: impl Into<String>) -> Self {
+        self.referral_code = Some(code.into());
+        self
+    }
+
+    pub fn with_utm(mut self, utm: OnboardingUtm) -> Self {
+        self.utm = Some(utm);
+        self
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MarketSummary {
+    pub symbol: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub mark_price: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub last_traded_price: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub bid: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub ask: f64,
+    #[serde(
+        default,
+        rename = "volume_24h",
+        alias = "volume_24",
+        deserialize_with = "deserialize_optional_string_to_f64",
+        serialize_with = "serialize_optional_f64_as_string"
+    )]
+    pub volume_24: Option<f64>,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub total_volume: f64,
+    pub created_at: u64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub underlying_price: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub open_interest: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub funding_rate: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub price_change_rate_24h: f64,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_optional_string_to_f64",
+        serialize_with = "serialize_optional_f64_as_string"
+    )]
+    pub bid_iv: Option<f64>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_optional_string_to_f64",
+        serialize_with = "serialize_optional_f64_as_string"
+    )]
+    pub ask_iv: Option<f64>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_optional_string_to_f64",
+        serialize_with = "serialize_optional_f64_as_string"
+    )]
+    pub last_iv: Option<f64>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_optional_string_to_f64",
+        serialize_with = "serialize_optional_f64_as_string"
+    )]
+    pub delta: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OptionType {
+    CALL,
+    PUT,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Delta1CrossMarginParams {
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub imf_base: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub imf_factor: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub imf_shift: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub mmf_factor: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MarketChainDetails {
+    pub collateral_address: Option<String>,
+    pub contract_address: Option<String>,
+    pub fee_account_address: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_string_to_f64",
+        serialize_with = "serialize_f64_as_string"
+    )]
+    pub fee_maker: f64,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_string_to_f64",
+        serialize_with = "serialize_f64_as_string"
+    )]
+    pub fee_taker: f64,
+    pub insurance_fund_address: String,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_string_to_f64",
+        serialize_with = "serialize_f64_as_string"
+    )]
+    pub liquidation_fee: f64,
+    pub oracle_address: String,
+    pub symbol: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct FeeWithCap {
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub fee: f64,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_string_to_f64",
+        serialize_with = "serialize_f64_as_string"
+    )]
+    pub fee_cap: f64,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_string_to_f64",
+        serialize_with = "serialize_f64_as_string"
+    )]
+    pub fee_floor: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MakerTakerFee {
+    pub maker_fee: FeeWithCap,
+    pub taker_fee: FeeWithCap,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MarketFeeConfig {
+    pub api_fee: MakerTakerFee,
+    pub interactive_fee: MakerTakerFee,
+    pub rpi_fee: MakerTakerFee,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct OptionMarginParams {
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub long_itm: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub premium_multiplier: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub short_itm: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub short_otm: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub short_put_cap: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct OptionCrossMarginParams {
+    pub imf: OptionMarginParams,
+    pub mmf: OptionMarginParams,
+}
+
+/// Kline resolution, serialized as the number of minutes as expected by the API
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(into = "u32", try_from = "u32")]
+pub enum KlineResolution {
+    Min1 = 1,
+    Min3 = 3,
+    Min5 = 5,
+    Min15 = 15,
+    Min30 = 30,
+    Hour1 = 60,
+}
+
+impl From<KlineResolution> for u32 {
+    fn from(resolution: KlineResolution) -> Self {
+        resolution as u32
+    }
+}
+
+impl TryFrom<u32> for KlineResolution {
+    type Error = Error;
+
+    fn try_from(minutes: u32) -> Result<Self> {
+        match minutes {
+            1 => Ok(KlineResolution::Min1),
+            3 => Ok(KlineResolution::Min3),
+            5 => Ok(KlineResolution::Min5),
+            15 => Ok(KlineResolution::Min15),
+            30 => Ok(KlineResolution::Min30),
+            60 => Ok(KlineResolution::Hour1),
+            _ => Err(Error::TypeConversionError(format!(
+                "Unsupported kline resolution {minutes}"
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KlinePriceKind {
+    Last,
+    Mark,
+    Underlying,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct KlineParams {
+    /// Start time in UTC timestamp (milliseconds since epoch)
+    pub start_at: u64,
+    /// End time in UTC timestamp (milliseconds since epoch)
+    pub end_at: u64,
+    pub symbol: String,
+    pub resolution: KlineResolution,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_kind: Option<KlinePriceKind>,
+}
+
+#[derive(Clone, Debug, Serialize_tuple, Deserialize_tuple, PartialEq)]
+pub struct Kline {
+    pub timestamp_ms: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct OrderBookParams {
+    /// Defaults to 20
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u16>,
+    /// Price tick for aggregation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price_tick: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct OrderBookResponse {
+    /// List of Ask sizes and prices
+    pub asks: Vec<(String, String)>,
+    /// List of Bid sizes and prices
+    pub bids: Vec<(String, String)>,
+    /// Last update to the orderbook in milliseconds
+    pub last_updated_at: u64,
+    /// Market name
+    pub market: String,
+    /// Sequence number of the orderbook
+    pub seq_no: u64,
+}
+
+/// Order book including RPI orders, along with the best levels seen by API and interactive
+/// traders
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct OrderBookInteractiveResponse {
+    /// List of Ask sizes and prices
+    #[serde(default)]
+    pub asks: Vec<(String, String)>,
+    /// List of Bid sizes and prices
+    #[serde(default)]
+    pub bids: Vec<(String, String)>,
+    /// Size on the best ask from API (excluding RPI)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best_ask_api: Option<(String, String)>,
+    /// Size on the best ask from the UI (including RPI)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best_ask_interactive: Option<(String, String)>,
+    /// Size on the best bid from API (excluding RPI)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best_bid_api: Option<(String, String)>,
+    /// Size on the best bid from the UI (including RPI)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best_bid_interactive: Option<(String, String)>,
+    /// Last update to the orderbook in milliseconds
+    pub last_updated_at: u64,
+    /// Market name
+    pub market: String,
+    /// Sequence number of the orderbook
+    pub seq_no: u64,
+}
+
+/// Size of RPI orders at an interactive best level given the API best level of the same side
+fn rpi_size(interactive: &(String, String), api: Option<&(String, String)>) -> Option<Decimal> {
+    let price = Decimal::from_str(&interactive.0).ok()?;
+    let size = Decimal::from_str(&interactive.1).ok()?;
+    let Some((api_price, api_size)) = api else {
+        return Some(size);
+    };
+    if Decimal::from_str(api_price).ok()? != price {
+        // API orders rest behind the interactive best level, which is all RPI
+        return Some(size);
+    }
+    Some((size - Decimal::from_str(api_size).ok()?).max(Decimal::ZERO))
+}
+
+impl OrderBookInteractiveResponse {
+    /// Size of the RPI orders at the best bid, only available to interactive traders
+    ///
+    /// None if the book has no bids or a size is not a number
+    pub fn rpi_best_bid_size(&self) -> Option<Decimal> {
+        let best = self
+            .best_bid_interactive
+            .as_ref()
+            .or_else(|| self.bids.first())?;
+        rpi_size(best, self.best_bid_api.as_ref())
+    }
+
+    /// Size of the RPI orders at the best ask, only available to interactive traders
+    ///
+    /// None if the book has no asks or a size is not a number
+    pub fn rpi_best_ask_size(&self) -> Option<Decimal> {
+        let best = self
+            .best_ask_interactive
+            .as_ref()
+            .or_else(|| self.asks.first())?;
+        rpi_size(best, self.best_ask_api.as_ref())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MarketSummaryStatic {
+    pub asset_kind: String,
+    pub base_currency: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_details: Option<MarketChainDetails>,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub clamp_rate: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta1_cross_margin_params: Option<Delta1CrossMarginParams>,
+    pub expiry_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_config: Option<MarketFeeConfig>,
+    pub funding_multiplier: f64,
+    pub funding_period_hours: u16,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub interest_rate: f64,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_optional_string_to_f64",
+        serialize_with = "serialize_optional_f64_as_string"
+    )]
+    pub iv_bands_width: Option<f64>,
+    pub market_kind: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub max_funding_rate: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub max_funding_rate_change: f64,
+    pub max_open_orders: i64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub max_order_size: f64,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_string_to_f64",
+        serialize_with = "serialize_f64_as_string"
+    )]
+    pub max_slippage: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub max_tob_spread: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub min_notional: f64,
+    pub open_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub option_cross_margin_params: Option<OptionCrossMarginParams>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub option_type: Option<OptionType>,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub oracle_ewma_factor: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub order_size_increment: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub position_limit: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub price_bands_width: f64,
+    pub price_feed_id: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub price_tick_size: f64,
+    pub quote_currency: String,
+    pub settlement_currency: String,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_optional_string_to_f64",
+        serialize_with = "serialize_optional_f64_as_string"
+    )]
+    pub strike_price: Option<f64>,
+    pub symbol: String,
+    pub tags: Vec<String>,
+}
+
+impl MarketSummaryStatic {
+    /// Highest leverage allowed by the initial margin fraction of a perpetual, None for other
+    /// markets
+    pub fn max_leverage(&self) -> Option<u64> {
+        let imf_base = self.delta1_cross_margin_params.as_ref()?.imf_base;
+        (imf_base > 0.).then(|| (1. / imf_base).floor() as u64)
+    }
+
+    /// Whether positions in the market can be margined in isolation, only perpetual futures
+    pub fn supports_isolated_margin(&self) -> bool {
+        self.asset_kind == "PERP"
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BBO {
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub bid: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub bid_size: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub ask: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub ask_size: f64,
+
+    pub market: String,
+    pub last_updated_at: u64,
+    /// Sequence number of the orderbook
+    #[serde(default)]
+    pub seq_no: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Side {
+    #[serde(alias = "buy")]
+    BUY,
+    #[serde(alias = "sell")]
+    SELL,
+}
+
+wire_names!(Side { BUY, SELL });
+
+impl Side {
+    pub fn felt(&self) -> Felt {
+        match self {
+            Side::BUY => Felt::ONE,
+            Side::SELL => Felt::TWO,
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TradeType {
+    #[serde(alias = "fill")]
+    FILL,
+    #[serde(alias = "liquidation")]
+    LIQUIDATION,
+    #[serde(alias = "rpi")]
+    RPI,
+    #[serde(alias = "transfer")]
+    TRANSFER,
+    #[serde(alias = "settle_market")]
+    SETTLE_MARKET,
+    #[serde(alias = "block_trade")]
+    BLOCK_TRADE,
+}
+
+wire_names!(TradeType {
+    FILL,
+    LIQUIDATION,
+    RPI,
+    TRANSFER,
+    SETTLE_MARKET,
+    BLOCK_TRADE
+});
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Trade {
+    pub created_at: u64,
+    pub id: String,
+    pub market: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub price: f64,
+    pub side: Side,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub size: f64,
+    pub trade_type: TradeType,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Level {
+    pub side: Side,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub price: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub size: f64,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OrderBookUpdateType {
+    #[serde(rename = "s")]
+    Snapshot,
+    #[serde(rename = "d")]
+    Delta,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderBook {
+    pub seq_no: u64,
+    pub market: String,
+    pub last_updated_at: u64,
+    pub update_type: OrderBookUpdateType,
+    pub deletes: Vec<Level>,
+    pub inserts: Vec<Level>,
+    pub updates: Vec<Level>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OrderInstruction {
+    #[serde(alias = "gtc")]
+    GTC,
+    #[serde(alias = "ioc")]
+    IOC,
+    #[serde(alias = "post_only")]
+    POST_ONLY,
+    #[serde(alias = "rpi")]
+    RPI,
+}
+
+wire_names!(OrderInstruction {
+    GTC,
+    IOC,
+    POST_ONLY,
+    RPI
+});
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OrderStatus {
+    #[serde(alias = "new")]
+    NEW,
+    #[serde(alias = "open")]
+    OPEN,
+    #[serde(alias = "closed")]
+    CLOSED,
+    #[serde(alias = "untriggered")]
+    UNTRIGGERED,
+}
+
+wire_names!(OrderStatus {
+    NEW,
+    OPEN,
+    CLOSED,
+    UNTRIGGERED
+});
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum OrderType {
+    #[serde(alias = "market")]
+    MARKET,
+    #[serde(alias = "limit")]
+    LIMIT,
+    #[serde(alias = "stop_market")]
+    STOP_MARKET,
+    #[serde(alias = "stop_limit")]
+    STOP_LIMIT,
+    #[serde(alias = "take_profit_limit")]
+    TAKE_PROFIT_LIMIT,
+    #[serde(alias = "take_profit_market")]
+    TAKE_PROFIT_MARKET,
+    #[serde(alias = "stop_loss_market")]
+    STOP_LOSS_MARKET,
+    #[serde(alias = "stop_loss_limit")]
+    STOP_LOSS_LIMIT,
+}
+
+wire_names!(OrderType {
+    MARKET,
+    LIMIT,
+    STOP_MARKET,
+    STOP_LIMIT,
+    TAKE_PROFIT_LIMIT,
+    TAKE_PROFIT_MARKET,
+    STOP_LOSS_MARKET,
+    STOP_LOSS_LIMIT
+});
+
+impl OrderType {
+    pub fn felt(&self) -> Result<Felt> {
+        match self {
+            OrderType::MARKET => cairo_short_string_to_felt("MARKET"),
+            OrderType::LIMIT => cairo_short_string_to_felt("LIMIT"),
+            OrderType::STOP_MARKET => cairo_short_string_to_felt("STOP_MARKET"),
+            OrderType::STOP_LIMIT => cairo_short_string_to_felt("STOP_LIMIT"),
+            OrderType::TAKE_PROFIT_LIMIT => cairo_short_string_to_felt("TAKE_PROFIT_LIMIT"),
+            OrderType::TAKE_PROFIT_MARKET => cairo_short_string_to_felt("TAKE_PROFIT_MARKET"),
+            OrderType::STOP_LOSS_MARKET => cairo_short_string_to_felt("STOP_LOSS_MARKET"),
+            OrderType::STOP_LOSS_LIMIT => cairo_short_string_to_felt("STOP_LOSS_LIMIT"),
+        }
+    }
+}
+
+/// Encode an ASCII string of at most 31 characters as a Cairo short string
+fn cairo_short_string_to_felt(value: &str) -> Result<Felt> {
+    if !value.is_ascii() || value.len() > 31 {
+        return Err(Error::StarknetError(format!(
+            "{value:?} is not a Cairo short string"
+        )));
+    }
+    Ok(Felt::from_bytes_be_slice(value.as_bytes()))
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OrderFlags {
+    #[serde(alias = "reduce_only")]
+    REDUCE_ONLY,
+    #[serde(alias = "stop_condition_below_trigger")]
+    STOP_CONDITION_BELOW_TRIGGER,
+    #[serde(alias = "stop_condition_above_trigger")]
+    STOP_CONDITION_ABOVE_TRIGGER,
+    #[serde(alias = "interactive")]
+    INTERACTIVE,
+    #[serde(alias = "target_strategy_vwap")]
+    TARGET_STRATEGY_VWAP,
+}
+
+wire_names!(OrderFlags {
+    REDUCE_ONLY,
+    STOP_CONDITION_BELOW_TRIGGER,
+    STOP_CONDITION_ABOVE_TRIGGER,
+    INTERACTIVE,
+    TARGET_STRATEGY_VWAP
+});
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum STPType {
+    #[serde(alias = "expire_maker")]
+    EXPIRE_MAKER,
+    #[serde(alias = "expire_taker")]
+    EXPIRE_TAKER,
+    #[serde(alias = "expire_both")]
+    EXPIRE_BOTH,
+}
+
+wire_names!(STPType {
+    EXPIRE_MAKER,
+    EXPIRE_TAKER,
+    EXPIRE_BOTH
+});
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OrderRequest {
+    pub instruction: OrderInstruction,
+    pub market: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<Decimal>,
+    pub side: Side,
+    pub size: Decimal,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    pub flags: Vec<OrderFlags>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stp: Option<STPType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_price: Option<Decimal>,
+}
+
+impl OrderRequest {
+    /// Attach a signature to the request, e.g. one computed by an external signer over
+    /// `order_message_hash`
+    ///
+    /// `signature_timestamp` is the timestamp in milliseconds the signed message was built with.
+    pub fn into_order(self, signature: [Felt; 2], signature_timestamp: u128) -> Order {
+        Order {
+            instruction: self.instruction,
+            market: self.market,
+            price: self.price,
+            side: self.side,
+            size: self.size,
+            order_type: self.order_type,
+            client_id: self.client_id,
+            flags: self.flags,
+            recv_window: self.recv_window,
+            stp: self.stp,
+            trigger_price: self.trigger_price,
+            signature,
+            signature_timestamp,
+        }
+    }
+}
+
+/// Format a signature as the API expects it in order payloads and auth headers, a JSON array
+/// of the decimal `r` and `s` in a string: `["r","s"]`
+pub fn format_signature(signature: &[Felt; 2]) -> String {
+    format!(
+        r#"["{}","{}"]"#,
+        signature[0].to_bigint(),
+        signature[1].to_bigint()
+    )
+}
+
+/// Parse a signature formatted by `format_signature`, with decimal or `0x` hex components
+pub fn parse_signature(value: &str) -> Result<[Felt; 2]> {
+    let error = || Error::TypeConversionError(format!("Invalid signature {value:?}"));
+    let components = value
+        .trim()
+        .strip_prefix('[')
+        .and_then(|value| value.strip_suffix(']'))
+        .ok_or_else(error)?;
+    let mut felts = components.split(',').map(|component| {
+        let component = component.trim();
+        let component = component
+            .strip_prefix('"')
+            .and_then(|component| component.strip_suffix('"'))
+            .unwrap_or(component);
+        Felt::from_str(component).map_err(|_| error())
+    });
+    match (felts.next(), felts.next(), felts.next()) {
+        (Some(r), Some(s), None) => Ok([r?, s?]),
+        _ => Err(error()),
+    }
+}
+
+fn serialize_signature_as_string<S>(
+    value: &[Felt; 2],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format_signature(value))
+}
+
+/// Read a signature in the format written by `serialize_signature_as_string`, or as a plain
+/// array of felts
+fn deserialize_signature<'de, D>(deserializer: D) -> std::result::Result<[Felt; 2], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SignatureRepr {
+        Formatted(String),
+        Felts([Felt; 2]),
+    }
+
+    match SignatureRepr::deserialize(deserializer)? {
+        SignatureRepr::Formatted(value) => {
+            parse_signature(&value).map_err(serde::de::Error::custom)
+        }
+        SignatureRepr::Felts(signature) => Ok(signature),
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Order {
+    pub instruction: OrderInstruction,
+    pub market: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<Decimal>,
+    pub side: Side,
+    #[serde(
+        serialize_with = "serialize_signature_as_string",
+        deserialize_with = "deserialize_signature"
+    )]
+    pub signature: [Felt; 2],
+    pub signature_timestamp: u128,
+    pub size: Decimal,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    pub flags: Vec<OrderFlags>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recv_window: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stp: Option<STPType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_price: Option<Decimal>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ModifyOrderRequest {
+    pub id: String,
+    pub market: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<Decimal>,
+    pub side: Side,
+    pub size: Decimal,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+}
+
+impl ModifyOrderRequest {
+    /// Attach a signature to the modification, see `OrderRequest::into_order`
+    pub fn into_modify_order(self, signature: [Felt; 2], signature_timestamp: u128) -> ModifyOrder {
+        ModifyOrder {
+            id: self.id,
+            market: self.market,
+            price: self.price,
+            side: self.side,
+            signature,
+            signature_timestamp,
+            size: self.size,
+            order_type: self.order_type,
+        }
+    }
+}
+
+/// Rejection of one order of a batch
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BatchOrderError {
+    pub error: Option<String>,
+    pub message: String,
+}
+
+/// Outcome of a batch order creation
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BatchOrdersResponse {
+    /// Orders which were accepted
+    #[serde(default)]
+    pub orders: Vec<OrderUpdate>,
+    /// Reasons the other orders were rejected
+    #[serde(default)]
+    pub errors: Vec<BatchOrderError>,
+}
+
+/// Outcome of an order modification
+///
+/// Paradex modifies an order in place when it can, and otherwise cancels it and places a
+/// replacement with a new id. Either way the client id of the order is kept.
+#[derive(Clone, Debug)]
+pub enum ModifyOrderResult {
+    /// The order kept its id
+    Modified(OrderUpdate),
+    /// The order was cancelled and replaced by a new order
+    Replaced {
+        /// Id of the cancelled order
+        replaced_id: String,
+        order: OrderUpdate,
+    },
+}
+
+impl ModifyOrderResult {
+    #[doc(hidden)]
+    pub fn new(requested_id: &str, order: OrderUpdate) -> Self {
+        if order.id == requested_id {
+            ModifyOrderResult::Modified(order)
+        } else {
+            ModifyOrderResult::Replaced {
+                replaced_id: requested_id.to_string(),
+                order,
+            }
+        }
+    }
+
+    /// The order after the modification
+    pub fn order(&self) -> &OrderUpdate {
+        match self {
+            ModifyOrderResult::Modified(order) | ModifyOrderResult::Replaced { order, .. } => order,
+        }
+    }
+
+    pub fn into_order(self) -> OrderUpdate {
+        match self {
+            ModifyOrderResult::Modified(order) | ModifyOrderResult::Replaced { order, .. } => order,
+        }
+    }
+
+    pub fn is_replaced(&self) -> bool {
+        matches!(self, ModifyOrderResult::Replaced { .. })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ModifyOrder {
+    pub id: String,
+    pub market: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<Decimal>,
+    pub side: Side,
+    #[serde(
+        serialize_with = "serialize_signature_as_string",
+        deserialize_with = "deserialize_signature"
+    )]
+    pub signature: [Felt; 2],
+    pub signature_timestamp: u128,
+    pub size: Decimal,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderUpdate {
+    pub account: String,
+    pub cancel_reason: String,
+    pub client_id: String,
+    pub created_at: u64,
+    pub id: String,
+    pub instruction: OrderInstruction,
+    pub last_updated_at: u64,
+    pub market: String,
+    pub price: Option<Decimal>,
+    pub remaining_size: Decimal,
+    pub side: Side,
+    pub size: Decimal,
+    pub status: OrderStatus,
+    pub timestamp: u64,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    pub seq_no: u64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub avg_fill_price: f64,
+    pub received_at: u64,
+    pub published_at: u64,
+    pub flags: Vec<OrderFlags>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_price: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stp: Option<STPType>,
+}
+
+impl OrderUpdate {
+    /// Whether this is a conditional order still waiting for its trigger price
+    ///
+    /// Untriggered orders are not on the book, so they neither consume margin nor can be
+    /// filled until the trigger fires.
+    pub fn is_untriggered(&self) -> bool {
+        self.status == OrderStatus::UNTRIGGERED
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderUpdates {
+    pub results: Vec<OrderUpdate>,
+}
+
+/// Open orders split by whether they rest on the book or wait for a trigger
+#[derive(Clone, Debug, Default)]
+pub struct OpenOrders {
+    /// Orders on the book, which can be filled at any time
+    pub resting: Vec<OrderUpdate>,
+    /// Untriggered conditional orders, e.g. stop losses and take profits
+    pub conditional: Vec<OrderUpdate>,
+}
+
+impl From<OrderUpdates> for OpenOrders {
+    fn from(orders: OrderUpdates) -> Self {
+        let (conditional, resting) = orders
+            .results
+            .into_iter()
+            .partition(OrderUpdate::is_untriggered);
+        Self {
+            resting,
+            conditional,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum FillLiquidity {
+    #[serde(alias = "taker")]
+    TAKER,
+    #[serde(alias = "maker")]
+    MAKER,
+}
+
+wire_names!(FillLiquidity { TAKER, MAKER });
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum FillType {
+    #[serde(alias = "fill")]
+    FILL,
+    #[serde(alias = "liquidation")]
+    LIQUIDATION,
+}
+
+wire_names!(FillType { FILL, LIQUIDATION });
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Fill {
+    pub client_id: String,
+    pub created_at: u64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub fee: f64,
+    pub fee_currency: String,
+    pub id: String,
+    pub liquidity: FillLiquidity,
+    pub market: String,
+    pub order_id: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub price: f64,
+    pub side: Side,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub size: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub remaining_size: f64,
+    //pub seq_no : u64, //in paradex documentation, but does not appear to be sent.
+    pub fill_type: FillType,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub realized_pnl: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TransferStatus {
+    PENDING,
+    AVAILABLE,
+    COMPLETED,
+    FAILED,
+}
+
+wire_names!(TransferStatus {
+    PENDING,
+    AVAILABLE,
+    COMPLETED,
+    FAILED
+});
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TransferBridge {
+    STARKGATE,
+    LAYERSWAP,
+    RHINOFI,
+    HYPERLANE,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TransferDirection {
+    IN,
+    OUT,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TransferKind {
+    DEPOSIT,
+    WITHDRAWAL,
+    UNWINDING,
+    VAULT_DEPOSIT,
+    VAULT_WITHDRAWAL,
+    AUTO_WITHDRAWAL,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Transfer {
+    pub account: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub amount: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub auto_withdrawal_fee: f64,
+    pub bridge: TransferBridge,
+    pub counterparty: String,
+    pub created_at: u64,
+    pub direction: TransferDirection,
+    pub external_account: String,
+    pub external_chain: String,
+    pub external_txn_hash: String,
+    pub failure_reason: String,
+    pub id: String,
+    pub kind: TransferKind,
+    pub last_updated_at: u64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub socialized_loss_factor: f64,
+    pub status: TransferStatus,
+    pub token: String,
+    pub txn_hash: String,
+    pub vault_address: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub vault_unwind_completion_percentage: f64,
+}
+
+/// Performance summary of a vault
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VaultSummary {
+    pub address: String,
+    /// Price of one vault share (vtoken) in USDC
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub vtoken_price: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub vtoken_supply: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub tvl: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub total_pnl: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub total_roi: f64,
+    /// Deposits net of withdrawals in USDC
+    #[serde(
+        default,
+        deserialize_with = "deserialize_optional_string_to_f64",
+        serialize_with = "serialize_optional_f64_as_string"
+    )]
+    pub net_deposits: Option<f64>,
+    #[serde(default)]
+    pub num_depositors: u64,
+}
+
+/// Holdings of the authenticated account in a vault
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VaultAccountSummary {
+    pub address: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub deposited_amount: f64,
+    /// Vault shares held by the account
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub vtoken_amount: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub total_pnl: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub total_roi: f64,
+    pub created_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultHistoryType {
+    Pnl,
+    Price,
+    Roi,
+    Tvl,
+}
+
+/// Time series of a vault metric, `data[i]` was sampled at `timestamps[i]`
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VaultHistory {
+    pub address: String,
+    #[serde_as(as = "Vec<PickFirst<(_, DisplayFromStr)>>")]
+    pub data: Vec<f64>,
+    pub timestamps: Vec<u64>,
+}
+
+impl VaultHistory {
+    /// Samples as `(timestamp, value)` pairs in chronological order
+    pub fn points(&self) -> impl Iterator<Item = (u64, f64)> + '_ {
+        self.timestamps
+            .iter()
+            .copied()
+            .zip(self.data.iter().copied())
+    }
+}
+
+impl Transfer {
+    /// The socialized loss applied to this transfer, if any
+    pub fn socialized_loss(&self) -> Option<SocializedLoss> {
+        (self.socialized_loss_factor > 0.).then(|| SocializedLoss {
+            transfer_id: self.id.clone(),
+            kind: self.kind.clone(),
+            token: self.token.clone(),
+            factor: self.socialized_loss_factor,
+            amount: self.amount,
+            loss: self.amount * self.socialized_loss_factor,
+            created_at: self.created_at,
+        })
+    }
+}
+
+/// Haircut applied to a transfer when the insurance fund could not cover losses on the venue
+#[derive(Clone, Debug, PartialEq)]
+pub struct SocializedLoss {
+    pub transfer_id: String,
+    pub kind: TransferKind,
+    pub token: String,
+    /// Share of the transfer amount taken, between 0 and 1
+    pub factor: f64,
+    /// Transfer amount the factor applies to
+    pub amount: f64,
+    /// Amount lost to the haircut, `amount * factor`
+    pub loss: f64,
+    pub created_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FundingPayment {
+    pub id: String,
+    pub market: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub payment: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub index: f64,
+    pub fill_id: String,
+    pub created_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FundingData {
+    pub market: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub funding_index: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub funding_premium: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub funding_rate: f64,
+    pub created_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AccountStatus {
+    ACTIVE,
+    LIQUIDATION,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountInformation {
+    pub account: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub account_value: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub free_collateral: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub initial_margin_requirement: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub maintenance_margin_requirement: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub margin_cushion: f64,
+    pub seq_no: u64,
+    pub settlement_asset: String,
+    pub status: AccountStatus,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub total_collateral: f64,
+    pub updated_at: u64,
+}
+
+/// Sub-account of the authenticated main account
+///
+/// Sub-accounts are separate Paradex accounts with their own margin and positions, signed for
+/// with the Stark key of their parent account.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SubAccount {
+    pub account: String,
+    pub parent_account: String,
+    pub public_key: String,
+    #[serde(default)]
+    pub created_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum MarginType {
+    #[serde(alias = "cross")]
+    CROSS,
+    #[serde(alias = "isolated")]
+    ISOLATED,
+}
+
+wire_names!(MarginType { CROSS, ISOLATED });
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MarginConfig {
+    pub market: String,
+    pub leverage: u64,
+    pub margin_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isolated_margin_leverage: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountMarginConfigurations {
+    pub account: String,
+    pub configs: Vec<MarginConfig>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountMarginUpdate {
+    pub leverage: u64,
+    pub margin_type: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountMarginUpdateResponse {
+    pub account: String,
+    pub leverage: u64,
+    pub margin_type: String,
+    pub market: String,
+}
+
+/// Public profile and settings of the authenticated account
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountProfile {
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub is_username_private: bool,
+    /// Maximum slippage of market orders from the mark price by market symbol, as a fraction
+    #[serde(default)]
+    pub market_max_slippage: std::collections::HashMap<String, Decimal>,
+    /// Code others can sign up with to be referred by this account
+    #[serde(default)]
+    pub referral_code: String,
+    /// Referral code this account signed up with
+    #[serde(default)]
+    pub referred_by: String,
+    /// Terms of the referral program of `referred_by`, including the fee discount
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub referral: Option<ReferralConfig>,
+}
+
+/// Terms of a referral program
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReferralConfig {
+    pub name: String,
+    #[serde(default)]
+    pub referral_type: String,
+    /// Share of the referred accounts' fees paid to the referrer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commission_rate: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commission_volume_cap: Option<Decimal>,
+    /// Discount on the trading fees of the referred account
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discount_rate: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum_volume: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub points_bonus_rate: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub points_bonus_volume_cap: Option<Decimal>,
+}
+
+/// New username of the account, see `Client::update_username`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UsernameUpdate {
+    pub username: String,
+    pub is_username_private: bool,
+}
+
+/// New maximum slippage of market orders in a market, see `Client::update_max_slippage`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MaxSlippageUpdate {
+    pub market: String,
+    /// Fraction of the mark price, e.g. `0.05` for 5%
+    pub max_slippage: Decimal,
+}
+
+/// Account details including the fee rates it trades at
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountInfo {
+    pub account: String,
+    #[serde(default)]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_account: Option<String>,
+    #[serde(default)]
+    pub public_key: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub created_at: u64,
+    /// Fee rates of the account's tier by fee name, e.g. `maker_fee` and `taker_fee`
+    #[serde(default)]
+    pub fees: std::collections::HashMap<String, Decimal>,
+}
+
+/// Rewards earned from an account referred by the authenticated account
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReferralSummary {
+    /// Address of the referred account
+    pub address: String,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub referral_code: String,
+    #[serde(default)]
+    pub referral_rewards: Decimal,
+    #[serde(default)]
+    pub volume_traded: Decimal,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BalanceEvent {
+    pub fill_id: String,
+    pub market: String,
+    pub status: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub settlement_asset_balance_before: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub settlement_asset_balance_after: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub settlement_asset_price: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub funding_index: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub realized_pnl: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub fees: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub realized_funding: f64,
+    pub created_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Balance {
+    pub token: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub size: f64,
+    pub last_updated_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Balances {
+    pub results: Vec<Balance>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PositionStatus {
+    #[serde(alias = "open")]
+    OPEN,
+    #[serde(alias = "closed")]
+    CLOSED,
+}
+
+wire_names!(PositionStatus { OPEN, CLOSED });
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PositionSide {
+    #[serde(alias = "short")]
+    SHORT,
+    #[serde(alias = "long")]
+    LONG,
+}
+
+wire_names!(PositionSide { SHORT, LONG });
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Position {
+    pub account: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub average_entry_price: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub average_entry_price_usd: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub average_exit_price: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub cached_funding_index: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub cost: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub cost_usd: f64,
+    pub id: String,
+    pub last_fill_id: String,
+    pub last_updated_at: u64,
+    pub leverage: String,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub liquidation_price: f64,
+    pub market: String,
+    pub seq_no: u64,
+    pub side: PositionSide,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub size: f64,
+    pub status: PositionStatus,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub realized_positional_funding_pnl: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub realized_positional_pnl: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub unrealized_funding_pnl: f64,
+    #[serde(
+        serialize_with = "serialize_f64_as_string",
+        deserialize_with = "deserialize_string_to_f64"
+    )]
+    pub unrealized_pnl: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Positions {
+    pub results: Vec<Position>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CancelByMarketResponse {
+    pub market: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[doc(hidden)]
+pub struct RestError {
+    pub error: Option<String>,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CursorResult<T> {
+    pub next: Option<String>,
+    pub prev: Option<String>,
+    pub results: Vec<T>,
+}
+
+/// Query parameters shared by the paginated list endpoints
+///
+/// Every list endpoint takes a subset of these and ignores the others. The per-endpoint filter
+/// names, e.g. `FillsFilter` or `TransfersFilter`, are aliases of it, so a new list endpoint
+/// takes ListParams instead of growing another parameter struct.
+#[derive(Clone, Debug, Default, Serialize, PartialEq, Eq)]
+pub struct ListParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side: Option<Side>,
+    /// Status in the endpoint's own status names, set with `order_status` or `transfer_status`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub order_type: Option<OrderType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// Start time in milliseconds since the epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_at: Option<i64>,
+    /// End time in milliseconds since the epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_at: Option<i64>,
+    /// Number of results fetched per page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u32>,
+    /// Cursor of the page to start from, as returned in `CursorResult::next`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+impl ListParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn market(mut self, market: impl Into<String>) -> Self {
+        self.market = Some(market.into());
+        self
+    }
+
+    pub fn side(mut self, side: Side) -> Self {
+        self.side = Some(side);
+        self
+    }
+
+    /// Filter orders on their status
+    pub fn order_status(mut self, status: OrderStatus) -> Self {
+        self.status = Some(status.to_string());
+        self
+    }
+
+    /// Filter transfers on their status
+    pub fn transfer_status(mut self, status: TransferStatus) -> Self {
+        self.status = Some(status.to_string());
+        self
+    }
+
+    pub fn order_type(mut self, order_type: OrderType) -> Self {
+        self.order_type = Some(order_type);
+        self
+    }
+
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    pub fn start(mut self, start: chrono::DateTime<chrono::Utc>) -> Self {
+        self.start_at = Some(start.timestamp_millis());
+        self
+    }
+
+    pub fn end(mut self, end: chrono::DateTime<chrono::Utc>) -> Self {
+        self.end_at = Some(end.timestamp_millis());
+        self
+    }
+
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    pub fn cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+}
+
+/// Query filters for the fills history, by market and time range
+pub type FillsFilter = ListParams;
+
+/// Query filters for the orders history, by market, side, status, type, client id and time
+/// range
+pub type OrdersHistoryFilter = ListParams;
+
+/// Orders history filters under the naming used by the other request parameter structs
+pub type OrdersHistoryParams = ListParams;
+
+/// Query filters for the funding payments history, by market and time range
+pub type FundingPaymentsFilter = ListParams;
+
+/// Query filters for the transfers history, by status and time range
+pub type TransfersFilter = ListParams;
+
+/// Query filters for the public trade tape, by market and time range
+pub type TradesFilter = ListParams;
+
+/// Query filters for the funding data history, by market, which is required, and time range
+pub type FundingDataFilter = ListParams;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wire_names() {
+        assert_eq!(Side::BUY.to_string(), "BUY");
+        assert_eq!("sell".parse::<Side>().unwrap(), Side::SELL);
+        assert_eq!(
+            "Post_Only".parse::<OrderInstruction>().unwrap(),
+            OrderInstruction::POST_ONLY
+        );
+        assert_eq!(OrderType::STOP_LIMIT.as_str(), "STOP_LIMIT");
+        assert!("hold".parse::<Side>().is_err());
+
+        let side: Side = serde_json::from_str(r#""buy""#).unwrap();
+        assert_eq!(side, Side::BUY);
+        assert_eq!(serde_json::to_string(&side).unwrap(), r#""BUY""#);
+        let order_type: OrderType = serde_json::from_str(r#""take_profit_limit""#).unwrap();
+        assert_eq!(order_type, OrderType::TAKE_PROFIT_LIMIT);
+    }
+
+    #[test]
+    fn signature_format() {
+        let signature = [Felt::from(12), Felt::from_hex_unchecked("0xff")];
+        let formatted = format_signature(&signature);
+        assert_eq!(formatted, r#"["12","255"]"#);
+        assert_eq!(parse_signature(&formatted).unwrap(), signature);
+        assert_eq!(parse_signature(r#"[ "0xc", "255" ]"#).unwrap(), signature);
+        assert!(parse_signature(r#"["12"]"#).is_err());
+        assert!(parse_signature(r#"["12","255","1"]"#).is_err());
+        assert!(parse_signature("12,255").is_err());
+
+        let order = OrderRequest {
+            instruction: OrderInstruction::GTC,
+            market: "BTC-USD-PERP".into(),
+            price: None,
+            side: Side::BUY,
+            size: Decimal::ONE,
+            order_type: OrderType::MARKET,
+            client_id: None,
+            flags: Vec::new(),
+            recv_window: None,
+            stp: None,
+            trigger_price: None,
+        }
+        .into_order(signature, 1000);
+        let payload = serde_json::to_string(&order).unwrap();
+        assert!(payload.contains(r#""signature":"[\"12\",\"255\"]""#));
+        assert_eq!(serde_json::from_str::<Order>(&payload).unwrap(), order);
+    }
+
+    #[test]
+    fn rate_error() {
+        let text = r#"{"message":"rate limit exceeded"}"#;
+        let error = serde_json::from_str::<RestError>(text).unwrap();
+        assert_eq!(error.message, "rate limit exceeded");
+        assert!(error.error.is_none());
+    }
+}
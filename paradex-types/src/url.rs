@@ -0,0 +1,38 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum URL {
+    Production,
+    Testnet,
+    /// Self-hosted endpoints, e.g. a proxy, regional gateway or mock server
+    ///
+    /// `rest` is the base URL the `/v1/...` paths are appended to, `ws` the full websocket
+    /// endpoint including its `/v1` path.
+    Custom {
+        rest: String,
+        ws: String,
+    },
+}
+
+impl URL {
+    pub fn custom(rest: impl Into<String>, ws: impl Into<String>) -> Self {
+        URL::Custom {
+            rest: rest.into(),
+            ws: ws.into(),
+        }
+    }
+
+    pub fn rest(&self) -> &str {
+        match self {
+            URL::Production => "https://api.prod.paradex.trade",
+            URL::Testnet => "https://api.testnet.paradex.trade",
+            URL::Custom { rest, .. } => rest.trim_end_matches('/'),
+        }
+    }
+
+    pub fn websocket(&self) -> &str {
+        match self {
+            URL::Production => "wss://ws.api.prod.paradex.trade/v1",
+            URL::Testnet => "wss://ws.api.testnet.paradex.trade/v1",
+            URL::Custom { ws, .. } => ws,
+        }
+    }
+}
@@ -0,0 +1,308 @@
+//! Cached account state kept current from the private websocket channels.
+//!
+//! [`AccountState`] bootstraps from the rest account, positions and balance endpoints and then
+//! applies account, position and balance event updates. Every update is applied only if it is
+//! newer than what is cached, so rest responses and websocket updates can arrive in any order.
+//! Monitors built on the account, such as `MarginMonitor`, follow the applied updates with
+//! [`AccountState::on_change`].
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use log::warn;
+
+use crate::error::Result;
+use crate::exchange::Exchange;
+use crate::listeners::Listeners;
+use crate::rest::Client;
+use crate::structs::{AccountInformation, Balance, BalanceEvent, Position, PositionStatus};
+use crate::ws::{
+    AccountSubscription, BalanceEventsSubscription, ChannelEvent, Identifier, PositionSubscription,
+};
+
+/// Settlement asset assumed for balance events received before the account information
+const DEFAULT_SETTLEMENT_ASSET: &str = "USDC";
+
+/// Consistent copy of the cached account state
+#[derive(Debug, Clone, Default)]
+pub struct AccountSnapshot {
+    pub account: Option<AccountInformation>,
+    /// Latest update of every position by market, including closed positions
+    pub positions: HashMap<String, Position>,
+    /// Balances by token
+    pub balances: HashMap<String, Balance>,
+}
+
+impl AccountSnapshot {
+    pub fn account_value(&self) -> Option<f64> {
+        self.account.as_ref().map(|account| account.account_value)
+    }
+
+    pub fn free_collateral(&self) -> Option<f64> {
+        self.account.as_ref().map(|account| account.free_collateral)
+    }
+
+    /// Open position in a market
+    pub fn position(&self, market: &str) -> Option<&Position> {
+        self.positions
+            .get(market)
+            .filter(|position| position.status == PositionStatus::OPEN)
+    }
+
+    pub fn open_positions(&self) -> impl Iterator<Item = &Position> {
+        self.positions
+            .values()
+            .filter(|position| position.status == PositionStatus::OPEN)
+    }
+
+    pub fn balance(&self, token: &str) -> Option<f64> {
+        self.balances.get(token).map(|balance| balance.size)
+    }
+
+    fn settlement_asset(&self) -> &str {
+        self.account
+            .as_ref()
+            .map_or(DEFAULT_SETTLEMENT_ASSET, |account| {
+                account.settlement_asset.as_str()
+            })
+    }
+}
+
+/// Account state cache shared between clones
+#[derive(Clone, Default)]
+pub struct AccountState {
+    state: Arc<RwLock<AccountSnapshot>>,
+    listeners: Listeners<AccountSnapshot>,
+    /// Held while an update is applied and notified, so listeners see the updates in order
+    updating: Arc<Mutex<()>>,
+}
+
+impl AccountState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy of the current state
+    pub fn snapshot(&self) -> AccountSnapshot {
+        self.state.read().unwrap().clone()
+    }
+
+    /// Read the current state without copying it
+    pub fn read<R>(&self, f: impl FnOnce(&AccountSnapshot) -> R) -> R {
+        f(&self.state.read().unwrap())
+    }
+
+    /// Call `callback` with the current state, then with the new state after every applied
+    /// update
+    ///
+    /// Updates ignored for being older than the cached state do not call it. The callback must
+    /// not apply updates to this state itself.
+    pub fn on_change(&self, callback: impl Fn(&AccountSnapshot) + Send + Sync + 'static) {
+        let _updating = self.updating.lock().unwrap();
+        callback(&self.snapshot());
+        self.listeners.push(callback);
+    }
+
+    /// Load the account information, positions and balances from the rest API
+    ///
+    /// Call after `attach`, so no update is missed between the rest responses and the first
+    /// websocket messages.
+    ///
+    /// # Errors
+    ///
+    /// If any of the requests fails
+    pub async fn bootstrap(&self, client: &Client) -> Result<()> {
+        let (account, positions, balances) = tokio::try_join!(
+            client.account_information(),
+            client.positions(),
+            client.balance()
+        )?;
+        self.on_account(&account);
+        for position in &positions.results {
+            self.on_position(position);
+        }
+        for balance in &balances.results {
+            self.on_balance(balance);
+        }
+        Ok(())
+    }
+
+    /// Feed the account, positions and balance events channels into the cache
+    ///
+    /// # Returns
+    ///
+    /// The identifiers of the subscriptions
+    ///
+    /// # Errors
+    ///
+    /// If any of the subscriptions cannot be made
+    pub async fn attach<E: Exchange>(&self, exchange: &E) -> Result<Vec<Identifier>> {
+        let state = self.clone();
+        let account = exchange
+            .subscribe_typed(AccountSubscription, move |event| match event {
+                ChannelEvent::Data(account) => state.on_account(account),
+                ChannelEvent::Error(e) => warn!("Account subscription error {e:?}"),
+                _ => {}
+            })
+            .await?;
+        let state = self.clone();
+        let positions = exchange
+            .subscribe_typed(PositionSubscription, move |event| match event {
+                ChannelEvent::Data(position) => state.on_position(position),
+                ChannelEvent::Error(e) => warn!("Positions subscription error {e:?}"),
+                _ => {}
+            })
+            .await?;
+        let state = self.clone();
+        let balances = exchange
+            .subscribe_typed(BalanceEventsSubscription, move |event| match event {
+                ChannelEvent::Data(balance_event) => state.on_balance_event(balance_event),
+                ChannelEvent::Error(e) => warn!("Balance events subscription error {e:?}"),
+                _ => {}
+            })
+            .await?;
+        Ok(vec![account, positions, balances])
+    }
+
+    /// Apply an account update unless a newer one was applied already
+    pub fn on_account(&self, account: &AccountInformation) {
+        self.update(|state| {
+            let newer = state
+                .account
+                .as_ref()
+                .is_none_or(|current| current.seq_no < account.seq_no);
+            if newer {
+                state.account = Some(account.clone());
+            }
+            newer
+        });
+    }
+
+    /// Apply a position update unless a newer one was applied already
+    pub fn on_position(&self, position: &Position) {
+        self.update(|state| {
+            let newer = state
+                .positions
+                .get(&position.market)
+                .is_none_or(|current| current.seq_no < position.seq_no);
+            if newer {
+                state
+                    .positions
+                    .insert(position.market.clone(), position.clone());
+            }
+            newer
+        });
+    }
+
+    /// Apply a balance unless the cached balance of the token was updated later
+    pub fn on_balance(&self, balance: &Balance) {
+        self.update(|state| {
+            let newer = state
+                .balances
+                .get(&balance.token)
+                .is_none_or(|current| current.last_updated_at < balance.last_updated_at);
+            if newer {
+                state
+                    .balances
+                    .insert(balance.token.clone(), balance.clone());
+            }
+            newer
+        });
+    }
+
+    /// Apply the settlement asset balance after a balance event, unless the cached balance
+    /// was updated later
+    pub fn on_balance_event(&self, event: &BalanceEvent) {
+        let token = self.read(|state| state.settlement_asset().to_string());
+        self.on_balance(&Balance {
+            token,
+            size: event.settlement_asset_balance_after,
+            last_updated_at: event.created_at,
+        });
+    }
+
+    /// Apply `f` to the state, notifying the listeners with the new state if it returns true
+    fn update(&self, f: impl FnOnce(&mut AccountSnapshot) -> bool) {
+        let _updating = self.updating.lock().unwrap();
+        let snapshot = {
+            let mut state = self.state.write().unwrap();
+            if !f(&mut state) || self.listeners.is_empty() {
+                return;
+            }
+            state.clone()
+        };
+        self.listeners.notify(&snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{Balances, Positions};
+
+    #[test]
+    fn updates_in_any_order() {
+        let state = AccountState::new();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = changes.clone();
+        state.on_change(move |snapshot| recorded.lock().unwrap().push(snapshot.account_value()));
+        let mut account: AccountInformation =
+            serde_json::from_str(include_str!("../tests/fixtures/account_information.json"))
+                .unwrap();
+        let mut position =
+            serde_json::from_str::<Positions>(include_str!("../tests/fixtures/positions.json"))
+                .unwrap()
+                .results
+                .remove(0);
+        let balance =
+            serde_json::from_str::<Balances>(include_str!("../tests/fixtures/balances.json"))
+                .unwrap()
+                .results
+                .remove(0);
+        let balance_event: BalanceEvent =
+            serde_json::from_str(include_str!("../tests/fixtures/balance_event.json")).unwrap();
+
+        // A websocket update arrives before the older rest response
+        let mut newer = account.clone();
+        newer.seq_no += 1;
+        newer.account_value = 1.;
+        state.on_account(&newer);
+        state.on_account(&account);
+        assert_eq!(state.snapshot().account_value(), Some(1.));
+        account.seq_no += 2;
+        state.on_account(&account);
+        assert_eq!(state.read(|s| s.free_collateral()), Some(73276.47229774));
+
+        state.on_position(&position);
+        position.seq_no -= 1;
+        position.size = 5.;
+        state.on_position(&position);
+        assert_eq!(
+            state.read(|s| s.position("BTC-USD-PERP").unwrap().size),
+            -0.345
+        );
+
+        // The balance event predates the rest balance, so it is ignored
+        state.on_balance(&balance);
+        state.on_balance_event(&balance_event);
+        assert_eq!(state.read(|s| s.balance("USDC")), Some(123003.62));
+        let mut later = balance_event.clone();
+        later.created_at = 1681462770115;
+        state.on_balance_event(&later);
+        assert_eq!(state.read(|s| s.balance("USDC")), Some(1012.4));
+
+        // Listeners see the state on registration and after every applied update only
+        let account_value = account.account_value;
+        assert_eq!(
+            *changes.lock().unwrap(),
+            [
+                None,
+                Some(1.),
+                Some(account_value),
+                Some(account_value),
+                Some(account_value),
+                Some(account_value)
+            ]
+        );
+    }
+}
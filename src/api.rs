@@ -0,0 +1,81 @@
+//! Order entry and query operations shared by live and simulated clients.
+//!
+//! Strategy code written against [`ParadexApi`] runs unchanged on the rest [`Client`] or on a
+//! [`SimClient`](crate::sim::SimClient) filling orders locally against streamed market data.
+//! The methods mirror those of the same name on [`Client`]. Venues which also stream market
+//! data implement [`Exchange`](crate::exchange::Exchange) on top of it.
+
+use std::future::Future;
+
+use crate::error::Result;
+use crate::rest::Client;
+use crate::structs::{
+    BBO, CancelByMarketResponse, MarketSummaryStatic, ModifyOrderRequest, ModifyOrderResult,
+    OrderRequest, OrderUpdate,
+};
+
+/// Order placement, cancellation and queries of a Paradex account
+pub trait ParadexApi: Clone + Send + Sync + 'static {
+    fn create_order(
+        &self,
+        order_request: OrderRequest,
+    ) -> impl Future<Output = Result<OrderUpdate>> + Send;
+
+    fn modify_order(
+        &self,
+        modify_order_request: ModifyOrderRequest,
+    ) -> impl Future<Output = Result<ModifyOrderResult>> + Send;
+
+    fn cancel_order(&self, order_id: String) -> impl Future<Output = Result<()>> + Send;
+
+    /// Cancel all open orders, returning the ids of the cancelled orders
+    fn cancel_all_orders(&self) -> impl Future<Output = Result<Vec<String>>> + Send;
+
+    fn cancel_all_orders_for_market(
+        &self,
+        market: String,
+    ) -> impl Future<Output = Result<CancelByMarketResponse>> + Send;
+
+    fn open_orders(&self) -> impl Future<Output = Result<Vec<OrderUpdate>>> + Send;
+
+    fn bbo(&self, market_symbol: String) -> impl Future<Output = Result<BBO>> + Send;
+
+    fn markets(&self) -> impl Future<Output = Result<Vec<MarketSummaryStatic>>> + Send;
+}
+
+impl ParadexApi for Client {
+    async fn create_order(&self, order_request: OrderRequest) -> Result<OrderUpdate> {
+        Client::create_order(self, order_request).await
+    }
+
+    async fn modify_order(
+        &self,
+        modify_order_request: ModifyOrderRequest,
+    ) -> Result<ModifyOrderResult> {
+        Client::modify_order(self, modify_order_request).await
+    }
+
+    async fn cancel_order(&self, order_id: String) -> Result<()> {
+        Client::cancel_order(self, order_id).await
+    }
+
+    async fn cancel_all_orders(&self) -> Result<Vec<String>> {
+        Client::cancel_all_orders(self).await
+    }
+
+    async fn cancel_all_orders_for_market(&self, market: String) -> Result<CancelByMarketResponse> {
+        Client::cancel_all_orders_for_market(self, market).await
+    }
+
+    async fn open_orders(&self) -> Result<Vec<OrderUpdate>> {
+        Ok(Client::open_orders(self).await?.results)
+    }
+
+    async fn bbo(&self, market_symbol: String) -> Result<BBO> {
+        Client::bbo(self, market_symbol).await
+    }
+
+    async fn markets(&self) -> Result<Vec<MarketSummaryStatic>> {
+        Client::markets(self).await
+    }
+}
@@ -0,0 +1,283 @@
+//! Fill processing that resumes where it left off across restarts.
+//!
+//! [`FillProcessor`] persists the position of the last processed fill in a [`StateStore`].
+//! On start, and after every websocket reconnect, it backfills the fills received since then
+//! over REST while buffering the fills channel, then drains the buffer and continues with the
+//! live stream. Fills at or before the stored offset are dropped, so each fill is handed to
+//! the callback once.
+
+use std::sync::{Arc, Mutex};
+
+use chrono::{TimeZone, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::error::{Error, Result};
+use crate::exchange::Exchange;
+use crate::rest::Client;
+use crate::store::StateStore;
+use crate::structs::{Fill, FillsFilter};
+use crate::ws::{ChannelEvent, FillsSubscription, Identifier};
+
+/// Position of the last processed fill
+///
+/// Fills are ordered by creation time. Several fills can share a creation time, so the ids of
+/// the fills processed at the latest time are kept as well.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FillOffset {
+    /// Creation time of the latest processed fill in milliseconds
+    pub created_at: u64,
+    /// Ids of the processed fills created at `created_at`
+    pub ids: Vec<String>,
+}
+
+impl FillOffset {
+    /// Whether the fill is at or before this offset
+    pub fn is_processed(&self, fill: &Fill) -> bool {
+        fill.created_at < self.created_at
+            || (fill.created_at == self.created_at && self.ids.contains(&fill.id))
+    }
+
+    /// Move the offset past a fill
+    pub fn advance(&mut self, fill: &Fill) {
+        if fill.created_at > self.created_at {
+            self.created_at = fill.created_at;
+            self.ids.clear();
+        }
+        if fill.created_at == self.created_at {
+            self.ids.push(fill.id.clone());
+        }
+    }
+}
+
+/// Namespace of the fill offsets in the state store, keyed by account
+pub const FILL_OFFSET_NAMESPACE: &str = "fill_offset";
+
+type FillFn = Arc<dyn Fn(&Fill) + Send + Sync>;
+
+struct ProcessorState {
+    offset: Option<FillOffset>,
+    /// Latest offset, written to the state store in the background
+    saved: watch::Sender<Option<FillOffset>>,
+    /// Number of backfills in flight, during which stream fills are buffered
+    backfilling: usize,
+    buffered: Vec<Fill>,
+    disconnected: bool,
+}
+
+impl ProcessorState {
+    fn process(&mut self, fill: &Fill, callback: &FillFn) {
+        let offset = self.offset.get_or_insert_with(FillOffset::default);
+        if offset.is_processed(fill) {
+            return;
+        }
+        callback(fill);
+        offset.advance(fill);
+        self.saved.send_replace(Some(offset.clone()));
+    }
+
+    fn on_stream_fill(&mut self, fill: &Fill, callback: &FillFn) {
+        if self.backfilling > 0 {
+            self.buffered.push(fill.clone());
+        } else {
+            self.process(fill, callback);
+        }
+    }
+
+    /// Process backfilled fills followed by the fills buffered in the meantime
+    fn finish_backfill(&mut self, mut fills: Vec<Fill>, callback: &FillFn) {
+        self.backfilling = self.backfilling.saturating_sub(1);
+        if self.backfilling == 0 {
+            fills.append(&mut self.buffered);
+        }
+        fills.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        for fill in &fills {
+            self.process(fill, callback);
+        }
+    }
+}
+
+/// Hands every fill of the account to a callback once, across restarts and reconnects
+///
+/// Clones share the same state.
+#[derive(Clone)]
+pub struct FillProcessor {
+    client: Client,
+    state: Arc<Mutex<ProcessorState>>,
+    callback: FillFn,
+}
+
+impl FillProcessor {
+    /// Create a processor resuming from the offset of the client's account in `store`
+    ///
+    /// The callback runs while the processor is locked, in fill order. The offset is saved in
+    /// the background after the callback returns, so a crash in between delivers that fill
+    /// again on restart. Only the latest offset is written when saves fall behind.
+    ///
+    /// # Errors
+    ///
+    /// If the offset cannot be loaded
+    pub async fn new<S: StateStore>(
+        client: Client,
+        store: S,
+        callback: impl Fn(&Fill) + Send + Sync + 'static,
+    ) -> Result<Self> {
+        let key = client
+            .account()
+            .map_or_else(|| "default".to_string(), |account| account.to_hex_string());
+        let offset = store.get_json(FILL_OFFSET_NAMESPACE, &key).await?;
+        let (saved, mut receiver) = watch::channel(offset.clone());
+        tokio::spawn(async move {
+            while receiver.changed().await.is_ok() {
+                let latest = receiver.borrow_and_update().clone();
+                if let Some(offset) = latest
+                    && let Err(e) = store.put_json(FILL_OFFSET_NAMESPACE, &key, &offset).await
+                {
+                    warn!("Could not save fill offset: {e}");
+                }
+            }
+        });
+        Ok(Self {
+            client,
+            state: Arc::new(Mutex::new(ProcessorState {
+                offset,
+                saved,
+                backfilling: 0,
+                buffered: Vec::new(),
+                disconnected: false,
+            })),
+            callback: Arc::new(callback),
+        })
+    }
+
+    /// The offset of the last processed fill
+    pub fn offset(&self) -> Option<FillOffset> {
+        self.state.lock().unwrap().offset.clone()
+    }
+
+    /// Subscribe to the fills channel and backfill the fills missed since the stored offset
+    ///
+    /// Without a stored offset nothing is backfilled and processing starts with the stream.
+    ///
+    /// # Returns
+    ///
+    /// The Identifier of the fills subscription
+    ///
+    /// # Errors
+    ///
+    /// If the subscription cannot be made or the backfill fails
+    pub async fn start<E: Exchange>(&self, exchange: &E) -> Result<Identifier> {
+        self.state.lock().unwrap().backfilling += 1;
+        let processor = self.clone();
+        let identifier = exchange
+            .subscribe_typed(FillsSubscription::all(), move |event| match event {
+                ChannelEvent::Data(fill) => processor
+                    .state
+                    .lock()
+                    .unwrap()
+                    .on_stream_fill(fill, &processor.callback),
+                ChannelEvent::Disconnected => processor.state.lock().unwrap().disconnected = true,
+                ChannelEvent::Connected => processor.on_reconnect(),
+                ChannelEvent::Error(e) => warn!("Fills subscription error {e:?}"),
+                _ => {}
+            })
+            .await;
+        let identifier = match identifier {
+            Ok(identifier) => identifier,
+            Err(e) => {
+                self.state.lock().unwrap().backfilling -= 1;
+                return Err(e);
+            }
+        };
+        self.backfill().await?;
+        Ok(identifier)
+    }
+
+    fn on_reconnect(&self) {
+        {
+            let mut state = self.state.lock().unwrap();
+            if !std::mem::take(&mut state.disconnected) {
+                return;
+            }
+            state.backfilling += 1;
+        }
+        let processor = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = processor.backfill().await {
+                warn!("Could not backfill fills after reconnecting: {e}");
+            }
+        });
+    }
+
+    /// Fetch the fills since the offset and process them along with the buffered stream fills
+    async fn backfill(&self) -> Result<()> {
+        let since = self.state.lock().unwrap().offset.clone();
+        let fills = match since {
+            Some(offset) => {
+                let start = Utc
+                    .timestamp_millis_opt(offset.created_at as i64)
+                    .single()
+                    .ok_or_else(|| Error::TimeError(format!("bad offset {offset:?}")))?;
+                self.client.fills(FillsFilter::new().start(start)).await
+            }
+            None => Ok(Vec::new()),
+        };
+        let mut state = self.state.lock().unwrap();
+        match fills {
+            Ok(fills) => {
+                info!("Backfilled {} fills", fills.len());
+                state.finish_backfill(fills, &self.callback);
+                Ok(())
+            }
+            Err(e) => {
+                // Process what was buffered rather than holding the stream back indefinitely
+                state.finish_backfill(Vec::new(), &self.callback);
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fill;
+
+    #[test]
+    fn backfill_then_stream() {
+        let (saved, receiver) = watch::channel(None);
+        let processed = Arc::new(Mutex::new(Vec::new()));
+        let recorded = processed.clone();
+        let callback: FillFn = Arc::new(move |fill| recorded.lock().unwrap().push(fill.id.clone()));
+        let mut state = ProcessorState {
+            offset: Some(FillOffset {
+                created_at: 10,
+                ids: vec!["a".into()],
+            }),
+            saved,
+            backfilling: 1,
+            buffered: Vec::new(),
+            disconnected: false,
+        };
+
+        // Stream fills arriving during the backfill overlap with the REST results
+        state.on_stream_fill(&fill("d", 30), &callback);
+        state.on_stream_fill(&fill("c", 20), &callback);
+        state.finish_backfill(
+            vec![fill("c", 20), fill("b", 10), fill("a", 10), fill("z", 5)],
+            &callback,
+        );
+        state.on_stream_fill(&fill("d", 30), &callback);
+        state.on_stream_fill(&fill("e", 30), &callback);
+
+        assert_eq!(*processed.lock().unwrap(), vec!["b", "c", "d", "e"]);
+        assert_eq!(
+            *receiver.borrow(),
+            Some(FillOffset {
+                created_at: 30,
+                ids: vec!["d".into(), "e".into()],
+            })
+        );
+    }
+}
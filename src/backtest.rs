@@ -0,0 +1,481 @@
+//! Recording of live market data and replay through the [`Exchange`] trait.
+//!
+//! A [`Recorder`] writes the messages of live subscriptions as JSON lines. A
+//! [`BacktestExchange`] replays such a recording to the same subscription callbacks a strategy
+//! uses live, while orders are matched by a [`PaperExchange`] against the replayed book.
+
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use jsonrpsee_types::Notification;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::api::ParadexApi;
+use crate::error::{Error, Result};
+use crate::exchange::Exchange;
+use crate::paper::{PaperEvent, PaperExchange};
+use crate::structs::{
+    BBO, CancelByMarketResponse, MarketSummaryStatic, ModifyOrderRequest, ModifyOrderResult,
+    OrderRequest, OrderUpdate,
+};
+use crate::ws::{CallbackFn, Channel, Identifier, Message};
+
+/// A subscription message as received from the websocket
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedMessage {
+    /// Local receive time in milliseconds
+    pub received_at: u64,
+    pub channel: String,
+    pub data: Value,
+}
+
+impl RecordedMessage {
+    /// Parse the recorded data into the message a live subscription would have delivered
+    ///
+    /// # Errors
+    ///
+    /// If the channel name is unknown or the data does not match the channel
+    pub fn message(&self) -> Result<Message> {
+        let channel = Channel::from_str(&self.channel)?;
+        let notification = Notification::new(
+            "subscription".into(),
+            json!({"channel": self.channel, "data": self.data}),
+        );
+        match channel.to_message(notification) {
+            Message::Error(e) => Err(e),
+            message => Ok(message),
+        }
+    }
+}
+
+/// Read a recording written by a [`Recorder`], one message per line
+pub fn read_recording(reader: impl BufRead) -> impl Iterator<Item = Result<RecordedMessage>> {
+    reader
+        .lines()
+        .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+        .map(|line| {
+            let line = line.map_err(|e| Error::IoError(e.to_string()))?;
+            serde_json::from_str(&line).map_err(|e| Error::DeserializationError(e.to_string()))
+        })
+}
+
+/// Records subscription messages as JSON lines
+#[derive(Clone)]
+pub struct Recorder {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl Recorder {
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Arc::new(Mutex::new(Box::new(writer))),
+        }
+    }
+
+    /// Callback recording the data messages of a subscription to `channel`
+    pub fn callback(&self, channel: &Channel) -> CallbackFn {
+        let recorder = self.clone();
+        let channel = channel.channel_name();
+        Arc::new(move |message: &Message| {
+            let Some(data) = message_data(message) else {
+                return;
+            };
+            let received_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis() as u64)
+                .unwrap_or_default();
+            if let Err(e) = recorder.record(&RecordedMessage {
+                received_at,
+                channel: channel.clone(),
+                data,
+            }) {
+                warn!("Could not record message on {channel}: {e}");
+            }
+        })
+    }
+
+    /// Append a message to the recording
+    ///
+    /// # Errors
+    ///
+    /// If the message cannot be written
+    pub fn record(&self, message: &RecordedMessage) -> Result<()> {
+        let line =
+            serde_json::to_string(message).map_err(|e| Error::JsonParseError(e.to_string()))?;
+        let mut writer = self.writer.lock().unwrap();
+        writeln!(writer, "{line}").map_err(|e| Error::IoError(e.to_string()))
+    }
+
+    /// Flush buffered messages to the underlying writer
+    ///
+    /// # Errors
+    ///
+    /// If the writer cannot be flushed
+    pub fn flush(&self) -> Result<()> {
+        self.writer
+            .lock()
+            .unwrap()
+            .flush()
+            .map_err(|e| Error::IoError(e.to_string()))
+    }
+}
+
+/// Wire payload of a data message, None for control messages
+fn message_data(message: &Message) -> Option<Value> {
+    let data = match message {
+        Message::Connected
+        | Message::Disconnected
+        | Message::Unsubscribed
+        | Message::Error(_)
+        | Message::AuthRefreshed
+        | Message::AuthFailed(_)
+        | Message::Reconnecting { .. }
+        | Message::ConnectionFailed(_) => {
+            return None;
+        }
+        Message::BBO(data) => serde_json::to_value(data),
+        Message::MarketSummary(data) => serde_json::to_value(data),
+        Message::OrderBook(data)
+        | Message::OrderBookDeltas(data)
+        | Message::OrderBookInteractive(data) => serde_json::to_value(data),
+        Message::Trades(data) => serde_json::to_value(data),
+        Message::FundingData(data) => serde_json::to_value(data),
+        Message::Orders(data) => serde_json::to_value(data),
+        Message::Fills(data) => serde_json::to_value(data),
+        Message::Position(data) => serde_json::to_value(data),
+        Message::Account(data) => serde_json::to_value(data),
+        Message::BalanceEvent(data) => serde_json::to_value(data),
+        Message::FundingPayments(data) => serde_json::to_value(data),
+        Message::Transfers(data) => serde_json::to_value(data),
+        Message::Kline(data) => serde_json::to_value(data),
+    };
+    data.inspect_err(|e| warn!("Could not serialize {message:?}: {e}"))
+        .ok()
+}
+
+struct BacktestState {
+    paper: PaperExchange,
+    markets: Vec<MarketSummaryStatic>,
+    subscriptions: Vec<(String, Identifier, CallbackFn)>,
+    next_id: u64,
+}
+
+impl BacktestState {
+    /// Callbacks subscribed to `channel` paired with the message to deliver to them
+    fn deliveries(&self, channel: &str, message: &Message) -> Vec<(CallbackFn, Message)> {
+        self.subscriptions
+            .iter()
+            .filter(|(name, _, _)| name == channel)
+            .map(|(_, _, callback)| (Arc::clone(callback), message.clone()))
+            .collect()
+    }
+
+    /// Route pending simulated order and fill updates to the private channel subscribers
+    fn paper_deliveries(&mut self) -> Vec<(CallbackFn, Message)> {
+        let mut deliveries = Vec::new();
+        for event in self.paper.take_events() {
+            let (prefix, market, message) = match event {
+                PaperEvent::Order(update) => {
+                    ("orders", update.market.clone(), Message::Orders(update))
+                }
+                PaperEvent::Fill(fill) => ("fills", fill.market.clone(), Message::Fills(fill)),
+            };
+            for channel in [format!("{prefix}.{market}"), format!("{prefix}.ALL")] {
+                deliveries.extend(self.deliveries(&channel, &message));
+            }
+        }
+        deliveries
+    }
+}
+
+fn deliver(deliveries: Vec<(CallbackFn, Message)>) {
+    for (callback, message) in deliveries {
+        callback(&message);
+    }
+}
+
+/// Exchange replaying recorded market data, with orders filled by a [`PaperExchange`]
+///
+/// Callbacks are invoked inline as messages are replayed. Strategies which act on messages from
+/// spawned tasks get a chance to run between messages, as the replay yields to the runtime
+/// after each one.
+#[derive(Clone)]
+pub struct BacktestExchange {
+    state: Arc<Mutex<BacktestState>>,
+}
+
+impl BacktestExchange {
+    pub fn new(paper: PaperExchange) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(BacktestState {
+                paper,
+                markets: Vec::new(),
+                subscriptions: Vec::new(),
+                next_id: 0,
+            })),
+        }
+    }
+
+    /// Return `markets` from `markets()`, which returns none by default
+    pub fn with_markets(self, markets: Vec<MarketSummaryStatic>) -> Self {
+        self.state.lock().unwrap().markets = markets;
+        self
+    }
+
+    /// Inspect the simulated venue, e.g. to check open orders after a replay
+    pub fn with_paper<R>(&self, f: impl FnOnce(&PaperExchange) -> R) -> R {
+        f(&self.state.lock().unwrap().paper)
+    }
+
+    /// Run `f` on the simulated venue, then deliver the order and fill updates it produced
+    fn apply<R>(&self, f: impl FnOnce(&mut PaperExchange) -> R) -> R {
+        let (result, deliveries) = {
+            let mut state = self.state.lock().unwrap();
+            let result = f(&mut state.paper);
+            (result, state.paper_deliveries())
+        };
+        deliver(deliveries);
+        result
+    }
+
+    /// Replay recorded messages in order
+    ///
+    /// Order book deltas and trades drive the simulated matching. Every message is delivered to
+    /// subscribers of its channel, followed by any order and fill updates it caused.
+    ///
+    /// # Errors
+    ///
+    /// If the recording cannot be read or contains a message that does not parse
+    pub async fn replay(
+        &self,
+        recording: impl IntoIterator<Item = Result<RecordedMessage>>,
+    ) -> Result<()> {
+        for recorded in recording {
+            let recorded = recorded?;
+            let message = recorded.message()?;
+            let deliveries = {
+                let mut state = self.state.lock().unwrap();
+                match &message {
+                    Message::OrderBookDeltas(update) => {
+                        if let Err(e) = state.paper.on_order_book(update) {
+                            warn!("Replayed order book is out of sequence: {e}");
+                        }
+                    }
+                    Message::Trades(trade) => state.paper.on_trade(trade),
+                    _ => {}
+                }
+                let mut deliveries = state.deliveries(&recorded.channel, &message);
+                deliveries.extend(state.paper_deliveries());
+                deliveries
+            };
+            deliver(deliveries);
+            tokio::task::yield_now().await;
+        }
+        Ok(())
+    }
+}
+
+impl Exchange for BacktestExchange {
+    async fn subscribe(&self, channel: Channel, callback: CallbackFn) -> Result<Identifier> {
+        channel.validate()?;
+        let identifier = {
+            let mut state = self.state.lock().unwrap();
+            let identifier = Identifier(state.next_id);
+            state.next_id += 1;
+            state
+                .subscriptions
+                .push((channel.channel_name(), identifier, Arc::clone(&callback)));
+            identifier
+        };
+        callback(&Message::Connected);
+        Ok(identifier)
+    }
+
+    async fn unsubscribe(&self, identifier: Identifier) -> Result<()> {
+        let removed = {
+            let mut state = self.state.lock().unwrap();
+            let index = state
+                .subscriptions
+                .iter()
+                .position(|(_, id, _)| *id == identifier);
+            index.map(|index| state.subscriptions.remove(index))
+        };
+        match removed {
+            Some((_, _, callback)) => callback(&Message::Unsubscribed),
+            None => warn!(
+                "Received unsubscribe request for {identifier:?} but could not locate subscription"
+            ),
+        }
+        Ok(())
+    }
+}
+
+impl ParadexApi for BacktestExchange {
+    async fn create_order(&self, order_request: OrderRequest) -> Result<OrderUpdate> {
+        self.apply(|paper| paper.place_order(order_request))
+    }
+
+    /// The simulated venue replaces the order with a new one keeping its client id, see
+    /// `PaperExchange::modify_order`
+    async fn modify_order(
+        &self,
+        modify_order_request: ModifyOrderRequest,
+    ) -> Result<ModifyOrderResult> {
+        self.apply(|paper| paper.modify_order(modify_order_request))
+    }
+
+    async fn cancel_order(&self, order_id: String) -> Result<()> {
+        self.apply(|paper| paper.cancel_order(&order_id).map(|_| ()))
+    }
+
+    async fn cancel_all_orders(&self) -> Result<Vec<String>> {
+        let cancelled = self.apply(|paper| paper.cancel_all_orders(None));
+        Ok(cancelled.into_iter().map(|order| order.id).collect())
+    }
+
+    async fn cancel_all_orders_for_market(&self, market: String) -> Result<CancelByMarketResponse> {
+        let cancelled = self.apply(|paper| paper.cancel_all_orders(Some(&market)));
+        Ok(CancelByMarketResponse {
+            message: format!("{} orders cancelled", cancelled.len()),
+            market,
+        })
+    }
+
+    async fn open_orders(&self) -> Result<Vec<OrderUpdate>> {
+        Ok(self.state.lock().unwrap().paper.open_orders(None))
+    }
+
+    async fn bbo(&self, market_symbol: String) -> Result<BBO> {
+        self.state.lock().unwrap().paper.bbo(&market_symbol)
+    }
+
+    async fn markets(&self) -> Result<Vec<MarketSummaryStatic>> {
+        Ok(self.state.lock().unwrap().markets.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::parse_decimal;
+    use crate::structs::{OrderInstruction, OrderType, Side, Trade};
+    use crate::ws::{ChannelEvent, FillsSubscription, TradesSubscription};
+
+    const MARKET: &str = "BTC-USD-PERP";
+
+    fn recording() -> String {
+        let snapshot = json!({
+            "seq_no": 1,
+            "market": MARKET,
+            "last_updated_at": 1000,
+            "update_type": "s",
+            "deletes": [],
+            "inserts": [
+                {"side": "BUY", "price": "99", "size": "1"},
+                {"side": "SELL", "price": "100", "size": "1"},
+            ],
+            "updates": [],
+        });
+        let trade: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/trade.json")).unwrap();
+        let mut through = trade.clone();
+        through["side"] = json!("SELL");
+        through["price"] = json!("98");
+        [
+            json!({"received_at": 1000, "channel": "order_book.BTC-USD-PERP.deltas", "data": snapshot}),
+            json!({"received_at": 2000, "channel": "trades.BTC-USD-PERP", "data": trade}),
+            json!({"received_at": 3000, "channel": "trades.BTC-USD-PERP", "data": through}),
+        ]
+        .map(|line| line.to_string())
+        .join("\n")
+    }
+
+    #[test]
+    fn recorder_round_trip() {
+        let file = std::env::temp_dir().join(format!("paradex-recording-{}", std::process::id()));
+        let recorder = Recorder::new(std::fs::File::create(&file).unwrap());
+        let channel = Channel::Trades {
+            market_symbol: MARKET.into(),
+        };
+        let trade: Trade =
+            serde_json::from_str(include_str!("../tests/fixtures/trade.json")).unwrap();
+        let callback = recorder.callback(&channel);
+        callback(&Message::Connected);
+        callback(&Message::Trades(trade.clone()));
+        recorder.flush().unwrap();
+
+        let reader = std::io::BufReader::new(std::fs::File::open(&file).unwrap());
+        let recorded: Vec<RecordedMessage> = read_recording(reader).map(Result::unwrap).collect();
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].channel, "trades.BTC-USD-PERP");
+        let Message::Trades(replayed) = recorded[0].message().unwrap() else {
+            panic!("expected a trade");
+        };
+        assert_eq!(replayed.id, trade.id);
+        assert_eq!(replayed.price, trade.price);
+
+        let full = Recorder::new(std::io::Cursor::new([0u8; 8]));
+        assert!(matches!(full.record(&recorded[0]), Err(Error::IoError(_))));
+    }
+
+    #[tokio::test]
+    async fn replay_fills_resting_order() {
+        let exchange = BacktestExchange::new(PaperExchange::new());
+        let trades = Arc::new(Mutex::new(0));
+        let fills = Arc::new(Mutex::new(Vec::new()));
+        let trade_count = Arc::clone(&trades);
+        exchange
+            .subscribe_typed(TradesSubscription::new(MARKET), move |event| {
+                if let ChannelEvent::Data(_) = event {
+                    *trade_count.lock().unwrap() += 1;
+                }
+            })
+            .await
+            .unwrap();
+        let fill_log = Arc::clone(&fills);
+        exchange
+            .subscribe_typed(FillsSubscription::all(), move |event| {
+                if let ChannelEvent::Data(fill) = event {
+                    fill_log.lock().unwrap().push((fill.price, fill.size));
+                }
+            })
+            .await
+            .unwrap();
+
+        let mut lines = recording().lines().map(String::from).collect::<Vec<_>>();
+        let rest = lines.split_off(1);
+        let parse = |line: String| {
+            serde_json::from_str::<RecordedMessage>(&line)
+                .map_err(|e| Error::DeserializationError(e.to_string()))
+        };
+        exchange.replay(lines.into_iter().map(parse)).await.unwrap();
+
+        let order = exchange
+            .create_order(OrderRequest {
+                instruction: OrderInstruction::GTC,
+                market: MARKET.into(),
+                price: Some(parse_decimal("99").unwrap()),
+                side: Side::BUY,
+                size: parse_decimal("0.5").unwrap(),
+                order_type: OrderType::LIMIT,
+                client_id: None,
+                flags: vec![],
+                recv_window: None,
+                stp: None,
+                trigger_price: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(exchange.open_orders().await.unwrap()[0].id, order.id);
+
+        exchange.replay(rest.into_iter().map(parse)).await.unwrap();
+        assert_eq!(*trades.lock().unwrap(), 2);
+        assert_eq!(*fills.lock().unwrap(), vec![(99., 0.5)]);
+        assert!(exchange.open_orders().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+//! Bars aggregated from the trades stream.
+//!
+//! Besides time bars, [`BarAggregator`] builds the activity based bars used in quantitative
+//! research: tick bars close after a number of trades, volume bars after a traded size and
+//! notional (dollar) bars after a traded notional. Activity based bars sample more often when
+//! the market is busy, which gives returns closer to normally distributed than time bars.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use log::warn;
+
+use crate::error::Result;
+use crate::exchange::Exchange;
+use crate::structs::{Side, Trade};
+use crate::ws::{ChannelEvent, Identifier, TradesSubscription};
+
+/// When a bar closes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BarKind {
+    /// Bars covering fixed intervals aligned to the epoch, e.g. every minute on the minute
+    Time(Duration),
+    /// Bars of a number of trades
+    Tick(u64),
+    /// Bars of a traded size in base asset
+    Volume(f64),
+    /// Bars of a traded notional in quote asset
+    Notional(f64),
+}
+
+/// Open, high, low, close and traded amounts of a run of trades
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bar {
+    pub market: String,
+    /// Start of the interval for time bars, time of the first trade otherwise, in milliseconds
+    pub start: u64,
+    /// End of the interval for time bars, time of the last trade otherwise, in milliseconds
+    pub end: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    /// Size bought by takers
+    pub buy_volume: f64,
+    pub notional: f64,
+    pub trades: u64,
+}
+
+impl Bar {
+    fn new(trade: &Trade, start: u64, end: u64) -> Self {
+        Self {
+            market: trade.market.clone(),
+            start,
+            end,
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: 0.,
+            buy_volume: 0.,
+            notional: 0.,
+            trades: 0,
+        }
+    }
+
+    fn add(&mut self, trade: &Trade) {
+        self.high = self.high.max(trade.price);
+        self.low = self.low.min(trade.price);
+        self.close = trade.price;
+        self.volume += trade.size;
+        if trade.side == Side::BUY {
+            self.buy_volume += trade.size;
+        }
+        self.notional += trade.size * trade.price;
+        self.trades += 1;
+    }
+
+    /// Volume weighted average price
+    pub fn vwap(&self) -> f64 {
+        if self.volume > 0. {
+            self.notional / self.volume
+        } else {
+            self.close
+        }
+    }
+}
+
+/// Builds bars of one kind from a sequence of trades
+///
+/// Trades are never split between bars, so volume and notional bars close on the trade that
+/// reaches the threshold and may exceed it. Time bars close on the first trade after their
+/// interval, and intervals without trades produce no bar.
+#[derive(Debug, Clone)]
+pub struct BarAggregator {
+    kind: BarKind,
+    current: Option<Bar>,
+}
+
+impl BarAggregator {
+    pub fn new(kind: BarKind) -> Self {
+        Self {
+            kind,
+            current: None,
+        }
+    }
+
+    pub fn kind(&self) -> BarKind {
+        self.kind
+    }
+
+    /// The bar being built, if any trade has been added to it
+    pub fn current(&self) -> Option<&Bar> {
+        self.current.as_ref()
+    }
+
+    /// Add a trade, returning the bars closed by it
+    ///
+    /// At most one bar closes before the trade, when the trade falls after the interval of
+    /// the current time bar, and one bar can close on the trade itself.
+    pub fn on_trade(&mut self, trade: &Trade) -> Vec<Bar> {
+        let mut closed = Vec::new();
+        if let BarKind::Time(interval) = self.kind {
+            let interval = (interval.as_millis() as u64).max(1);
+            let start = trade.created_at - trade.created_at % interval;
+            match &self.current {
+                Some(bar) if start < bar.end => {}
+                _ => closed.extend(
+                    self.current
+                        .replace(Bar::new(trade, start, start + interval)),
+                ),
+            }
+        }
+        let bar = self
+            .current
+            .get_or_insert_with(|| Bar::new(trade, trade.created_at, trade.created_at));
+        bar.add(trade);
+        if !matches!(self.kind, BarKind::Time(_)) {
+            bar.end = trade.created_at;
+        }
+        let full = match self.kind {
+            BarKind::Time(_) => false,
+            BarKind::Tick(trades) => bar.trades >= trades,
+            BarKind::Volume(volume) => bar.volume >= volume,
+            BarKind::Notional(notional) => bar.notional >= notional,
+        };
+        if full {
+            closed.extend(self.current.take());
+        }
+        closed
+    }
+
+    /// Close the current bar early, e.g. when the stream stops
+    pub fn flush(&mut self) -> Option<Bar> {
+        self.current.take()
+    }
+}
+
+/// Subscribe to the trades of a market and call `callback` with every closed bar
+///
+/// # Parameters
+///
+/// * `exchange` - The exchange to subscribe with
+/// * `market` - The market symbol
+/// * `kind` - When bars close
+/// * `callback` - Called with every closed bar
+///
+/// # Returns
+///
+/// The Identifier of the trades subscription
+///
+/// # Errors
+///
+/// If the subscription cannot be made
+pub async fn subscribe_bars<E: Exchange>(
+    exchange: &E,
+    market: impl Into<String>,
+    kind: BarKind,
+    callback: impl Fn(&Bar) + Send + Sync + 'static,
+) -> Result<Identifier> {
+    let aggregator = Mutex::new(BarAggregator::new(kind));
+    exchange
+        .subscribe_typed(TradesSubscription::new(market), move |event| match event {
+            ChannelEvent::Data(trade) => {
+                let closed = aggregator.lock().unwrap().on_trade(trade);
+                for bar in &closed {
+                    callback(bar);
+                }
+            }
+            ChannelEvent::Error(e) => warn!("Trades subscription error {e:?}"),
+            _ => {}
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(created_at: u64, price: f64, size: f64, side: Side) -> Trade {
+        let mut trade: Trade =
+            serde_json::from_str(include_str!("../tests/fixtures/trade.json")).unwrap();
+        trade.created_at = created_at;
+        trade.price = price;
+        trade.size = size;
+        trade.side = side;
+        trade
+    }
+
+    fn trades() -> Vec<Trade> {
+        vec![
+            trade(1_000, 100., 1., Side::BUY),
+            trade(30_000, 102., 2., Side::SELL),
+            trade(59_999, 99., 1., Side::BUY),
+            trade(60_000, 101., 3., Side::BUY),
+            trade(185_000, 103., 1., Side::SELL),
+        ]
+    }
+
+    fn run(kind: BarKind) -> (Vec<Bar>, Option<Bar>) {
+        let mut aggregator = BarAggregator::new(kind);
+        let bars = trades()
+            .iter()
+            .flat_map(|trade| aggregator.on_trade(trade))
+            .collect();
+        (bars, aggregator.flush())
+    }
+
+    #[test]
+    fn time_bars() {
+        let (bars, last) = run(BarKind::Time(Duration::from_secs(60)));
+        assert_eq!(bars.len(), 2);
+        let first = &bars[0];
+        assert_eq!((first.start, first.end), (0, 60_000));
+        assert_eq!(
+            (first.open, first.high, first.low, first.close),
+            (100., 102., 99., 99.)
+        );
+        assert_eq!((first.volume, first.buy_volume, first.trades), (4., 2., 3));
+        assert!((first.vwap() - 100.75).abs() < 1e-9);
+        // The interval without trades produces no bar
+        assert_eq!((bars[1].start, bars[1].end), (60_000, 120_000));
+        assert_eq!(last.unwrap().start, 180_000);
+    }
+
+    #[test]
+    fn activity_bars() {
+        let (bars, last) = run(BarKind::Tick(2));
+        assert_eq!(bars.len(), 2);
+        assert_eq!((bars[1].start, bars[1].end), (59_999, 60_000));
+        assert_eq!(last.unwrap().trades, 1);
+
+        let (bars, last) = run(BarKind::Volume(3.));
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].volume, 3.);
+        assert_eq!(bars[1].volume, 4.);
+        assert_eq!(last.unwrap().close, 103.);
+
+        let (bars, last) = run(BarKind::Notional(250.));
+        assert_eq!(
+            bars.iter().map(|bar| bar.trades).collect::<Vec<_>>(),
+            vec![2, 2]
+        );
+        assert!(last.is_some());
+    }
+}
@@ -0,0 +1,351 @@
+//! L1 deposits through the StarkGate bridges listed in `SystemConfig::bridged_tokens`.
+//!
+//! A deposit is a pair of Ethereum transactions: the bridge is approved to spend the token,
+//! then `depositWithMessage` sends it to Paraclear with the Paradex account as the message,
+//! crediting the account once the message is consumed on L2. [`EthRpc`] is the minimal client
+//! of an L1 node's JSON-RPC API used to sign and send them, and `Client::deposit` puts it
+//! together. Requires the `bridge` feature.
+
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use alloy_consensus::{SignableTransaction, TxEip1559, TxEnvelope};
+use alloy_eips::eip2718::Encodable2718;
+use alloy_primitives::{Address, B256, Bytes, TxKind, U256};
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{SolCall, sol};
+use log::trace;
+use rust_decimal::Decimal;
+use serde_json::{Value, json};
+use starknet_core::types::Felt;
+
+use crate::error::{Error, Result};
+use crate::structs::BridgedToken;
+use crate::transaction::{json_rpc, scale_amount};
+
+/// How long to wait for the approval to be mined before depositing
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Interval between polls for a transaction receipt
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Margin added on top of the estimated gas, in percent
+const GAS_MARGIN_PERCENT: u64 = 20;
+
+sol! {
+    function allowance(address owner, address spender) external view returns (uint256);
+    function approve(address spender, uint256 amount) external returns (bool);
+    function estimateDepositFeeWei() external view returns (uint256);
+    function depositWithMessage(
+        address token,
+        uint256 amount,
+        uint256 l2Recipient,
+        uint256[] message
+    ) external payable;
+}
+
+/// Client of the JSON-RPC API of an Ethereum node
+#[derive(Debug, Clone)]
+pub struct EthRpc {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl EthRpc {
+    pub fn new(client: reqwest::Client, url: impl Into<String>) -> Self {
+        Self {
+            client,
+            url: url.into(),
+        }
+    }
+
+    /// Call a view function
+    ///
+    /// # Errors
+    ///
+    /// If the request fails or the call reverts
+    pub async fn call(&self, to: Address, input: Vec<u8>) -> Result<Bytes> {
+        let result = self
+            .request(
+                "eth_call",
+                json!([{"to": to, "data": Bytes::from(input)}, "latest"]),
+            )
+            .await?;
+        parse(&result)
+    }
+
+    /// Sign a transaction calling `to` and send it, filling in the nonce, gas and fees
+    ///
+    /// # Returns
+    ///
+    /// The hash of the transaction
+    ///
+    /// # Errors
+    ///
+    /// If the request fails, the transaction would revert or the node rejects it
+    pub async fn send_transaction(
+        &self,
+        signer: &PrivateKeySigner,
+        to: Address,
+        value: U256,
+        input: Vec<u8>,
+    ) -> Result<B256> {
+        let from = signer.address();
+        let input = Bytes::from(input);
+        let chain_id = quantity(self.request("eth_chainId", json!([])).await?)?;
+        let nonce = quantity(
+            self.request("eth_getTransactionCount", json!([from, "pending"]))
+                .await?,
+        )?;
+        let gas = quantity(
+            self.request(
+                "eth_estimateGas",
+                json!([{"from": from, "to": to, "value": value, "data": input}]),
+            )
+            .await?,
+        )?;
+        let priority_fee: u128 =
+            parse(&self.request("eth_maxPriorityFeePerGas", json!([])).await?)?;
+        let block = self
+            .request("eth_getBlockByNumber", json!(["latest", false]))
+            .await?;
+        let base_fee: u128 = parse(&block["baseFeePerGas"])?;
+
+        let transaction = TxEip1559 {
+            chain_id,
+            nonce,
+            gas_limit: gas + gas * GAS_MARGIN_PERCENT / 100,
+            // Leave room for the base fee to double before inclusion
+            max_fee_per_gas: base_fee * 2 + priority_fee,
+            max_priority_fee_per_gas: priority_fee,
+            to: TxKind::Call(to),
+            value,
+            access_list: Default::default(),
+            input,
+        };
+        let raw = sign_transaction(signer, transaction)?;
+        let hash = self
+            .request("eth_sendRawTransaction", json!([Bytes::from(raw)]))
+            .await?;
+        parse(&hash)
+    }
+
+    /// Wait until a transaction is mined
+    ///
+    /// # Errors
+    ///
+    /// * `Error::DeadlineExceeded` if it is not mined within `timeout`
+    /// * `Error::EthereumError` if it reverted
+    pub async fn wait_for_receipt(&self, hash: B256, timeout: Duration) -> Result<()> {
+        let started = Instant::now();
+        loop {
+            let receipt = self
+                .request("eth_getTransactionReceipt", json!([hash]))
+                .await?;
+            if !receipt.is_null() {
+                return match receipt["status"].as_str() {
+                    Some("0x1") => Ok(()),
+                    _ => Err(Error::EthereumError(format!("Transaction {hash} reverted"))),
+                };
+            }
+            if started.elapsed() >= timeout {
+                return Err(Error::DeadlineExceeded(timeout));
+            }
+            trace!("Waiting for transaction {hash} to be mined");
+            tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        json_rpc(
+            &self.client,
+            &self.url,
+            method,
+            params,
+            Error::EthereumError,
+        )
+        .await
+    }
+}
+
+fn quantity(value: Value) -> Result<u64> {
+    parse::<U256>(&value)?
+        .try_into()
+        .map_err(|e| Error::EthereumError(format!("Invalid quantity {value}: {e}")))
+}
+
+fn parse<T: serde::de::DeserializeOwned>(value: &Value) -> Result<T> {
+    serde_json::from_value(value.clone())
+        .map_err(|e| Error::EthereumError(format!("Unexpected value {value}: {e}")))
+}
+
+/// Sign an EIP-1559 transaction, returning it encoded for `eth_sendRawTransaction`
+fn sign_transaction(signer: &PrivateKeySigner, transaction: TxEip1559) -> Result<Vec<u8>> {
+    let signature = signer
+        .sign_hash_sync(&transaction.signature_hash())
+        .map_err(|e| Error::EthereumError(e.to_string()))?;
+    Ok(TxEnvelope::from(transaction.into_signed(signature)).encoded_2718())
+}
+
+fn address(hex: &str) -> Result<Address> {
+    Address::from_str(hex).map_err(|e| Error::TypeConversionError(format!("{hex}: {e}")))
+}
+
+fn felt_to_u256(felt: Felt) -> U256 {
+    U256::from_be_bytes(felt.to_bytes_be())
+}
+
+/// The calldata depositing `amount` of `token` into Paraclear for `account`
+///
+/// StarkGate's L1 `StarknetTokenBridge.depositWithMessage(token, amount, l2Recipient, message)`
+/// sends an L1 to L2 message which the L2 `TokenBridge` handles in
+/// `handle_deposit_with_message`: it mints the tokens to `l2Recipient` and then calls
+/// `on_receive(l2_token, amount, depositor, message)` on it. Paraclear is the recipient, and
+/// its `on_receive` credits the Paradex account given as the single element of `message`.
+fn deposit_calldata(
+    token: &BridgedToken,
+    amount: U256,
+    paraclear: Felt,
+    account: Felt,
+) -> Result<Vec<u8>> {
+    Ok(depositWithMessageCall {
+        token: address(&token.l1_token_address)?,
+        amount,
+        l2Recipient: felt_to_u256(paraclear),
+        message: vec![felt_to_u256(account)],
+    }
+    .abi_encode())
+}
+
+/// Deposit `amount` of a bridged token from `signer`'s L1 address into Paraclear for `account`
+///
+/// Approves the bridge first if its allowance is too low, waiting for the approval to be
+/// mined, then sends the deposit along with the L1 to L2 message fee quoted by the bridge.
+///
+/// # Returns
+///
+/// The hash of the L1 deposit transaction
+///
+/// # Errors
+///
+/// If an address cannot be parsed or a transaction cannot be sent
+pub async fn deposit(
+    rpc: &EthRpc,
+    signer: &PrivateKeySigner,
+    token: &BridgedToken,
+    paraclear_address: &str,
+    account: Felt,
+    amount: Decimal,
+) -> Result<B256> {
+    let paraclear = Felt::from_hex(paraclear_address)
+        .map_err(|e| Error::TypeConversionError(format!("{paraclear_address}: {e}")))?;
+    let amount = felt_to_u256(scale_amount(amount, token.decimals)?);
+    let token_address = address(&token.l1_token_address)?;
+    let bridge = address(&token.l1_bridge_address)?;
+
+    let allowance = rpc
+        .call(
+            token_address,
+            allowanceCall {
+                owner: signer.address(),
+                spender: bridge,
+            }
+            .abi_encode(),
+        )
+        .await?;
+    let allowance = allowanceCall::abi_decode_returns(&allowance)
+        .map_err(|e| Error::EthereumError(e.to_string()))?;
+    if allowance < amount {
+        let approval = rpc
+            .send_transaction(
+                signer,
+                token_address,
+                U256::ZERO,
+                approveCall {
+                    spender: bridge,
+                    amount,
+                }
+                .abi_encode(),
+            )
+            .await?;
+        rpc.wait_for_receipt(approval, APPROVAL_TIMEOUT).await?;
+    }
+
+    let fee = rpc
+        .call(bridge, estimateDepositFeeWeiCall {}.abi_encode())
+        .await?;
+    let fee = estimateDepositFeeWeiCall::abi_decode_returns(&fee)
+        .map_err(|e| Error::EthereumError(e.to_string()))?;
+    rpc.send_transaction(
+        signer,
+        bridge,
+        fee,
+        deposit_calldata(token, amount, paraclear, account)?,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_eips::eip2718::Decodable2718;
+
+    #[test]
+    fn signed_deposit() {
+        let config: crate::structs::SystemConfig =
+            serde_json::from_str(include_str!("../tests/fixtures/system_config.json")).unwrap();
+        let token = config.bridged_token("USDC").unwrap();
+        let amount = felt_to_u256(scale_amount(Decimal::new(125, 1), token.decimals).unwrap());
+        assert_eq!(amount, U256::from(12_500_000));
+
+        let account = Felt::from_hex_unchecked("0xacc");
+        let paraclear = Felt::from_hex(&config.paraclear_address).unwrap();
+        let calldata = deposit_calldata(token, amount, paraclear, account).unwrap();
+        let decoded = depositWithMessageCall::abi_decode(&calldata).unwrap();
+        assert_eq!(decoded.token, address(&token.l1_token_address).unwrap());
+        assert_eq!(decoded.amount, amount);
+        assert_eq!(decoded.l2Recipient, felt_to_u256(paraclear));
+        assert_eq!(decoded.message, vec![U256::from(0xacc)]);
+
+        // The ABI layout StarkGate's bridge expects, word by word
+        let word = |index: usize| &calldata[4 + 32 * index..4 + 32 * (index + 1)];
+        assert_eq!(
+            calldata[..4],
+            alloy_primitives::keccak256("depositWithMessage(address,uint256,uint256,uint256[])")
+                [..4]
+        );
+        assert_eq!(calldata.len(), 4 + 32 * 6);
+        assert_eq!(
+            word(0)[12..],
+            address(&token.l1_token_address).unwrap().as_slice()[..]
+        );
+        assert_eq!(U256::from_be_slice(word(1)), U256::from(12_500_000));
+        assert_eq!(word(2), paraclear.to_bytes_be());
+        // Offset of the message array, its length and the account
+        assert_eq!(U256::from_be_slice(word(3)), U256::from(4 * 32));
+        assert_eq!(U256::from_be_slice(word(4)), U256::ONE);
+        assert_eq!(U256::from_be_slice(word(5)), U256::from(0xacc));
+
+        let signer = PrivateKeySigner::random();
+        let raw = sign_transaction(
+            &signer,
+            TxEip1559 {
+                chain_id: 1,
+                to: TxKind::Call(address(&token.l1_bridge_address).unwrap()),
+                input: calldata.into(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let envelope = TxEnvelope::decode_2718(&mut raw.as_slice()).unwrap();
+        let signed = envelope.as_eip1559().unwrap();
+        assert_eq!(
+            signed
+                .signature()
+                .recover_address_from_prehash(&signed.signature_hash())
+                .unwrap(),
+            signer.address()
+        );
+    }
+}
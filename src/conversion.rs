@@ -0,0 +1,198 @@
+//! Currency conversion of notionals and PnL.
+//!
+//! Paradex quotes perpetuals in USD but settles collateral, fees and PnL in the settlement
+//! currency, USDC. [`CurrencyConverter`] values currencies in USD from the mark or underlying
+//! (oracle) prices of perpetual market summaries, so amounts can be reported in USD, in the
+//! settlement currency or in another asset, e.g. for instruments quoted in something other
+//! than USD or dashboards spanning several assets. USDC has no market of its own; its USD price
+//! can be implied from a position with [`settlement_usd_price`] or set explicitly.
+
+use std::collections::HashMap;
+
+use crate::structs::{MarketSummary, MarketSummaryStatic, Position};
+
+/// Currency every price is expressed in
+pub const USD: &str = "USD";
+
+/// Price of a market summary used to value its base currency
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PriceSource {
+    #[default]
+    Mark,
+    /// The oracle price of the underlying
+    Underlying,
+}
+
+/// Base and quote currency of a perpetual symbol such as `BTC-USD-PERP`
+fn perpetual_currencies(symbol: &str) -> Option<(&str, &str)> {
+    let mut parts = symbol.split('-');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(base), Some(quote), Some("PERP"), None) => Some((base, quote)),
+        _ => None,
+    }
+}
+
+/// USD price of the settlement currency implied by a position, which reports its cost both in
+/// the settlement currency and in USD
+pub fn settlement_usd_price(position: &Position) -> Option<f64> {
+    let price = position.cost_usd / position.cost;
+    (price.is_finite() && price > 0.).then_some(price)
+}
+
+/// USD prices of currencies
+#[derive(Clone, Debug, PartialEq)]
+pub struct CurrencyConverter {
+    usd_prices: HashMap<String, f64>,
+}
+
+impl Default for CurrencyConverter {
+    fn default() -> Self {
+        Self {
+            usd_prices: HashMap::from([(USD.to_string(), 1.)]),
+        }
+    }
+}
+
+impl CurrencyConverter {
+    /// Price the base currency of every perpetual market in USD
+    ///
+    /// Perpetuals quoted in another currency are priced through the USD price of their quote
+    /// currency. Summaries of options and futures are ignored, as are prices which are not
+    /// positive.
+    pub fn new(summaries: &[MarketSummary], source: PriceSource) -> Self {
+        let mut converter = Self::default();
+        let mut quoted: Vec<(&str, &str, f64)> = summaries
+            .iter()
+            .filter_map(|summary| {
+                let (base, quote) = perpetual_currencies(&summary.symbol)?;
+                let price = match source {
+                    PriceSource::Mark => summary.mark_price,
+                    PriceSource::Underlying => summary.underlying_price,
+                };
+                (price.is_finite() && price > 0.).then_some((base, quote, price))
+            })
+            .collect();
+        // Resolve USD quoted markets first, then markets quoted in currencies priced so far
+        while !quoted.is_empty() {
+            let before = quoted.len();
+            quoted.retain(|(base, quote, price)| match converter.usd_price(quote) {
+                Some(quote_price) => {
+                    converter
+                        .usd_prices
+                        .entry(base.to_string())
+                        .or_insert(price * quote_price);
+                    false
+                }
+                None => true,
+            });
+            if quoted.len() == before {
+                break;
+            }
+        }
+        converter
+    }
+
+    /// Set the USD price of a currency, e.g. of the settlement currency
+    pub fn set_usd_price(&mut self, currency: impl Into<String>, price: f64) {
+        self.usd_prices.insert(currency.into(), price);
+    }
+
+    /// USD value of one unit of `currency`, None if it is not priced
+    pub fn usd_price(&self, currency: &str) -> Option<f64> {
+        self.usd_prices.get(currency).copied()
+    }
+
+    /// Convert `amount` of `from` into `to`, None if either currency is not priced
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> Option<f64> {
+        if from == to {
+            return Some(amount);
+        }
+        Some(amount * self.usd_price(from)? / self.usd_price(to)?)
+    }
+
+    /// USD value of `amount` of `currency`
+    pub fn to_usd(&self, amount: f64, currency: &str) -> Option<f64> {
+        self.convert(amount, currency, USD)
+    }
+
+    /// Amount of `currency` worth `usd`
+    pub fn from_usd(&self, usd: f64, currency: &str) -> Option<f64> {
+        self.convert(usd, USD, currency)
+    }
+
+    /// Notional of `size` contracts of a market at `price`, in `currency`
+    pub fn notional(
+        &self,
+        market: &MarketSummaryStatic,
+        size: f64,
+        price: f64,
+        currency: &str,
+    ) -> Option<f64> {
+        self.convert(size * price, &market.quote_currency, currency)
+    }
+
+    /// Unrealized PnL of a position including funding, converted from the market's settlement
+    /// currency into `currency`
+    pub fn unrealized_pnl(
+        &self,
+        market: &MarketSummaryStatic,
+        position: &Position,
+        currency: &str,
+    ) -> Option<f64> {
+        self.convert(
+            position.unrealized_pnl + position.unrealized_funding_pnl,
+            &market.settlement_currency,
+            currency,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{Positions, ResultsContainer};
+
+    fn summary(symbol: &str, mark_price: f64) -> MarketSummary {
+        let mut summary: MarketSummary =
+            serde_json::from_str(include_str!("../tests/fixtures/market_summary.json")).unwrap();
+        summary.symbol = symbol.into();
+        summary.mark_price = mark_price;
+        summary
+    }
+
+    #[test]
+    fn conversions() {
+        let mut converter = CurrencyConverter::new(
+            &[
+                summary("ETH-BTC-PERP", 0.05),
+                summary("BTC-USD-PERP", 30000.),
+                summary("BTC-USD-31DEC25-30000-C", 1000.),
+                summary("SOL-USD-PERP", f64::NAN),
+            ],
+            PriceSource::Mark,
+        );
+        assert_eq!(converter.usd_price("BTC"), Some(30000.));
+        assert_eq!(converter.usd_price("ETH"), Some(1500.));
+        assert_eq!(converter.usd_price("SOL"), None);
+        assert_eq!(converter.convert(3000., "USD", "ETH"), Some(2.));
+        assert_eq!(converter.to_usd(10., "USDC"), None);
+
+        let positions: Positions =
+            serde_json::from_str(include_str!("../tests/fixtures/positions.json")).unwrap();
+        let position = &positions.results[0];
+        let rate = settlement_usd_price(position).unwrap();
+        converter.set_usd_price("USDC", rate);
+        assert_eq!(converter.from_usd(rate * 10., "USDC"), Some(10.));
+
+        let markets: ResultsContainer<Vec<MarketSummaryStatic>> =
+            serde_json::from_str(include_str!("../tests/fixtures/markets.json")).unwrap();
+        let market = &markets.results[0];
+        assert_eq!(
+            converter.notional(market, 0.5, 30000., &market.quote_currency),
+            Some(15000.)
+        );
+        let pnl = position.unrealized_pnl + position.unrealized_funding_pnl;
+        let pnl_btc = converter.unrealized_pnl(market, position, "BTC").unwrap();
+        assert!((pnl_btc - pnl * rate / 30000.).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,129 @@
+use std::str::FromStr;
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use starknet_crypto::Felt;
+
+use crate::error::{Error, Result};
+
+/// Number of decimal places the venue keeps for prices and sizes. Signed order values are
+/// scaled by `10^PARACLEAR_DECIMALS` into integers.
+pub const PARACLEAR_DECIMALS: u32 = 8;
+
+/// Parse a decimal string, rejecting values with more precision than the venue keeps
+///
+/// # Errors
+///
+/// If the string is not a decimal or has more than `PARACLEAR_DECIMALS` decimal places
+pub fn parse_decimal(value: &str) -> Result<Decimal> {
+    let decimal = Decimal::from_str(value)
+        .map_err(|e| Error::TypeConversionError(format!("Could not parse {value:?}: {e}")))?;
+    check_precision(decimal)
+}
+
+/// Truncate a value to venue precision, so that what is sent matches what is signed
+pub fn quantize(value: Decimal) -> Decimal {
+    value
+        .round_dp_with_strategy(PARACLEAR_DECIMALS, RoundingStrategy::ToZero)
+        .normalize()
+}
+
+/// Scale a value into the integer representation used in order signatures
+///
+/// # Errors
+///
+/// If the value has more than `PARACLEAR_DECIMALS` decimal places or does not fit in an i64
+pub fn to_venue_units(value: Decimal) -> Result<i64> {
+    let value = check_precision(value)?;
+    value
+        .checked_mul(Decimal::from(10_i64.pow(PARACLEAR_DECIMALS)))
+        .and_then(|scaled| scaled.to_i64())
+        .ok_or_else(|| Error::TypeConversionError(format!("Could not convert {value} to i64")))
+}
+
+/// Inverse of `to_venue_units`
+pub fn from_venue_units(units: i64) -> Decimal {
+    Decimal::new(units, PARACLEAR_DECIMALS).normalize()
+}
+
+/// Convert a price or size into the Felt used in signed payloads, scaled by
+/// `10^PARACLEAR_DECIMALS`. Negative values map to their field negation.
+///
+/// This is the conversion applied to order sizes and prices when signing, and should be used
+/// when building other signed payloads (e.g. vault operations or block trades) by hand.
+///
+/// # Errors
+///
+/// If the value has more than `PARACLEAR_DECIMALS` decimal places or does not fit in an i64
+pub fn decimal_to_felt(value: Decimal) -> Result<Felt> {
+    Ok(Felt::from(to_venue_units(value)?))
+}
+
+/// Inverse of `decimal_to_felt`
+///
+/// # Errors
+///
+/// If the Felt does not represent a signed 64 bit scaled value
+pub fn felt_to_decimal(felt: Felt) -> Result<Decimal> {
+    i64::try_from(felt)
+        .map(from_venue_units)
+        .map_err(|e| Error::TypeConversionError(format!("Could not convert {felt} to i64: {e}")))
+}
+
+fn check_precision(value: Decimal) -> Result<Decimal> {
+    let value = value.normalize();
+    if value.scale() > PARACLEAR_DECIMALS {
+        Err(Error::TypeConversionError(format!(
+            "{value} has more than {PARACLEAR_DECIMALS} decimal places"
+        )))
+    } else {
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn venue_units_round_trip() {
+        for value in ["0.001", "100000", "30001.2", "0.00000001", "-12.5"] {
+            let decimal = parse_decimal(value).unwrap();
+            let units = to_venue_units(decimal).unwrap();
+            assert_eq!(from_venue_units(units), decimal);
+            assert_eq!(from_venue_units(units).to_string(), value);
+        }
+        assert_eq!(
+            to_venue_units(parse_decimal("0.001").unwrap()).unwrap(),
+            100_000
+        );
+    }
+
+    #[test]
+    fn felt_round_trip() {
+        let size = parse_decimal("0.001").unwrap();
+        assert_eq!(decimal_to_felt(size).unwrap(), Felt::from(100_000));
+        assert_eq!(felt_to_decimal(Felt::from(100_000)).unwrap(), size);
+
+        let negative = parse_decimal("-2.5").unwrap();
+        assert_eq!(decimal_to_felt(negative).unwrap(), -Felt::from(250_000_000));
+        assert_eq!(
+            felt_to_decimal(decimal_to_felt(negative).unwrap()).unwrap(),
+            negative
+        );
+
+        assert!(felt_to_decimal(Felt::from_hex_unchecked("0x1234567890abcdef1234")).is_err());
+    }
+
+    #[test]
+    fn excess_precision() {
+        assert!(parse_decimal("0.000000001").is_err());
+        assert!(parse_decimal("1.100000000").is_ok());
+        assert!(parse_decimal("abc").is_err());
+
+        let value = Decimal::from_str("1.234567891").unwrap();
+        assert!(to_venue_units(value).is_err());
+        assert_eq!(quantize(value).to_string(), "1.23456789");
+        assert_eq!(to_venue_units(quantize(value)).unwrap(), 123_456_789);
+    }
+}
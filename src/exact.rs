@@ -0,0 +1,322 @@
+//! Decimal-typed market data and account structs.
+//!
+//! The structs in [`crate::structs`] parse prices and sizes into `f64`, which is the right
+//! trade-off on latency sensitive paths but loses precision when summing fees or PnL. The
+//! structs here deserialize the same payloads into [`Decimal`] so accounting code sees the
+//! values exactly as the venue sent them. Each converts into its `f64` counterpart for code
+//! that wants both.
+
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::structs::{
+    self, FillLiquidity, FillType, PositionSide, PositionStatus, Side, TradeType, TransferBridge,
+    TransferDirection, TransferKind, TransferStatus,
+};
+
+/// Fields the venue leaves empty instead of omitting, e.g. the liquidation price of an account
+/// without margin requirement
+fn deserialize_optional_decimal<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<Decimal>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(s) if s.is_empty() => Ok(None),
+        Some(s) => s.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+fn serialize_optional_decimal<S>(
+    value: &Option<Decimal>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        None => serializer.serialize_str(""),
+        Some(decimal) => serializer.serialize_str(&decimal.to_string()),
+    }
+}
+
+fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or(f64::NAN)
+}
+
+fn optional_to_f64(value: Option<Decimal>) -> f64 {
+    value.map_or(f64::NAN, to_f64)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct BBO {
+    pub bid: Decimal,
+    pub bid_size: Decimal,
+    pub ask: Decimal,
+    pub ask_size: Decimal,
+    pub market: String,
+    pub last_updated_at: u64,
+    #[serde(default)]
+    pub seq_no: u64,
+}
+
+impl BBO {
+    /// Midpoint of the best bid and ask
+    pub fn mid(&self) -> Decimal {
+        (self.bid + self.ask) / Decimal::TWO
+    }
+}
+
+impl From<BBO> for structs::BBO {
+    fn from(bbo: BBO) -> Self {
+        Self {
+            bid: to_f64(bbo.bid),
+            bid_size: to_f64(bbo.bid_size),
+            ask: to_f64(bbo.ask),
+            ask_size: to_f64(bbo.ask_size),
+            market: bbo.market,
+            last_updated_at: bbo.last_updated_at,
+            seq_no: bbo.seq_no,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Trade {
+    pub created_at: u64,
+    pub id: String,
+    pub market: String,
+    pub price: Decimal,
+    pub side: Side,
+    pub size: Decimal,
+    pub trade_type: TradeType,
+}
+
+impl Trade {
+    /// Traded notional in quote currency
+    pub fn notional(&self) -> Decimal {
+        self.price * self.size
+    }
+}
+
+impl From<Trade> for structs::Trade {
+    fn from(trade: Trade) -> Self {
+        Self {
+            created_at: trade.created_at,
+            id: trade.id,
+            market: trade.market,
+            price: to_f64(trade.price),
+            side: trade.side,
+            size: to_f64(trade.size),
+            trade_type: trade.trade_type,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Fill {
+    pub client_id: String,
+    pub created_at: u64,
+    pub fee: Decimal,
+    pub fee_currency: String,
+    pub id: String,
+    pub liquidity: FillLiquidity,
+    pub market: String,
+    pub order_id: String,
+    pub price: Decimal,
+    pub side: Side,
+    pub size: Decimal,
+    pub remaining_size: Decimal,
+    pub fill_type: FillType,
+    pub realized_pnl: Decimal,
+}
+
+impl Fill {
+    /// Filled notional in quote currency
+    pub fn notional(&self) -> Decimal {
+        self.price * self.size
+    }
+}
+
+impl From<Fill> for structs::Fill {
+    fn from(fill: Fill) -> Self {
+        Self {
+            client_id: fill.client_id,
+            created_at: fill.created_at,
+            fee: to_f64(fill.fee),
+            fee_currency: fill.fee_currency,
+            id: fill.id,
+            liquidity: fill.liquidity,
+            market: fill.market,
+            order_id: fill.order_id,
+            price: to_f64(fill.price),
+            side: fill.side,
+            size: to_f64(fill.size),
+            remaining_size: to_f64(fill.remaining_size),
+            fill_type: fill.fill_type,
+            realized_pnl: to_f64(fill.realized_pnl),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Position {
+    pub account: String,
+    pub average_entry_price: Decimal,
+    pub average_entry_price_usd: Decimal,
+    #[serde(
+        deserialize_with = "deserialize_optional_decimal",
+        serialize_with = "serialize_optional_decimal"
+    )]
+    pub average_exit_price: Option<Decimal>,
+    pub cached_funding_index: Decimal,
+    pub cost: Decimal,
+    pub cost_usd: Decimal,
+    pub id: String,
+    pub last_fill_id: String,
+    pub last_updated_at: u64,
+    pub leverage: String,
+    #[serde(
+        deserialize_with = "deserialize_optional_decimal",
+        serialize_with = "serialize_optional_decimal"
+    )]
+    pub liquidation_price: Option<Decimal>,
+    pub market: String,
+    pub seq_no: u64,
+    pub side: PositionSide,
+    pub size: Decimal,
+    pub status: PositionStatus,
+    pub realized_positional_funding_pnl: Decimal,
+    pub realized_positional_pnl: Decimal,
+    pub unrealized_funding_pnl: Decimal,
+    pub unrealized_pnl: Decimal,
+}
+
+impl From<Position> for structs::Position {
+    fn from(position: Position) -> Self {
+        Self {
+            account: position.account,
+            average_entry_price: to_f64(position.average_entry_price),
+            average_entry_price_usd: to_f64(position.average_entry_price_usd),
+            average_exit_price: optional_to_f64(position.average_exit_price),
+            cached_funding_index: to_f64(position.cached_funding_index),
+            cost: to_f64(position.cost),
+            cost_usd: to_f64(position.cost_usd),
+            id: position.id,
+            last_fill_id: position.last_fill_id,
+            last_updated_at: position.last_updated_at,
+            leverage: position.leverage,
+            liquidation_price: optional_to_f64(position.liquidation_price),
+            market: position.market,
+            seq_no: position.seq_no,
+            side: position.side,
+            size: to_f64(position.size),
+            status: position.status,
+            realized_positional_funding_pnl: to_f64(position.realized_positional_funding_pnl),
+            realized_positional_pnl: to_f64(position.realized_positional_pnl),
+            unrealized_funding_pnl: to_f64(position.unrealized_funding_pnl),
+            unrealized_pnl: to_f64(position.unrealized_pnl),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Balance {
+    pub token: String,
+    pub size: Decimal,
+    pub last_updated_at: u64,
+}
+
+impl From<Balance> for structs::Balance {
+    fn from(balance: Balance) -> Self {
+        Self {
+            token: balance.token,
+            size: to_f64(balance.size),
+            last_updated_at: balance.last_updated_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct FundingPayment {
+    pub id: String,
+    pub market: String,
+    pub payment: Decimal,
+    pub index: Decimal,
+    pub fill_id: String,
+    pub created_at: u64,
+}
+
+impl From<FundingPayment> for structs::FundingPayment {
+    fn from(payment: FundingPayment) -> Self {
+        Self {
+            id: payment.id,
+            market: payment.market,
+            payment: to_f64(payment.payment),
+            index: to_f64(payment.index),
+            fill_id: payment.fill_id,
+            created_at: payment.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Transfer {
+    pub account: String,
+    pub amount: Decimal,
+    pub auto_withdrawal_fee: Decimal,
+    pub bridge: TransferBridge,
+    pub counterparty: String,
+    pub created_at: u64,
+    pub direction: TransferDirection,
+    pub external_account: String,
+    pub external_chain: String,
+    pub external_txn_hash: String,
+    pub failure_reason: String,
+    pub id: String,
+    pub kind: TransferKind,
+    pub last_updated_at: u64,
+    pub socialized_loss_factor: Decimal,
+    pub status: TransferStatus,
+    pub token: String,
+    pub txn_hash: String,
+    pub vault_address: String,
+    /// Empty unless the transfer is a vault withdrawal waiting for the vault to unwind
+    #[serde(
+        deserialize_with = "deserialize_optional_decimal",
+        serialize_with = "serialize_optional_decimal"
+    )]
+    pub vault_unwind_completion_percentage: Option<Decimal>,
+}
+
+impl From<Transfer> for structs::Transfer {
+    fn from(transfer: Transfer) -> Self {
+        Self {
+            account: transfer.account,
+            amount: to_f64(transfer.amount),
+            auto_withdrawal_fee: to_f64(transfer.auto_withdrawal_fee),
+            bridge: transfer.bridge,
+            counterparty: transfer.counterparty,
+            created_at: transfer.created_at,
+            direction: transfer.direction,
+            external_account: transfer.external_account,
+            external_chain: transfer.external_chain,
+            external_txn_hash: transfer.external_txn_hash,
+            failure_reason: transfer.failure_reason,
+            id: transfer.id,
+            kind: transfer.kind,
+            last_updated_at: transfer.last_updated_at,
+            socialized_loss_factor: to_f64(transfer.socialized_loss_factor),
+            status: transfer.status,
+            token: transfer.token,
+            txn_hash: transfer.txn_hash,
+            vault_address: transfer.vault_address,
+            vault_unwind_completion_percentage: optional_to_f64(
+                transfer.vault_unwind_completion_percentage,
+            ),
+        }
+    }
+}
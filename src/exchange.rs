@@ -0,0 +1,115 @@
+//! Venue abstraction shared by live trading and backtests.
+//!
+//! [`Exchange`] adds market data subscriptions to the order entry and queries of
+//! [`ParadexApi`]. Strategies written against it run unchanged on a [`LiveExchange`], which
+//! forwards to a rest [`Client`] and [`WebsocketManager`], or on a
+//! [`BacktestExchange`](crate::backtest::BacktestExchange) replaying recorded market data.
+
+use std::future::Future;
+
+use crate::api::ParadexApi;
+use crate::error::Result;
+use crate::rest::Client;
+use crate::structs::{
+    BBO, CancelByMarketResponse, MarketSummaryStatic, ModifyOrderRequest, ModifyOrderResult,
+    OrderRequest, OrderUpdate,
+};
+use crate::ws::{
+    CallbackFn, Channel, ChannelEvent, Identifier, SubscriptionSpec, WebsocketManager,
+};
+
+/// Order entry and market data subscriptions of a venue
+pub trait Exchange: ParadexApi {
+    /// Subscribe to a channel, delivering every message to the callback
+    fn subscribe(
+        &self,
+        channel: Channel,
+        callback: CallbackFn,
+    ) -> impl Future<Output = Result<Identifier>> + Send;
+
+    fn unsubscribe(&self, identifier: Identifier) -> impl Future<Output = Result<()>> + Send;
+
+    /// Subscribe to a channel with a typed callback, see `WebsocketManager::subscribe_typed`
+    fn subscribe_typed<S, F>(
+        &self,
+        spec: S,
+        callback: F,
+    ) -> impl Future<Output = Result<Identifier>> + Send
+    where
+        S: SubscriptionSpec,
+        F: for<'a> Fn(ChannelEvent<'a, S::Payload>) + Send + Sync + 'static,
+    {
+        self.subscribe(
+            spec.into_channel(),
+            WebsocketManager::typed_handler::<S, F>(callback),
+        )
+    }
+}
+
+/// The Paradex venue, trading through a rest Client and streaming through a WebsocketManager
+#[derive(Clone)]
+pub struct LiveExchange {
+    client: Client,
+    manager: WebsocketManager,
+}
+
+impl LiveExchange {
+    pub fn new(client: Client, manager: WebsocketManager) -> Self {
+        Self { client, manager }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn manager(&self) -> &WebsocketManager {
+        &self.manager
+    }
+}
+
+impl ParadexApi for LiveExchange {
+    async fn create_order(&self, order_request: OrderRequest) -> Result<OrderUpdate> {
+        self.client.create_order(order_request).await
+    }
+
+    async fn modify_order(
+        &self,
+        modify_order_request: ModifyOrderRequest,
+    ) -> Result<ModifyOrderResult> {
+        self.client.modify_order(modify_order_request).await
+    }
+
+    async fn cancel_order(&self, order_id: String) -> Result<()> {
+        self.client.cancel_order(order_id).await
+    }
+
+    async fn cancel_all_orders(&self) -> Result<Vec<String>> {
+        self.client.cancel_all_orders().await
+    }
+
+    async fn cancel_all_orders_for_market(&self, market: String) -> Result<CancelByMarketResponse> {
+        self.client.cancel_all_orders_for_market(market).await
+    }
+
+    async fn open_orders(&self) -> Result<Vec<OrderUpdate>> {
+        Ok(self.client.open_orders().await?.results)
+    }
+
+    async fn bbo(&self, market_symbol: String) -> Result<BBO> {
+        self.client.bbo(market_symbol).await
+    }
+
+    async fn markets(&self) -> Result<Vec<MarketSummaryStatic>> {
+        self.client.markets().await
+    }
+}
+
+impl Exchange for LiveExchange {
+    async fn subscribe(&self, channel: Channel, callback: CallbackFn) -> Result<Identifier> {
+        self.manager.subscribe(channel, callback).await
+    }
+
+    async fn unsubscribe(&self, identifier: Identifier) -> Result<()> {
+        self.manager.unsubscribe(identifier).await
+    }
+}
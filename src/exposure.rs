@@ -0,0 +1,215 @@
+//! Account-wide exposure aggregated per underlying.
+//!
+//! [`Exposure`] combines [`Position`]s with the [`MarketSummary`] of their markets. Perpetual
+//! and future positions carry a delta of one unit of the underlying per contract, option
+//! positions the delta reported in their market summary, so the net delta of an underlying is
+//! what has to be traded on its perpetual to hedge the book.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::structs::{MarketSummary, Position, PositionStatus};
+
+/// Exposure to a single underlying, e.g. `BTC`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UnderlyingExposure {
+    /// Net delta in units of the underlying
+    pub delta: f64,
+    /// Net delta valued at the underlying price
+    pub delta_notional: f64,
+    /// Sum of the absolute mark value of the positions
+    pub gross_notional: f64,
+    /// Sum of the signed mark value of the positions
+    pub net_notional: f64,
+    /// Number of open positions on the underlying
+    pub positions: usize,
+}
+
+/// Delta and notional exposure of an account
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Exposure {
+    /// Exposure per underlying, keyed by the base currency of the market symbol
+    pub underlyings: BTreeMap<String, UnderlyingExposure>,
+    /// Sum of the absolute mark value of all positions
+    pub gross_notional: f64,
+    /// Sum of the signed mark value of all positions
+    pub net_notional: f64,
+    /// Markets with an open position but no market summary, left out of the totals
+    pub unpriced: Vec<String>,
+}
+
+impl Exposure {
+    /// Aggregate the open positions using the market summaries of their markets
+    ///
+    /// Closed positions are ignored. Summaries of markets without a position are allowed, so
+    /// the latest update of every market on the `markets_summary` channel can be passed as is.
+    pub fn new(positions: &[Position], summaries: &[MarketSummary]) -> Self {
+        let summaries: HashMap<&str, &MarketSummary> = summaries
+            .iter()
+            .map(|summary| (summary.symbol.as_str(), summary))
+            .collect();
+        let mut exposure = Self::default();
+        for position in positions {
+            if position.status == PositionStatus::CLOSED || position.size == 0. {
+                continue;
+            }
+            let Some(summary) = summaries.get(position.market.as_str()) else {
+                exposure.unpriced.push(position.market.clone());
+                continue;
+            };
+            let delta = position.size * summary.delta.unwrap_or(1.);
+            let notional = position.size * summary.mark_price;
+
+            let underlying = exposure
+                .underlyings
+                .entry(underlying(&position.market).to_string())
+                .or_default();
+            underlying.delta += delta;
+            underlying.delta_notional += delta * summary.underlying_price;
+            underlying.gross_notional += notional.abs();
+            underlying.net_notional += notional;
+            underlying.positions += 1;
+
+            exposure.gross_notional += notional.abs();
+            exposure.net_notional += notional;
+        }
+        exposure
+    }
+
+    /// Net delta of an underlying in units of the underlying, zero without positions
+    pub fn delta(&self, underlying: &str) -> f64 {
+        self.underlyings
+            .get(underlying)
+            .map_or(0., |exposure| exposure.delta)
+    }
+
+    /// Share of each underlying in the gross notional, between 0 and 1
+    pub fn concentration(&self) -> BTreeMap<&str, f64> {
+        self.underlyings
+            .iter()
+            .map(|(underlying, exposure)| {
+                let share = if self.gross_notional > 0. {
+                    exposure.gross_notional / self.gross_notional
+                } else {
+                    0.
+                };
+                (underlying.as_str(), share)
+            })
+            .collect()
+    }
+
+    /// Underlying with the largest share of the gross notional and its share
+    pub fn largest_concentration(&self) -> Option<(&str, f64)> {
+        self.concentration()
+            .into_iter()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// Herfindahl-Hirschman index of the gross notional across underlyings
+    ///
+    /// 1 when all exposure is on a single underlying, approaching 0 as it is spread evenly
+    /// over more underlyings.
+    pub fn herfindahl_index(&self) -> f64 {
+        self.concentration()
+            .values()
+            .map(|share| share * share)
+            .sum()
+    }
+}
+
+/// Underlying of a market symbol, e.g. `BTC` for `BTC-USD-PERP` and `BTC-USD-30000-C`
+fn underlying(market: &str) -> &str {
+    market.split('-').next().unwrap_or(market)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn position(market: &str, size: f64) -> Position {
+        serde_json::from_value(json!({
+            "account": "0x1",
+            "average_entry_price": "0",
+            "average_entry_price_usd": "0",
+            "average_exit_price": "0",
+            "cached_funding_index": "0",
+            "cost": "0",
+            "cost_usd": "0",
+            "id": market,
+            "last_fill_id": "1",
+            "last_updated_at": 0,
+            "leverage": "",
+            "liquidation_price": "",
+            "market": market,
+            "realized_positional_funding_pnl": "0",
+            "realized_positional_pnl": "0",
+            "seq_no": 0,
+            "side": if size < 0. { "SHORT" } else { "LONG" },
+            "size": size.to_string(),
+            "status": "OPEN",
+            "unrealized_funding_pnl": "0",
+            "unrealized_pnl": "0",
+        }))
+        .unwrap()
+    }
+
+    fn summary(
+        symbol: &str,
+        mark_price: f64,
+        underlying_price: f64,
+        delta: Option<f64>,
+    ) -> MarketSummary {
+        let mut summary = json!({
+            "symbol": symbol,
+            "mark_price": mark_price.to_string(),
+            "last_traded_price": "0",
+            "bid": "0",
+            "ask": "0",
+            "total_volume": "0",
+            "created_at": 0,
+            "underlying_price": underlying_price.to_string(),
+            "open_interest": "0",
+            "funding_rate": "0",
+            "price_change_rate_24h": "0",
+        });
+        if let Some(delta) = delta {
+            summary["delta"] = delta.to_string().into();
+        }
+        serde_json::from_value(summary).unwrap()
+    }
+
+    #[test]
+    fn options_hedged_with_perp() {
+        let positions = [
+            position("BTC-USD-PERP", -1.),
+            position("BTC-USD-30000-C", 2.),
+            position("ETH-USD-PERP", 10.),
+            position("SOL-USD-PERP", 5.),
+        ];
+        let summaries = [
+            summary("BTC-USD-PERP", 30000., 30000., None),
+            summary("BTC-USD-30000-C", 1500., 30000., Some(0.5)),
+            summary("ETH-USD-PERP", 2000., 2000., None),
+        ];
+        let exposure = Exposure::new(&positions, &summaries);
+
+        assert_eq!(exposure.delta("BTC"), 0.);
+        assert_eq!(exposure.delta("ETH"), 10.);
+        assert_eq!(exposure.delta("SOL"), 0.);
+        assert_eq!(exposure.unpriced, vec!["SOL-USD-PERP".to_string()]);
+
+        let btc = &exposure.underlyings["BTC"];
+        assert_eq!(btc.positions, 2);
+        assert_eq!(btc.delta_notional, 0.);
+        assert_eq!(btc.gross_notional, 33000.);
+        assert_eq!(btc.net_notional, -27000.);
+
+        assert_eq!(exposure.gross_notional, 53000.);
+        assert_eq!(exposure.net_notional, -7000.);
+        let (largest, share) = exposure.largest_concentration().unwrap();
+        assert_eq!(largest, "BTC");
+        assert!((share - 33. / 53.).abs() < 1e-12);
+        let hhi = (33f64 / 53.).powi(2) + (20f64 / 53.).powi(2);
+        assert!((exposure.herfindahl_index() - hhi).abs() < 1e-12);
+    }
+}
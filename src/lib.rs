@@ -1,8 +1,54 @@
+pub mod account;
+pub mod api;
+pub mod backfill;
+pub mod backtest;
+pub mod bars;
+#[cfg(feature = "bridge")]
+pub mod bridge;
+pub mod conversion;
+pub mod decimal;
 pub mod error;
+pub mod exact;
+pub mod exchange;
+pub mod exposure;
+pub mod liquidation;
+mod listeners;
+pub mod margin;
+pub mod markets;
+pub mod markout;
 pub mod message;
 #[cfg(feature = "onboarding")]
 pub mod onboarding;
+pub mod options;
+pub mod order_builder;
+pub mod orderbook;
+pub mod paper;
+pub mod prelude;
+pub mod query;
+pub mod queue;
+pub mod quoter;
+pub mod reports;
 pub mod rest;
+pub mod signer;
+pub mod sim;
+pub mod stark;
+pub mod store;
+pub mod strategy;
 pub mod structs;
+#[cfg(test)]
+mod testing;
+pub mod tracker;
+pub mod transaction;
 pub mod url;
+pub mod vault;
 pub mod ws;
+
+/// Whether the crate was built with the `onboarding` feature
+///
+/// Without it, the rest client constructors taking an Ethereum private key or account return
+/// `Error::FeatureDisabled`.
+pub const ONBOARDING_SUPPORTED: bool = cfg!(feature = "onboarding");
+
+pub use chrono;
+pub use rust_decimal;
+pub use starknet_crypto;
@@ -0,0 +1,331 @@
+//! Partial liquidation estimates and episode tracking.
+//!
+//! Paradex liquidates an account whose value falls below its maintenance margin requirement
+//! in steps of `partial_liquidation_share_increment` of every position, until the account is
+//! back above the requirement plus a `partial_liquidation_buffer`. [`LiquidationTracker`]
+//! estimates the share that would be liquidated from the account and position streams, and
+//! reports the progress of a liquidation while the account is in one.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use log::warn;
+
+use crate::error::Result;
+use crate::exchange::Exchange;
+use crate::listeners::Listeners;
+use crate::structs::{AccountInformation, AccountStatus, Position, PositionStatus, SystemConfig};
+use crate::ws::{AccountSubscription, ChannelEvent, Identifier, PositionSubscription};
+
+/// Partial liquidation parameters of the venue
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiquidationParams {
+    /// Fraction of the maintenance margin the account must exceed after a partial liquidation
+    pub buffer: f64,
+    /// Positions are liquidated in multiples of this share
+    pub share_increment: f64,
+    /// Fee charged on liquidation, as a fraction of the maintenance margin of the liquidated share
+    pub fee: f64,
+}
+
+impl From<&SystemConfig> for LiquidationParams {
+    fn from(config: &SystemConfig) -> Self {
+        Self {
+            buffer: config.partial_liquidation_buffer,
+            share_increment: config.partial_liquidation_share_increment,
+            fee: config.liquidation_fee,
+        }
+    }
+}
+
+impl LiquidationParams {
+    /// Share of every position that would be liquidated to restore the account, between 0 and 1
+    ///
+    /// Liquidating a share `s` of every position scales the maintenance margin requirement
+    /// `mmr` down to `mmr * (1 - s)` and costs `fee * mmr * s`, so the smallest multiple of the
+    /// share increment satisfying `value - fee * mmr * s >= (1 + buffer) * mmr * (1 - s)` is
+    /// taken. This is an estimate: prices move while positions are closed.
+    pub fn liquidation_share(&self, account_value: f64, maintenance_margin: f64) -> f64 {
+        if maintenance_margin <= 0. || account_value >= maintenance_margin {
+            return 0.;
+        }
+        let target = (1. + self.buffer) * maintenance_margin;
+        let denominator = target - self.fee * maintenance_margin;
+        if account_value <= 0. || denominator <= 0. {
+            return 1.;
+        }
+        let share = ((target - account_value) / denominator).clamp(0., 1.);
+        if self.share_increment > 0. {
+            // Rounding error must not push an exact multiple up a step
+            ((share / self.share_increment - 1e-9).ceil() * self.share_increment).min(1.)
+        } else {
+            share
+        }
+    }
+}
+
+/// Estimated liquidation of one position
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionLiquidation {
+    pub market: String,
+    /// Current signed size of the position
+    pub size: f64,
+    /// Signed size that would be liquidated at the current margin cushion
+    pub to_liquidate: f64,
+    /// Signed size of the position when the liquidation episode started, if in one
+    pub size_at_start: Option<f64>,
+}
+
+impl PositionLiquidation {
+    /// Fraction of the position closed since the liquidation episode started
+    pub fn liquidated_share(&self) -> Option<f64> {
+        self.size_at_start
+            .filter(|start| *start != 0.)
+            .map(|start| (1. - self.size / start).clamp(0., 1.))
+    }
+}
+
+/// Liquidation estimate of the account at the time of an update
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiquidationEstimate {
+    /// Whether the venue reports the account as being liquidated
+    pub in_liquidation: bool,
+    pub account_value: f64,
+    pub maintenance_margin_requirement: f64,
+    pub margin_cushion: f64,
+    /// Share of every position that would be liquidated, zero while above maintenance margin
+    pub share: f64,
+    pub positions: Vec<PositionLiquidation>,
+    pub updated_at: u64,
+}
+
+/// Change in the liquidation status of the account
+#[derive(Debug, Clone, PartialEq)]
+pub enum LiquidationEvent {
+    Started(LiquidationEstimate),
+    /// Account or position update while the account is being liquidated
+    Progress(LiquidationEstimate),
+    Ended(LiquidationEstimate),
+}
+
+#[derive(Default)]
+struct TrackerState {
+    account: Option<AccountInformation>,
+    positions: HashMap<String, Position>,
+    /// Position sizes when the current liquidation episode started
+    sizes_at_start: Option<HashMap<String, f64>>,
+}
+
+/// Tracks the account's distance to liquidation and the progress of liquidation episodes
+///
+/// Clones share the same state.
+#[derive(Clone)]
+pub struct LiquidationTracker {
+    params: LiquidationParams,
+    state: Arc<Mutex<TrackerState>>,
+    listeners: Listeners<LiquidationEvent>,
+}
+
+impl LiquidationTracker {
+    pub fn new(params: LiquidationParams) -> Self {
+        Self {
+            params,
+            state: Arc::default(),
+            listeners: Listeners::default(),
+        }
+    }
+
+    /// Call `callback` when a liquidation episode starts, progresses and ends
+    pub fn on_event(&self, callback: impl Fn(&LiquidationEvent) + Send + Sync + 'static) {
+        self.listeners.push(callback);
+    }
+
+    /// Feed the account and positions channels into the tracker
+    ///
+    /// # Returns
+    ///
+    /// The identifiers of the account and positions subscriptions
+    ///
+    /// # Errors
+    ///
+    /// If either subscription cannot be made
+    pub async fn attach<E: Exchange>(&self, exchange: &E) -> Result<Vec<Identifier>> {
+        let tracker = self.clone();
+        let account = exchange
+            .subscribe_typed(AccountSubscription, move |event| match event {
+                ChannelEvent::Data(account) => tracker.on_account(account),
+                ChannelEvent::Error(e) => warn!("Account subscription error {e:?}"),
+                _ => {}
+            })
+            .await?;
+        let tracker = self.clone();
+        let positions = exchange
+            .subscribe_typed(PositionSubscription, move |event| match event {
+                ChannelEvent::Data(position) => tracker.on_position(position),
+                ChannelEvent::Error(e) => warn!("Positions subscription error {e:?}"),
+                _ => {}
+            })
+            .await?;
+        Ok(vec![account, positions])
+    }
+
+    /// Apply an account update, ignoring updates older than the current one
+    pub fn on_account(&self, account: &AccountInformation) {
+        let event = {
+            let mut state = self.state.lock().unwrap();
+            if state
+                .account
+                .as_ref()
+                .is_some_and(|current| current.seq_no >= account.seq_no)
+            {
+                return;
+            }
+            state.account = Some(account.clone());
+            let in_liquidation = account.status == AccountStatus::LIQUIDATION;
+            match (in_liquidation, state.sizes_at_start.is_some()) {
+                (true, false) => {
+                    state.sizes_at_start = Some(
+                        state
+                            .positions
+                            .iter()
+                            .map(|(market, position)| (market.clone(), position.size))
+                            .collect(),
+                    );
+                    Some(LiquidationEvent::Started(self.estimate_locked(&state)))
+                }
+                (true, true) => Some(LiquidationEvent::Progress(self.estimate_locked(&state))),
+                (false, true) => {
+                    let estimate = self.estimate_locked(&state);
+                    state.sizes_at_start = None;
+                    Some(LiquidationEvent::Ended(estimate))
+                }
+                (false, false) => None,
+            }
+        };
+        self.notify(event);
+    }
+
+    /// Apply a position update, ignoring updates older than the current one
+    pub fn on_position(&self, position: &Position) {
+        let event = {
+            let mut state = self.state.lock().unwrap();
+            if state
+                .positions
+                .get(&position.market)
+                .is_some_and(|current| current.seq_no >= position.seq_no)
+            {
+                return;
+            }
+            if position.status == PositionStatus::CLOSED && state.sizes_at_start.is_none() {
+                state.positions.remove(&position.market);
+            } else {
+                state
+                    .positions
+                    .insert(position.market.clone(), position.clone());
+            }
+            state
+                .sizes_at_start
+                .is_some()
+                .then(|| LiquidationEvent::Progress(self.estimate_locked(&state)))
+        };
+        self.notify(event);
+    }
+
+    /// Estimate at the latest account update, None before the first one
+    pub fn estimate(&self) -> Option<LiquidationEstimate> {
+        let state = self.state.lock().unwrap();
+        state.account.as_ref()?;
+        Some(self.estimate_locked(&state))
+    }
+
+    fn estimate_locked(&self, state: &TrackerState) -> LiquidationEstimate {
+        let account = state
+            .account
+            .as_ref()
+            .expect("estimates are only made after an account update");
+        let share = self.params.liquidation_share(
+            account.account_value,
+            account.maintenance_margin_requirement,
+        );
+        let mut positions: Vec<PositionLiquidation> = state
+            .positions
+            .values()
+            .map(|position| PositionLiquidation {
+                market: position.market.clone(),
+                size: position.size,
+                to_liquidate: position.size * share,
+                size_at_start: state
+                    .sizes_at_start
+                    .as_ref()
+                    .and_then(|sizes| sizes.get(&position.market).copied()),
+            })
+            .collect();
+        positions.sort_by(|a, b| a.market.cmp(&b.market));
+        LiquidationEstimate {
+            in_liquidation: account.status == AccountStatus::LIQUIDATION,
+            account_value: account.account_value,
+            maintenance_margin_requirement: account.maintenance_margin_requirement,
+            margin_cushion: account.margin_cushion,
+            share,
+            positions,
+            updated_at: account.updated_at,
+        }
+    }
+
+    fn notify(&self, event: Option<LiquidationEvent>) {
+        let Some(event) = event else {
+            return;
+        };
+        self.listeners.notify(&event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{account, position};
+
+    const PARAMS: LiquidationParams = LiquidationParams {
+        buffer: 0.2,
+        share_increment: 0.05,
+        fee: 0.7,
+    };
+
+    #[test]
+    fn liquidation_share() {
+        assert_eq!(PARAMS.liquidation_share(1500., 1000.), 0.);
+        assert!((PARAMS.liquidation_share(900., 1000.) - 0.6).abs() < 1e-9);
+        assert!((PARAMS.liquidation_share(990., 1000.) - 0.45).abs() < 1e-9);
+        assert_eq!(PARAMS.liquidation_share(-10., 1000.), 1.);
+    }
+
+    #[test]
+    fn episode() {
+        let tracker = LiquidationTracker::new(PARAMS);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        tracker.on_event(move |event| recorded.lock().unwrap().push(event.clone()));
+
+        tracker.on_position(&position(1, -2.));
+        tracker.on_account(&account(1, 1500., AccountStatus::ACTIVE));
+        assert_eq!(tracker.estimate().unwrap().share, 0.);
+
+        tracker.on_account(&account(2, 900., AccountStatus::LIQUIDATION));
+        tracker.on_position(&position(2, -1.));
+        // Stale update
+        tracker.on_position(&position(1, -2.));
+        tracker.on_account(&account(3, 1300., AccountStatus::ACTIVE));
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        let LiquidationEvent::Started(started) = &events[0] else {
+            panic!("expected start, got {:?}", events[0]);
+        };
+        assert!((started.positions[0].to_liquidate + 1.2).abs() < 1e-9);
+        let LiquidationEvent::Progress(progress) = &events[1] else {
+            panic!("expected progress, got {:?}", events[1]);
+        };
+        assert_eq!(progress.positions[0].liquidated_share(), Some(0.5));
+        assert!(matches!(&events[2], LiquidationEvent::Ended(ended) if ended.share == 0.));
+    }
+}
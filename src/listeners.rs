@@ -0,0 +1,41 @@
+use std::sync::{Arc, Mutex};
+
+type ListenerFn<T> = Arc<dyn Fn(&T) + Send + Sync>;
+
+/// Callbacks registered for the events of a tracker or monitor
+///
+/// Clones share the same callbacks.
+pub(crate) struct Listeners<T>(Arc<Mutex<Vec<ListenerFn<T>>>>);
+
+impl<T> Listeners<T> {
+    pub(crate) fn push(&self, callback: impl Fn(&T) + Send + Sync + 'static) {
+        self.0.lock().unwrap().push(Arc::new(callback));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.lock().unwrap().is_empty()
+    }
+
+    /// Call every callback with `event`
+    ///
+    /// The callbacks are called without holding the lock, so they may register further
+    /// callbacks.
+    pub(crate) fn notify(&self, event: &T) {
+        let listeners = self.0.lock().unwrap().clone();
+        for listener in listeners {
+            listener(event);
+        }
+    }
+}
+
+impl<T> Clone for Listeners<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Default for Listeners<T> {
+    fn default() -> Self {
+        Self(Arc::default())
+    }
+}
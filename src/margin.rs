@@ -0,0 +1,398 @@
+//! Margin warnings from the account, positions and market summary channels.
+//!
+//! The Paradex websocket API has no margin events channel, so there is no `Channel` variant for
+//! one: the account channel carries the margin requirement and [`AccountStatus`] of the account,
+//! positions carry their liquidation price and market summaries the mark price.
+//! [`MarginMonitor`] follows the account and positions of an [`AccountState`] along with the mark
+//! prices, and raises a [`MarginEvent`] whenever the account crosses a [`MarginLevel`] or changes
+//! status, so risk engines can react to approaching liquidations without polling
+//! `account_information()`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use log::warn;
+
+use crate::account::{AccountSnapshot, AccountState};
+use crate::error::Result;
+use crate::exchange::Exchange;
+use crate::listeners::Listeners;
+use crate::structs::{AccountInformation, AccountStatus, MarketSummary};
+use crate::ws::{ChannelEvent, Identifier, MarketSummarySubscription};
+
+/// Margin ratios, maintenance margin requirement over account value, and distances of the
+/// closest position to its liquidation price at which the account enters the warning and
+/// critical levels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarginThresholds {
+    pub warning: f64,
+    pub critical: f64,
+    /// Distance as defined by `PositionProximity::distance`
+    pub warning_distance: f64,
+    pub critical_distance: f64,
+}
+
+impl Default for MarginThresholds {
+    fn default() -> Self {
+        Self {
+            warning: 0.5,
+            critical: 0.8,
+            warning_distance: 0.05,
+            critical_distance: 0.02,
+        }
+    }
+}
+
+/// Severity of the account's margin position, in increasing order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MarginLevel {
+    Healthy,
+    Warning,
+    Critical,
+    /// The venue reports the account as being liquidated
+    Liquidation,
+}
+
+/// Distance of a position's mark price to its liquidation price
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionProximity {
+    pub market: String,
+    pub size: f64,
+    /// Latest mark price of the market, NaN before the first market summary
+    pub mark_price: f64,
+    /// NaN when the venue reports none
+    pub liquidation_price: f64,
+    /// Adverse price move taking the position to its liquidation price, as a fraction of the
+    /// mark price, NaN when either price is unknown
+    pub distance: f64,
+}
+
+/// Margin state of the account when it changed level or status
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarginEvent {
+    pub level: MarginLevel,
+    /// None for the first account update
+    pub previous_level: Option<MarginLevel>,
+    pub status: AccountStatus,
+    /// None for the first account update
+    pub previous_status: Option<AccountStatus>,
+    /// Maintenance margin requirement over account value, 1 or more when liquidatable
+    pub margin_ratio: f64,
+    pub account_value: f64,
+    pub maintenance_margin_requirement: f64,
+    pub margin_cushion: f64,
+    /// Open positions, closest to liquidation first
+    pub positions: Vec<PositionProximity>,
+    pub updated_at: u64,
+}
+
+#[derive(Default)]
+struct MonitorState {
+    account: AccountSnapshot,
+    mark_prices: HashMap<String, f64>,
+}
+
+/// Raises margin events from an account state and the market summary channel
+///
+/// Clones share the same state.
+#[derive(Clone)]
+pub struct MarginMonitor {
+    thresholds: MarginThresholds,
+    state: Arc<Mutex<MonitorState>>,
+    listeners: Listeners<MarginEvent>,
+}
+
+/// Adverse move from `mark_price` to `liquidation_price` as a fraction of the mark price
+fn distance(mark_price: f64, liquidation_price: f64) -> f64 {
+    (mark_price - liquidation_price).abs() / mark_price
+}
+
+fn margin_ratio(account: &AccountInformation) -> f64 {
+    if account.maintenance_margin_requirement <= 0. {
+        0.
+    } else if account.account_value <= 0. {
+        f64::INFINITY
+    } else {
+        account.maintenance_margin_requirement / account.account_value
+    }
+}
+
+impl MarginMonitor {
+    pub fn new(thresholds: MarginThresholds) -> Self {
+        Self {
+            thresholds,
+            state: Arc::default(),
+            listeners: Listeners::default(),
+        }
+    }
+
+    /// Call `callback` when the account changes margin level or status
+    ///
+    /// Account, position and mark price updates all raise an event when they change the level,
+    /// the latter two by moving the closest position across a distance threshold. The first
+    /// account update raises an event unless the account is healthy; no events are raised
+    /// before it.
+    pub fn on_event(&self, callback: impl Fn(&MarginEvent) + Send + Sync + 'static) {
+        self.listeners.push(callback);
+    }
+
+    /// Follow the account and positions of `account_state`, which is kept current with
+    /// `AccountState::attach`
+    pub fn watch(&self, account_state: &AccountState) {
+        let monitor = self.clone();
+        account_state.on_change(move |snapshot| monitor.on_account_state(snapshot));
+    }
+
+    /// Feed the market summary channel into the monitor
+    ///
+    /// # Returns
+    ///
+    /// The identifier of the market summary subscription
+    ///
+    /// # Errors
+    ///
+    /// If the subscription cannot be made
+    pub async fn attach<E: Exchange>(&self, exchange: &E) -> Result<Identifier> {
+        let monitor = self.clone();
+        exchange
+            .subscribe_typed(MarketSummarySubscription, move |event| match event {
+                ChannelEvent::Data(summary) => monitor.on_market_summary(summary),
+                ChannelEvent::Error(e) => warn!("Market summary subscription error {e:?}"),
+                _ => {}
+            })
+            .await
+    }
+
+    /// Apply the account state after an update, see `AccountState::on_change`
+    pub fn on_account_state(&self, snapshot: &AccountSnapshot) {
+        self.update(|state| {
+            state.account = snapshot.clone();
+            true
+        });
+    }
+
+    /// Record the mark price of a market
+    pub fn on_market_summary(&self, summary: &MarketSummary) {
+        if !summary.mark_price.is_finite() {
+            return;
+        }
+        self.update(|state| {
+            state
+                .mark_prices
+                .insert(summary.symbol.clone(), summary.mark_price);
+            true
+        });
+    }
+
+    /// Margin state at the latest account update, None before the first one
+    pub fn current(&self) -> Option<MarginEvent> {
+        let state = self.state.lock().unwrap();
+        state.account.account.as_ref()?;
+        Some(self.event_locked(&state, None, None))
+    }
+
+    /// Apply `f` to the state, raising an event if it returns true and the level or status
+    /// changed
+    fn update(&self, f: impl FnOnce(&mut MonitorState) -> bool) {
+        let event = {
+            let mut state = self.state.lock().unwrap();
+            let previous = self.assess(&state);
+            if !f(&mut state) {
+                return;
+            }
+            let Some((level, status)) = self.assess(&state) else {
+                return;
+            };
+            let changed = match &previous {
+                Some((previous_level, previous_status)) => {
+                    *previous_level != level || *previous_status != status
+                }
+                None => level != MarginLevel::Healthy,
+            };
+            if !changed {
+                return;
+            }
+            let (previous_level, previous_status) = previous.unzip();
+            self.event_locked(&state, previous_level, previous_status)
+        };
+        self.listeners.notify(&event);
+    }
+
+    /// Level and status of the account, None before the first account update
+    fn assess(&self, state: &MonitorState) -> Option<(MarginLevel, AccountStatus)> {
+        let account = state.account.account.as_ref()?;
+        Some((self.level(state, account), account.status.clone()))
+    }
+
+    fn level(&self, state: &MonitorState, account: &AccountInformation) -> MarginLevel {
+        if account.status == AccountStatus::LIQUIDATION {
+            return MarginLevel::Liquidation;
+        }
+        let ratio = margin_ratio(account);
+        let closest = state
+            .account
+            .open_positions()
+            .filter_map(|position| {
+                let mark_price = state.mark_prices.get(&position.market)?;
+                Some(distance(*mark_price, position.liquidation_price))
+            })
+            .filter(|distance| !distance.is_nan())
+            .fold(f64::INFINITY, f64::min);
+        let thresholds = &self.thresholds;
+        if ratio >= thresholds.critical || closest <= thresholds.critical_distance {
+            MarginLevel::Critical
+        } else if ratio >= thresholds.warning || closest <= thresholds.warning_distance {
+            MarginLevel::Warning
+        } else {
+            MarginLevel::Healthy
+        }
+    }
+
+    fn event_locked(
+        &self,
+        state: &MonitorState,
+        previous_level: Option<MarginLevel>,
+        previous_status: Option<AccountStatus>,
+    ) -> MarginEvent {
+        let account = state
+            .account
+            .account
+            .as_ref()
+            .expect("events are only made after an account update");
+        let mut positions: Vec<PositionProximity> = state
+            .account
+            .open_positions()
+            .map(|position| {
+                let mark_price = state
+                    .mark_prices
+                    .get(&position.market)
+                    .copied()
+                    .unwrap_or(f64::NAN);
+                PositionProximity {
+                    market: position.market.clone(),
+                    size: position.size,
+                    mark_price,
+                    liquidation_price: position.liquidation_price,
+                    distance: distance(mark_price, position.liquidation_price),
+                }
+            })
+            .collect();
+        positions.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        MarginEvent {
+            level: self.level(state, account),
+            previous_level,
+            status: account.status.clone(),
+            previous_status,
+            margin_ratio: margin_ratio(account),
+            account_value: account.account_value,
+            maintenance_margin_requirement: account.maintenance_margin_requirement,
+            margin_cushion: account.margin_cushion,
+            positions,
+            updated_at: account.updated_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::PositionStatus;
+    use crate::testing::{account, position};
+
+    #[test]
+    fn level_transitions() {
+        let monitor = MarginMonitor::new(MarginThresholds::default());
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        monitor.on_event(move |event| recorded.lock().unwrap().push(event.clone()));
+        let account_state = AccountState::new();
+        monitor.watch(&account_state);
+
+        let mut short = position(1, -2.);
+        short.liquidation_price = 90.;
+        account_state.on_position(&short);
+        let mut summary: MarketSummary =
+            serde_json::from_str(include_str!("../tests/fixtures/market_summary.json")).unwrap();
+        summary.symbol = short.market.clone();
+        summary.mark_price = 100.;
+        monitor.on_market_summary(&summary);
+
+        account_state.on_account(&account(1, 4000., AccountStatus::ACTIVE));
+        account_state.on_account(&account(2, 3000., AccountStatus::ACTIVE));
+        account_state.on_account(&account(3, 1800., AccountStatus::ACTIVE));
+        // Stale update
+        account_state.on_account(&account(1, 4000., AccountStatus::ACTIVE));
+        account_state.on_account(&account(4, 1100., AccountStatus::ACTIVE));
+        account_state.on_account(&account(5, 900., AccountStatus::LIQUIDATION));
+        account_state.on_account(&account(6, 3000., AccountStatus::ACTIVE));
+
+        let events = events.lock().unwrap();
+        let levels: Vec<_> = events
+            .iter()
+            .map(|event| (event.previous_level, event.level))
+            .collect();
+        assert_eq!(
+            levels,
+            vec![
+                (Some(MarginLevel::Healthy), MarginLevel::Warning),
+                (Some(MarginLevel::Warning), MarginLevel::Critical),
+                (Some(MarginLevel::Critical), MarginLevel::Liquidation),
+                (Some(MarginLevel::Liquidation), MarginLevel::Healthy),
+            ]
+        );
+        assert_eq!(events[2].previous_status, Some(AccountStatus::ACTIVE));
+        assert_eq!(events[2].status, AccountStatus::LIQUIDATION);
+        assert!((events[0].margin_ratio - 0.5555).abs() < 1e-3);
+        assert!((events[0].positions[0].distance - 0.1).abs() < 1e-9);
+        assert_eq!(monitor.current().unwrap().level, MarginLevel::Healthy);
+    }
+
+    #[test]
+    fn position_transitions() {
+        let monitor = MarginMonitor::new(MarginThresholds::default());
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        monitor.on_event(move |event| recorded.lock().unwrap().push(event.clone()));
+        let account_state = AccountState::new();
+        monitor.watch(&account_state);
+
+        let mut short = position(1, -2.);
+        short.liquidation_price = 102.;
+        account_state.on_position(&short);
+        let mut summary: MarketSummary =
+            serde_json::from_str(include_str!("../tests/fixtures/market_summary.json")).unwrap();
+        summary.symbol = short.market.clone();
+        summary.mark_price = 90.;
+        monitor.on_market_summary(&summary);
+        account_state.on_account(&account(1, 4000., AccountStatus::ACTIVE));
+        assert!(events.lock().unwrap().is_empty());
+
+        // The short's liquidation price comes closer, then the mark price rises towards it
+        short.seq_no = 2;
+        short.liquidation_price = 94.;
+        account_state.on_position(&short);
+        summary.mark_price = 92.;
+        monitor.on_market_summary(&summary);
+        summary.mark_price = 92.5;
+        monitor.on_market_summary(&summary);
+        short.seq_no = 3;
+        short.status = PositionStatus::CLOSED;
+        account_state.on_position(&short);
+
+        let events = events.lock().unwrap();
+        let levels: Vec<_> = events
+            .iter()
+            .map(|event| (event.previous_level, event.level))
+            .collect();
+        assert_eq!(
+            levels,
+            vec![
+                (Some(MarginLevel::Healthy), MarginLevel::Warning),
+                (Some(MarginLevel::Warning), MarginLevel::Critical),
+                (Some(MarginLevel::Critical), MarginLevel::Healthy),
+            ]
+        );
+        assert!((events[1].positions[0].distance - 1.5 / 92.5).abs() < 1e-9);
+        assert!(events[2].positions.is_empty());
+    }
+}
@@ -0,0 +1,380 @@
+//! Market configurations kept current by polling.
+//!
+//! Paradex does not broadcast instrument configuration changes over the websocket, so
+//! [`MarketRegistry`] refetches the static market list and diffs it against its cache. Every
+//! new listing, delisting and parameter change, such as a new tick size or position limit, is
+//! reported as a [`MarketConfigChanged`] event.
+//!
+//! A registry installed on a client also caches the market summaries, and with a TTL is
+//! refetched by lookups through the client once stale, so the tick size, size increment and
+//! minimum notional of a market can be looked up without a request per order.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use tokio::task::JoinHandle;
+
+use crate::api::ParadexApi;
+use crate::error::Result;
+use crate::structs::{MarketSummary, MarketSummaryStatic};
+
+/// Change of the configuration of a market between two fetches of the market list
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarketConfigChanged {
+    Listed(MarketSummaryStatic),
+    Delisted(MarketSummaryStatic),
+    Updated {
+        previous: Box<MarketSummaryStatic>,
+        current: MarketSummaryStatic,
+    },
+}
+
+impl MarketConfigChanged {
+    pub fn symbol(&self) -> &str {
+        match self {
+            MarketConfigChanged::Listed(market) | MarketConfigChanged::Delisted(market) => {
+                &market.symbol
+            }
+            MarketConfigChanged::Updated { current, .. } => &current.symbol,
+        }
+    }
+}
+
+/// Background task of `MarketRegistry::poll`, stopped when dropped
+pub struct MarketRegistryPoller(JoinHandle<()>);
+
+impl MarketRegistryPoller {
+    /// Stop polling, same as dropping the poller
+    pub fn stop(self) {}
+}
+
+impl Drop for MarketRegistryPoller {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Markets and their summaries as of one fetch
+struct CachedMarkets {
+    fetched_at: Instant,
+    markets: BTreeMap<String, MarketSummaryStatic>,
+    summaries: BTreeMap<String, MarketSummary>,
+}
+
+/// Cache of the market configurations and summaries by symbol, shared between clones
+///
+/// Kept current either by `poll`, or when installed on a client with `Client::with_market_cache`
+/// or `Client::with_market_registry`, by lookups through the client which refetch it once it is
+/// stale.
+#[derive(Clone, Default)]
+pub struct MarketRegistry {
+    /// How long fetched markets are used before being considered stale, None for ever
+    ttl: Option<Duration>,
+    /// None until the market list was first loaded, or since it was invalidated
+    cached: Arc<RwLock<Option<CachedMarkets>>>,
+    /// Held while refreshing, so concurrent lookups of a stale cache fetch only once
+    refreshing: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl MarketRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry whose markets are stale once older than `ttl`
+    pub fn with_ttl(ttl: Duration) -> Self {
+        Self {
+            ttl: Some(ttl),
+            ..Self::default()
+        }
+    }
+
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<MarketSummaryStatic> {
+        self.lookup(|cached| cached.markets.get(symbol).cloned())
+    }
+
+    /// The cached summary of a market, as of the last refresh through a client
+    pub fn summary(&self, symbol: &str) -> Option<MarketSummary> {
+        self.lookup(|cached| cached.summaries.get(symbol).cloned())
+    }
+
+    /// Every cached market, ordered by symbol
+    pub fn markets(&self) -> Vec<MarketSummaryStatic> {
+        self.lookup(|cached| Some(cached.markets.values().cloned().collect()))
+            .unwrap_or_default()
+    }
+
+    pub fn tick_size(&self, symbol: &str) -> Option<f64> {
+        self.lookup(|cached| Some(cached.markets.get(symbol)?.price_tick_size))
+    }
+
+    pub fn size_increment(&self, symbol: &str) -> Option<f64> {
+        self.lookup(|cached| Some(cached.markets.get(symbol)?.order_size_increment))
+    }
+
+    pub fn min_notional(&self, symbol: &str) -> Option<f64> {
+        self.lookup(|cached| Some(cached.markets.get(symbol)?.min_notional))
+    }
+
+    /// Whether the market list was loaded
+    pub fn is_loaded(&self) -> bool {
+        self.cached.read().unwrap().is_some()
+    }
+
+    /// Whether the market list was never loaded, was invalidated or is older than the TTL
+    pub fn is_stale(&self) -> bool {
+        self.cached.read().unwrap().as_ref().is_none_or(|cached| {
+            self.ttl
+                .is_some_and(|ttl| cached.fetched_at.elapsed() >= ttl)
+        })
+    }
+
+    /// Empty the cache, so the next lookup through a client refetches it
+    ///
+    /// The next update then reports no changes, as for the first one.
+    pub fn invalidate(&self) {
+        self.cached.write().unwrap().take();
+    }
+
+    /// Replace the cached markets with a fetched market list, returning the changes against it
+    ///
+    /// The first list only fills the cache and reports no changes. Changes are ordered by
+    /// symbol. Cached summaries are kept.
+    pub fn update(&self, markets: Vec<MarketSummaryStatic>) -> Vec<MarketConfigChanged> {
+        let mut current = markets
+            .into_iter()
+            .map(|market| (market.symbol.clone(), market))
+            .collect::<BTreeMap<_, _>>();
+        let mut cache = self.cached.write().unwrap();
+        let previous = match cache.as_mut() {
+            Some(cached) => {
+                cached.fetched_at = Instant::now();
+                std::mem::replace(&mut cached.markets, current.clone())
+            }
+            None => {
+                *cache = Some(CachedMarkets {
+                    fetched_at: Instant::now(),
+                    markets: current,
+                    summaries: BTreeMap::new(),
+                });
+                return Vec::new();
+            }
+        };
+        drop(cache);
+
+        let mut changes = Vec::new();
+        for (symbol, previous) in previous {
+            match current.remove(&symbol) {
+                None => changes.push(MarketConfigChanged::Delisted(previous)),
+                Some(current) if !same_config(&previous, &current) => {
+                    changes.push(MarketConfigChanged::Updated {
+                        previous: Box::new(previous),
+                        current,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        changes.extend(current.into_values().map(MarketConfigChanged::Listed));
+        changes.sort_by(|a, b| a.symbol().cmp(b.symbol()));
+        changes
+    }
+
+    /// Replace the cached summaries, which are only kept once the market list was loaded
+    pub fn update_summaries(&self, summaries: Vec<MarketSummary>) {
+        if let Some(cached) = self.cached.write().unwrap().as_mut() {
+            cached.summaries = summaries
+                .into_iter()
+                .map(|summary| (summary.symbol.clone(), summary))
+                .collect();
+        }
+    }
+
+    fn lookup<T>(&self, f: impl FnOnce(&CachedMarkets) -> Option<T>) -> Option<T> {
+        self.cached.read().unwrap().as_ref().and_then(f)
+    }
+
+    /// Fetch the market list and update the cache with it, see `update`
+    ///
+    /// # Errors
+    ///
+    /// If the market list cannot be fetched
+    pub async fn refresh<A: ParadexApi>(&self, api: &A) -> Result<Vec<MarketConfigChanged>> {
+        Ok(self.update(api.markets().await?))
+    }
+
+    /// Refresh the cache every `interval` in the background, delivering every change to the
+    /// callback
+    ///
+    /// The first refresh is immediate and only reports changes if the cache was loaded before.
+    /// Failed fetches are logged and keep the cached markets.
+    pub fn poll<A, F>(&self, api: A, interval: Duration, callback: F) -> MarketRegistryPoller
+    where
+        A: ParadexApi,
+        F: Fn(&MarketConfigChanged) + Send + Sync + 'static,
+    {
+        let registry = self.clone();
+        MarketRegistryPoller(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                match registry.refresh(&api).await {
+                    Ok(changes) => changes.iter().for_each(&callback),
+                    Err(e) => warn!("Could not poll the markets: {e}"),
+                }
+            }
+        }))
+    }
+
+    /// Refresh the markets and summaries with `fetch` if the cache is stale, waiting for a
+    /// refresh already running instead of fetching again
+    pub(crate) async fn refresh_if_stale<F, Fut>(&self, fetch: F) -> Result<()>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(Vec<MarketSummaryStatic>, Vec<MarketSummary>)>>,
+    {
+        if !self.is_stale() {
+            return Ok(());
+        }
+        let _refreshing = self.refreshing.lock().await;
+        if self.is_stale() {
+            self.apply(fetch().await?);
+        }
+        Ok(())
+    }
+
+    /// Refresh the markets and summaries with `fetch` whether the cache is stale or not
+    pub(crate) async fn refresh_with<F, Fut>(&self, fetch: F) -> Result<()>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(Vec<MarketSummaryStatic>, Vec<MarketSummary>)>>,
+    {
+        let _refreshing = self.refreshing.lock().await;
+        self.apply(fetch().await?);
+        Ok(())
+    }
+
+    fn apply(&self, (markets, summaries): (Vec<MarketSummaryStatic>, Vec<MarketSummary>)) {
+        for change in self.update(markets) {
+            info!("Market configuration changed: {change:?}");
+        }
+        self.update_summaries(summaries);
+    }
+}
+
+/// Compare configurations by their serialized form, in which unset numbers parsed as NaN are
+/// equal to each other
+fn same_config(a: &MarketSummaryStatic, b: &MarketSummaryStatic) -> bool {
+    a == b || serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::ResultsContainer;
+
+    #[test]
+    fn update() {
+        let markets: ResultsContainer<Vec<MarketSummaryStatic>> =
+            serde_json::from_str(include_str!("../tests/fixtures/markets.json")).unwrap();
+        let markets = markets.results;
+        assert!(markets.len() >= 2);
+        let registry = MarketRegistry::new();
+        assert!(registry.update(markets.clone()).is_empty());
+        assert!(registry.is_loaded());
+        assert!(registry.update(markets.clone()).is_empty());
+
+        let mut changed = markets.clone();
+        let delisted = changed.remove(0);
+        changed[0].price_tick_size *= 10.;
+        let mut listed = changed[0].clone();
+        listed.symbol = "NEW-USD-PERP".to_string();
+        changed.push(listed);
+
+        // The fixture leaves numbers unset, which parse as NaN, so compare by symbol
+        let changes = registry.update(changed.clone());
+        let kinds = changes
+            .iter()
+            .map(|change| match change {
+                MarketConfigChanged::Listed(_) => ("listed", change.symbol()),
+                MarketConfigChanged::Delisted(_) => ("delisted", change.symbol()),
+                MarketConfigChanged::Updated { previous, current } => {
+                    assert_eq!(previous.price_tick_size * 10., current.price_tick_size);
+                    ("updated", change.symbol())
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(kinds.len(), 3);
+        assert!(kinds.contains(&("delisted", delisted.symbol.as_str())));
+        assert!(kinds.contains(&("listed", "NEW-USD-PERP")));
+        assert!(kinds.contains(&("updated", changed[0].symbol.as_str())));
+        assert!(changes.is_sorted_by(|a, b| a.symbol() <= b.symbol()));
+        assert_eq!(
+            registry.get(&changed[0].symbol).unwrap().price_tick_size,
+            changed[0].price_tick_size
+        );
+        assert_eq!(registry.get(&delisted.symbol), None);
+        assert_eq!(registry.markets().len(), changed.len());
+    }
+
+    #[tokio::test]
+    async fn refresh_through_client() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let markets: ResultsContainer<Vec<MarketSummaryStatic>> =
+            serde_json::from_str(include_str!("../tests/fixtures/markets.json")).unwrap();
+        let markets = markets.results;
+        let summary: MarketSummary =
+            serde_json::from_str(include_str!("../tests/fixtures/market_summary.json")).unwrap();
+        let fetches = AtomicUsize::new(0);
+        let fetch = || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok((markets.clone(), vec![summary.clone()]))
+        };
+
+        let registry = MarketRegistry::with_ttl(Duration::from_millis(100));
+        assert!(registry.is_stale());
+        assert_eq!(registry.get(&markets[0].symbol), None);
+        // Concurrent lookups of a stale registry fetch once
+        let (a, b) = tokio::join!(
+            registry.refresh_if_stale(fetch),
+            registry.refresh_if_stale(fetch)
+        );
+        a.unwrap();
+        b.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert!(!registry.is_stale());
+        assert_eq!(registry.markets().len(), markets.len());
+        assert_eq!(
+            registry.tick_size(&markets[0].symbol),
+            Some(markets[0].price_tick_size)
+        );
+        assert_eq!(
+            registry.size_increment(&markets[0].symbol),
+            Some(markets[0].order_size_increment)
+        );
+        assert_eq!(registry.summary("BTC-USD-PERP").unwrap().bid, summary.bid);
+        assert_eq!(registry.min_notional("NEW-USD-PERP"), None);
+
+        registry.refresh_if_stale(fetch).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        registry.refresh_with(fetch).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(registry.is_stale());
+        registry.refresh_if_stale(fetch).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+        registry.invalidate();
+        assert!(registry.is_stale());
+        assert_eq!(registry.get(&markets[0].symbol), None);
+    }
+}
@@ -0,0 +1,417 @@
+//! Execution quality of the account's fills measured against the mid price.
+//!
+//! [`MarkoutAnalyzer`] keeps a short history of each market's mid price from the BBO channel
+//! and, for every fill, records the mid at the time of the fill and at fixed horizons after
+//! it. The edge against the mid at the fill is the spread captured, the edge against the later
+//! mids is the mark-out: how the price moved after trading, which is negative when fills are
+//! picked off by better informed flow. Statistics are grouped by a strategy tag derived from
+//! each fill and summarized in a serializable [`MarkoutReport`].
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::exchange::Exchange;
+use crate::structs::{BBO, Fill, Side};
+use crate::ws::{BboSubscription, ChannelEvent, FillsSubscription, Identifier};
+
+/// Horizons mark-outs are measured at unless configured otherwise
+pub const DEFAULT_HORIZONS: [Duration; 3] = [
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+    Duration::from_secs(30),
+];
+
+/// How long mids are kept beyond the longest horizon, for fills delivered late
+const MID_RETENTION_MS: u64 = 60_000;
+
+/// Mark-outs of the fills of a strategy at one horizon
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarkoutStats {
+    pub horizon_ms: u64,
+    /// Fills with a mid price at the horizon
+    pub count: u64,
+    /// Average edge against the mid at the horizon in basis points, positive when favorable
+    pub mean_bps: f64,
+    /// Average edge weighted by fill size
+    pub size_weighted_bps: f64,
+}
+
+/// Execution quality of the fills of a strategy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagReport {
+    pub fills: u64,
+    pub volume: f64,
+    pub notional: f64,
+    /// Average edge against the mid at the time of the fill in basis points, None when no
+    /// mid was known for any fill
+    pub spread_bps: Option<f64>,
+    /// Statistics at each horizon, shortest first
+    pub markouts: Vec<MarkoutStats>,
+}
+
+/// Execution quality by strategy tag
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarkoutReport {
+    pub tags: BTreeMap<String, TagReport>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct HorizonSums {
+    count: u64,
+    bps: f64,
+    weighted_bps: f64,
+    size: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct TagSums {
+    fills: u64,
+    volume: f64,
+    notional: f64,
+    spread_count: u64,
+    spread_bps: f64,
+    horizons: Vec<HorizonSums>,
+}
+
+/// A fill waiting for the mid at its horizons
+#[derive(Debug, Clone)]
+struct PendingFill {
+    tag: String,
+    fill: Fill,
+    mid_at_fill: Option<f64>,
+    mids: Vec<Option<f64>>,
+}
+
+impl PendingFill {
+    /// Edge of the fill against `mid` in basis points, positive when the fill was better
+    fn edge_bps(&self, mid: f64) -> f64 {
+        let edge = match self.fill.side {
+            Side::BUY => mid - self.fill.price,
+            Side::SELL => self.fill.price - mid,
+        };
+        edge / self.fill.price * 10_000.
+    }
+}
+
+type TagFn = Arc<dyn Fn(&Fill) -> String + Send + Sync>;
+
+struct State {
+    horizons: Vec<u64>,
+    /// Mid prices by market, oldest first
+    mids: HashMap<String, VecDeque<(u64, f64)>>,
+    pending: Vec<PendingFill>,
+    fill_ids: HashSet<String>,
+    tags: BTreeMap<String, TagSums>,
+}
+
+impl State {
+    /// Last mid of a market at or before `time`
+    fn mid_at(&self, market: &str, time: u64) -> Option<f64> {
+        let mids = self.mids.get(market)?;
+        let index = mids.partition_point(|(updated_at, _)| *updated_at <= time);
+        index.checked_sub(1).map(|index| mids[index].1)
+    }
+
+    /// Time of the latest mid of a market
+    fn latest(&self, market: &str) -> Option<u64> {
+        self.mids
+            .get(market)?
+            .back()
+            .map(|(updated_at, _)| *updated_at)
+    }
+
+    /// Record the mids at every horizon the market's history covers, and close fills whose
+    /// horizons have all passed
+    fn settle(&mut self, market: &str) {
+        let Some(latest) = self.latest(market) else {
+            return;
+        };
+        let mut pending = std::mem::take(&mut self.pending);
+        pending.retain_mut(|fill| {
+            if fill.fill.market != market {
+                return true;
+            }
+            for (index, horizon) in self.horizons.iter().enumerate() {
+                let time = fill.fill.created_at + horizon;
+                if fill.mids[index].is_none() && time <= latest {
+                    fill.mids[index] = self.mid_at(market, time);
+                }
+            }
+            let done = self
+                .horizons
+                .iter()
+                .all(|horizon| fill.fill.created_at + horizon <= latest);
+            if done {
+                self.record(fill);
+            }
+            !done
+        });
+        self.pending = pending;
+    }
+
+    fn record(&mut self, fill: &PendingFill) {
+        let horizons = self.horizons.len();
+        let sums = self.tags.entry(fill.tag.clone()).or_default();
+        sums.horizons.resize(horizons, HorizonSums::default());
+        sums.fills += 1;
+        sums.volume += fill.fill.size;
+        sums.notional += fill.fill.size * fill.fill.price;
+        if let Some(mid) = fill.mid_at_fill {
+            sums.spread_count += 1;
+            sums.spread_bps += fill.edge_bps(mid);
+        }
+        for (horizon, mid) in sums.horizons.iter_mut().zip(&fill.mids) {
+            if let Some(mid) = mid {
+                let bps = fill.edge_bps(*mid);
+                horizon.count += 1;
+                horizon.bps += bps;
+                horizon.weighted_bps += bps * fill.fill.size;
+                horizon.size += fill.fill.size;
+            }
+        }
+    }
+}
+
+/// Measures the mark-outs of fills against the mid price from the BBO channel
+///
+/// Clones share the same history and statistics.
+#[derive(Clone)]
+pub struct MarkoutAnalyzer {
+    state: Arc<Mutex<State>>,
+    tag: TagFn,
+}
+
+impl Default for MarkoutAnalyzer {
+    fn default() -> Self {
+        Self::new(&DEFAULT_HORIZONS)
+    }
+}
+
+impl MarkoutAnalyzer {
+    /// Measure mark-outs at `horizons` after each fill
+    ///
+    /// Fills are tagged with their client id up to its last `-`, so that orders with client
+    /// ids like `quote-42` are grouped under `quote`, see `with_tag`.
+    pub fn new(horizons: &[Duration]) -> Self {
+        let mut horizons: Vec<u64> = horizons
+            .iter()
+            .map(|horizon| horizon.as_millis() as u64)
+            .collect();
+        horizons.sort_unstable();
+        horizons.dedup();
+        Self {
+            state: Arc::new(Mutex::new(State {
+                horizons,
+                mids: HashMap::new(),
+                pending: Vec::new(),
+                fill_ids: HashSet::new(),
+                tags: BTreeMap::new(),
+            })),
+            tag: Arc::new(|fill: &Fill| match fill.client_id.rsplit_once('-') {
+                Some((prefix, _)) => prefix.to_string(),
+                None => fill.client_id.clone(),
+            }),
+        }
+    }
+
+    /// Group fills by the strategy tag returned by `tag` instead
+    pub fn with_tag(mut self, tag: impl Fn(&Fill) -> String + Send + Sync + 'static) -> Self {
+        self.tag = Arc::new(tag);
+        self
+    }
+
+    /// Feed the BBO channel of `markets` and the fills channel into the analyzer
+    ///
+    /// # Returns
+    ///
+    /// The identifiers of the subscriptions
+    ///
+    /// # Errors
+    ///
+    /// If any subscription cannot be made
+    pub async fn attach<E: Exchange>(
+        &self,
+        exchange: &E,
+        markets: &[&str],
+    ) -> Result<Vec<Identifier>> {
+        let mut identifiers = Vec::new();
+        for market in markets {
+            let analyzer = self.clone();
+            identifiers.push(
+                exchange
+                    .subscribe_typed(BboSubscription::new(*market), move |event| match event {
+                        ChannelEvent::Data(bbo) => analyzer.on_bbo(bbo),
+                        ChannelEvent::Error(e) => warn!("BBO subscription error {e:?}"),
+                        _ => {}
+                    })
+                    .await?,
+            );
+        }
+        let analyzer = self.clone();
+        identifiers.push(
+            exchange
+                .subscribe_typed(FillsSubscription::all(), move |event| match event {
+                    ChannelEvent::Data(fill) => analyzer.on_fill(fill),
+                    ChannelEvent::Error(e) => warn!("Fills subscription error {e:?}"),
+                    _ => {}
+                })
+                .await?,
+        );
+        Ok(identifiers)
+    }
+
+    /// Record the mid price of a BBO update
+    ///
+    /// Updates with an empty side or older than the last recorded one are ignored.
+    pub fn on_bbo(&self, bbo: &BBO) {
+        if !(bbo.bid.is_finite() && bbo.ask.is_finite()) {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        let retention = state.horizons.last().copied().unwrap_or(0) + MID_RETENTION_MS;
+        let mids = state.mids.entry(bbo.market.clone()).or_default();
+        if mids
+            .back()
+            .is_some_and(|(updated_at, _)| *updated_at > bbo.last_updated_at)
+        {
+            return;
+        }
+        mids.push_back((bbo.last_updated_at, (bbo.bid + bbo.ask) / 2.));
+        while mids
+            .front()
+            .is_some_and(|(updated_at, _)| updated_at + retention < bbo.last_updated_at)
+        {
+            mids.pop_front();
+        }
+        state.settle(&bbo.market);
+    }
+
+    /// Start measuring a fill, ignoring fills which were recorded already
+    pub fn on_fill(&self, fill: &Fill) {
+        let tag = (self.tag)(fill);
+        let mut state = self.state.lock().unwrap();
+        if !state.fill_ids.insert(fill.id.clone()) {
+            return;
+        }
+        let pending = PendingFill {
+            tag,
+            mid_at_fill: state.mid_at(&fill.market, fill.created_at),
+            mids: vec![None; state.horizons.len()],
+            fill: fill.clone(),
+        };
+        state.pending.push(pending);
+        state.settle(&fill.market);
+    }
+
+    /// Number of fills waiting for their longest horizon to pass
+    pub fn pending(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+
+    /// Statistics of the fills whose horizons have all passed
+    pub fn report(&self) -> MarkoutReport {
+        let state = self.state.lock().unwrap();
+        let tags = state
+            .tags
+            .iter()
+            .map(|(tag, sums)| {
+                let markouts = state
+                    .horizons
+                    .iter()
+                    .zip(&sums.horizons)
+                    .map(|(horizon_ms, horizon)| MarkoutStats {
+                        horizon_ms: *horizon_ms,
+                        count: horizon.count,
+                        mean_bps: average(horizon.bps, horizon.count as f64),
+                        size_weighted_bps: average(horizon.weighted_bps, horizon.size),
+                    })
+                    .collect();
+                let report = TagReport {
+                    fills: sums.fills,
+                    volume: sums.volume,
+                    notional: sums.notional,
+                    spread_bps: (sums.spread_count > 0)
+                        .then(|| sums.spread_bps / sums.spread_count as f64),
+                    markouts,
+                };
+                (tag.clone(), report)
+            })
+            .collect();
+        MarkoutReport { tags }
+    }
+}
+
+fn average(sum: f64, weight: f64) -> f64 {
+    if weight > 0. { sum / weight } else { 0. }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn bbo(last_updated_at: u64, mid: f64) -> BBO {
+        BBO {
+            bid: mid - 0.5,
+            bid_size: 1.,
+            ask: mid + 0.5,
+            ask_size: 1.,
+            market: "BTC-USD-PERP".into(),
+            last_updated_at,
+            seq_no: 0,
+        }
+    }
+
+    fn fill(id: &str, client_id: &str, created_at: u64, side: Side, price: f64) -> Fill {
+        Fill {
+            client_id: client_id.into(),
+            market: "BTC-USD-PERP".into(),
+            side,
+            price,
+            size: 1.,
+            ..testing::fill(id, created_at)
+        }
+    }
+
+    #[test]
+    fn markouts_by_tag() {
+        let analyzer = MarkoutAnalyzer::new(&[Duration::from_secs(5), Duration::from_secs(1)]);
+        analyzer.on_bbo(&bbo(1_000, 100.));
+        // A bid filled half a point under the mid, then the price drops through it
+        analyzer.on_fill(&fill("1", "quote-1", 1_500, Side::BUY, 99.5));
+        analyzer.on_fill(&fill("1", "quote-1", 1_500, Side::BUY, 99.5));
+        analyzer.on_bbo(&bbo(2_000, 99.));
+        analyzer.on_bbo(&bbo(2_600, 99.));
+        assert_eq!(analyzer.pending(), 1);
+        assert!(analyzer.report().tags.is_empty());
+        analyzer.on_bbo(&bbo(6_500, 101.));
+        assert_eq!(analyzer.pending(), 0);
+
+        // A fill delivered late gets the mids of the horizons which passed already
+        analyzer.on_fill(&fill("2", "hedge", 2_500, Side::SELL, 99.));
+        assert_eq!(analyzer.pending(), 1);
+        analyzer.on_bbo(&bbo(7_500, 97.));
+        let report = analyzer.report();
+        let quote = &report.tags["quote"];
+        assert_eq!(quote.fills, 1);
+        assert_eq!(quote.spread_bps, Some(0.5 / 99.5 * 10_000.));
+        assert_eq!(quote.markouts[0].horizon_ms, 1_000);
+        assert_eq!(quote.markouts[0].mean_bps, -0.5 / 99.5 * 10_000.);
+        assert_eq!(quote.markouts[1].mean_bps, 1.5 / 99.5 * 10_000.);
+        let hedge = &report.tags["hedge"];
+        assert_eq!(hedge.spread_bps, Some(0.));
+        assert_eq!(hedge.markouts[0].mean_bps, 0.);
+        assert_eq!(hedge.markouts[1].size_weighted_bps, 2. / 99. * 10_000.);
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(
+            serde_json::from_str::<MarkoutReport>(&json).unwrap(),
+            report
+        );
+    }
+}
@@ -1,13 +1,16 @@
-use std::sync::LazyLock;
+use std::sync::{LazyLock, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::decimal::decimal_to_felt;
 use crate::error::{Error, Result};
-use crate::structs::{ModifyOrderRequest, OrderRequest};
-use cached::SizedCache;
+use crate::signer::StarkSigner;
+use crate::structs::{
+    ModifyOrder, ModifyOrderRequest, Order, OrderRequest, OrderType, Side, format_signature,
+};
 use cached::proc_macro::cached;
+use cached::{Cached, SizedCache};
 use reqwest::header::{HeaderMap, HeaderValue};
 use rust_decimal::Decimal;
-use rust_decimal::prelude::ToPrimitive;
 use starknet_core::crypto::compute_hash_on_elements;
 use starknet_core::types::Felt;
 use starknet_core::utils::{
@@ -113,6 +116,33 @@ fn domain_hash(chain_id: Felt) -> Result<Felt> {
     ]))
 }
 
+/// Market symbol encoded as a short string felt, cached as it is part of every order hash
+///
+/// # Errors
+///
+/// If the market symbol is not a valid short string
+#[cached(
+    ty = "SizedCache<String, Felt>",
+    create = "{ SizedCache::with_size(1000) }",
+    convert = r#"{ market.to_string() }"#,
+    result = true
+)]
+pub fn market_felt(market: &str) -> Result<Felt> {
+    cairo_short_string_to_felt(market).map_err(|e| Error::StarknetError(e.to_string()))
+}
+
+/// Populate the market symbol cache ahead of the first order in each market
+///
+/// # Errors
+///
+/// If a market symbol is not a valid short string
+pub fn warm_market_felts<'a>(markets: impl IntoIterator<Item = &'a str>) -> Result<()> {
+    for market in markets {
+        market_felt(market)?;
+    }
+    Ok(())
+}
+
 static REQUEST_TYPE_HASH: LazyLock<Felt> = LazyLock::new(|| {
     starknet_keccak(
         "Request(method:felt,path:felt,body:felt,timestamp:felt,expiration:felt)".as_bytes(),
@@ -131,13 +161,7 @@ pub fn onboarding_message_hash(chain_id: Felt, address: Felt) -> Result<Felt> {
             .map_err(|e| Error::StarknetError(e.to_string()))?,
     ]);
 
-    let mut hasher = PedersenHasher::default();
-    hasher.update(STARKNET_MESSAGE_PREFIX);
-    hasher.update(domain_hash(chain_id)?);
-    hasher.update(address);
-    hasher.update(constant_hash);
-
-    Ok(hasher.finalize())
+    message_hash(chain_id, address, constant_hash)
 }
 
 #[cfg(feature = "onboarding")]
@@ -147,20 +171,47 @@ pub fn onboarding_headers(
     signing_key: &SigningKey,
     account: &Felt,
 ) -> Result<HeaderMap> {
-    let system_timestamp = SystemTime::now();
-    let timestamp: u128 = system_timestamp
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| Error::TimeError(e.to_string()))?
-        .as_secs()
-        .into();
-
+    let timestamp = unix_timestamp(SystemTime::now())?;
     let message_hash = crate::message::onboarding_message_hash(*l2_chain, *account)?;
     let signature = signing_key
         .sign(&message_hash)
         .map_err(|e| Error::StarknetError(e.to_string()))?;
+    Ok(onboarding_header_map(
+        ethereum_account,
+        account,
+        &signature,
+        timestamp,
+    ))
+}
+
+/// Onboarding headers signed by `signer`, see `onboarding_headers`
+#[cfg(feature = "onboarding")]
+pub async fn onboarding_headers_with_signer(
+    ethereum_account: &str,
+    l2_chain: &Felt,
+    signer: &dyn StarkSigner,
+    account: &Felt,
+) -> Result<HeaderMap> {
+    let timestamp = unix_timestamp(SystemTime::now())?;
+    let message_hash = crate::message::onboarding_message_hash(*l2_chain, *account)?;
+    let signature = signer.sign(message_hash).await?;
+    Ok(onboarding_header_map(
+        ethereum_account,
+        account,
+        &signature,
+        timestamp,
+    ))
+}
 
+#[cfg(feature = "onboarding")]
+fn onboarding_header_map(
+    ethereum_account: &str,
+    account: &Felt,
+    signature: &Signature,
+    timestamp: u128,
+) -> HeaderMap {
     let account_str = account.to_hex_string();
-    let signature_str = format!(r#"["{}","{}"]"#, signature.r, signature.s);
+    let signature_str = format_signature(&[signature.r, signature.s]);
 
     let mut header_map: HeaderMap<HeaderValue> = HeaderMap::with_capacity(4);
     header_map.insert(
@@ -170,7 +221,16 @@ pub fn onboarding_headers(
     header_map.insert("PARADEX-STARKNET-ACCOUNT", account_str.parse().unwrap());
     header_map.insert("PARADEX-STARKNET-SIGNATURE", signature_str.parse().unwrap());
     header_map.insert("PARADEX-TIMESTAMP", timestamp.to_string().parse().unwrap());
-    Ok(header_map)
+    header_map
+}
+
+/// Seconds since the epoch
+fn unix_timestamp(time: SystemTime) -> Result<u128> {
+    Ok(time
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| Error::TimeError(e.to_string()))?
+        .as_secs()
+        .into())
 }
 
 pub fn auth_message_hash(
@@ -188,13 +248,7 @@ pub fn auth_message_hash(
         expiration.into(),
     ]);
 
-    let mut hasher = PedersenHasher::default();
-    hasher.update(STARKNET_MESSAGE_PREFIX);
-    hasher.update(domain_hash(chain_id)?);
-    hasher.update(address);
-    hasher.update(request_hash);
-
-    Ok(hasher.finalize())
+    message_hash(chain_id, address, request_hash)
 }
 
 pub fn auth_headers(
@@ -203,21 +257,45 @@ pub fn auth_headers(
     account: &Felt,
 ) -> Result<(SystemTime, HeaderMap)> {
     let system_timestamp = SystemTime::now();
-    let timestamp: u128 = system_timestamp
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| Error::TimeError(e.to_string()))?
-        .as_secs()
-        .into();
-
+    let timestamp = unix_timestamp(system_timestamp)?;
     let expiration = timestamp + 60 * 60;
     let message_hash =
         crate::message::auth_message_hash(*l2_chain, timestamp, expiration, *account)?;
     let signature = signing_key
         .sign(&message_hash)
         .map_err(|e| Error::StarknetError(e.to_string()))?;
+    Ok((
+        system_timestamp,
+        auth_header_map(account, &signature, timestamp, expiration),
+    ))
+}
+
+/// Auth headers signed by `signer`, see `auth_headers`
+pub async fn auth_headers_with_signer(
+    l2_chain: &Felt,
+    signer: &dyn StarkSigner,
+    account: &Felt,
+) -> Result<(SystemTime, HeaderMap)> {
+    let system_timestamp = SystemTime::now();
+    let timestamp = unix_timestamp(system_timestamp)?;
+    let expiration = timestamp + 60 * 60;
+    let message_hash =
+        crate::message::auth_message_hash(*l2_chain, timestamp, expiration, *account)?;
+    let signature = signer.sign(message_hash).await?;
+    Ok((
+        system_timestamp,
+        auth_header_map(account, &signature, timestamp, expiration),
+    ))
+}
 
+fn auth_header_map(
+    account: &Felt,
+    signature: &Signature,
+    timestamp: u128,
+    expiration: u128,
+) -> HeaderMap {
     let account_str = account.to_hex_string();
-    let signature_str = format!(r#"["{}","{}"]"#, signature.r, signature.s);
+    let signature_str = format_signature(&[signature.r, signature.s]);
 
     let mut header_map: HeaderMap<HeaderValue> = HeaderMap::with_capacity(4);
     header_map.insert("PARADEX-STARKNET-ACCOUNT", account_str.parse().unwrap());
@@ -227,7 +305,7 @@ pub fn auth_headers(
         "PARADEX-SIGNATURE-EXPIRATION",
         expiration.to_string().parse().unwrap(),
     );
-    Ok((system_timestamp, header_map))
+    header_map
 }
 
 static ORDER_TYPE_HASH: LazyLock<Felt> = LazyLock::new(|| {
@@ -237,6 +315,56 @@ static ORDER_TYPE_HASH: LazyLock<Felt> = LazyLock::new(|| {
     )
 });
 
+/// Hash of a message signed by `address`, given the hash of the message struct
+fn message_hash(chain_id: Felt, address: Felt, struct_hash: Felt) -> Result<Felt> {
+    let mut hasher = PedersenHasher::default();
+    hasher.update(STARKNET_MESSAGE_PREFIX);
+    hasher.update(domain_hash(chain_id)?);
+    hasher.update(address);
+    hasher.update(struct_hash);
+    Ok(hasher.finalize())
+}
+
+/// The struct elements shared by orders and order modifications
+fn order_elements(
+    type_hash: Felt,
+    signature_timestamp_ms: u128,
+    market: &str,
+    side: Side,
+    order_type: OrderType,
+    size: Decimal,
+    price: Option<Decimal>,
+) -> Result<Vec<Felt>> {
+    Ok(vec![
+        type_hash,
+        signature_timestamp_ms.into(),
+        market_felt(market)?,
+        side.felt(),
+        order_type.felt()?,
+        decimal_to_felt(size)?,
+        price.map_or(Ok(Felt::ZERO), decimal_to_felt)?,
+    ])
+}
+
+/// Hash of an order as signed by `address`
+pub fn order_message_hash(
+    order_request: &OrderRequest,
+    signature_timestamp_ms: u128,
+    chain_id: Felt,
+    address: Felt,
+) -> Result<Felt> {
+    let elements = order_elements(
+        *ORDER_TYPE_HASH,
+        signature_timestamp_ms,
+        &order_request.market,
+        order_request.side,
+        order_request.order_type,
+        order_request.size,
+        order_request.price,
+    )?;
+    message_hash(chain_id, address, compute_hash_on_elements(&elements))
+}
+
 pub fn sign_order(
     order_request: &OrderRequest,
     signing_key: &SigningKey,
@@ -244,50 +372,117 @@ pub fn sign_order(
     chain_id: Felt,
     address: Felt,
 ) -> Result<Signature> {
-    const QUANTIZE_FACTOR: rust_decimal::Result<Decimal> = Decimal::try_new(10_i64.pow(8), 0);
-    let quantize_factor = QUANTIZE_FACTOR.unwrap();
-    let price_scaled = if let Some(value) = &order_request.price {
-        (value * quantize_factor).to_i64().ok_or_else(|| {
-            Error::TypeConversionError(format!(
-                "Could not convert order price {:?} to i64 ",
-                order_request.price
-            ))
-        })?
-    } else {
-        0
-    };
-    let size_scaled = (order_request.size * quantize_factor)
-        .to_i64()
-        .ok_or_else(|| {
-            Error::TypeConversionError(format!(
-                "Could not convert order size {} to i64 ",
-                order_request.size
-            ))
-        })?;
-
-    let order_hash = compute_hash_on_elements(&[
-        *ORDER_TYPE_HASH,
-        signature_timestamp_ms.into(),
-        cairo_short_string_to_felt(order_request.market.as_str())
-            .map_err(|e| Error::StarknetError(e.to_string()))?,
-        order_request.side.felt(),
-        order_request.order_type.felt()?,
-        size_scaled.into(),
-        price_scaled.into(),
-    ]);
-
-    let mut hasher = PedersenHasher::default();
-    hasher.update(STARKNET_MESSAGE_PREFIX);
-    hasher.update(domain_hash(chain_id)?);
-    hasher.update(address);
-    hasher.update(order_hash);
-
-    let hash = hasher.finalize();
+    let hash = order_message_hash(order_request, signature_timestamp_ms, chain_id, address)?;
     signing_key
         .sign(&hash)
         .map_err(|e| Error::StarknetError(e.to_string()))
 }
 
+/// Sign an order with its signature timestamp truncated to a multiple of `bucket_ms`.
+///
+/// Identical orders (market, side, type, size and price) signed within the same bucket share a
+/// signature timestamp, so re-quotes reuse a cached signature instead of hashing and signing
+/// again. `bucket_ms` must stay well below the order's recv window, as the truncated timestamp
+/// can be up to `bucket_ms` in the past.
+///
+/// Returns the signature along with the signature timestamp it was computed for.
+pub fn sign_order_bucketed(
+    order_request: &OrderRequest,
+    signing_key: &SigningKey,
+    now_ms: u128,
+    bucket_ms: u128,
+    chain_id: Felt,
+    address: Felt,
+) -> Result<([Felt; 2], u128)> {
+    let signature_timestamp_ms = now_ms - now_ms % bucket_ms.max(1);
+    let signature = sign_order_cached(
+        order_request,
+        signing_key,
+        signature_timestamp_ms,
+        chain_id,
+        address,
+    )?;
+    Ok((signature, signature_timestamp_ms))
+}
+
+/// Sign an order with `signer`, see `sign_order`
+pub async fn sign_order_with_signer(
+    order_request: &OrderRequest,
+    signer: &dyn StarkSigner,
+    signature_timestamp_ms: u128,
+    chain_id: Felt,
+    address: Felt,
+) -> Result<Signature> {
+    let hash = order_message_hash(order_request, signature_timestamp_ms, chain_id, address)?;
+    signer.sign(hash).await
+}
+
+type SignerSignatures = SizedCache<(Felt, Felt), [Felt; 2]>;
+
+/// Signatures made by signers for bucketed orders, by public key and message hash
+static SIGNER_SIGNATURES: LazyLock<Mutex<SignerSignatures>> =
+    LazyLock::new(|| Mutex::new(SizedCache::with_size(1000)));
+
+/// Sign an order with `signer` with its signature timestamp truncated to a multiple of
+/// `bucket_ms`, see `sign_order_bucketed`
+///
+/// Signatures are cached by message hash, so re-quotes within a bucket are hashed again but
+/// not sent to the signer.
+pub async fn sign_order_bucketed_with_signer(
+    order_request: &OrderRequest,
+    signer: &dyn StarkSigner,
+    now_ms: u128,
+    bucket_ms: u128,
+    chain_id: Felt,
+    address: Felt,
+) -> Result<([Felt; 2], u128)> {
+    let signature_timestamp_ms = now_ms - now_ms % bucket_ms.max(1);
+    let hash = order_message_hash(order_request, signature_timestamp_ms, chain_id, address)?;
+    let key = (signer.public_key(), hash);
+    if let Some(signature) = SIGNER_SIGNATURES.lock().unwrap().cache_get(&key) {
+        return Ok((*signature, signature_timestamp_ms));
+    }
+    let signature = signer.sign(hash).await?;
+    let signature = [signature.r, signature.s];
+    SIGNER_SIGNATURES.lock().unwrap().cache_set(key, signature);
+    Ok((signature, signature_timestamp_ms))
+}
+
+/// The order fields, timestamp, chain, account and public key of the signing key
+type OrderSignatureKey = (
+    String,
+    Side,
+    OrderType,
+    Decimal,
+    Option<Decimal>,
+    u128,
+    Felt,
+    Felt,
+    Felt,
+);
+
+#[cached(
+    ty = "SizedCache<OrderSignatureKey, Result<[Felt; 2]>>",
+    create = "{ SizedCache::with_size(1000) }",
+    convert = r#"{ (order_request.market.clone(), order_request.side, order_request.order_type, order_request.size, order_request.price, signature_timestamp_ms, chain_id, address, signing_key.verifying_key().scalar()) }"#
+)]
+fn sign_order_cached(
+    order_request: &OrderRequest,
+    signing_key: &SigningKey,
+    signature_timestamp_ms: u128,
+    chain_id: Felt,
+    address: Felt,
+) -> Result<[Felt; 2]> {
+    let signature = sign_order(
+        order_request,
+        signing_key,
+        signature_timestamp_ms,
+        chain_id,
+        address,
+    )?;
+    Ok([signature.r, signature.s])
+}
+
 static MODIFY_ORDER_TYPE_HASH: std::sync::LazyLock<Felt> = std::sync::LazyLock::new(|| {
     starknet_core::utils::starknet_keccak(
         "ModifyOrder(timestamp:felt,market:felt,side:felt,orderType:felt,size:felt,price:felt,id:felt)"
@@ -303,6 +498,26 @@ fn str_to_felt(s: &str) -> Result<Felt> {
     }
 }
 
+/// Hash of an order modification as signed by `address`
+pub fn modify_order_message_hash(
+    order_request: &ModifyOrderRequest,
+    signature_timestamp_ms: u128,
+    chain_id: Felt,
+    address: Felt,
+) -> Result<Felt> {
+    let mut elements = order_elements(
+        *MODIFY_ORDER_TYPE_HASH,
+        signature_timestamp_ms,
+        &order_request.market,
+        order_request.side,
+        order_request.order_type,
+        order_request.size,
+        order_request.price,
+    )?;
+    elements.push(str_to_felt(order_request.id.as_str())?);
+    message_hash(chain_id, address, compute_hash_on_elements(&elements))
+}
+
 pub fn sign_modify_order(
     order_request: &ModifyOrderRequest,
     signing_key: &SigningKey,
@@ -310,56 +525,112 @@ pub fn sign_modify_order(
     chain_id: Felt,
     address: Felt,
 ) -> Result<Signature> {
-    const QUANTIZE_FACTOR: rust_decimal::Result<Decimal> = Decimal::try_new(10_i64.pow(8), 0);
-    let quantize_factor = QUANTIZE_FACTOR.unwrap();
-    let price_scaled = if let Some(value) = &order_request.price {
-        (value * quantize_factor).to_i64().ok_or_else(|| {
-            Error::TypeConversionError(format!(
-                "Could not convert order price {:?} to i64 ",
-                order_request.price
-            ))
-        })?
-    } else {
-        0
-    };
-    let size_scaled = (order_request.size * quantize_factor)
-        .to_i64()
-        .ok_or_else(|| {
-            Error::TypeConversionError(format!(
-                "Could not convert order size {} to i64 ",
-                order_request.size
-            ))
-        })?;
-
-    let order_hash = compute_hash_on_elements(&[
-        *MODIFY_ORDER_TYPE_HASH,
-        signature_timestamp_ms.into(),
-        cairo_short_string_to_felt(order_request.market.as_str())
-            .map_err(|e| Error::StarknetError(e.to_string()))?,
-        order_request.side.felt(),
-        order_request.order_type.felt()?,
-        size_scaled.into(),
-        price_scaled.into(),
-        str_to_felt(order_request.id.as_str())?,
-    ]);
-
-    let mut hasher = PedersenHasher::default();
-    hasher.update(STARKNET_MESSAGE_PREFIX);
-    hasher.update(domain_hash(chain_id)?);
-    hasher.update(address);
-    hasher.update(order_hash);
-
-    let hash = hasher.finalize();
+    let hash = modify_order_message_hash(order_request, signature_timestamp_ms, chain_id, address)?;
     signing_key
         .sign(&hash)
         .map_err(|e| Error::StarknetError(e.to_string()))
 }
 
+/// Sign an order modification with `signer`, see `sign_modify_order`
+pub async fn sign_modify_order_with_signer(
+    order_request: &ModifyOrderRequest,
+    signer: &dyn StarkSigner,
+    signature_timestamp_ms: u128,
+    chain_id: Felt,
+    address: Felt,
+) -> Result<Signature> {
+    let hash = modify_order_message_hash(order_request, signature_timestamp_ms, chain_id, address)?;
+    signer.sign(hash).await
+}
+
+/// Whether `signature` is a valid signature of `hash` by the key pair of `public_key`
+///
+/// # Errors
+///
+/// If the public key is not a valid curve point or the signature is out of range
+pub fn verify_signature(public_key: Felt, hash: Felt, signature: [Felt; 2]) -> Result<bool> {
+    starknet_crypto::verify(&public_key, &hash, &signature[0], &signature[1])
+        .map_err(|e| Error::StarknetError(e.to_string()))
+}
+
+/// Whether a signed order was signed for `address` by the key pair of `public_key`
+///
+/// Useful to check the output of an external signer before submitting the order.
+///
+/// # Errors
+///
+/// If the order cannot be hashed, e.g. for an invalid market symbol, or the public key or
+/// signature are malformed
+pub fn verify_order_signature(
+    order: &Order,
+    public_key: Felt,
+    chain_id: Felt,
+    address: Felt,
+) -> Result<bool> {
+    let elements = order_elements(
+        *ORDER_TYPE_HASH,
+        order.signature_timestamp,
+        &order.market,
+        order.side,
+        order.order_type,
+        order.size,
+        order.price,
+    )?;
+    let hash = message_hash(chain_id, address, compute_hash_on_elements(&elements))?;
+    verify_signature(public_key, hash, order.signature)
+}
+
+/// Whether a signed order modification was signed for `address` by the key pair of
+/// `public_key`
+///
+/// # Errors
+///
+/// If the modification cannot be hashed, or the public key or signature are malformed
+pub fn verify_modify_order_signature(
+    order: &ModifyOrder,
+    public_key: Felt,
+    chain_id: Felt,
+    address: Felt,
+) -> Result<bool> {
+    let mut elements = order_elements(
+        *MODIFY_ORDER_TYPE_HASH,
+        order.signature_timestamp,
+        &order.market,
+        order.side,
+        order.order_type,
+        order.size,
+        order.price,
+    )?;
+    elements.push(str_to_felt(order.id.as_str())?);
+    let hash = message_hash(chain_id, address, compute_hash_on_elements(&elements))?;
+    verify_signature(public_key, hash, order.signature)
+}
+
+/// Whether the signature of an auth request, as sent in its `PARADEX-STARKNET-SIGNATURE`
+/// header, was made for `address` by the key pair of `public_key`
+///
+/// # Errors
+///
+/// If the public key or signature are malformed
+pub fn verify_auth_signature(
+    signature: [Felt; 2],
+    timestamp: u128,
+    expiration: u128,
+    public_key: Felt,
+    chain_id: Felt,
+    address: Felt,
+) -> Result<bool> {
+    let hash = auth_message_hash(chain_id, timestamp, expiration, address)?;
+    verify_signature(public_key, hash, signature)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::structs::{Order, OrderInstruction, OrderRequest, OrderType, Side};
+    use crate::structs::{
+        ModifyOrderRequest, Order, OrderInstruction, OrderRequest, OrderType, Side,
+    };
     use rust_decimal::Decimal;
     use rust_decimal::prelude::FromPrimitive;
     use starknet_core::types::Felt;
@@ -376,6 +647,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_market_felt() {
+        warm_market_felts(["BTC-USD-PERP", "ETH-USD-PERP"]).unwrap();
+        assert_eq!(
+            market_felt("BTC-USD-PERP").unwrap(),
+            cairo_short_string_to_felt("BTC-USD-PERP").unwrap()
+        );
+        assert!(market_felt("A-MARKET-SYMBOL-LONGER-THAN-31-CHARS").is_err());
+    }
+
     #[test]
     fn test_account_address() {
         let public_key = Felt::from_raw([1, 2, 3, 4]);
@@ -474,4 +755,136 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_verify_signatures() {
+        let order_request = OrderRequest {
+            instruction: OrderInstruction::GTC,
+            market: "BTC-USD-PERP".into(),
+            price: Decimal::from_f64(100000.),
+            side: Side::SELL,
+            size: Decimal::from_f64(0.5).unwrap(),
+            order_type: OrderType::LIMIT,
+            client_id: None,
+            flags: vec![],
+            recv_window: None,
+            stp: None,
+            trigger_price: None,
+        };
+        let signing_key = SigningKey::from_secret_scalar(Felt::from_raw([1, 2, 3, 4]));
+        let public_key = signing_key.verifying_key().scalar();
+        let chain_id = Felt::from_raw([5, 6, 7, 8]);
+        let address = Felt::from_raw([9, 10, 11, 12]);
+
+        let signature = sign_order(&order_request, &signing_key, 1000, chain_id, address).unwrap();
+        let mut order = order_request.into_order([signature.r, signature.s], 1000);
+        assert!(verify_order_signature(&order, public_key, chain_id, address).unwrap());
+        assert!(!verify_order_signature(&order, public_key, chain_id, Felt::ONE).unwrap());
+        order.size = Decimal::ONE;
+        assert!(!verify_order_signature(&order, public_key, chain_id, address).unwrap());
+
+        let modify_request = ModifyOrderRequest {
+            id: "1234".into(),
+            market: "BTC-USD-PERP".into(),
+            price: Decimal::from_f64(99000.),
+            side: Side::SELL,
+            size: Decimal::ONE,
+            order_type: OrderType::LIMIT,
+        };
+        let signature =
+            sign_modify_order(&modify_request, &signing_key, 2000, chain_id, address).unwrap();
+        let modify = modify_request.into_modify_order([signature.r, signature.s], 2000);
+        assert!(verify_modify_order_signature(&modify, public_key, chain_id, address).unwrap());
+
+        let hash = auth_message_hash(chain_id, 10, 20, address).unwrap();
+        let signature = signing_key.sign(&hash).unwrap();
+        let signature = [signature.r, signature.s];
+        assert!(verify_auth_signature(signature, 10, 20, public_key, chain_id, address).unwrap());
+        assert!(!verify_auth_signature(signature, 10, 21, public_key, chain_id, address).unwrap());
+    }
+
+    #[test]
+    fn test_sign_order_bucketed() {
+        let order_request = OrderRequest {
+            instruction: OrderInstruction::POST_ONLY,
+            market: "ETH-USD-PERP".into(),
+            price: Decimal::from_f64(2500.5),
+            side: Side::SELL,
+            size: Decimal::from_f64(0.1).unwrap(),
+            order_type: OrderType::LIMIT,
+            client_id: None,
+            flags: vec![],
+            recv_window: None,
+            stp: None,
+            trigger_price: None,
+        };
+        let signing_key = SigningKey::from_secret_scalar(Felt::from_raw([1, 2, 3, 4]));
+        let chain_id = Felt::from_raw([5, 6, 7, 8]);
+        let address = Felt::from_raw([9, 10, 11, 12]);
+
+        let (first, first_timestamp) = sign_order_bucketed(
+            &order_request,
+            &signing_key,
+            123_456_100,
+            1000,
+            chain_id,
+            address,
+        )
+        .unwrap();
+        let (second, second_timestamp) = sign_order_bucketed(
+            &order_request,
+            &signing_key,
+            123_456_900,
+            1000,
+            chain_id,
+            address,
+        )
+        .unwrap();
+        assert_eq!(first_timestamp, 123_456_000);
+        assert_eq!(second_timestamp, first_timestamp);
+        assert_eq!(first, second);
+
+        let direct = sign_order(
+            &order_request,
+            &signing_key,
+            first_timestamp,
+            chain_id,
+            address,
+        )
+        .unwrap();
+        assert_eq!(first, [direct.r, direct.s]);
+
+        let (next_bucket, next_timestamp) = sign_order_bucketed(
+            &order_request,
+            &signing_key,
+            123_457_000,
+            1000,
+            chain_id,
+            address,
+        )
+        .unwrap();
+        assert_eq!(next_timestamp, 123_457_000);
+        assert_ne!(next_bucket, first);
+
+        // Another key signs the same order in the same bucket anew
+        let other_key = SigningKey::from_secret_scalar(Felt::from_raw([4, 3, 2, 1]));
+        let (other, _) = sign_order_bucketed(
+            &order_request,
+            &other_key,
+            123_456_100,
+            1000,
+            chain_id,
+            address,
+        )
+        .unwrap();
+        let direct = sign_order(
+            &order_request,
+            &other_key,
+            first_timestamp,
+            chain_id,
+            address,
+        )
+        .unwrap();
+        assert_eq!(other, [direct.r, direct.s]);
+    }
 }
@@ -4,8 +4,7 @@ use sha2::{Digest, Sha256};
 use starknet_crypto::Felt;
 use thiserror::Error;
 
-/// STARK curve order (hex) — identique à StarkWare docs / JS implementation.
-const STARK_EC_ORDER_HEX: &str = "0800000000000010ffffffffffffffffb781126dcae7b2321e66a241adc64d2f";
+use crate::stark::STARK_EC_ORDER_HEX;
 
 #[derive(Debug, Error)]
 pub enum KeyDerivationError {
@@ -53,7 +52,7 @@ pub fn grind_key(seed: &[u8]) -> Result<[u8; 32], KeyDerivationError> {
 /// Derive Paradex / Stark private key (Felt) from an Ethereum signature bytes.
 ///
 /// - `sig_bytes` : ECDSA signature bytes. We read the first 64 bytes as r||s (big-endian).
-///                 Accepts 64 or 65-length sig (ignores v if present).
+///   Accepts 64 or 65-length sig (ignores v if present).
 pub fn private_key_from_signature(sig_bytes: &[u8]) -> Result<Felt, KeyDerivationError> {
     if sig_bytes.len() < 64 {
         return Err(KeyDerivationError::SigTooShort);
@@ -101,7 +100,7 @@ mod tests {
     use std::str::FromStr;
 
     fn hex_to_bytes(hex: &str) -> Vec<u8> {
-        assert!(hex.len() % 2 == 0, "hex length must be even");
+        assert!(hex.len().is_multiple_of(2), "hex length must be even");
         hex.as_bytes()
             .chunks(2)
             .map(|pair| {
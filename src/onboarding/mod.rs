@@ -4,6 +4,8 @@ use alloy_signer_local::PrivateKeySigner;
 use alloy_sol_types::{Eip712Domain, SolStruct, sol};
 use starknet_crypto::Felt;
 
+use crate::error::{Error, Result};
+
 mod key_derivation;
 use key_derivation::private_key_from_signature;
 
@@ -13,7 +15,12 @@ sol! {
     }
 }
 
-pub fn get_paradex_private_key(eth_signer: &PrivateKeySigner) -> Felt {
+/// EIP-712 hash of the "STARK Key" message whose signature the Paradex key is derived from.
+///
+/// Wallets that cannot be driven through a local `PrivateKeySigner` (hardware wallets, remote
+/// signers) can sign this hash themselves and pass the signature to
+/// `paradex_private_key_from_signature`.
+pub fn stark_key_signing_hash() -> B256 {
     let domain = Eip712Domain::new(
         Some("Paradex".into()),
         Some("1".into()),
@@ -26,7 +33,21 @@ pub fn get_paradex_private_key(eth_signer: &PrivateKeySigner) -> Felt {
         action: "STARK Key".into(),
     };
 
-    let digest: B256 = message.eip712_signing_hash(&domain);
+    message.eip712_signing_hash(&domain)
+}
+
+/// Derive the Paradex private key from a 64 or 65 byte ECDSA signature of
+/// `stark_key_signing_hash`
+///
+/// # Errors
+///
+/// If the signature is too short
+pub fn paradex_private_key_from_signature(sig_bytes: &[u8]) -> Result<Felt> {
+    private_key_from_signature(sig_bytes).map_err(|e| Error::StarknetError(e.to_string()))
+}
+
+pub fn get_paradex_private_key(eth_signer: &PrivateKeySigner) -> Felt {
+    let digest = stark_key_signing_hash();
 
     let sig = eth_signer
         .sign_hash_sync(&digest)
@@ -55,4 +76,20 @@ mod tests {
                 .expect("Failed to parse expected account");
         assert_eq!(paradex_account, expected_account);
     }
+
+    #[test]
+    fn test_key_from_external_signature() {
+        let eth_signer = LocalSigner::from_str(
+            "0x58d27b1d66da0dee9193105c848855b43eeceb14844f2b1de00cdcb1bdce3643",
+        )
+        .expect("Failed to create signer");
+        let signature = eth_signer
+            .sign_hash_sync(&stark_key_signing_hash())
+            .expect("Failed to sign");
+        assert_eq!(
+            paradex_private_key_from_signature(&signature.as_bytes()).unwrap(),
+            get_paradex_private_key(&eth_signer)
+        );
+        assert!(paradex_private_key_from_signature(&[0u8; 10]).is_err());
+    }
 }
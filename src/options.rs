@@ -0,0 +1,217 @@
+//! Historical data of option markets for volatility research.
+//!
+//! [`OptionChainFilter`] picks the option instruments of an underlying, optionally of a single
+//! expiry, from the market list. `Client::option_history` downloads the trades and klines of
+//! each of them with a bounded number of instruments in flight, on top of the client's rate
+//! limits, and consolidates them in an [`OptionHistory`].
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::structs::{Kline, KlineResolution, MarketSummaryStatic, OptionType, Trade};
+
+/// Instruments downloaded at once unless configured otherwise
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Selects the option instruments of an underlying
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptionChainFilter {
+    /// Base currency of the options, e.g. `ETH`
+    pub underlying: String,
+    /// Expiry in milliseconds since the epoch, 0 for perpetual options
+    pub expiry_at: Option<i64>,
+    pub option_type: Option<OptionType>,
+}
+
+impl OptionChainFilter {
+    pub fn new(underlying: impl Into<String>) -> Self {
+        Self {
+            underlying: underlying.into(),
+            expiry_at: None,
+            option_type: None,
+        }
+    }
+
+    pub fn expiry(mut self, expiry_at: i64) -> Self {
+        self.expiry_at = Some(expiry_at);
+        self
+    }
+
+    pub fn option_type(mut self, option_type: OptionType) -> Self {
+        self.option_type = Some(option_type);
+        self
+    }
+
+    /// Whether a market is an option selected by the filter
+    pub fn matches(&self, market: &MarketSummaryStatic) -> bool {
+        market
+            .option_type
+            .as_ref()
+            .is_some_and(|option_type| self.option_type.as_ref().is_none_or(|t| t == option_type))
+            && market.base_currency == self.underlying
+            && self
+                .expiry_at
+                .is_none_or(|expiry_at| market.expiry_at == expiry_at)
+    }
+
+    /// The selected options among `markets`, ordered by expiry, strike and type
+    pub fn select<'a>(&self, markets: &'a [MarketSummaryStatic]) -> Vec<&'a MarketSummaryStatic> {
+        let mut selected: Vec<_> = markets.iter().filter(|m| self.matches(m)).collect();
+        selected.sort_by(|a, b| {
+            a.expiry_at
+                .cmp(&b.expiry_at)
+                .then(
+                    a.strike_price
+                        .unwrap_or(0.)
+                        .total_cmp(&b.strike_price.unwrap_or(0.)),
+                )
+                .then_with(|| a.symbol.cmp(&b.symbol))
+        });
+        selected
+    }
+}
+
+/// What `Client::option_history` downloads
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptionHistoryRequest {
+    pub chain: OptionChainFilter,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Whether to download the trades of each instrument
+    pub trades: bool,
+    /// Resolution of the klines to download, None to skip them
+    pub resolution: Option<KlineResolution>,
+    /// Maximum number of instruments downloaded at once
+    pub concurrency: usize,
+}
+
+impl OptionHistoryRequest {
+    /// Download the trades of the options selected by `chain` between `start` and `end`
+    pub fn new(chain: OptionChainFilter, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            chain,
+            start,
+            end,
+            trades: true,
+            resolution: None,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Download klines at `resolution` as well
+    pub fn klines(mut self, resolution: KlineResolution) -> Self {
+        self.resolution = Some(resolution);
+        self
+    }
+
+    /// Skip the trades, e.g. when only the klines are needed
+    pub fn without_trades(mut self) -> Self {
+        self.trades = false;
+        self
+    }
+
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+}
+
+/// History of one option instrument
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OptionInstrument {
+    pub symbol: String,
+    pub option_type: OptionType,
+    pub strike_price: f64,
+    pub expiry_at: i64,
+    /// Trades ordered by time
+    pub trades: Vec<Trade>,
+    pub klines: Vec<Kline>,
+}
+
+impl OptionInstrument {
+    /// An instrument without history, None when the market is not an option
+    pub fn new(market: &MarketSummaryStatic) -> Option<Self> {
+        Some(Self {
+            symbol: market.symbol.clone(),
+            option_type: market.option_type.clone()?,
+            strike_price: market.strike_price?,
+            expiry_at: market.expiry_at,
+            trades: Vec::new(),
+            klines: Vec::new(),
+        })
+    }
+}
+
+/// A trade of an option along with the terms of the option
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OptionTrade {
+    pub option_type: OptionType,
+    pub strike_price: f64,
+    pub expiry_at: i64,
+    #[serde(flatten)]
+    pub trade: Trade,
+}
+
+/// History of the options of an underlying
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OptionHistory {
+    pub underlying: String,
+    pub start_at: i64,
+    pub end_at: i64,
+    /// Instruments ordered by expiry, strike and type
+    pub instruments: Vec<OptionInstrument>,
+}
+
+impl OptionHistory {
+    /// The trades of every instrument in a single list ordered by time
+    pub fn trades(&self) -> Vec<OptionTrade> {
+        let mut trades: Vec<_> = self
+            .instruments
+            .iter()
+            .flat_map(|instrument| {
+                instrument.trades.iter().map(|trade| OptionTrade {
+                    option_type: instrument.option_type.clone(),
+                    strike_price: instrument.strike_price,
+                    expiry_at: instrument.expiry_at,
+                    trade: trade.clone(),
+                })
+            })
+            .collect();
+        trades.sort_by(|a, b| {
+            (a.trade.created_at, &a.trade.id).cmp(&(b.trade.created_at, &b.trade.id))
+        });
+        trades
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::ResultsContainer;
+
+    #[test]
+    fn option_chain() {
+        let markets: ResultsContainer<Vec<MarketSummaryStatic>> =
+            serde_json::from_str(include_str!("../tests/fixtures/markets.json")).unwrap();
+        let mut markets = markets.results;
+        let mut put = markets[1].clone();
+        put.symbol = "ETH-USD-2500-P".into();
+        put.option_type = Some(OptionType::PUT);
+        put.strike_price = Some(2500.);
+        markets.push(put);
+
+        let chain = OptionChainFilter::new("ETH");
+        let symbols: Vec<_> = chain.select(&markets).iter().map(|m| &m.symbol).collect();
+        assert_eq!(symbols, ["ETH-USD-2500-P", "ETH-USD-3000-C"]);
+        assert_eq!(
+            chain
+                .clone()
+                .option_type(OptionType::CALL)
+                .select(&markets)
+                .len(),
+            1
+        );
+        assert!(chain.expiry(1).select(&markets).is_empty());
+        assert!(OptionChainFilter::new("BTC").select(&markets).is_empty());
+    }
+}
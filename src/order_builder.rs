@@ -0,0 +1,456 @@
+//! Validated construction of [`OrderRequest`]s against a market's static data.
+//!
+//! [`OrderBuilder`] checks an order's price tick, size increment, minimum notional and maximum
+//! size against a [`MarketSummaryStatic`], typically looked up with `Client::order_builder` in
+//! the client's market registry, and either rounds the order onto the market's grid or rejects
+//! it before it is signed.
+
+use std::borrow::Cow;
+
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use thiserror::Error;
+
+use crate::structs::{
+    MarketSummaryStatic, OrderFlags, OrderInstruction, OrderRequest, OrderType, STPType, Side,
+};
+
+/// A reason an order does not conform to its market
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum OrderValidationError {
+    #[error("Invalid Market Data: {0:?}")]
+    InvalidMarketData(String),
+    #[error("Missing Price: order_type={0:?}")]
+    MissingPrice(OrderType),
+    #[error("Unexpected Price: order_type={0:?}")]
+    UnexpectedPrice(OrderType),
+    #[error("Missing Trigger Price: order_type={0:?}")]
+    MissingTriggerPrice(OrderType),
+    #[error("Unsupported Instruction: instruction={instruction:?} order_type={order_type:?}")]
+    UnsupportedInstruction {
+        instruction: OrderInstruction,
+        order_type: OrderType,
+    },
+    #[error("Invalid Price Tick: price={price:?} tick_size={tick_size:?}")]
+    InvalidPriceTick { price: Decimal, tick_size: Decimal },
+    #[error("Invalid Size Increment: size={size:?} increment={increment:?}")]
+    InvalidSizeIncrement { size: Decimal, increment: Decimal },
+    #[error("Non Positive Size: size={0:?}")]
+    NonPositiveSize(Decimal),
+    #[error("Below Min Notional: notional={notional:?} min_notional={min_notional:?}")]
+    BelowMinNotional {
+        notional: Decimal,
+        min_notional: Decimal,
+    },
+    #[error("Above Max Order Size: size={size:?} max_order_size={max_order_size:?}")]
+    AboveMaxOrderSize {
+        size: Decimal,
+        max_order_size: Decimal,
+    },
+}
+
+/// Builder for an OrderRequest in a given market
+///
+/// ```no_run
+/// # use paradex::order_builder::OrderBuilder;
+/// # use paradex::structs::{MarketSummaryStatic, Side};
+/// # use rust_decimal::Decimal;
+/// # fn example(btc: &MarketSummaryStatic) {
+/// let order = OrderBuilder::limit(btc, Side::BUY, Decimal::new(1, 3), Decimal::new(60000, 0))
+///     .post_only()
+///     .client_id("my-order")
+///     .build();
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct OrderBuilder<'a> {
+    market: Cow<'a, MarketSummaryStatic>,
+    side: Side,
+    order_type: OrderType,
+    size: Decimal,
+    price: Option<Decimal>,
+    trigger_price: Option<Decimal>,
+    instruction: OrderInstruction,
+    client_id: Option<String>,
+    flags: Vec<OrderFlags>,
+    stp: Option<STPType>,
+    recv_window: Option<u64>,
+    round: bool,
+}
+
+impl<'a> OrderBuilder<'a> {
+    pub fn new(
+        market: &'a MarketSummaryStatic,
+        side: Side,
+        order_type: OrderType,
+        size: Decimal,
+    ) -> Self {
+        Self::with_market(Cow::Borrowed(market), side, order_type, size)
+    }
+
+    fn with_market(
+        market: Cow<'a, MarketSummaryStatic>,
+        side: Side,
+        order_type: OrderType,
+        size: Decimal,
+    ) -> Self {
+        Self {
+            market,
+            side,
+            order_type,
+            size,
+            price: None,
+            trigger_price: None,
+            instruction: OrderInstruction::GTC,
+            client_id: None,
+            flags: Vec::new(),
+            stp: None,
+            recv_window: None,
+            round: false,
+        }
+    }
+
+    /// Builder owning its market, e.g. one looked up in a MarketRegistry
+    pub fn owned(
+        market: MarketSummaryStatic,
+        side: Side,
+        order_type: OrderType,
+        size: Decimal,
+    ) -> OrderBuilder<'static> {
+        OrderBuilder::with_market(Cow::Owned(market), side, order_type, size)
+    }
+
+    /// Good till cancelled limit order
+    pub fn limit(
+        market: &'a MarketSummaryStatic,
+        side: Side,
+        size: Decimal,
+        price: Decimal,
+    ) -> Self {
+        Self::new(market, side, OrderType::LIMIT, size).price(price)
+    }
+
+    /// Market order, sent with the IOC instruction
+    pub fn market(market: &'a MarketSummaryStatic, side: Side, size: Decimal) -> Self {
+        Self::new(market, side, OrderType::MARKET, size).instruction(OrderInstruction::IOC)
+    }
+
+    pub fn price(mut self, price: Decimal) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    pub fn trigger_price(mut self, trigger_price: Decimal) -> Self {
+        self.trigger_price = Some(trigger_price);
+        self
+    }
+
+    pub fn instruction(mut self, instruction: OrderInstruction) -> Self {
+        self.instruction = instruction;
+        self
+    }
+
+    pub fn post_only(self) -> Self {
+        self.instruction(OrderInstruction::POST_ONLY)
+    }
+
+    pub fn ioc(self) -> Self {
+        self.instruction(OrderInstruction::IOC)
+    }
+
+    pub fn reduce_only(self) -> Self {
+        self.flag(OrderFlags::REDUCE_ONLY)
+    }
+
+    pub fn flag(mut self, flag: OrderFlags) -> Self {
+        if !self.flags.contains(&flag) {
+            self.flags.push(flag);
+        }
+        self
+    }
+
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    pub fn stp(mut self, stp: STPType) -> Self {
+        self.stp = Some(stp);
+        self
+    }
+
+    pub fn recv_window(mut self, recv_window: u64) -> Self {
+        self.recv_window = Some(recv_window);
+        self
+    }
+
+    /// Round prices and sizes onto the market's grid instead of rejecting them
+    ///
+    /// Prices round away from the market, down for buys and up for sells, and sizes round down
+    /// to the size increment. Minimum notional and maximum size are checked after rounding.
+    pub fn round_to_market(mut self, round: bool) -> Self {
+        self.round = round;
+        self
+    }
+
+    /// Validate the order against the market and build the request
+    ///
+    /// # Errors
+    ///
+    /// Every validation failure found, rather than only the first one
+    pub fn build(self) -> std::result::Result<OrderRequest, Vec<OrderValidationError>> {
+        let mut errors = Vec::new();
+        let tick_size = market_decimal("price_tick_size", self.market.price_tick_size);
+        let increment = market_decimal("order_size_increment", self.market.order_size_increment);
+        let min_notional = market_decimal("min_notional", self.market.min_notional);
+        let max_order_size = market_decimal("max_order_size", self.market.max_order_size);
+        let (tick_size, increment, min_notional, max_order_size) =
+            match (tick_size, increment, min_notional, max_order_size) {
+                (Ok(tick_size), Ok(increment), Ok(min_notional), Ok(max_order_size)) => {
+                    (tick_size, increment, min_notional, max_order_size)
+                }
+                (tick_size, increment, min_notional, max_order_size) => {
+                    return Err([tick_size, increment, min_notional, max_order_size]
+                        .into_iter()
+                        .filter_map(|value| value.err())
+                        .collect());
+                }
+            };
+
+        let is_limit = matches!(
+            self.order_type,
+            OrderType::LIMIT
+                | OrderType::STOP_LIMIT
+                | OrderType::TAKE_PROFIT_LIMIT
+                | OrderType::STOP_LOSS_LIMIT
+        );
+        let is_triggered = !matches!(self.order_type, OrderType::LIMIT | OrderType::MARKET);
+        match (is_limit, self.price) {
+            (true, None) => errors.push(OrderValidationError::MissingPrice(self.order_type)),
+            (false, Some(_)) => errors.push(OrderValidationError::UnexpectedPrice(self.order_type)),
+            _ => {}
+        }
+        if is_triggered && self.trigger_price.is_none() {
+            errors.push(OrderValidationError::MissingTriggerPrice(self.order_type));
+        }
+        if !is_limit
+            && matches!(
+                self.instruction,
+                OrderInstruction::POST_ONLY | OrderInstruction::RPI
+            )
+        {
+            errors.push(OrderValidationError::UnsupportedInstruction {
+                instruction: self.instruction.clone(),
+                order_type: self.order_type,
+            });
+        }
+
+        let price_strategy = match self.side {
+            Side::BUY => RoundingStrategy::ToNegativeInfinity,
+            Side::SELL => RoundingStrategy::ToPositiveInfinity,
+        };
+        let price = self.price.filter(|_| is_limit).map(|price| {
+            self.snap(price, tick_size, price_strategy, &mut errors, |price| {
+                OrderValidationError::InvalidPriceTick { price, tick_size }
+            })
+        });
+        let trigger_price = self.trigger_price.map(|trigger_price| {
+            self.snap(
+                trigger_price,
+                tick_size,
+                price_strategy,
+                &mut errors,
+                |price| OrderValidationError::InvalidPriceTick { price, tick_size },
+            )
+        });
+        let size = self.snap(
+            self.size,
+            increment,
+            RoundingStrategy::ToZero,
+            &mut errors,
+            |size| OrderValidationError::InvalidSizeIncrement { size, increment },
+        );
+
+        if size <= Decimal::ZERO {
+            errors.push(OrderValidationError::NonPositiveSize(size));
+        }
+        if size > max_order_size {
+            errors.push(OrderValidationError::AboveMaxOrderSize {
+                size,
+                max_order_size,
+            });
+        }
+        // Market orders have no price to compute a notional from until they execute
+        if let Some(price) = price.or(trigger_price)
+            && size > Decimal::ZERO
+            && size * price < min_notional
+        {
+            errors.push(OrderValidationError::BelowMinNotional {
+                notional: size * price,
+                min_notional,
+            });
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(OrderRequest {
+            instruction: self.instruction,
+            market: self.market.symbol.clone(),
+            price,
+            side: self.side,
+            size,
+            order_type: self.order_type,
+            client_id: self.client_id,
+            flags: self.flags,
+            recv_window: self.recv_window,
+            stp: self.stp,
+            trigger_price,
+        })
+    }
+
+    /// Round `value` onto multiples of `step` when rounding is enabled, otherwise record an
+    /// error if it is off the grid
+    fn snap(
+        &self,
+        value: Decimal,
+        step: Decimal,
+        strategy: RoundingStrategy,
+        errors: &mut Vec<OrderValidationError>,
+        error: impl FnOnce(Decimal) -> OrderValidationError,
+    ) -> Decimal {
+        if step.is_zero() || (value % step).is_zero() {
+            return value.normalize();
+        }
+        if self.round {
+            ((value / step).round_dp_with_strategy(0, strategy) * step).normalize()
+        } else {
+            errors.push(error(value));
+            value
+        }
+    }
+}
+
+fn market_decimal(field: &str, value: f64) -> std::result::Result<Decimal, OrderValidationError> {
+    Decimal::from_f64(value)
+        .filter(|value| !value.is_sign_negative())
+        .ok_or_else(|| OrderValidationError::InvalidMarketData(format!("{field}={value}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::parse_decimal;
+    use crate::structs::ResultsContainer;
+
+    fn btc() -> MarketSummaryStatic {
+        let markets: ResultsContainer<Vec<MarketSummaryStatic>> =
+            serde_json::from_str(include_str!("../tests/fixtures/markets.json")).unwrap();
+        markets.results.into_iter().next().unwrap()
+    }
+
+    fn decimal(value: &str) -> Decimal {
+        parse_decimal(value).unwrap()
+    }
+
+    #[test]
+    fn valid_limit_order() {
+        let market = btc();
+        let order = OrderBuilder::limit(&market, Side::BUY, decimal("0.001"), decimal("60000.1"))
+            .post_only()
+            .reduce_only()
+            .client_id("a")
+            .build()
+            .unwrap();
+        assert_eq!(order.market, "BTC-USD-PERP");
+        assert_eq!(order.price, Some(decimal("60000.1")));
+        assert_eq!(order.instruction, OrderInstruction::POST_ONLY);
+        assert_eq!(order.flags, vec![OrderFlags::REDUCE_ONLY]);
+    }
+
+    #[test]
+    fn rejects_with_every_error() {
+        let market = btc();
+        let errors = OrderBuilder::limit(&market, Side::BUY, decimal("0.000015"), decimal("1.05"))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                OrderValidationError::InvalidPriceTick {
+                    price: decimal("1.05"),
+                    tick_size: decimal("0.1"),
+                },
+                OrderValidationError::InvalidSizeIncrement {
+                    size: decimal("0.000015"),
+                    increment: decimal("0.00001"),
+                },
+                OrderValidationError::BelowMinNotional {
+                    notional: decimal("0.00001575"),
+                    min_notional: decimal("10"),
+                },
+            ]
+        );
+
+        let errors = OrderBuilder::market(&market, Side::SELL, decimal("101"))
+            .post_only()
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                OrderValidationError::UnsupportedInstruction {
+                    instruction: OrderInstruction::POST_ONLY,
+                    order_type: OrderType::MARKET,
+                },
+                OrderValidationError::AboveMaxOrderSize {
+                    size: decimal("101"),
+                    max_order_size: decimal("100"),
+                },
+            ]
+        );
+
+        let errors = OrderBuilder::new(&market, Side::SELL, OrderType::STOP_LIMIT, decimal("1"))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                OrderValidationError::MissingPrice(OrderType::STOP_LIMIT),
+                OrderValidationError::MissingTriggerPrice(OrderType::STOP_LIMIT),
+            ]
+        );
+    }
+
+    #[test]
+    fn rounds_onto_market_grid() {
+        let market = btc();
+        let buy = OrderBuilder::limit(
+            &market,
+            Side::BUY,
+            decimal("0.0012345"),
+            decimal("60000.19"),
+        )
+        .round_to_market(true)
+        .build()
+        .unwrap();
+        assert_eq!(buy.price, Some(decimal("60000.1")));
+        assert_eq!(buy.size, decimal("0.00123"));
+
+        let sell = OrderBuilder::limit(&market, Side::SELL, decimal("0.001"), decimal("60000.11"))
+            .round_to_market(true)
+            .build()
+            .unwrap();
+        assert_eq!(sell.price, Some(decimal("60000.2")));
+
+        // Rounding the size down to nothing is still rejected
+        let errors = OrderBuilder::market(&market, Side::BUY, decimal("0.000001"))
+            .round_to_market(true)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![OrderValidationError::NonPositiveSize(Decimal::ZERO)]
+        );
+    }
+}
@@ -0,0 +1,687 @@
+//! Local order book maintenance on top of the order book deltas websocket channel.
+//!
+//! [`LocalOrderBook`] applies snapshot and delta updates with sequence number checks, while
+//! [`OrderBookEngine`] wires books to [`OrderBookDeltasSubscription`]s and re-snapshots them
+//! over REST when a gap in the sequence numbers is detected. Both books and [`BookDepth`]
+//! levels, e.g. parsed from a REST [`OrderBookResponse`], provide analytics such as the
+//! microprice, size imbalance and the average price of taking a given size.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::{info, warn};
+use tokio::task::JoinHandle;
+
+use crate::error::{Error, Result};
+use crate::rest::Client;
+use crate::structs::{
+    Level, OrderBook, OrderBookParams, OrderBookResponse, OrderBookUpdateType, Side,
+};
+use crate::ws::{ChannelEvent, Identifier, OrderBookDeltasSubscription, WebsocketManager};
+
+mod analytics;
+
+/// Depth requested from the REST order book when re-snapshotting after a gap
+const RESNAPSHOT_DEPTH: u16 = 100;
+/// Delay between failed REST re-snapshot attempts
+const RESNAPSHOT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+pub type BookCallback = Arc<dyn Fn(&LocalOrderBook) + Send + Sync + 'static>;
+
+/// Price key ordering f64 prices by their total order
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Price(f64);
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Top levels of each side of a book, best price first, as (price, size) pairs
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BookDepth {
+    pub bids: Vec<(f64, f64)>,
+    pub asks: Vec<(f64, f64)>,
+}
+
+/// Top levels of a synced book at the time it was sampled
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthSnapshot {
+    pub market: String,
+    pub seq_no: u64,
+    /// Time of the last update applied to the book in milliseconds
+    pub last_updated_at: u64,
+    pub depth: BookDepth,
+}
+
+/// Samples a book on a fixed interval until stopped or dropped, see `OrderBookEngine::sample_depth`
+pub struct DepthSampler(JoinHandle<()>);
+
+impl DepthSampler {
+    /// Stop sampling, same as dropping the sampler
+    pub fn stop(self) {}
+}
+
+impl Drop for DepthSampler {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// In-memory order book for a single market, maintained from snapshots and sequenced deltas
+#[derive(Clone, Debug)]
+pub struct LocalOrderBook {
+    market: String,
+    seq_no: Option<u64>,
+    last_updated_at: u64,
+    bids: BTreeMap<Price, f64>,
+    asks: BTreeMap<Price, f64>,
+}
+
+impl LocalOrderBook {
+    pub fn new(market: impl Into<String>) -> Self {
+        Self {
+            market: market.into(),
+            seq_no: None,
+            last_updated_at: 0,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+        }
+    }
+
+    pub fn market(&self) -> &str {
+        &self.market
+    }
+
+    /// Sequence number of the last applied update, None until a snapshot has been applied
+    pub fn seq_no(&self) -> Option<u64> {
+        self.seq_no
+    }
+
+    /// Time of the last applied update in milliseconds
+    pub fn last_updated_at(&self) -> u64 {
+        self.last_updated_at
+    }
+
+    /// Whether a snapshot has been applied and no gap has been seen since
+    pub fn is_synced(&self) -> bool {
+        self.seq_no.is_some()
+    }
+
+    /// Best bid as (price, size)
+    pub fn best_bid(&self) -> Option<(f64, f64)> {
+        self.bids
+            .iter()
+            .next_back()
+            .map(|(price, size)| (price.0, *size))
+    }
+
+    /// Best ask as (price, size)
+    pub fn best_ask(&self) -> Option<(f64, f64)> {
+        self.asks
+            .iter()
+            .next()
+            .map(|(price, size)| (price.0, *size))
+    }
+
+    /// Resting size at `price` on `side`, zero if there is no such level
+    pub fn size_at(&self, side: Side, price: f64) -> f64 {
+        let levels = match side {
+            Side::BUY => &self.bids,
+            Side::SELL => &self.asks,
+        };
+        levels.get(&Price(price)).copied().unwrap_or(0.)
+    }
+
+    /// Up to `levels` levels of each side, best price first
+    pub fn depth(&self, levels: usize) -> BookDepth {
+        BookDepth {
+            bids: self
+                .bids
+                .iter()
+                .rev()
+                .take(levels)
+                .map(|(price, size)| (price.0, *size))
+                .collect(),
+            asks: self
+                .asks
+                .iter()
+                .take(levels)
+                .map(|(price, size)| (price.0, *size))
+                .collect(),
+        }
+    }
+
+    /// Up to `levels` levels of each side along with the book's sequence number, None while
+    /// the book is not synced
+    pub fn depth_snapshot(&self, levels: usize) -> Option<DepthSnapshot> {
+        Some(DepthSnapshot {
+            market: self.market.clone(),
+            seq_no: self.seq_no?,
+            last_updated_at: self.last_updated_at,
+            depth: self.depth(levels),
+        })
+    }
+
+    /// Take `size` off the level at `price` on `side`, removing the level once it is empty
+    ///
+    /// Used to apply simulated executions locally. The next update of the level from the
+    /// venue replaces the result.
+    pub fn take(&mut self, side: Side, price: f64, size: f64) {
+        let remaining = self.size_at(side, price) - size;
+        self.set_level(&Level {
+            side,
+            price,
+            size: remaining,
+        });
+    }
+
+    /// Clear the book, marking it as unsynced until the next snapshot
+    pub fn reset(&mut self) {
+        self.seq_no = None;
+        self.bids.clear();
+        self.asks.clear();
+    }
+
+    /// Apply a snapshot or delta from the order book deltas channel
+    ///
+    /// Deltas at or below the current sequence number are ignored as stale.
+    ///
+    /// # Errors
+    ///
+    /// `Error::OrderBookSequenceGap` if a delta does not directly follow the last applied
+    /// update, or arrives before any snapshot. The book is reset in that case.
+    pub fn apply(&mut self, update: &OrderBook) -> Result<()> {
+        match update.update_type {
+            OrderBookUpdateType::Snapshot => {
+                self.reset();
+            }
+            OrderBookUpdateType::Delta => match self.seq_no {
+                Some(seq_no) if update.seq_no <= seq_no => return Ok(()),
+                Some(seq_no) if update.seq_no == seq_no + 1 => {}
+                expected => {
+                    self.reset();
+                    return Err(Error::OrderBookSequenceGap {
+                        market: self.market.clone(),
+                        expected: expected.map(|seq_no| seq_no + 1),
+                        received: update.seq_no,
+                    });
+                }
+            },
+        }
+        for level in &update.deletes {
+            self.side_mut(level.side).remove(&Price(level.price));
+        }
+        for level in update.updates.iter().chain(&update.inserts) {
+            self.set_level(level);
+        }
+        self.seq_no = Some(update.seq_no);
+        self.last_updated_at = update.last_updated_at;
+        Ok(())
+    }
+
+    /// Replace the book with a REST order book snapshot
+    ///
+    /// # Errors
+    ///
+    /// If a price or size in the snapshot is not a number. The book is reset in that case.
+    pub fn apply_snapshot(&mut self, snapshot: &OrderBookResponse) -> Result<()> {
+        self.reset();
+        for (side, levels) in [(Side::BUY, &snapshot.bids), (Side::SELL, &snapshot.asks)] {
+            for (price, size) in levels {
+                match (f64::from_str(price), f64::from_str(size)) {
+                    (Ok(price), Ok(size)) => self.set_level(&Level { side, price, size }),
+                    _ => {
+                        self.reset();
+                        return Err(Error::TypeConversionError(format!(
+                            "Invalid order book level ({price}, {size}) for {}",
+                            self.market
+                        )));
+                    }
+                }
+            }
+        }
+        self.seq_no = Some(snapshot.seq_no);
+        self.last_updated_at = snapshot.last_updated_at;
+        Ok(())
+    }
+
+    fn side_mut(&mut self, side: Side) -> &mut BTreeMap<Price, f64> {
+        match side {
+            Side::BUY => &mut self.bids,
+            Side::SELL => &mut self.asks,
+        }
+    }
+
+    fn set_level(&mut self, level: &Level) {
+        let book_side = self.side_mut(level.side);
+        if level.size > 0. {
+            book_side.insert(Price(level.price), level.size);
+        } else {
+            book_side.remove(&Price(level.price));
+        }
+    }
+}
+
+type SharedBook = Arc<Mutex<TrackedBook>>;
+
+/// Book state shared between the websocket callback and the re-snapshot task
+struct TrackedBook {
+    book: LocalOrderBook,
+    /// Deltas received while a REST re-snapshot is in flight
+    pending: Vec<OrderBook>,
+    resnapshotting: bool,
+}
+
+impl TrackedBook {
+    /// Apply a REST snapshot followed by the deltas buffered since the gap
+    fn catch_up(&mut self, snapshot: &OrderBookResponse) -> Result<()> {
+        self.book.apply_snapshot(snapshot)?;
+        let pending = std::mem::take(&mut self.pending);
+        pending
+            .iter()
+            .try_for_each(|update| self.book.apply(update))
+    }
+}
+
+/// Maintains a LocalOrderBook per market from order book deltas subscriptions
+///
+/// Gaps in the delta sequence numbers trigger a REST snapshot, after which deltas buffered in
+/// the meantime are replayed. Books are reset on disconnect and rebuilt from the snapshot the
+/// server sends after resubscribing.
+#[derive(Clone)]
+pub struct OrderBookEngine {
+    client: Client,
+    books: Arc<Mutex<HashMap<String, (Identifier, SharedBook)>>>,
+}
+
+impl OrderBookEngine {
+    /// Create a new engine using the rest client to re-snapshot books
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            books: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Subscribe to the order book deltas of a market and maintain its book
+    ///
+    /// # Parameters
+    ///
+    /// * `manager` - The WebsocketManager to subscribe with
+    /// * `market` - The market symbol
+    /// * `on_change` - Called with the updated book after every applied update. The book is
+    ///   locked while the callback runs, so it must not call back into the engine for the same
+    ///   market.
+    ///
+    /// # Returns
+    ///
+    /// The Identifier of the underlying subscription
+    ///
+    /// # Errors
+    ///
+    /// If the subscription cannot be sent
+    pub async fn track(
+        &self,
+        manager: &WebsocketManager,
+        market: impl Into<String>,
+        on_change: impl Fn(&LocalOrderBook) + Send + Sync + 'static,
+    ) -> Result<Identifier> {
+        let market = market.into();
+        let tracked = Arc::new(Mutex::new(TrackedBook {
+            book: LocalOrderBook::new(market.clone()),
+            pending: Vec::new(),
+            resnapshotting: false,
+        }));
+        let on_change: BookCallback = Arc::new(on_change);
+        let client = self.client.clone();
+        let state = tracked.clone();
+        let identifier = manager
+            .subscribe_typed(
+                OrderBookDeltasSubscription::new(market.clone()),
+                move |event| match event {
+                    ChannelEvent::Data(update) => {
+                        Self::on_update(&client, &state, &on_change, update)
+                    }
+                    ChannelEvent::Disconnected => {
+                        let mut tracked = state.lock().unwrap();
+                        tracked.book.reset();
+                        tracked.pending.clear();
+                        tracked.resnapshotting = false;
+                    }
+                    _ => {}
+                },
+            )
+            .await?;
+        let previous = self
+            .books
+            .lock()
+            .unwrap()
+            .insert(market, (identifier, tracked));
+        if let Some((previous, _)) = previous {
+            manager.unsubscribe(previous).await?;
+        }
+        Ok(identifier)
+    }
+
+    /// Stop maintaining the book of a market and unsubscribe from its deltas
+    ///
+    /// # Errors
+    ///
+    /// If the unsubscribe request cannot be sent
+    pub async fn untrack(&self, manager: &WebsocketManager, market: &str) -> Result<()> {
+        let removed = self.books.lock().unwrap().remove(market);
+        if let Some((identifier, _)) = removed {
+            manager.unsubscribe(identifier).await?;
+        }
+        Ok(())
+    }
+
+    /// Get a copy of the current book of a market
+    pub fn book(&self, market: &str) -> Option<LocalOrderBook> {
+        self.with_book(market, LocalOrderBook::clone)
+    }
+
+    /// Run a function against the current book of a market without copying it
+    pub fn with_book<R>(&self, market: &str, f: impl FnOnce(&LocalOrderBook) -> R) -> Option<R> {
+        let tracked = self.books.lock().unwrap().get(market)?.1.clone();
+        let tracked = tracked.lock().unwrap();
+        Some(f(&tracked.book))
+    }
+
+    /// Emit the top levels of a market's book every `interval`, independently of how often
+    /// deltas arrive
+    ///
+    /// A snapshot is emitted on every tick while the market is tracked and its book is synced,
+    /// whether or not the book changed since the previous tick. Ticks are skipped while the
+    /// book is re-snapshotting or the market is not tracked.
+    ///
+    /// # Parameters
+    ///
+    /// * `market` - The market symbol, tracked with `track`
+    /// * `interval` - Time between samples
+    /// * `levels` - Levels of each side to include
+    /// * `callback` - Called with every sample, on the sampling task
+    ///
+    /// # Returns
+    ///
+    /// A DepthSampler which stops sampling when stopped or dropped
+    pub fn sample_depth(
+        &self,
+        market: impl Into<String>,
+        interval: Duration,
+        levels: usize,
+        callback: impl Fn(&DepthSnapshot) + Send + Sync + 'static,
+    ) -> DepthSampler {
+        let market = market.into();
+        let engine = self.clone();
+        DepthSampler(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let snapshot = engine
+                    .with_book(&market, |book| book.depth_snapshot(levels))
+                    .flatten();
+                if let Some(snapshot) = snapshot {
+                    callback(&snapshot);
+                }
+            }
+        }))
+    }
+
+    fn on_update(
+        client: &Client,
+        state: &SharedBook,
+        on_change: &BookCallback,
+        update: &OrderBook,
+    ) {
+        let mut tracked = state.lock().unwrap();
+        if tracked.resnapshotting {
+            if update.update_type == OrderBookUpdateType::Snapshot {
+                tracked.resnapshotting = false;
+                tracked.pending.clear();
+            } else {
+                tracked.pending.push(update.clone());
+                return;
+            }
+        }
+        match tracked.book.apply(update) {
+            Ok(()) => on_change(&tracked.book),
+            Err(e) => {
+                warn!("{e}, re-snapshotting over REST");
+                tracked.resnapshotting = true;
+                tokio::spawn(Self::resnapshot(
+                    client.clone(),
+                    update.market.clone(),
+                    state.clone(),
+                    on_change.clone(),
+                ));
+            }
+        }
+    }
+
+    async fn resnapshot(
+        client: Client,
+        market: String,
+        state: SharedBook,
+        on_change: BookCallback,
+    ) {
+        let params = OrderBookParams {
+            depth: Some(RESNAPSHOT_DEPTH),
+            price_tick: None,
+        };
+        loop {
+            match client.orderbook(market.clone(), params.clone()).await {
+                Ok(snapshot) => {
+                    let mut tracked = state.lock().unwrap();
+                    if !tracked.resnapshotting {
+                        return;
+                    }
+                    match tracked.catch_up(&snapshot) {
+                        Ok(()) => {
+                            info!("Order book {market} re-snapshotted at {}", snapshot.seq_no);
+                            tracked.resnapshotting = false;
+                            on_change(&tracked.book);
+                            return;
+                        }
+                        Err(e) => warn!("Could not catch up order book {market}: {e}"),
+                    }
+                }
+                Err(e) => warn!("Could not fetch order book snapshot for {market}: {e}"),
+            }
+            tokio::time::sleep(RESNAPSHOT_RETRY_DELAY).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(side: Side, price: f64, size: f64) -> Level {
+        Level { side, price, size }
+    }
+
+    fn update(
+        update_type: OrderBookUpdateType,
+        seq_no: u64,
+        inserts: Vec<Level>,
+        updates: Vec<Level>,
+        deletes: Vec<Level>,
+    ) -> OrderBook {
+        OrderBook {
+            seq_no,
+            market: "BTC-USD-PERP".into(),
+            last_updated_at: seq_no * 1000,
+            update_type,
+            deletes,
+            inserts,
+            updates,
+        }
+    }
+
+    fn snapshot() -> OrderBook {
+        update(
+            OrderBookUpdateType::Snapshot,
+            10,
+            vec![
+                level(Side::BUY, 100., 1.),
+                level(Side::BUY, 99.5, 2.),
+                level(Side::SELL, 101., 3.),
+                level(Side::SELL, 102., 4.),
+            ],
+            vec![],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn snapshot_and_deltas() {
+        let mut book = LocalOrderBook::new("BTC-USD-PERP");
+        assert!(!book.is_synced());
+        book.apply(&snapshot()).unwrap();
+        assert_eq!(book.best_bid(), Some((100., 1.)));
+        assert_eq!(book.best_ask(), Some((101., 3.)));
+
+        book.apply(&update(
+            OrderBookUpdateType::Delta,
+            11,
+            vec![level(Side::BUY, 100.5, 0.5)],
+            vec![level(Side::SELL, 101., 1.5)],
+            vec![level(Side::BUY, 99.5, 0.)],
+        ))
+        .unwrap();
+        assert_eq!(book.seq_no(), Some(11));
+        assert_eq!(
+            book.depth(5),
+            BookDepth {
+                bids: vec![(100.5, 0.5), (100., 1.)],
+                asks: vec![(101., 1.5), (102., 4.)],
+            }
+        );
+
+        // stale deltas are ignored
+        book.apply(&update(
+            OrderBookUpdateType::Delta,
+            11,
+            vec![],
+            vec![],
+            vec![level(Side::BUY, 100.5, 0.)],
+        ))
+        .unwrap();
+        assert_eq!(book.best_bid(), Some((100.5, 0.5)));
+        assert_eq!(book.depth(1).asks, vec![(101., 1.5)]);
+    }
+
+    #[test]
+    fn sequence_gap() {
+        let mut book = LocalOrderBook::new("BTC-USD-PERP");
+        let delta = update(OrderBookUpdateType::Delta, 12, vec![], vec![], vec![]);
+        assert!(matches!(
+            book.apply(&delta),
+            Err(Error::OrderBookSequenceGap {
+                expected: None,
+                received: 12,
+                ..
+            })
+        ));
+
+        book.apply(&snapshot()).unwrap();
+        assert!(matches!(
+            book.apply(&delta),
+            Err(Error::OrderBookSequenceGap {
+                expected: Some(11),
+                received: 12,
+                ..
+            })
+        ));
+        assert!(!book.is_synced());
+        assert_eq!(book.best_bid(), None);
+    }
+
+    #[test]
+    fn rest_snapshot_catch_up() {
+        let mut tracked = TrackedBook {
+            book: LocalOrderBook::new("BTC-USD-PERP"),
+            pending: vec![
+                update(OrderBookUpdateType::Delta, 20, vec![], vec![], vec![]),
+                update(
+                    OrderBookUpdateType::Delta,
+                    21,
+                    vec![level(Side::SELL, 100.9, 1.)],
+                    vec![],
+                    vec![],
+                ),
+            ],
+            resnapshotting: true,
+        };
+        let snapshot = OrderBookResponse {
+            asks: vec![("101".into(), "2".into())],
+            bids: vec![("100".into(), "1.5".into())],
+            last_updated_at: 1,
+            market: "BTC-USD-PERP".into(),
+            seq_no: 20,
+        };
+        tracked.catch_up(&snapshot).unwrap();
+        assert_eq!(tracked.book.seq_no(), Some(21));
+        assert_eq!(tracked.book.best_bid(), Some((100., 1.5)));
+        assert_eq!(tracked.book.best_ask(), Some((100.9, 1.)));
+        assert!(tracked.pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn depth_sampling() {
+        let engine = OrderBookEngine::new(Client::with_api_token(
+            reqwest::Client::new(),
+            crate::url::URL::Testnet,
+            "token".into(),
+        ));
+        let tracked = Arc::new(Mutex::new(TrackedBook {
+            book: LocalOrderBook::new("BTC-USD-PERP"),
+            pending: Vec::new(),
+            resnapshotting: false,
+        }));
+        engine
+            .books
+            .lock()
+            .unwrap()
+            .insert("BTC-USD-PERP".into(), (Identifier(1), tracked.clone()));
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let sampler = engine.sample_depth(
+            "BTC-USD-PERP",
+            Duration::from_millis(10),
+            1,
+            move |snapshot| sender.send(snapshot.clone()).unwrap(),
+        );
+
+        // Nothing is emitted before the book is synced
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(receiver.is_empty());
+        tracked.lock().unwrap().book.apply(&snapshot()).unwrap();
+        let sample = receiver.recv().await.unwrap();
+        assert_eq!(sample.seq_no, 10);
+        assert_eq!(
+            sample.depth,
+            BookDepth {
+                bids: vec![(100., 1.)],
+                asks: vec![(101., 3.)],
+            }
+        );
+
+        sampler.stop();
+        assert!(receiver.recv().await.is_none());
+    }
+}
@@ -0,0 +1,231 @@
+use std::str::FromStr;
+
+use super::{BookDepth, LocalOrderBook};
+use crate::error::{Error, Result};
+use crate::structs::{OrderBookResponse, Side};
+
+/// Mid price of the best bid and ask
+fn mid(bid: Option<(f64, f64)>, ask: Option<(f64, f64)>) -> Option<f64> {
+    Some((bid?.0 + ask?.0) / 2.)
+}
+
+/// Mid price weighted by the size on the opposite side, leaning towards the thinner side
+fn micro(bid: Option<(f64, f64)>, ask: Option<(f64, f64)>) -> Option<f64> {
+    let ((bid, bid_size), (ask, ask_size)) = (bid?, ask?);
+    let total = bid_size + ask_size;
+    (total > 0.).then(|| (bid * ask_size + ask * bid_size) / total)
+}
+
+/// Bid size minus ask size over their sum, from -1 for only asks to 1 for only bids
+fn imbalance_of(
+    bids: impl Iterator<Item = (f64, f64)>,
+    asks: impl Iterator<Item = (f64, f64)>,
+) -> Option<f64> {
+    let bid_size: f64 = bids.map(|(_, size)| size).sum();
+    let ask_size: f64 = asks.map(|(_, size)| size).sum();
+    let total = bid_size + ask_size;
+    (total > 0.).then(|| (bid_size - ask_size) / total)
+}
+
+/// Average price of taking `size` from levels ordered best first, None if they are too thin
+fn vwap_of(levels: impl Iterator<Item = (f64, f64)>, size: f64) -> Option<f64> {
+    if size <= 0. {
+        return None;
+    }
+    let mut remaining = size;
+    let mut notional = 0.;
+    for (price, level_size) in levels {
+        let taken = remaining.min(level_size);
+        notional += taken * price;
+        remaining -= taken;
+        if remaining <= 0. {
+            return Some(notional / size);
+        }
+    }
+    None
+}
+
+/// Merge levels into buckets of `bucket` in price, rounding bids down and asks up
+fn aggregate_levels(levels: &[(f64, f64)], bucket: f64, side: Side) -> Vec<(f64, f64)> {
+    let mut aggregated: Vec<(f64, f64)> = Vec::new();
+    for &(price, size) in levels {
+        // Guard against prices a rounding error away from a bucket boundary
+        let steps = price / bucket;
+        let steps = match side {
+            Side::BUY => (steps + 1e-9).floor(),
+            Side::SELL => (steps - 1e-9).ceil(),
+        };
+        let price = steps * bucket;
+        match aggregated.last_mut() {
+            Some((last, total)) if *last == price => *total += size,
+            _ => aggregated.push((price, size)),
+        }
+    }
+    aggregated
+}
+
+impl BookDepth {
+    pub fn mid_price(&self) -> Option<f64> {
+        mid(self.bids.first().copied(), self.asks.first().copied())
+    }
+
+    /// Top of book mid price weighted by the size on the opposite side
+    pub fn microprice(&self) -> Option<f64> {
+        micro(self.bids.first().copied(), self.asks.first().copied())
+    }
+
+    /// Size imbalance over the top `levels` levels of each side, from -1 for only asks to 1
+    /// for only bids, None if both sides are empty
+    pub fn imbalance(&self, levels: usize) -> Option<f64> {
+        imbalance_of(
+            self.bids.iter().take(levels).copied(),
+            self.asks.iter().take(levels).copied(),
+        )
+    }
+
+    /// Average price a market order of `size` on `side` would fill at, None if the depth is
+    /// too thin
+    pub fn vwap_to_size(&self, side: Side, size: f64) -> Option<f64> {
+        match side {
+            Side::BUY => vwap_of(self.asks.iter().copied(), size),
+            Side::SELL => vwap_of(self.bids.iter().copied(), size),
+        }
+    }
+
+    /// Group levels into price buckets of `bucket`, e.g. `10.` to see the book in steps of 10
+    ///
+    /// Bids are rounded down and asks up to their bucket, so that a bucket never shows a better
+    /// price than any level in it. The last bucket of each side is partial when the depth was
+    /// cut off inside it.
+    ///
+    /// # Errors
+    ///
+    /// If `bucket` is not a positive number
+    pub fn aggregate(&self, bucket: f64) -> Result<BookDepth> {
+        if !(bucket.is_finite() && bucket > 0.) {
+            return Err(Error::TypeConversionError(format!(
+                "Invalid price bucket {bucket}"
+            )));
+        }
+        Ok(BookDepth {
+            bids: aggregate_levels(&self.bids, bucket, Side::BUY),
+            asks: aggregate_levels(&self.asks, bucket, Side::SELL),
+        })
+    }
+}
+
+impl TryFrom<&OrderBookResponse> for BookDepth {
+    type Error = Error;
+
+    /// Parse the levels of a REST order book snapshot, which are sorted best first
+    fn try_from(snapshot: &OrderBookResponse) -> Result<Self> {
+        let parse = |levels: &[(String, String)]| {
+            levels
+                .iter()
+                .map(
+                    |(price, size)| match (f64::from_str(price), f64::from_str(size)) {
+                        (Ok(price), Ok(size)) => Ok((price, size)),
+                        _ => Err(Error::TypeConversionError(format!(
+                            "Invalid order book level ({price}, {size}) for {}",
+                            snapshot.market
+                        ))),
+                    },
+                )
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(BookDepth {
+            bids: parse(&snapshot.bids)?,
+            asks: parse(&snapshot.asks)?,
+        })
+    }
+}
+
+impl LocalOrderBook {
+    pub fn mid_price(&self) -> Option<f64> {
+        mid(self.best_bid(), self.best_ask())
+    }
+
+    /// Top of book mid price weighted by the size on the opposite side
+    pub fn microprice(&self) -> Option<f64> {
+        micro(self.best_bid(), self.best_ask())
+    }
+
+    /// Size imbalance over the top `levels` levels of each side, see `BookDepth::imbalance`
+    pub fn imbalance(&self, levels: usize) -> Option<f64> {
+        imbalance_of(
+            self.bids
+                .iter()
+                .rev()
+                .take(levels)
+                .map(|(price, size)| (price.0, *size)),
+            self.asks
+                .iter()
+                .take(levels)
+                .map(|(price, size)| (price.0, *size)),
+        )
+    }
+
+    /// Average price a market order of `size` on `side` would fill at, None if the book is
+    /// too thin
+    pub fn vwap_to_size(&self, side: Side, size: f64) -> Option<f64> {
+        match side {
+            Side::BUY => vwap_of(self.asks.iter().map(|(price, size)| (price.0, *size)), size),
+            Side::SELL => vwap_of(
+                self.bids.iter().rev().map(|(price, size)| (price.0, *size)),
+                size,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn analytics() {
+        let snapshot: OrderBookResponse =
+            serde_json::from_str(include_str!("../../tests/fixtures/orderbook_response.json"))
+                .unwrap();
+        let depth = BookDepth::try_from(&snapshot).unwrap();
+        let mut book = LocalOrderBook::new(snapshot.market.clone());
+        book.apply_snapshot(&snapshot).unwrap();
+        assert_eq!(book.depth(usize::MAX), depth);
+        assert_eq!(book.mid_price(), depth.mid_price());
+        assert_eq!(book.microprice(), depth.microprice());
+        assert_eq!(book.imbalance(3), depth.imbalance(3));
+        assert_eq!(
+            book.vwap_to_size(Side::BUY, 1.),
+            depth.vwap_to_size(Side::BUY, 1.)
+        );
+
+        let depth = BookDepth {
+            bids: vec![(100., 1.), (99.5, 3.), (98.2, 4.)],
+            asks: vec![(101., 3.), (102., 1.), (104.5, 2.)],
+        };
+        assert_eq!(depth.mid_price(), Some(100.5));
+        // One on the bid against three on the ask leans towards the bid
+        assert_eq!(depth.microprice(), Some(100.25));
+        assert_eq!(depth.imbalance(1), Some(-0.5));
+        assert_eq!(depth.imbalance(2), Some(0.));
+        assert_eq!(depth.vwap_to_size(Side::BUY, 4.), Some(101.25));
+        assert_eq!(depth.vwap_to_size(Side::SELL, 2.), Some(99.75));
+        assert_eq!(depth.vwap_to_size(Side::SELL, 9.), None);
+        assert_eq!(
+            depth.aggregate(1.).unwrap(),
+            BookDepth {
+                bids: vec![(100., 1.), (99., 3.), (98., 4.)],
+                asks: vec![(101., 3.), (102., 1.), (105., 2.)],
+            }
+        );
+        assert_eq!(
+            depth.aggregate(5.).unwrap(),
+            BookDepth {
+                bids: vec![(100., 1.), (95., 7.)],
+                asks: vec![(105., 6.)],
+            }
+        );
+        assert!(depth.aggregate(0.).is_err());
+        assert_eq!(BookDepth::default().mid_price(), None);
+    }
+}
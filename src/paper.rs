@@ -0,0 +1,777 @@
+//! Paper trading against live market data.
+//!
+//! [`PaperExchange`] simulates order execution against a [`LocalOrderBook`] per market, fed from
+//! the same order book and trades channels used for live trading. Marketable orders take
+//! liquidity from the book when placed, removing the filled size from it until the venue next
+//! updates those levels. Resting limit orders join the back of the queue at their price level:
+//! they fill in full once a trade prints through their price, and partially as volume trades at
+//! their price after the size queued ahead of them has been worked through.
+//!
+//! A trade at a level also shrinks that level in the next book update. Each is only counted once
+//! against the queue: the book timestamp tells whether a trade is already reflected in the book,
+//! and a shrinking level only advances the queue as cancellations once no trade accounts for it.
+
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+
+use crate::decimal::quantize;
+use crate::error::{Error, Result};
+use crate::orderbook::LocalOrderBook;
+use crate::structs::{
+    BBO, Fill, FillLiquidity, FillType, ModifyOrderRequest, ModifyOrderResult, OrderBook,
+    OrderBookResponse, OrderInstruction, OrderRequest, OrderStatus, OrderType, OrderUpdate, Side,
+    Trade,
+};
+
+/// Account reported on simulated orders
+const PAPER_ACCOUNT: &str = "paper";
+/// Currency reported on simulated fill fees
+const FEE_CURRENCY: &str = "USDC";
+const CANCEL_REASON_USER: &str = "USER_CANCELED";
+const CANCEL_REASON_POST_ONLY: &str = "POST_ONLY_WOULD_CROSS";
+const CANCEL_REASON_LIQUIDITY: &str = "NOT_ENOUGH_LIQUIDITY";
+
+/// Order and fill updates produced by the simulation, in the order they happened
+#[derive(Clone, Debug)]
+pub enum PaperEvent {
+    Order(OrderUpdate),
+    Fill(Fill),
+}
+
+#[derive(Clone, Debug)]
+struct SimulatedOrder {
+    update: OrderUpdate,
+    /// Size resting at the order's price level ahead of it
+    queue_ahead: f64,
+    /// Size of the order's level in the book when last seen
+    level_size: f64,
+    /// Volume traded at the order's level that the book does not reflect yet
+    traded_unseen: f64,
+    filled_notional: f64,
+}
+
+impl SimulatedOrder {
+    fn limit_price(&self) -> Option<f64> {
+        self.update.price.and_then(|price| price.to_f64())
+    }
+
+    fn filled_size(&self) -> f64 {
+        (self.update.size - self.update.remaining_size)
+            .to_f64()
+            .unwrap_or(0.)
+    }
+
+    /// Work a trade of `volume` at the order's price through the queue ahead of it
+    ///
+    /// # Returns
+    ///
+    /// The part of `volume` left once the queue ahead has been consumed
+    fn trade_at_level(&mut self, volume: f64, book_updated_at: u64, traded_at: u64) -> f64 {
+        // A trade the book already reflects is part of what the book removed from the level,
+        // so the queue must not be capped at the level before this trade is worked through
+        if traded_at > book_updated_at {
+            // Size the book removed without trades accounting for it was cancelled, so no more
+            // than what is left at the level can be ahead
+            self.queue_ahead = self
+                .queue_ahead
+                .min((self.level_size - self.traded_unseen).max(0.));
+            self.traded_unseen += volume;
+        }
+        let consumed = self.queue_ahead.min(volume);
+        self.queue_ahead -= consumed;
+        volume - consumed
+    }
+}
+
+/// Simulated exchange matching orders against locally maintained order books
+#[derive(Clone, Debug, Default)]
+pub struct PaperExchange {
+    books: HashMap<String, LocalOrderBook>,
+    /// Open orders in placement order, so that earlier orders keep time priority
+    orders: Vec<SimulatedOrder>,
+    events: Vec<PaperEvent>,
+    maker_fee_rate: f64,
+    taker_fee_rate: f64,
+    next_id: u64,
+    seq_no: u64,
+    /// Simulation clock in milliseconds, driven by market data timestamps
+    now: u64,
+}
+
+impl PaperExchange {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Charge fees on simulated fills as a fraction of notional
+    pub fn with_fee_rates(mut self, maker_fee_rate: f64, taker_fee_rate: f64) -> Self {
+        self.maker_fee_rate = maker_fee_rate;
+        self.taker_fee_rate = taker_fee_rate;
+        self
+    }
+
+    /// Simulated order book for a market, if any market data has been received for it
+    pub fn book(&self, market: &str) -> Option<&LocalOrderBook> {
+        self.books.get(market)
+    }
+
+    /// Open simulated orders, optionally restricted to a single market
+    pub fn open_orders(&self, market: Option<&str>) -> Vec<OrderUpdate> {
+        self.orders
+            .iter()
+            .filter(|order| market.is_none_or(|market| order.update.market == market))
+            .map(|order| order.update.clone())
+            .collect()
+    }
+
+    /// Order and fill updates produced since the last call
+    pub fn take_events(&mut self) -> Vec<PaperEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Replace a market's book with a REST order book snapshot
+    ///
+    /// # Errors
+    ///
+    /// If a price or size in the snapshot is not a number.
+    pub fn on_order_book_snapshot(&mut self, snapshot: &OrderBookResponse) -> Result<()> {
+        self.book_mut(&snapshot.market).apply_snapshot(snapshot)?;
+        self.now = self.now.max(snapshot.last_updated_at);
+        self.reconcile_queues(&snapshot.market);
+        Ok(())
+    }
+
+    /// Apply an order book update from the order book deltas channel
+    ///
+    /// Resting orders move up the queue when size ahead of them is removed from their level.
+    ///
+    /// # Errors
+    ///
+    /// `Error::OrderBookSequenceGap` if the update does not follow the last applied one. The
+    /// book is reset and orders can not be placed in that market until the next snapshot.
+    pub fn on_order_book(&mut self, update: &OrderBook) -> Result<()> {
+        self.book_mut(&update.market).apply(update)?;
+        self.now = self.now.max(update.last_updated_at);
+        self.reconcile_queues(&update.market);
+        Ok(())
+    }
+
+    /// Match resting orders against a trade from the trades channel
+    ///
+    /// Orders priced through the trade fill in full at their limit price. Orders priced at the
+    /// trade fill from whatever traded volume is left once the size queued ahead of them, and
+    /// any earlier simulated orders at the same price, have been consumed.
+    pub fn on_trade(&mut self, trade: &Trade) {
+        self.now = self.now.max(trade.created_at);
+        let resting_side = match trade.side {
+            Side::BUY => Side::SELL,
+            Side::SELL => Side::BUY,
+        };
+        let mut candidates: Vec<(usize, f64)> = self
+            .orders
+            .iter()
+            .enumerate()
+            .filter(|(_, order)| {
+                order.update.market == trade.market && order.update.side == resting_side
+            })
+            .filter_map(|(index, order)| {
+                let price = order.limit_price()?;
+                let reached = match resting_side {
+                    Side::BUY => price >= trade.price,
+                    Side::SELL => price <= trade.price,
+                };
+                reached.then_some((index, price))
+            })
+            .collect();
+        // Best priced orders first, stable so that earlier orders keep time priority
+        candidates.sort_by(|(_, a), (_, b)| match resting_side {
+            Side::BUY => b.total_cmp(a),
+            Side::SELL => a.total_cmp(b),
+        });
+
+        let book_updated_at = self
+            .books
+            .get(&trade.market)
+            .map_or(0, LocalOrderBook::last_updated_at);
+        // Volume at the trade price already filled for earlier simulated orders at that price
+        let mut filled_at_price = 0.;
+        for (index, price) in candidates {
+            let order = &mut self.orders[index];
+            let remaining = order.update.remaining_size;
+            let size = if price == trade.price {
+                let reached = order.trade_at_level(trade.size, book_updated_at, trade.created_at);
+                let size = to_decimal((reached - filled_at_price).max(0.)).min(remaining);
+                filled_at_price += size.to_f64().unwrap_or(0.);
+                size
+            } else {
+                remaining
+            };
+            if !size.is_zero() {
+                self.fill(index, size, price, FillLiquidity::MAKER);
+            }
+        }
+        self.orders
+            .retain(|order| order.update.status != OrderStatus::CLOSED);
+    }
+
+    /// Place a simulated order
+    ///
+    /// Market orders and the marketable part of limit orders fill immediately against the book.
+    /// Any remaining size of a good till cancelled limit order rests at the back of its price
+    /// level's queue.
+    ///
+    /// # Returns
+    ///
+    /// The order state after placement
+    ///
+    /// # Errors
+    ///
+    /// If the market has no synced book, or the order is not a market or limit order with a
+    /// positive size.
+    pub fn place_order(&mut self, request: OrderRequest) -> Result<OrderUpdate> {
+        if !matches!(request.order_type, OrderType::MARKET | OrderType::LIMIT) {
+            return Err(Error::PaperTradingError(format!(
+                "unsupported order type {:?}",
+                request.order_type
+            )));
+        }
+        if request.size <= Decimal::ZERO {
+            return Err(Error::PaperTradingError(format!(
+                "order size {} is not positive",
+                request.size
+            )));
+        }
+        let limit = match (request.order_type, request.price) {
+            (OrderType::MARKET, _) => None,
+            (_, Some(price)) => Some(price.to_f64().ok_or_else(|| {
+                Error::TypeConversionError(format!("price {price} is not representable"))
+            })?),
+            (_, None) => {
+                return Err(Error::PaperTradingError(
+                    "limit order without a price".into(),
+                ));
+            }
+        };
+        let book = self
+            .books
+            .get(&request.market)
+            .filter(|book| book.is_synced())
+            .ok_or_else(|| {
+                Error::PaperTradingError(format!("no synced order book for {}", request.market))
+            })?;
+        let depth = book.depth(usize::MAX);
+        let (book_side, opposite) = match request.side {
+            Side::BUY => (Side::SELL, depth.asks),
+            Side::SELL => (Side::BUY, depth.bids),
+        };
+        let crosses = |price: f64| match (limit, request.side) {
+            (None, _) => true,
+            (Some(limit), Side::BUY) => price <= limit,
+            (Some(limit), Side::SELL) => price >= limit,
+        };
+        let queue_ahead = limit.map_or(0., |limit| book.size_at(request.side, limit));
+
+        self.next_id += 1;
+        let update = OrderUpdate {
+            account: PAPER_ACCOUNT.into(),
+            cancel_reason: String::new(),
+            client_id: request.client_id.clone().unwrap_or_default(),
+            created_at: self.now,
+            id: format!("paper-{}", self.next_id),
+            instruction: request.instruction.clone(),
+            last_updated_at: self.now,
+            market: request.market.clone(),
+            price: request.price.filter(|_| limit.is_some()),
+            remaining_size: request.size,
+            side: request.side,
+            size: request.size,
+            status: OrderStatus::NEW,
+            timestamp: self.now,
+            order_type: request.order_type,
+            seq_no: 0,
+            avg_fill_price: f64::NAN,
+            received_at: self.now,
+            published_at: self.now,
+            flags: request.flags.clone(),
+            trigger_price: None,
+            stp: request.stp.clone(),
+        };
+        self.orders.push(SimulatedOrder {
+            update,
+            queue_ahead,
+            level_size: queue_ahead,
+            traded_unseen: 0.,
+            filled_notional: 0.,
+        });
+        let index = self.orders.len() - 1;
+
+        let would_cross = opposite.first().is_some_and(|(price, _)| crosses(*price));
+        if request.instruction == OrderInstruction::POST_ONLY && would_cross {
+            self.close(index, CANCEL_REASON_POST_ONLY);
+            return Ok(self.orders.remove(index).update);
+        }
+        for (price, size) in opposite {
+            let remaining = self.orders[index].update.remaining_size;
+            if remaining.is_zero() || !crosses(price) {
+                break;
+            }
+            let size = to_decimal(size).min(remaining);
+            if !size.is_zero() {
+                self.fill(index, size, price, FillLiquidity::TAKER);
+                self.book_mut(&request.market)
+                    .take(book_side, price, size.to_f64().unwrap_or(0.));
+            }
+        }
+
+        let order = &self.orders[index];
+        if order.update.status == OrderStatus::CLOSED {
+            return Ok(self.orders.remove(index).update);
+        }
+        if limit.is_none() || request.instruction == OrderInstruction::IOC {
+            self.close(index, CANCEL_REASON_LIQUIDITY);
+            return Ok(self.orders.remove(index).update);
+        }
+        if order.update.status == OrderStatus::NEW {
+            self.transition(index, OrderStatus::OPEN, "");
+        }
+        Ok(self.orders[index].update.clone())
+    }
+
+    /// Cancel an open simulated order
+    ///
+    /// # Errors
+    ///
+    /// If there is no open order with this id.
+    pub fn cancel_order(&mut self, order_id: &str) -> Result<OrderUpdate> {
+        let index = self
+            .orders
+            .iter()
+            .position(|order| order.update.id == order_id)
+            .ok_or_else(|| Error::PaperTradingError(format!("no open order {order_id}")))?;
+        self.close(index, CANCEL_REASON_USER);
+        Ok(self.orders.remove(index).update)
+    }
+
+    /// Cancel all open simulated orders, optionally restricted to a single market
+    pub fn cancel_all_orders(&mut self, market: Option<&str>) -> Vec<OrderUpdate> {
+        let ids: Vec<String> = self
+            .open_orders(market)
+            .into_iter()
+            .map(|order| order.id)
+            .collect();
+        ids.iter()
+            .filter_map(|id| self.cancel_order(id).ok())
+            .collect()
+    }
+
+    /// Replace an open simulated order with one at the requested price and size, keeping its
+    /// client id
+    ///
+    /// The replacement is placed before the order is cancelled, so the order keeps resting if
+    /// the replacement is rejected.
+    ///
+    /// # Errors
+    ///
+    /// If there is no open order with this id in the market, or the replacement cannot be
+    /// placed.
+    pub fn modify_order(&mut self, request: ModifyOrderRequest) -> Result<ModifyOrderResult> {
+        let order = self
+            .open_orders(Some(&request.market))
+            .into_iter()
+            .find(|order| order.id == request.id)
+            .ok_or_else(|| Error::PaperTradingError(format!("no open order {}", request.id)))?;
+        let replacement = self.place_order(OrderRequest {
+            instruction: order.instruction,
+            market: order.market,
+            price: request.price,
+            side: request.side,
+            size: request.size,
+            order_type: request.order_type,
+            client_id: Some(order.client_id).filter(|client_id| !client_id.is_empty()),
+            flags: order.flags,
+            recv_window: None,
+            stp: None,
+            trigger_price: None,
+        })?;
+        self.cancel_order(&order.id)?;
+        Ok(ModifyOrderResult::new(&order.id, replacement))
+    }
+
+    /// Best bid and ask of a market's simulated book, NaN with zero size for an empty side
+    ///
+    /// # Errors
+    ///
+    /// If the book of the market is not synced
+    pub fn bbo(&self, market: &str) -> Result<BBO> {
+        let book = self
+            .book(market)
+            .filter(|book| book.is_synced())
+            .ok_or_else(|| {
+                Error::PaperTradingError(format!("no synced order book for {market}"))
+            })?;
+        let (bid, bid_size) = book.best_bid().unwrap_or((f64::NAN, 0.));
+        let (ask, ask_size) = book.best_ask().unwrap_or((f64::NAN, 0.));
+        Ok(BBO {
+            bid,
+            bid_size,
+            ask,
+            ask_size,
+            last_updated_at: book.last_updated_at(),
+            market: market.to_string(),
+            seq_no: book.seq_no().unwrap_or_default(),
+        })
+    }
+
+    fn book_mut(&mut self, market: &str) -> &mut LocalOrderBook {
+        self.books
+            .entry(market.to_string())
+            .or_insert_with(|| LocalOrderBook::new(market))
+    }
+
+    /// Track the size shown at each resting order's level
+    ///
+    /// Size removed from a level is matched against trades the book did not reflect yet. The
+    /// queue only moves on the next trade at the level, so a removal the book shows before its
+    /// trade arrives is not counted twice.
+    fn reconcile_queues(&mut self, market: &str) {
+        let Some(book) = self.books.get(market) else {
+            return;
+        };
+        for order in self
+            .orders
+            .iter_mut()
+            .filter(|order| order.update.market == market)
+        {
+            if let Some(price) = order.limit_price() {
+                let size = book.size_at(order.update.side, price);
+                let removed = (order.level_size - size).max(0.);
+                order.traded_unseen -= removed.min(order.traded_unseen);
+                order.level_size = size;
+            }
+        }
+    }
+
+    fn fill(&mut self, index: usize, size: Decimal, price: f64, liquidity: FillLiquidity) {
+        self.next_id += 1;
+        let fee_rate = match liquidity {
+            FillLiquidity::MAKER => self.maker_fee_rate,
+            FillLiquidity::TAKER => self.taker_fee_rate,
+        };
+        let order = &mut self.orders[index];
+        let fill_size = size.to_f64().unwrap_or(0.);
+        order.update.remaining_size -= size;
+        order.filled_notional += fill_size * price;
+        order.update.avg_fill_price = order.filled_notional / order.filled_size();
+        self.events.push(PaperEvent::Fill(Fill {
+            client_id: order.update.client_id.clone(),
+            created_at: self.now,
+            fee: fill_size * price * fee_rate,
+            fee_currency: FEE_CURRENCY.into(),
+            id: format!("paper-fill-{}", self.next_id),
+            liquidity,
+            market: order.update.market.clone(),
+            order_id: order.update.id.clone(),
+            price,
+            side: order.update.side,
+            size: fill_size,
+            remaining_size: order.update.remaining_size.to_f64().unwrap_or(0.),
+            fill_type: FillType::FILL,
+            realized_pnl: 0.,
+        }));
+        if order.update.remaining_size.is_zero() {
+            self.transition(index, OrderStatus::CLOSED, "");
+        } else {
+            self.transition(index, OrderStatus::OPEN, "");
+        }
+    }
+
+    fn close(&mut self, index: usize, cancel_reason: &str) {
+        self.transition(index, OrderStatus::CLOSED, cancel_reason);
+    }
+
+    fn transition(&mut self, index: usize, status: OrderStatus, cancel_reason: &str) {
+        self.seq_no += 1;
+        let update = &mut self.orders[index].update;
+        update.status = status;
+        update.cancel_reason = cancel_reason.into();
+        update.seq_no = self.seq_no;
+        update.last_updated_at = self.now;
+        update.timestamp = self.now;
+        update.published_at = self.now;
+        self.events.push(PaperEvent::Order(update.clone()));
+    }
+}
+
+/// Convert a simulated size to venue precision
+fn to_decimal(value: f64) -> Decimal {
+    Decimal::from_f64(value).map_or(Decimal::ZERO, quantize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::parse_decimal;
+    use crate::structs::{Level, OrderBookUpdateType, TradeType};
+
+    const MARKET: &str = "BTC-USD-PERP";
+
+    fn decimal(value: &str) -> Decimal {
+        parse_decimal(value).unwrap()
+    }
+
+    fn exchange() -> PaperExchange {
+        let mut exchange = PaperExchange::new();
+        exchange
+            .on_order_book(&OrderBook {
+                seq_no: 1,
+                market: MARKET.into(),
+                last_updated_at: 1000,
+                update_type: OrderBookUpdateType::Snapshot,
+                deletes: vec![],
+                inserts: vec![
+                    Level {
+                        side: Side::BUY,
+                        price: 99.,
+                        size: 3.,
+                    },
+                    Level {
+                        side: Side::SELL,
+                        price: 100.,
+                        size: 1.,
+                    },
+                    Level {
+                        side: Side::SELL,
+                        price: 101.,
+                        size: 2.,
+                    },
+                ],
+                updates: vec![],
+            })
+            .unwrap();
+        exchange
+    }
+
+    fn order(
+        order_type: OrderType,
+        instruction: OrderInstruction,
+        side: Side,
+        price: Option<Decimal>,
+        size: Decimal,
+    ) -> OrderRequest {
+        OrderRequest {
+            instruction,
+            market: MARKET.into(),
+            price,
+            side,
+            size,
+            order_type,
+            client_id: None,
+            flags: vec![],
+            recv_window: None,
+            stp: None,
+            trigger_price: None,
+        }
+    }
+
+    fn trade(side: Side, price: f64, size: f64) -> Trade {
+        Trade {
+            created_at: 2000,
+            id: "1".into(),
+            market: MARKET.into(),
+            price,
+            side,
+            size,
+            trade_type: TradeType::FILL,
+        }
+    }
+
+    fn fills(events: &[PaperEvent]) -> Vec<(f64, f64)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                PaperEvent::Fill(fill) => Some((fill.price, fill.size)),
+                PaperEvent::Order(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn market_order_walks_the_book() {
+        let mut exchange = exchange();
+        let update = exchange
+            .place_order(order(
+                OrderType::MARKET,
+                OrderInstruction::GTC,
+                Side::BUY,
+                None,
+                decimal("2"),
+            ))
+            .unwrap();
+        assert_eq!(update.status, OrderStatus::CLOSED);
+        assert_eq!(update.remaining_size, Decimal::ZERO);
+        assert_eq!(update.avg_fill_price, 100.5);
+        assert_eq!(fills(&exchange.take_events()), vec![(100., 1.), (101., 1.)]);
+        let book = exchange.book(MARKET).unwrap();
+        assert_eq!(book.best_ask(), Some((101., 1.)));
+        assert_eq!(book.best_bid(), Some((99., 3.)));
+
+        // The next order only finds what the first one left
+        let update = exchange
+            .place_order(order(
+                OrderType::MARKET,
+                OrderInstruction::GTC,
+                Side::BUY,
+                None,
+                decimal("5"),
+            ))
+            .unwrap();
+        assert_eq!(update.cancel_reason, CANCEL_REASON_LIQUIDITY);
+        assert_eq!(update.remaining_size, decimal("4"));
+        assert_eq!(update.avg_fill_price, 101.);
+        assert_eq!(fills(&exchange.take_events()), vec![(101., 1.)]);
+        assert_eq!(exchange.book(MARKET).unwrap().best_ask(), None);
+    }
+
+    #[test]
+    fn resting_order_fills_after_queue() {
+        let mut exchange = exchange();
+        let update = exchange
+            .place_order(order(
+                OrderType::LIMIT,
+                OrderInstruction::GTC,
+                Side::BUY,
+                Some(decimal("99")),
+                decimal("1"),
+            ))
+            .unwrap();
+        assert_eq!(update.status, OrderStatus::OPEN);
+        exchange.take_events();
+
+        // Two of the three ahead in the queue trade
+        exchange.on_trade(&trade(Side::SELL, 99., 2.));
+        assert!(fills(&exchange.take_events()).is_empty());
+
+        // The last one ahead trades, leaving half a lot for the simulated order
+        exchange.on_trade(&trade(Side::SELL, 99., 1.5));
+        assert_eq!(fills(&exchange.take_events()), vec![(99., 0.5)]);
+        assert_eq!(exchange.open_orders(None)[0].remaining_size, decimal("0.5"));
+
+        // Buys do not touch resting bids
+        exchange.on_trade(&trade(Side::BUY, 99., 1.));
+        assert!(fills(&exchange.take_events()).is_empty());
+
+        // Trading through the price fills the rest
+        exchange.on_trade(&trade(Side::SELL, 98.5, 0.1));
+        assert_eq!(fills(&exchange.take_events()), vec![(99., 0.5)]);
+        assert!(exchange.open_orders(None).is_empty());
+    }
+
+    #[test]
+    fn queue_advances_on_cancellations_ahead() {
+        let mut exchange = exchange();
+        exchange
+            .place_order(order(
+                OrderType::LIMIT,
+                OrderInstruction::GTC,
+                Side::BUY,
+                Some(decimal("99")),
+                decimal("1"),
+            ))
+            .unwrap();
+        exchange
+            .on_order_book(&OrderBook {
+                seq_no: 2,
+                market: MARKET.into(),
+                last_updated_at: 1500,
+                update_type: OrderBookUpdateType::Delta,
+                deletes: vec![],
+                inserts: vec![],
+                updates: vec![Level {
+                    side: Side::BUY,
+                    price: 99.,
+                    size: 1.,
+                }],
+            })
+            .unwrap();
+        exchange.take_events();
+        exchange.on_trade(&trade(Side::SELL, 99., 1.25));
+        assert_eq!(fills(&exchange.take_events()), vec![(99., 0.25)]);
+    }
+
+    #[test]
+    fn queue_counts_trades_once() {
+        let mut exchange = exchange();
+        exchange
+            .place_order(order(
+                OrderType::LIMIT,
+                OrderInstruction::GTC,
+                Side::BUY,
+                Some(decimal("99")),
+                decimal("1"),
+            ))
+            .unwrap();
+        exchange.take_events();
+
+        // The book shows two of the three ahead trading before the trade itself arrives
+        let shrink = |seq_no, last_updated_at, size| OrderBook {
+            seq_no,
+            market: MARKET.into(),
+            last_updated_at,
+            update_type: OrderBookUpdateType::Delta,
+            deletes: vec![],
+            inserts: vec![],
+            updates: vec![Level {
+                side: Side::BUY,
+                price: 99.,
+                size,
+            }],
+        };
+        exchange.on_order_book(&shrink(2, 2500, 1.)).unwrap();
+        exchange.on_trade(&trade(Side::SELL, 99., 2.));
+        assert!(fills(&exchange.take_events()).is_empty());
+
+        // The trade arrives before the book shows it
+        let mut next = trade(Side::SELL, 99., 0.5);
+        next.created_at = 3000;
+        exchange.on_trade(&next);
+        assert!(fills(&exchange.take_events()).is_empty());
+        exchange.on_order_book(&shrink(3, 3000, 0.5)).unwrap();
+        next.created_at = 3500;
+        next.size = 1.;
+        exchange.on_trade(&next);
+        assert_eq!(fills(&exchange.take_events()), vec![(99., 0.5)]);
+    }
+
+    #[test]
+    fn post_only_and_cancel() {
+        let mut exchange = exchange();
+        let update = exchange
+            .place_order(order(
+                OrderType::LIMIT,
+                OrderInstruction::POST_ONLY,
+                Side::BUY,
+                Some(decimal("100")),
+                decimal("1"),
+            ))
+            .unwrap();
+        assert_eq!(update.status, OrderStatus::CLOSED);
+        assert_eq!(update.cancel_reason, CANCEL_REASON_POST_ONLY);
+
+        let update = exchange
+            .place_order(order(
+                OrderType::LIMIT,
+                OrderInstruction::POST_ONLY,
+                Side::SELL,
+                Some(decimal("102")),
+                decimal("1"),
+            ))
+            .unwrap();
+        assert_eq!(exchange.open_orders(Some(MARKET)).len(), 1);
+        let cancelled = exchange.cancel_order(&update.id).unwrap();
+        assert_eq!(cancelled.cancel_reason, CANCEL_REASON_USER);
+        assert!(exchange.cancel_order(&update.id).is_err());
+        assert!(exchange.open_orders(None).is_empty());
+    }
+}
@@ -0,0 +1,23 @@
+//! Commonly used types, re-exported so downstream crates do not need to pin matching
+//! versions of the underlying dependencies.
+//!
+//! ```
+//! use paradex::prelude::*;
+//! ```
+
+pub use chrono::{DateTime, TimeZone, Utc};
+pub use rust_decimal::Decimal;
+pub use starknet_crypto::Felt;
+
+pub use crate::api::ParadexApi;
+pub use crate::error::{Error, ParadexErrorCode};
+pub use crate::order_builder::{OrderBuilder, OrderValidationError};
+pub use crate::rest::Client;
+pub use crate::structs::{
+    MarketSummaryStatic, ModifyOrderRequest, ModifyOrderResult, OrderFlags, OrderInstruction,
+    OrderRequest, OrderStatus, OrderType, OrderUpdate, STPType, Side,
+};
+pub use crate::url::URL;
+pub use crate::ws::{
+    Channel, ChannelEvent, Identifier, Message, SubscriptionSpec, WebsocketManager,
+};
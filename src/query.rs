@@ -0,0 +1,137 @@
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+
+/// Encoding of request parameters into URL query pairs.
+///
+/// Implemented for every `Serialize` type, so parameter structs only need to derive
+/// `Serialize` with field names matching the API's query parameters.
+pub trait IntoQuery {
+    fn into_query(self) -> Result<Vec<(String, String)>>;
+}
+
+impl<T: Serialize> IntoQuery for T {
+    fn into_query(self) -> Result<Vec<(String, String)>> {
+        let encoded = serde_urlencoded::to_string(&self)
+            .map_err(|e| Error::TypeConversionError(e.to_string()))?;
+        serde_urlencoded::from_str(&encoded).map_err(|e| Error::TypeConversionError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{
+        FillsFilter, KlineParams, KlinePriceKind, KlineResolution, ListParams, OrderBookParams,
+        OrderStatus, OrderType, OrdersHistoryFilter, Side, TransferStatus, TransfersFilter,
+    };
+
+    fn pairs(values: &[(&str, &str)]) -> Vec<(String, String)> {
+        values
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn kline_params() {
+        let params = KlineParams {
+            start_at: 1,
+            end_at: 2,
+            symbol: "BTC-USD-PERP".into(),
+            resolution: KlineResolution::Min15,
+            price_kind: Some(KlinePriceKind::Underlying),
+        };
+        assert_eq!(
+            params.clone().into_query().unwrap(),
+            pairs(&[
+                ("start_at", "1"),
+                ("end_at", "2"),
+                ("symbol", "BTC-USD-PERP"),
+                ("resolution", "15"),
+                ("price_kind", "underlying"),
+            ])
+        );
+        let params = KlineParams {
+            price_kind: None,
+            ..params
+        };
+        assert_eq!(params.into_query().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn order_book_params() {
+        let params = OrderBookParams {
+            depth: Some(50),
+            price_tick: None,
+        };
+        assert_eq!(params.into_query().unwrap(), pairs(&[("depth", "50")]));
+    }
+
+    #[test]
+    fn orders_history_filter() {
+        let start = chrono::DateTime::from_timestamp_millis(1_700_000_000_000).unwrap();
+        let filter = OrdersHistoryFilter::new()
+            .market("ETH-USD-PERP")
+            .side(Side::SELL)
+            .order_status(OrderStatus::CLOSED)
+            .order_type(OrderType::STOP_LIMIT)
+            .start(start)
+            .page_size(100);
+        assert_eq!(
+            filter.into_query().unwrap(),
+            pairs(&[
+                ("market", "ETH-USD-PERP"),
+                ("side", "SELL"),
+                ("status", "CLOSED"),
+                ("type", "STOP_LIMIT"),
+                ("start_at", "1700000000000"),
+                ("page_size", "100"),
+            ])
+        );
+        assert!(FillsFilter::new().into_query().unwrap().is_empty());
+    }
+
+    #[test]
+    fn list_params() {
+        let params = ListParams::new()
+            .market("ETH-USD-PERP")
+            .side(Side::BUY)
+            .order_status(OrderStatus::OPEN)
+            .client_id("mm-1")
+            .page_size(50)
+            .cursor("abc");
+        assert_eq!(
+            params.into_query().unwrap(),
+            pairs(&[
+                ("market", "ETH-USD-PERP"),
+                ("side", "BUY"),
+                ("status", "OPEN"),
+                ("client_id", "mm-1"),
+                ("page_size", "50"),
+                ("cursor", "abc"),
+            ])
+        );
+        let filter = TransfersFilter::new().transfer_status(TransferStatus::COMPLETED);
+        assert_eq!(
+            filter.into_query().unwrap(),
+            pairs(&[("status", "COMPLETED")])
+        );
+    }
+
+    #[test]
+    fn key_value_pairs() {
+        let filters = vec![("status", TransferStatus::PENDING)];
+        assert_eq!(
+            filters.into_query().unwrap(),
+            pairs(&[("status", "PENDING")])
+        );
+        assert!(
+            Vec::<(&str, String)>::new()
+                .into_query()
+                .unwrap()
+                .is_empty()
+        );
+        assert!(().into_query().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,287 @@
+//! Best-effort queue position of the account's resting limit orders.
+//!
+//! The order book deltas channel shows the total size at each price but not who is in front of
+//! whom, so [`QueueEstimator`] infers it: an order joins the back of its level, with the size
+//! shown there when it is first seen open ahead of it. Size leaving the level is assumed to be
+//! ahead of the order, which moves it up the queue, while size joining the level is assumed to
+//! be behind it. A fill of the order means everything ahead of it has traded.
+//!
+//! Orders come from an [`OrderTracker`] and books from an [`OrderBookEngine`], see
+//! [`QueueEstimator::attach`] and [`QueueEstimator::track`].
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::error::Result;
+use crate::orderbook::{LocalOrderBook, OrderBookEngine};
+use crate::structs::{OrderStatus, OrderType, Side};
+use crate::tracker::{OrderTracker, TrackedOrder};
+use crate::ws::{Identifier, WebsocketManager};
+
+/// Estimated place of a resting order in its price level's queue
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueuePosition {
+    pub market: String,
+    pub side: Side,
+    pub price: f64,
+    /// Size estimated to be ahead of the order at its price
+    pub size_ahead: f64,
+    /// Total size shown at the price, including the order itself
+    pub level_size: f64,
+}
+
+#[derive(Clone, Debug)]
+struct QueueEntry {
+    position: QueuePosition,
+    remaining_size: f64,
+    filled_size: Decimal,
+    fills: usize,
+    /// Whether the size ahead has been estimated from a synced book yet
+    estimated: bool,
+}
+
+impl QueueEntry {
+    /// Move the order up when the size of others at its level shrank below the size ahead
+    fn observe(&mut self, book: &LocalOrderBook, created_at: Option<u64>) {
+        if !book.is_synced() {
+            return;
+        }
+        let level_size = book.size_at(self.position.side, self.position.price);
+        // The order itself is only part of the level once the book caught up with it
+        let includes_order =
+            created_at.is_none_or(|created_at| book.last_updated_at() >= created_at);
+        let others = if includes_order {
+            (level_size - self.remaining_size).max(0.)
+        } else {
+            level_size
+        };
+        self.position.level_size = level_size;
+        self.position.size_ahead = if self.estimated {
+            self.position.size_ahead.min(others)
+        } else {
+            others
+        };
+        self.estimated = true;
+    }
+}
+
+/// Estimates the queue position of resting limit orders from order book deltas and fills
+///
+/// Clones share the same estimates.
+#[derive(Clone, Default)]
+pub struct QueueEstimator {
+    entries: Arc<Mutex<HashMap<String, QueueEntry>>>,
+}
+
+impl QueueEstimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Estimate the queue position of the orders tracked by `tracker` against the books of
+    /// `engine`
+    ///
+    /// Only orders in markets tracked by the engine, e.g. with `QueueEstimator::track`, get an
+    /// estimate.
+    pub fn attach(&self, engine: &OrderBookEngine, tracker: &OrderTracker) {
+        let estimator = self.clone();
+        let engine = engine.clone();
+        tracker.on_change(move |order| {
+            let applied = engine.with_book(&order.update.market, |book| {
+                estimator.on_order(order, Some(book));
+            });
+            if applied.is_none() {
+                estimator.on_order(order, None);
+            }
+        });
+    }
+
+    /// Maintain the book of a market with `engine`, updating the estimates after every update
+    ///
+    /// # Returns
+    ///
+    /// The Identifier of the order book deltas subscription
+    ///
+    /// # Errors
+    ///
+    /// If the subscription cannot be sent
+    pub async fn track(
+        &self,
+        engine: &OrderBookEngine,
+        manager: &WebsocketManager,
+        market: impl Into<String>,
+    ) -> Result<Identifier> {
+        let estimator = self.clone();
+        engine
+            .track(manager, market, move |book| estimator.on_book(book))
+            .await
+    }
+
+    /// Apply the new state of an order, along with the current book of its market if known
+    ///
+    /// Orders which are no longer resting limit orders are dropped.
+    pub fn on_order(&self, order: &TrackedOrder, book: Option<&LocalOrderBook>) {
+        let update = &order.update;
+        let mut entries = self.entries.lock().unwrap();
+        let price = update.price.and_then(|price| price.to_f64());
+        let (Some(price), OrderType::LIMIT, OrderStatus::OPEN) =
+            (price, &update.order_type, &update.status)
+        else {
+            entries.remove(&update.id);
+            return;
+        };
+        let remaining_size = update.remaining_size.to_f64().unwrap_or(0.);
+        let filled_size = order.filled_size();
+        let fills = order.fills.len();
+        let entry = entries
+            .entry(update.id.clone())
+            .or_insert_with(|| QueueEntry {
+                position: QueuePosition {
+                    market: update.market.clone(),
+                    side: update.side,
+                    price,
+                    size_ahead: 0.,
+                    level_size: 0.,
+                },
+                remaining_size,
+                filled_size,
+                fills,
+                estimated: false,
+            });
+        // Either the fill or the order update showing it may arrive first
+        if filled_size > entry.filled_size || fills > entry.fills {
+            // Everything ahead traded before the order could fill
+            entry.position.size_ahead = 0.;
+            entry.estimated = true;
+        }
+        entry.remaining_size = remaining_size;
+        entry.filled_size = filled_size;
+        entry.fills = fills;
+        if let Some(book) = book.filter(|book| book.market() == update.market) {
+            entry.observe(book, Some(update.created_at));
+        }
+    }
+
+    /// Apply an update of a market's book
+    pub fn on_book(&self, book: &LocalOrderBook) {
+        for entry in self.entries.lock().unwrap().values_mut() {
+            if entry.position.market == book.market() {
+                entry.observe(book, None);
+            }
+        }
+    }
+
+    /// Estimated queue position of an order, None until its market's book has been seen
+    pub fn position(&self, order_id: &str) -> Option<QueuePosition> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(order_id)
+            .filter(|entry| entry.estimated)
+            .map(|entry| entry.position.clone())
+    }
+
+    /// Estimated queue positions of a market's resting orders by order id
+    pub fn positions(&self, market: &str) -> HashMap<String, QueuePosition> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, entry)| entry.estimated && entry.position.market == market)
+            .map(|(id, entry)| (id.clone(), entry.position.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{Level, OrderBook, OrderBookUpdateType, OrderUpdate};
+
+    fn book_update(
+        seq_no: u64,
+        update_type: OrderBookUpdateType,
+        bids: &[(f64, f64)],
+    ) -> OrderBook {
+        OrderBook {
+            seq_no,
+            market: "BTC-USD-PERP".into(),
+            last_updated_at: 1000 + seq_no,
+            update_type,
+            deletes: Vec::new(),
+            inserts: Vec::new(),
+            updates: bids
+                .iter()
+                .map(|&(price, size)| Level {
+                    side: Side::BUY,
+                    price,
+                    size,
+                })
+                .collect(),
+        }
+    }
+
+    fn order(remaining_size: i64, status: OrderStatus) -> TrackedOrder {
+        let mut update: OrderUpdate =
+            serde_json::from_str(include_str!("../tests/fixtures/order_update.json")).unwrap();
+        update.market = "BTC-USD-PERP".into();
+        update.side = Side::BUY;
+        update.order_type = OrderType::LIMIT;
+        update.price = Some(Decimal::from(100));
+        update.size = Decimal::from(2);
+        update.remaining_size = Decimal::from(remaining_size);
+        update.status = status;
+        update.created_at = 1002;
+        TrackedOrder {
+            state: crate::tracker::OrderState::Open,
+            update,
+            fills: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn size_ahead() {
+        let estimator = QueueEstimator::new();
+        let mut book = LocalOrderBook::new("BTC-USD-PERP");
+        book.apply(&book_update(
+            1,
+            OrderBookUpdateType::Snapshot,
+            &[(100., 5.)],
+        ))
+        .unwrap();
+        let open = order(2, OrderStatus::OPEN);
+        estimator.on_order(&open, None);
+        assert!(estimator.position(&open.update.id).is_none());
+        // The book predates the order, so all of its level is ahead
+        estimator.on_order(&open, Some(&book));
+        assert_eq!(estimator.position(&open.update.id).unwrap().size_ahead, 5.);
+
+        // The book catches up with the order joining the back of the level
+        book.apply(&book_update(2, OrderBookUpdateType::Delta, &[(100., 7.)]))
+            .unwrap();
+        estimator.on_book(&book);
+        let position = estimator.position(&open.update.id).unwrap();
+        assert_eq!((position.size_ahead, position.level_size), (5., 7.));
+
+        // Size joining behind does not move the order, size leaving moves it up
+        book.apply(&book_update(3, OrderBookUpdateType::Delta, &[(100., 9.)]))
+            .unwrap();
+        estimator.on_book(&book);
+        assert_eq!(estimator.position(&open.update.id).unwrap().size_ahead, 5.);
+        book.apply(&book_update(4, OrderBookUpdateType::Delta, &[(100., 5.)]))
+            .unwrap();
+        estimator.on_book(&book);
+        assert_eq!(estimator.position(&open.update.id).unwrap().size_ahead, 3.);
+
+        // A fill puts the order at the front
+        estimator.on_order(&order(1, OrderStatus::OPEN), Some(&book));
+        assert_eq!(estimator.position(&open.update.id).unwrap().size_ahead, 0.);
+        assert_eq!(estimator.positions("BTC-USD-PERP").len(), 1);
+
+        estimator.on_order(&order(0, OrderStatus::CLOSED), Some(&book));
+        assert!(estimator.position(&open.update.id).is_none());
+    }
+}
@@ -0,0 +1,602 @@
+//! Two-sided quoting with the minimal set of order changes.
+//!
+//! [`Quoter`] keeps at most one resting order per side of a market. Given a target bid and
+//! ask, it diffs them against the quoter's open orders in an [`OrderTracker`] and only sends
+//! what changed: a modification when the price or size moved, a creation when a side has no
+//! order and cancellations for sides that should be empty or hold extra orders. New orders of
+//! both sides go out in one batch request. Orders are told apart from the account's other
+//! orders by the prefix of their client id.
+//!
+//! Creations and modifications stay in flight until the tracker shows their outcome, and a side
+//! with a request in flight is not created or modified again, so quoting faster than order
+//! updates arrive does not duplicate orders.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use futures_util::future::join_all;
+use log::warn;
+use rust_decimal::Decimal;
+
+use crate::error::{Error, Result};
+use crate::order_builder::OrderBuilder;
+use crate::rest::Client;
+use crate::structs::{
+    MarketSummaryStatic, ModifyOrderRequest, OrderInstruction, OrderRequest, OrderType, Side,
+};
+use crate::tracker::{OrderState, OrderTracker, TrackedOrder};
+
+/// How long a request waits for the tracker to show its outcome before the side is quoted again
+const IN_FLIGHT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Target price and size of one side
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quote {
+    pub price: Decimal,
+    pub size: Decimal,
+}
+
+impl Quote {
+    pub fn new(price: Decimal, size: Decimal) -> Self {
+        Self { price, size }
+    }
+}
+
+/// Order changes bringing the resting orders to the target quotes
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QuoteActions {
+    pub create: Vec<OrderRequest>,
+    pub modify: Vec<ModifyOrderRequest>,
+    /// Ids of the orders to cancel
+    pub cancel: Vec<String>,
+}
+
+impl QuoteActions {
+    pub fn is_empty(&self) -> bool {
+        self.create.is_empty() && self.modify.is_empty() && self.cancel.is_empty()
+    }
+}
+
+/// A request sent on one side whose outcome the tracker does not show yet
+#[derive(Clone, Debug)]
+enum InFlight {
+    /// Creation of the order with this client id
+    Create { client_id: String, sent_at: Instant },
+    /// Modification of the order with this id to `target`
+    Modify {
+        order_id: String,
+        target: Quote,
+        sent_at: Instant,
+    },
+}
+
+impl InFlight {
+    /// Whether the tracker shows the outcome of the request, or it timed out
+    fn is_settled(&self, tracker: &OrderTracker, now: Instant) -> bool {
+        match self {
+            InFlight::Create { client_id, sent_at } => {
+                now.duration_since(*sent_at) >= IN_FLIGHT_TIMEOUT
+                    || tracker.order_by_client_id(client_id).is_some()
+            }
+            InFlight::Modify {
+                order_id,
+                target,
+                sent_at,
+            } => {
+                now.duration_since(*sent_at) >= IN_FLIGHT_TIMEOUT
+                    || tracker.order(order_id).is_none_or(|order| {
+                        order.state.is_closed()
+                            || (order.update.price == Some(target.price)
+                                && order.update.remaining_size == target.size)
+                    })
+            }
+        }
+    }
+}
+
+/// Maintains a bid and an ask on one market
+///
+/// The tracker must be attached to the orders channel, see `OrderTracker::attach`, so that
+/// orders placed by the quoter show up before the next call to `quote`. Clones share the client
+/// id sequence and the requests in flight.
+#[derive(Clone)]
+pub struct Quoter {
+    client: Client,
+    tracker: OrderTracker,
+    market: MarketSummaryStatic,
+    client_id_prefix: String,
+    instruction: OrderInstruction,
+    sequence: Arc<AtomicU64>,
+    in_flight: Arc<Mutex<HashMap<Side, InFlight>>>,
+}
+
+impl Quoter {
+    /// Quote `market` with post only orders whose client ids start with `quote-`
+    pub fn new(client: Client, tracker: OrderTracker, market: MarketSummaryStatic) -> Self {
+        let sequence = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        Self {
+            client,
+            tracker,
+            market,
+            client_id_prefix: "quote-".to_string(),
+            instruction: OrderInstruction::POST_ONLY,
+            sequence: Arc::new(AtomicU64::new(sequence)),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Quote the market `symbol` of the client's market registry, see `Quoter::new`
+    ///
+    /// # Errors
+    ///
+    /// * `Error::MissingMarketCache` if the client has no market registry
+    /// * `Error::UnknownMarket` if there is no market `symbol`
+    /// * If the markets cannot be retrieved
+    pub async fn for_market(client: Client, tracker: OrderTracker, symbol: &str) -> Result<Self> {
+        let market = client.cached_market(symbol).await?;
+        Ok(Self::new(client, tracker, market))
+    }
+
+    /// Prefix of the client ids of the quoter's orders, which must be unique to this quoter
+    pub fn client_id_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.client_id_prefix = prefix.into();
+        self
+    }
+
+    /// Instruction of new orders, POST_ONLY unless set
+    pub fn instruction(mut self, instruction: OrderInstruction) -> Self {
+        self.instruction = instruction;
+        self
+    }
+
+    /// Open orders of the quoter on one side, oldest first
+    pub fn resting(&self, side: Side) -> Vec<TrackedOrder> {
+        self.tracker
+            .open_orders_for_market(&self.market.symbol)
+            .into_iter()
+            .filter(|order| {
+                order.update.side == side
+                    && order.update.client_id.starts_with(&self.client_id_prefix)
+            })
+            .collect()
+    }
+
+    /// Work out the order changes needed to quote `bid` and `ask`, None leaving a side empty
+    ///
+    /// Sizes are compared with the remaining size of the resting order. Orders which the
+    /// venue has not put on the book yet are left alone until it has, and so are sides with a
+    /// creation or modification sent by `quote` in flight.
+    ///
+    /// # Errors
+    ///
+    /// If a new or modified order does not conform to the market
+    pub fn plan(&self, bid: Option<Quote>, ask: Option<Quote>) -> Result<QuoteActions> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        self.plan_with(&mut in_flight, bid, ask)
+    }
+
+    /// Bring the resting orders to the target quotes
+    ///
+    /// Cancellations and modifications are sent concurrently, then new orders in one batch.
+    /// Failures of individual orders are logged rather than returned, since the next call
+    /// works from the tracked state and retries what is still missing.
+    ///
+    /// # Returns
+    ///
+    /// The order changes which were sent
+    ///
+    /// # Errors
+    ///
+    /// If a new or modified order does not conform to the market
+    pub async fn quote(&self, bid: Option<Quote>, ask: Option<Quote>) -> Result<QuoteActions> {
+        let actions = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            let actions = self.plan_with(&mut in_flight, bid, ask)?;
+            let sent_at = Instant::now();
+            for request in &actions.create {
+                let client_id = request.client_id.clone().unwrap_or_default();
+                in_flight.insert(request.side, InFlight::Create { client_id, sent_at });
+            }
+            for request in &actions.modify {
+                let target = Quote::new(request.price.unwrap_or_default(), request.size);
+                let order_id = request.id.clone();
+                in_flight.insert(
+                    request.side,
+                    InFlight::Modify {
+                        order_id,
+                        target,
+                        sent_at,
+                    },
+                );
+            }
+            actions
+        };
+        let cancels = join_all(
+            actions
+                .cancel
+                .iter()
+                .map(|order_id| self.client.cancel_order(order_id.clone())),
+        );
+        let modifies = join_all(
+            actions
+                .modify
+                .iter()
+                .map(|request| self.client.modify_order(request.clone())),
+        );
+        let (cancels, modifies) = tokio::join!(cancels, modifies);
+        for result in cancels {
+            if let Err(e) = result {
+                warn!("Could not cancel quote: {e}");
+            }
+        }
+        for (request, result) in actions.modify.iter().zip(modifies) {
+            if let Err(e) = result {
+                warn!("Could not modify quote: {e}");
+                self.settle(request.side);
+            }
+        }
+        match actions.create.as_slice() {
+            [] => {}
+            [request] => {
+                if let Err(e) = self.client.create_order(request.clone()).await {
+                    warn!("Could not create quote: {e}");
+                    self.settle(request.side);
+                }
+            }
+            requests => match self.client.create_orders(requests.to_vec()).await {
+                Ok(response) => {
+                    for error in response.errors {
+                        warn!("Could not create quote: {error:?}");
+                    }
+                    for request in requests {
+                        let accepted = response
+                            .orders
+                            .iter()
+                            .any(|order| Some(&order.client_id) == request.client_id.as_ref());
+                        if !accepted {
+                            self.settle(request.side);
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("Could not create quotes: {e}");
+                    for request in requests {
+                        self.settle(request.side);
+                    }
+                }
+            },
+        }
+        Ok(actions)
+    }
+
+    /// Cancel the quotes on both sides
+    ///
+    /// # Errors
+    ///
+    /// Never, cancellation failures are logged
+    pub async fn cancel_all(&self) -> Result<QuoteActions> {
+        self.quote(None, None).await
+    }
+
+    /// Forget the request in flight on `side` after it failed
+    fn settle(&self, side: Side) {
+        self.in_flight.lock().unwrap().remove(&side);
+    }
+
+    fn plan_with(
+        &self,
+        in_flight: &mut HashMap<Side, InFlight>,
+        bid: Option<Quote>,
+        ask: Option<Quote>,
+    ) -> Result<QuoteActions> {
+        let now = Instant::now();
+        in_flight.retain(|_, request| !request.is_settled(&self.tracker, now));
+        let mut actions = QuoteActions::default();
+        self.plan_side(
+            Side::BUY,
+            bid,
+            in_flight.contains_key(&Side::BUY),
+            &mut actions,
+        )?;
+        self.plan_side(
+            Side::SELL,
+            ask,
+            in_flight.contains_key(&Side::SELL),
+            &mut actions,
+        )?;
+        Ok(actions)
+    }
+
+    fn plan_side(
+        &self,
+        side: Side,
+        target: Option<Quote>,
+        in_flight: bool,
+        actions: &mut QuoteActions,
+    ) -> Result<()> {
+        let mut resting = self.resting(side).into_iter();
+        let Some(target) = target else {
+            actions.cancel.extend(resting.map(|order| order.update.id));
+            return Ok(());
+        };
+        match resting.next() {
+            Some(order) => {
+                let update = &order.update;
+                if !in_flight
+                    && order.state != OrderState::New
+                    && (update.price != Some(target.price) || update.remaining_size != target.size)
+                {
+                    self.build_order(side, target, None)?;
+                    actions.modify.push(ModifyOrderRequest {
+                        id: update.id.clone(),
+                        market: update.market.clone(),
+                        price: Some(target.price),
+                        side,
+                        size: target.size,
+                        order_type: OrderType::LIMIT,
+                    });
+                }
+            }
+            None if in_flight => {}
+            None => {
+                let client_id = format!(
+                    "{}{}",
+                    self.client_id_prefix,
+                    self.sequence.fetch_add(1, Ordering::Relaxed)
+                );
+                actions
+                    .create
+                    .push(self.build_order(side, target, Some(client_id))?);
+            }
+        }
+        actions.cancel.extend(resting.map(|order| order.update.id));
+        Ok(())
+    }
+
+    /// Build the order quoting `target` on `side`, checking it conforms to the market
+    fn build_order(
+        &self,
+        side: Side,
+        target: Quote,
+        client_id: Option<String>,
+    ) -> Result<OrderRequest> {
+        let builder = OrderBuilder::limit(&self.market, side, target.size, target.price)
+            .instruction(self.instruction.clone());
+        match client_id {
+            Some(client_id) => builder.client_id(client_id),
+            None => builder,
+        }
+        .build()
+        .map_err(|errors| {
+            Error::InvalidOrder(
+                errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::parse_decimal;
+    use crate::markets::MarketRegistry;
+    use crate::structs::{OrderStatus, OrderUpdate, ResultsContainer};
+    use crate::testing;
+    use crate::url::URL;
+
+    fn decimal(value: &str) -> Decimal {
+        parse_decimal(value).unwrap()
+    }
+
+    fn update(
+        id: &str,
+        client_id: &str,
+        side: Side,
+        price: &str,
+        status: OrderStatus,
+    ) -> OrderUpdate {
+        let seq_no = if status == OrderStatus::CLOSED { 2 } else { 1 };
+        let update = testing::order_update(id, seq_no, status);
+        OrderUpdate {
+            created_at: update.created_at + id.parse::<u64>().unwrap(),
+            client_id: client_id.into(),
+            side,
+            price: Some(decimal(price)),
+            size: decimal("0.5"),
+            remaining_size: decimal("0.5"),
+            ..update
+        }
+    }
+
+    async fn quoter_for(tracker: &OrderTracker) -> Quoter {
+        let markets: ResultsContainer<Vec<MarketSummaryStatic>> =
+            serde_json::from_str(include_str!("../tests/fixtures/markets.json")).unwrap();
+        let registry = MarketRegistry::new();
+        registry.update(markets.results);
+        let client = Client::new(URL::Testnet, None)
+            .await
+            .unwrap()
+            .with_market_registry(registry);
+        Quoter::for_market(client, tracker.clone(), "BTC-USD-PERP")
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn minimal_changes() {
+        let tracker = OrderTracker::new();
+        let quoter = quoter_for(&tracker).await;
+
+        let actions = quoter
+            .plan(
+                Some(Quote::new(decimal("30000"), decimal("0.5"))),
+                Some(Quote::new(decimal("30001"), decimal("0.5"))),
+            )
+            .unwrap();
+        assert_eq!(actions.create.len(), 2);
+        assert!(
+            actions.create[0]
+                .client_id
+                .as_ref()
+                .unwrap()
+                .starts_with("quote-")
+        );
+        assert_ne!(actions.create[0].client_id, actions.create[1].client_id);
+
+        tracker.on_order_update(&update(
+            "1",
+            "quote-1",
+            Side::BUY,
+            "30000",
+            OrderStatus::OPEN,
+        ));
+        tracker.on_order_update(&update(
+            "2",
+            "quote-2",
+            Side::SELL,
+            "30001",
+            OrderStatus::OPEN,
+        ));
+        tracker.on_order_update(&update(
+            "3",
+            "quote-3",
+            Side::SELL,
+            "30002",
+            OrderStatus::OPEN,
+        ));
+        // Orders of other strategies are left alone
+        tracker.on_order_update(&update(
+            "4",
+            "manual",
+            Side::BUY,
+            "29000",
+            OrderStatus::OPEN,
+        ));
+
+        // The bid is unchanged, the ask moves and its extra order is cancelled
+        let actions = quoter
+            .plan(
+                Some(Quote::new(decimal("30000"), decimal("0.5"))),
+                Some(Quote::new(decimal("30001.5"), decimal("0.5"))),
+            )
+            .unwrap();
+        assert!(actions.create.is_empty());
+        assert_eq!(actions.modify.len(), 1);
+        assert_eq!(actions.modify[0].id, "2");
+        assert_eq!(actions.modify[0].price, Some(decimal("30001.5")));
+        assert_eq!(actions.cancel, vec!["3"]);
+
+        let actions = quoter.plan(None, None).unwrap();
+        assert_eq!(actions.cancel, vec!["1", "2", "3"]);
+
+        // Unacknowledged orders are not modified
+        tracker.on_order_update(&update(
+            "2",
+            "quote-2",
+            Side::SELL,
+            "30001",
+            OrderStatus::CLOSED,
+        ));
+        tracker.on_order_update(&update(
+            "3",
+            "quote-3",
+            Side::SELL,
+            "30002",
+            OrderStatus::CLOSED,
+        ));
+        tracker.on_order_update(&update(
+            "5",
+            "quote-5",
+            Side::SELL,
+            "30001",
+            OrderStatus::NEW,
+        ));
+        let actions = quoter
+            .plan(
+                Some(Quote::new(decimal("30000"), decimal("0.5"))),
+                Some(Quote::new(decimal("30003"), decimal("0.5"))),
+            )
+            .unwrap();
+        assert!(actions.is_empty());
+
+        let quoter = quoter_for(&OrderTracker::new()).await;
+        assert!(matches!(
+            quoter.plan(Some(Quote::new(decimal("30000.05"), decimal("0.5"))), None),
+            Err(Error::InvalidOrder(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn requests_in_flight() {
+        let tracker = OrderTracker::new();
+        let quoter = quoter_for(&tracker).await;
+        let bid = Some(Quote::new(decimal("30000"), decimal("0.5")));
+        let ask = Some(Quote::new(decimal("30001"), decimal("0.5")));
+
+        // The bid sent by an earlier quote is not on the tracker yet
+        quoter.in_flight.lock().unwrap().insert(
+            Side::BUY,
+            InFlight::Create {
+                client_id: "quote-1".into(),
+                sent_at: Instant::now(),
+            },
+        );
+        let actions = quoter.plan(bid, ask).unwrap();
+        assert_eq!(actions.create.len(), 1);
+        assert_eq!(actions.create[0].side, Side::SELL);
+
+        tracker.on_order_update(&update(
+            "1",
+            "quote-1",
+            Side::BUY,
+            "30000",
+            OrderStatus::OPEN,
+        ));
+        tracker.on_order_update(&update(
+            "2",
+            "quote-2",
+            Side::SELL,
+            "30001",
+            OrderStatus::OPEN,
+        ));
+        assert!(quoter.plan(bid, ask).unwrap().is_empty());
+
+        // A modification is not sent again until the tracker shows it
+        let moved = Quote::new(decimal("30001.5"), decimal("0.5"));
+        quoter.in_flight.lock().unwrap().insert(
+            Side::SELL,
+            InFlight::Modify {
+                order_id: "2".into(),
+                target: moved,
+                sent_at: Instant::now(),
+            },
+        );
+        assert!(quoter.plan(bid, Some(moved)).unwrap().is_empty());
+        let mut modified = update("2", "quote-2", Side::SELL, "30001.5", OrderStatus::OPEN);
+        modified.seq_no = 2;
+        tracker.on_order_update(&modified);
+        assert!(quoter.plan(bid, Some(moved)).unwrap().is_empty());
+        assert!(quoter.in_flight.lock().unwrap().is_empty());
+
+        // Modifications are checked against the market like new orders
+        assert!(matches!(
+            quoter.plan(bid, Some(Quote::new(decimal("30001.55"), decimal("0.5")))),
+            Err(Error::InvalidOrder(_))
+        ));
+        assert!(matches!(
+            quoter.plan(
+                bid,
+                Some(Quote::new(decimal("30001.5"), decimal("0.00001")))
+            ),
+            Err(Error::InvalidOrder(_))
+        ));
+    }
+}
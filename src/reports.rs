@@ -0,0 +1,464 @@
+//! Account statements for bookkeeping.
+//!
+//! [`Statement`] gathers the fills, funding payments and transfers of the account over a
+//! period, usually a calendar month, from the paginated history endpoints in exact decimals. It
+//! totals fees, realized PnL, funding and transfers per currency and reconstructs the balances
+//! at the start and end of the period: the venue only reports current balances, so they are
+//! rolled back over the activity since the end of the period. Every section exports as CSV.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use chrono::{DateTime, Months, NaiveDate, TimeDelta, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+use crate::exact::{Balance, Fill, FundingPayment, Transfer};
+use crate::rest::Client;
+use crate::structs::{ListParams, TransferDirection, TransferStatus};
+
+/// Time range covered by a statement, from `start` inclusive to `end` exclusive
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Period {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl Period {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self { start, end }
+    }
+
+    /// Calendar month in UTC, `month` counted from 1
+    ///
+    /// # Errors
+    ///
+    /// If there is no such month
+    pub fn month(year: i32, month: u32) -> Result<Self> {
+        let first = NaiveDate::from_ymd_opt(year, month, 1)
+            .ok_or_else(|| Error::TimeError(format!("Invalid month {year}-{month:02}")))?;
+        let next = first
+            .checked_add_months(Months::new(1))
+            .ok_or_else(|| Error::TimeError(format!("Invalid month {year}-{month:02}")))?;
+        Ok(Self::new(
+            first.and_time(Default::default()).and_utc(),
+            next.and_time(Default::default()).and_utc(),
+        ))
+    }
+}
+
+/// Fills, funding payments and transfers of the account over a time range
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Activity {
+    pub fills: Vec<Fill>,
+    pub funding_payments: Vec<FundingPayment>,
+    pub transfers: Vec<Transfer>,
+}
+
+impl Activity {
+    /// Fetch the activity from `start` up to `end`, or up to now without an end
+    ///
+    /// # Errors
+    ///
+    /// If any page of the fills, funding payments or transfers cannot be retrieved
+    pub async fn fetch(
+        client: &Client,
+        start: DateTime<Utc>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<Self> {
+        // The history endpoints include the end time, the period excludes it
+        let end = end.map(|end| end - TimeDelta::milliseconds(1));
+        let mut params = ListParams::new().start(start);
+        if let Some(end) = end {
+            params = params.end(end);
+        }
+        let (fills, funding_payments, transfers) = tokio::try_join!(
+            client.fills_exact(params.clone()),
+            client.funding_payments_exact(params.clone()),
+            client.transfers_exact(params),
+        )?;
+        Ok(Self {
+            fills,
+            funding_payments,
+            transfers,
+        })
+    }
+
+    /// Totals per currency, without balances
+    ///
+    /// Realized PnL and funding are in the settlement asset, fees in their fee currency.
+    /// Transfers count once completed.
+    fn totals(&self, settlement_asset: &str) -> BTreeMap<String, CurrencyTotals> {
+        let mut totals = BTreeMap::new();
+        for fill in &self.fills {
+            entry(&mut totals, settlement_asset).realized_pnl += fill.realized_pnl;
+            entry(&mut totals, &fill.fee_currency).fees += fill.fee;
+        }
+        for payment in &self.funding_payments {
+            entry(&mut totals, settlement_asset).funding += payment.payment;
+        }
+        for transfer in &self.transfers {
+            if transfer.status != TransferStatus::COMPLETED {
+                continue;
+            }
+            let totals = entry(&mut totals, &transfer.token);
+            match transfer.direction {
+                TransferDirection::IN => totals.deposits += transfer.amount,
+                TransferDirection::OUT => totals.withdrawals += transfer.amount,
+            }
+        }
+        totals
+    }
+}
+
+/// Totals of one currency over the statement period
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CurrencyTotals {
+    pub currency: String,
+    pub opening_balance: Decimal,
+    pub realized_pnl: Decimal,
+    /// Fees paid, negative for net rebates
+    pub fees: Decimal,
+    /// Funding received, negative when paid
+    pub funding: Decimal,
+    /// Completed transfers into the account
+    pub deposits: Decimal,
+    /// Completed transfers out of the account
+    pub withdrawals: Decimal,
+    pub closing_balance: Decimal,
+}
+
+impl CurrencyTotals {
+    fn new(currency: &str) -> Self {
+        Self {
+            currency: currency.to_string(),
+            opening_balance: Decimal::ZERO,
+            realized_pnl: Decimal::ZERO,
+            fees: Decimal::ZERO,
+            funding: Decimal::ZERO,
+            deposits: Decimal::ZERO,
+            withdrawals: Decimal::ZERO,
+            closing_balance: Decimal::ZERO,
+        }
+    }
+
+    /// Change of the balance over the period
+    pub fn net_change(&self) -> Decimal {
+        self.realized_pnl - self.fees + self.funding + self.deposits - self.withdrawals
+    }
+}
+
+/// Statement of the account over a period
+#[derive(Clone, Debug, PartialEq)]
+pub struct Statement {
+    pub period: Period,
+    /// Currency of the realized PnL and funding
+    pub settlement_asset: String,
+    pub activity: Activity,
+    /// Totals by currency, ordered by currency
+    pub totals: Vec<CurrencyTotals>,
+}
+
+impl Statement {
+    /// Fetch the statement of the account over a period
+    ///
+    /// Fetches the activity over the period and since its end concurrently with the current
+    /// balances, see [`Statement::new`].
+    ///
+    /// # Errors
+    ///
+    /// If the account information, balances or any page of the history cannot be retrieved
+    pub async fn fetch(client: &Client, period: Period) -> Result<Self> {
+        let (account, balances, activity, since) = tokio::try_join!(
+            client.account_information(),
+            client.balance_exact(),
+            Activity::fetch(client, period.start, Some(period.end)),
+            Activity::fetch(client, period.end, None),
+        )?;
+        Ok(Self::new(
+            period,
+            &account.settlement_asset,
+            activity,
+            &balances,
+            &since,
+        ))
+    }
+
+    /// Build the statement from the activity over the period
+    ///
+    /// The closing balances are the current `balances` less the changes made by the activity
+    /// `since` the end of the period, and the opening balances the closing ones less the changes
+    /// over the period. Balance changes the history endpoints do not report, such as
+    /// liquidation penalties, are therefore attributed to the wrong period.
+    pub fn new(
+        period: Period,
+        settlement_asset: &str,
+        activity: Activity,
+        balances: &[Balance],
+        since: &Activity,
+    ) -> Self {
+        let mut totals = activity.totals(settlement_asset);
+        let since = since.totals(settlement_asset);
+        for balance in balances {
+            entry(&mut totals, &balance.token).closing_balance = balance.size;
+        }
+        for (currency, later) in &since {
+            entry(&mut totals, currency).closing_balance -= later.net_change();
+        }
+        for totals in totals.values_mut() {
+            totals.opening_balance = totals.closing_balance - totals.net_change();
+        }
+        Self {
+            period,
+            settlement_asset: settlement_asset.to_string(),
+            activity,
+            totals: totals.into_values().collect(),
+        }
+    }
+
+    /// Totals by currency as CSV, one row per currency
+    pub fn totals_csv(&self) -> String {
+        let mut csv = Csv::new(&[
+            "currency",
+            "opening_balance",
+            "realized_pnl",
+            "fees",
+            "funding",
+            "deposits",
+            "withdrawals",
+            "closing_balance",
+        ]);
+        for totals in &self.totals {
+            csv.row(&[
+                totals.currency.clone(),
+                totals.opening_balance.to_string(),
+                totals.realized_pnl.to_string(),
+                totals.fees.to_string(),
+                totals.funding.to_string(),
+                totals.deposits.to_string(),
+                totals.withdrawals.to_string(),
+                totals.closing_balance.to_string(),
+            ]);
+        }
+        csv.0
+    }
+
+    /// Fills over the period as CSV
+    pub fn fills_csv(&self) -> String {
+        let mut csv = Csv::new(&[
+            "time",
+            "id",
+            "market",
+            "side",
+            "liquidity",
+            "fill_type",
+            "price",
+            "size",
+            "notional",
+            "fee",
+            "fee_currency",
+            "realized_pnl",
+            "order_id",
+            "client_id",
+        ]);
+        for fill in &self.activity.fills {
+            csv.row(&[
+                timestamp(fill.created_at),
+                fill.id.clone(),
+                fill.market.clone(),
+                format!("{:?}", fill.side),
+                format!("{:?}", fill.liquidity),
+                format!("{:?}", fill.fill_type),
+                fill.price.to_string(),
+                fill.size.to_string(),
+                fill.notional().to_string(),
+                fill.fee.to_string(),
+                fill.fee_currency.clone(),
+                fill.realized_pnl.to_string(),
+                fill.order_id.clone(),
+                fill.client_id.clone(),
+            ]);
+        }
+        csv.0
+    }
+
+    /// Funding payments over the period as CSV
+    pub fn funding_csv(&self) -> String {
+        let mut csv = Csv::new(&["time", "id", "market", "payment", "index", "fill_id"]);
+        for payment in &self.activity.funding_payments {
+            csv.row(&[
+                timestamp(payment.created_at),
+                payment.id.clone(),
+                payment.market.clone(),
+                payment.payment.to_string(),
+                payment.index.to_string(),
+                payment.fill_id.clone(),
+            ]);
+        }
+        csv.0
+    }
+
+    /// Transfers over the period as CSV, including those which did not complete
+    pub fn transfers_csv(&self) -> String {
+        let mut csv = Csv::new(&[
+            "time",
+            "id",
+            "kind",
+            "direction",
+            "status",
+            "token",
+            "amount",
+            "txn_hash",
+            "external_txn_hash",
+        ]);
+        for transfer in &self.activity.transfers {
+            csv.row(&[
+                timestamp(transfer.created_at),
+                transfer.id.clone(),
+                format!("{:?}", transfer.kind),
+                format!("{:?}", transfer.direction),
+                format!("{:?}", transfer.status),
+                transfer.token.clone(),
+                transfer.amount.to_string(),
+                transfer.txn_hash.clone(),
+                transfer.external_txn_hash.clone(),
+            ]);
+        }
+        csv.0
+    }
+}
+
+/// Totals of a currency, created empty
+fn entry<'a>(
+    totals: &'a mut BTreeMap<String, CurrencyTotals>,
+    currency: &str,
+) -> &'a mut CurrencyTotals {
+    totals
+        .entry(currency.to_string())
+        .or_insert_with(|| CurrencyTotals::new(currency))
+}
+
+/// RFC 3339 time of a timestamp in milliseconds
+fn timestamp(millis: u64) -> String {
+    DateTime::from_timestamp_millis(millis as i64)
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// CSV document with a header row
+struct Csv(String);
+
+impl Csv {
+    fn new(header: &[&str]) -> Self {
+        let mut csv = Self(String::new());
+        csv.row(
+            &header
+                .iter()
+                .map(|column| column.to_string())
+                .collect::<Vec<_>>(),
+        );
+        csv
+    }
+
+    fn row(&mut self, fields: &[String]) {
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                self.0.push(',');
+            }
+            if field.contains([',', '"', '\n', '\r']) {
+                let _ = write!(self.0, "\"{}\"", field.replace('"', "\"\""));
+            } else {
+                self.0.push_str(field);
+            }
+        }
+        self.0.push_str("\r\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::parse_decimal;
+
+    #[test]
+    fn month() {
+        let period = Period::month(2024, 12).unwrap();
+        assert_eq!(period.start.to_rfc3339(), "2024-12-01T00:00:00+00:00");
+        assert_eq!(period.end.to_rfc3339(), "2025-01-01T00:00:00+00:00");
+        assert!(Period::month(2024, 13).is_err());
+    }
+
+    #[test]
+    fn statement() {
+        let fill: Fill = serde_json::from_str(include_str!("../tests/fixtures/fill.json")).unwrap();
+        let payment: FundingPayment =
+            serde_json::from_str(include_str!("../tests/fixtures/funding_payment.json")).unwrap();
+        let transfer: Transfer =
+            serde_json::from_str(include_str!("../tests/fixtures/transfer.json")).unwrap();
+        let balances: Vec<Balance> = serde_json::from_value(
+            serde_json::from_str::<serde_json::Value>(include_str!(
+                "../tests/fixtures/balances.json"
+            ))
+            .unwrap()["results"]
+                .clone(),
+        )
+        .unwrap();
+        let failed = Transfer {
+            status: TransferStatus::FAILED,
+            ..transfer.clone()
+        };
+        let since = Activity {
+            fills: vec![fill.clone()],
+            funding_payments: Vec::new(),
+            transfers: vec![Transfer {
+                direction: TransferDirection::OUT,
+                amount: parse_decimal("3").unwrap(),
+                ..transfer.clone()
+            }],
+        };
+        let activity = Activity {
+            fills: vec![Fill {
+                client_id: "a,\"b\"".to_string(),
+                ..fill
+            }],
+            funding_payments: vec![payment],
+            transfers: vec![transfer, failed],
+        };
+        let period = Period::month(2023, 4).unwrap();
+        let statement = Statement::new(period, "USDC", activity, &balances, &since);
+
+        // 123003.620 now, less -12.5 PnL and 7.56 fees and 3 withdrawn since
+        let usdc = &statement.totals[0];
+        assert_eq!(statement.totals.len(), 1);
+        assert_eq!(usdc.closing_balance, parse_decimal("123026.680").unwrap());
+        assert_eq!(usdc.realized_pnl, parse_decimal("-12.5").unwrap());
+        assert_eq!(usdc.fees, parse_decimal("7.56").unwrap());
+        assert_eq!(usdc.funding, parse_decimal("34.4490622").unwrap());
+        assert_eq!(usdc.deposits, parse_decimal("100").unwrap());
+        assert_eq!(usdc.withdrawals, Decimal::ZERO);
+        assert_eq!(
+            usdc.opening_balance,
+            parse_decimal("122912.2909378").unwrap()
+        );
+
+        let totals = statement.totals_csv();
+        assert_eq!(
+            totals.lines().collect::<Vec<_>>(),
+            [
+                "currency,opening_balance,realized_pnl,fees,funding,deposits,withdrawals,closing_balance",
+                "USDC,122912.2909378,-12.5,7.56,34.4490622,100,0,123026.680",
+            ]
+        );
+        let fills = statement.fills_csv();
+        assert!(fills.lines().nth(1).unwrap().starts_with(
+            "2023-04-13T08:39:36.910+00:00,8615262148007718462,BTC-USD-PERP,BUY,TAKER,FILL,30000.12,0.5,15000.060"
+        ));
+        assert!(fills.contains(",\"a,\"\"b\"\"\"\r\n"));
+        assert_eq!(statement.funding_csv().lines().count(), 2);
+        let transfers = statement.transfers_csv();
+        assert_eq!(transfers.lines().count(), 3);
+        assert!(transfers.contains(",DEPOSIT,IN,COMPLETED,USDC,100,"));
+        assert!(transfers.contains(",DEPOSIT,IN,FAILED,USDC,100,"));
+    }
+}
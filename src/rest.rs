@@ -1,54 +1,187 @@
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use log::trace;
+use futures_util::future::{join_all, try_join, try_join_all};
+use futures_util::{Stream, StreamExt, TryStreamExt, stream};
+use log::{info, trace, warn};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
 use reqwest::header::{HeaderMap, HeaderValue};
 use starknet_core::types::Felt;
 use starknet_core::utils::cairo_short_string_to_felt;
 use starknet_signers::SigningKey;
 use tokio::sync::RwLock;
 
-#[cfg(feature = "onboarding")]
+#[cfg(any(feature = "onboarding", feature = "bridge"))]
 use alloy_signer_local::PrivateKeySigner;
 #[cfg(feature = "onboarding")]
 use serde_json::Value;
 
-use crate::error::{Error, Result};
+use crate::error::{Error, ParadexErrorCode, Result};
+use crate::exact;
+use crate::markets::MarketRegistry;
 #[cfg(feature = "onboarding")]
-use crate::message::onboarding_headers;
-use crate::message::{account_address, auth_headers, sign_modify_order, sign_order};
+use crate::message::onboarding_headers_with_signer;
+use crate::message::{
+    account_address, auth_headers_with_signer, sign_modify_order_with_signer,
+    sign_order_bucketed_with_signer, sign_order_with_signer,
+};
 #[cfg(feature = "onboarding")]
 use crate::onboarding::get_paradex_private_key;
+use crate::options::{OptionHistory, OptionHistoryRequest, OptionInstrument};
+use crate::order_builder::OrderBuilder;
+use crate::query::IntoQuery;
+use crate::signer::StarkSigner;
+use crate::stark::parse_private_key;
 #[cfg(feature = "onboarding")]
-use crate::structs::OnboardingRequest;
+use crate::stark::{format_key, public_key};
 use crate::structs::{
-    AccountInformation, AccountMarginConfigurations, AccountMarginUpdate,
-    AccountMarginUpdateResponse, BBO, Balances, CancelByMarketResponse, CursorResult, Fill,
-    FundingPayment, JWTToken, Kline, KlineParams, MarketSummaryStatic, ModifyOrderRequest,
-    OrderBookInteractiveResponse, OrderBookParams, OrderBookResponse, OrderRequest, OrderUpdate,
-    OrderUpdates, Positions, RestError, ResultsContainer, SystemConfig, SystemState,
-    SystemTimeResponse, Trade, Transfer, TransferStatus,
+    AccountInfo, AccountInformation, AccountMarginConfigurations, AccountMarginUpdate,
+    AccountMarginUpdateResponse, AccountProfile, BBO, Balances, BatchOrdersResponse, BridgedToken,
+    CancelByMarketResponse, CursorResult, Fill, FundingData, FundingPayment, JWTToken, Kline,
+    KlineParams, ListParams, MarginConfig, MarginType, MarketSummary, MarketSummaryStatic,
+    MaxSlippageUpdate, ModifyOrder, ModifyOrderRequest, ModifyOrderResult, OnboardingRequest,
+    OpenOrders, Order, OrderBookInteractiveResponse, OrderBookParams, OrderBookResponse,
+    OrderRequest, OrderType, OrderUpdate, OrderUpdates, Positions, ReferralSummary, RestError,
+    ResultsContainer, Side, SubAccount, SystemConfig, SystemState, SystemStatus,
+    SystemTimeResponse, Trade, Transfer, UsernameUpdate, VaultAccountSummary, VaultHistory,
+    VaultHistoryType, VaultSummary,
 };
+use crate::transaction::{Call, FeeBounds, InvokeTransaction, StarknetRpc, scale_amount};
 use crate::url::URL;
 
+mod address_book;
+mod approval;
+mod body;
+mod guard;
+mod order_log;
+mod rate_limit;
+mod status;
+
+use guard::{GuardState, is_rejection};
+use order_log::OrderLog;
+use rate_limit::{RateLimiter, is_rate_limited, retry_after};
+use status::StatusGate;
+
+pub use address_book::AddressBook;
+pub use approval::{Approver, SensitiveOperation};
+pub use guard::OrderGuard;
+pub use order_log::{OrderAction, OrderEvent, OrderSink, OrderTransport};
+pub use rate_limit::{EndpointClass, RateLimit, RetryPolicy};
+pub use status::SystemStatusPoller;
+
 const JWT_UPDATE_INTERVAL: u64 = 240;
 
-enum Method<Body: serde::Serialize> {
-    Get(Vec<(String, String)>),
-    Post(Body),
-    Put(Body),
+/// Poll intervals after which a polled system status no longer gates order flow
+const SYSTEM_STATUS_TTL_POLLS: u32 = 3;
+
+/// Characters escaped in a URL path segment, all but the unreserved ones of RFC 3986
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Escape a value, such as a client order id, for use as one segment of a request path
+fn path_segment(value: &str) -> String {
+    utf8_percent_encode(value, PATH_SEGMENT).to_string()
+}
+
+#[cfg(not(feature = "onboarding"))]
+fn onboarding_disabled() -> Error {
+    Error::FeatureDisabled("onboarding".to_string())
+}
+
+/// Run a REST call, abandoning it if it has not completed within `deadline`
+///
+/// Dropping the call cancels its in-flight request, including rate limit waits and retries.
+/// The exchange may still have processed a request abandoned after it was sent.
+///
+/// # Errors
+///
+/// `Error::DeadlineExceeded` if the call did not complete in time, otherwise the call's error
+pub async fn with_deadline<T>(
+    deadline: Duration,
+    call: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::time::timeout(deadline, call)
+        .await
+        .map_err(|_| Error::DeadlineExceeded(deadline))?
+}
+
+/// Derive the Paradex account address for a Stark public key from the system configuration
+fn account_for_public_key(public_key: Felt, system_config: &SystemConfig) -> Result<Felt> {
+    account_address(
+        public_key,
+        Felt::from_str(system_config.paraclear_account_proxy_hash.as_str())
+            .map_err(|e| Error::StarknetError(e.to_string()))?,
+        Felt::from_str(system_config.paraclear_account_hash.as_str())
+            .map_err(|e| Error::StarknetError(e.to_string()))?,
+    )
+}
+
+/// Check a margin update against the constraints of the market, if known
+fn check_margin_update(
+    symbol: &str,
+    market: &MarketSummaryStatic,
+    leverage: u64,
+    margin_type: MarginType,
+) -> Result<()> {
+    if leverage == 0 {
+        return Err(Error::InvalidMarginUpdate(format!(
+            "leverage of {symbol} must be at least 1"
+        )));
+    }
+    if margin_type == MarginType::ISOLATED && !market.supports_isolated_margin() {
+        return Err(Error::InvalidMarginUpdate(format!(
+            "{symbol} is a {} market, which cannot be isolated margined",
+            market.asset_kind
+        )));
+    }
+    if let Some(max_leverage) = market.max_leverage()
+        && leverage > max_leverage
+    {
+        return Err(Error::InvalidMarginUpdate(format!(
+            "leverage {leverage} of {symbol} exceeds the maximum of {max_leverage}"
+        )));
+    }
+    Ok(())
+}
+
+/// HTTP method along with its payload, serialized as the query string for `Get` and as the
+/// JSON body for `Post` and `Put`
+enum Method<Payload: serde::Serialize> {
+    Get(Payload),
+    Post(Payload),
+    Put(Payload),
     Delete,
 }
 
+#[derive(serde::Serialize)]
+struct VaultHistoryParams<'a> {
+    address: &'a str,
+    #[serde(rename = "type")]
+    history_type: VaultHistoryType,
+}
+
 /// Rest client following the paradex spec
 /// The client does not need to be wrapped in an Rc or Arc to re-use. The client can instead be Cloned which will re-use the sample internal components which are already wrapped in Arc.
 #[derive(Clone)]
 pub struct Client {
     url: URL,
     client: reqwest::Client,
-    l2_chain_private_key_account: Option<(Felt, SigningKey, Felt)>,
+    l2_chain_private_key_account: Option<(Felt, Arc<dyn StarkSigner>, Felt)>,
     jwt: Arc<RwLock<(SystemTime, String)>>, // the current valid JWT and timestamp created
+    signature_bucket_ms: Option<u128>,
+    api_token: Option<Arc<RwLock<String>>>, // pre-issued token used instead of Stark key auth
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: RateLimiter,
+    order_guard: GuardState,
+    order_log: OrderLog,
+    system_status: StatusGate,
+    withdrawal_whitelist: Option<AddressBook>,
+    approver: Option<Arc<dyn Approver>>,
+    market_registry: Option<MarketRegistry>,
 }
 
 impl Client {
@@ -71,6 +204,41 @@ impl Client {
         Self::with_client(reqwest::Client::new(), url, l2_private_key_hex_str).await
     }
 
+    /// Create a new Client instance acting on behalf of a sub-account of the key's main account
+    ///
+    /// # Parameters
+    ///
+    /// * `url` - A URL struct representing the base URL for the REST API
+    /// * `l2_private_key_hex_str` - The private key of the main account
+    /// * `subaccount_index` - Position of the sub-account in `list_subaccounts`, oldest first
+    ///
+    /// # Returns
+    ///
+    /// A Result with the new Client instance
+    ///
+    /// # Errors
+    ///
+    /// If the client cannot be created, the sub-accounts cannot be listed or there is no
+    /// sub-account at `subaccount_index`
+    pub async fn new_with_subaccount(
+        url: URL,
+        l2_private_key_hex_str: String,
+        subaccount_index: usize,
+    ) -> Result<Self> {
+        let client = Self::new(url, Some(l2_private_key_hex_str)).await?;
+        let subaccount = client
+            .list_subaccounts()
+            .await?
+            .into_iter()
+            .nth(subaccount_index)
+            .ok_or_else(|| {
+                Error::RestError(format!("no sub-account at index {subaccount_index}"))
+            })?;
+        let account =
+            Felt::from_hex(&subaccount.account).map_err(|e| Error::StarknetError(e.to_string()))?;
+        client.for_subaccount(account)
+    }
+
     /// Create a new Client instance given an Ethereum private key
     /// This will submit an onboarding request and should only need to be done once per private key
     #[cfg(feature = "onboarding")]
@@ -88,8 +256,25 @@ impl Client {
         .await
     }
 
+    /// Onboarding requires the `onboarding` feature, see `ONBOARDING_SUPPORTED`
+    ///
+    /// # Errors
+    ///
+    /// Always returns `Error::FeatureDisabled`
+    #[cfg(not(feature = "onboarding"))]
+    pub async fn new_with_eth_private_key(
+        _url: URL,
+        _eth_private_key_hex_str: String,
+        _onboarding_request: Option<OnboardingRequest>,
+    ) -> Result<Self> {
+        Err(onboarding_disabled())
+    }
+
     /// Create a new client instance with a custom reqwest client
     ///
+    /// Responses are requested and decompressed with gzip, brotli or deflate when `client` has
+    /// them enabled, as clients made with `reqwest::Client::new()` do.
+    ///
     /// # Parameters
     ///
     /// * `client` - A reqwest client
@@ -103,7 +288,6 @@ impl Client {
     /// # Errors
     ///
     /// If the client cannot be created
-    ///
     pub async fn with_client(
         client: reqwest::Client,
         url: URL,
@@ -114,30 +298,117 @@ impl Client {
             client,
             l2_chain_private_key_account: None,
             jwt: Arc::new(RwLock::new((UNIX_EPOCH, "".to_string()))),
+            signature_bucket_ms: None,
+            api_token: None,
+            retry_policy: None,
+            rate_limiter: RateLimiter::default(),
+            order_guard: GuardState::default(),
+            order_log: OrderLog::default(),
+            system_status: StatusGate::default(),
+            withdrawal_whitelist: None,
+            approver: None,
+            market_registry: None,
         };
         if let Some(hex_str) = l2_private_key_hex_str {
-            let signing_key = SigningKey::from_secret_scalar(
-                Felt::from_hex(hex_str.as_str())
-                    .map_err(|e| Error::StarknetError(e.to_string()))?,
-            );
-            let public_key = signing_key.verifying_key();
-            let system_config = new_client.system_config().await?;
-
-            let account = account_address(
-                public_key.scalar(),
-                Felt::from_str(system_config.paraclear_account_proxy_hash.as_str())
-                    .map_err(|e| Error::StarknetError(e.to_string()))?,
-                Felt::from_str(system_config.paraclear_account_hash.as_str())
-                    .map_err(|e| Error::StarknetError(e.to_string()))?,
-            )
+            let signing_key = SigningKey::from_secret_scalar(parse_private_key(&hex_str)?);
+            new_client.set_signer(Arc::new(signing_key)).await?;
+        }
+        Ok(new_client)
+    }
+
+    /// Create a new Client instance signing with `signer` instead of a private key held by
+    /// the client, e.g. to sign through a KMS, an HSM or a remote co-signer
+    ///
+    /// # Parameters
+    ///
+    /// * `url` - A URL struct representing the base URL for the REST API
+    /// * `signer` - The signer of orders, auth requests and transactions, whose public key
+    ///   determines the account
+    ///
+    /// # Returns
+    ///
+    /// A Result with the new Client instance
+    ///
+    /// # Errors
+    ///
+    /// If the system configuration cannot be retrieved
+    pub async fn new_with_signer(url: URL, signer: Arc<dyn StarkSigner>) -> Result<Self> {
+        let mut client = Self::with_client(reqwest::Client::new(), url, None).await?;
+        client.set_signer(signer).await?;
+        Ok(client)
+    }
+
+    /// Sign with `signer`, for the account derived from its public key
+    async fn set_signer(&mut self, signer: Arc<dyn StarkSigner>) -> Result<()> {
+        let system_config = self.system_config().await?;
+        let account = account_for_public_key(signer.public_key(), &system_config)?;
+        let chain_id = cairo_short_string_to_felt(system_config.starknet_chain_id.as_str())
             .map_err(|e| Error::StarknetError(e.to_string()))?;
+        self.l2_chain_private_key_account = Some((chain_id, signer, account));
+        Ok(())
+    }
 
-            let chain_id = cairo_short_string_to_felt(system_config.starknet_chain_id.as_str())
-                .map_err(|e| Error::StarknetError(e.to_string()))?;
+    /// Sign with `signer` for `account` on `chain_id`, without fetching the system config
+    #[cfg(test)]
+    pub(crate) fn with_signer_account(
+        mut self,
+        chain_id: Felt,
+        signer: Arc<dyn StarkSigner>,
+        account: Felt,
+    ) -> Self {
+        self.l2_chain_private_key_account = Some((chain_id, signer, account));
+        self
+    }
 
-            new_client.l2_chain_private_key_account = Some((chain_id, signing_key, account));
+    /// Create a new client instance authenticating with a pre-issued API token
+    ///
+    /// The token is sent as the bearer token of private requests instead of a JWT obtained by
+    /// signing with a Stark key, and can be replaced with `rotate_api_token`. Without a Stark key
+    /// the client cannot sign orders, so order creation and modification return
+    /// `Error::MissingPrivateKey`.
+    ///
+    /// # Parameters
+    ///
+    /// * `client` - A reqwest client
+    /// * `url` - A URL struct representing the base URL for the REST API
+    /// * `api_token` - The API token issued for the account
+    ///
+    /// # Returns
+    ///
+    /// The new Client instance
+    pub fn with_api_token(client: reqwest::Client, url: URL, api_token: String) -> Self {
+        Self {
+            url,
+            client,
+            l2_chain_private_key_account: None,
+            jwt: Arc::new(RwLock::new((UNIX_EPOCH, "".to_string()))),
+            signature_bucket_ms: None,
+            api_token: Some(Arc::new(RwLock::new(api_token))),
+            retry_policy: None,
+            rate_limiter: RateLimiter::default(),
+            order_guard: GuardState::default(),
+            order_log: OrderLog::default(),
+            system_status: StatusGate::default(),
+            withdrawal_whitelist: None,
+            approver: None,
+            market_registry: None,
         }
-        Ok(new_client)
+    }
+
+    /// Replace the API token of a client created with `with_api_token`
+    /// The new token is shared with every clone of the client
+    ///
+    /// # Parameters
+    ///
+    /// * `api_token` - The replacement API token
+    ///
+    /// # Errors
+    ///
+    /// `Error::MissingApiToken` if the client does not authenticate with an API token
+    pub async fn rotate_api_token(&self, api_token: String) -> Result<()> {
+        let lock = self.api_token.as_ref().ok_or(Error::MissingApiToken)?;
+        *lock.write().await = api_token;
+        Ok(())
     }
 
     /// Create a new client instance from an Ethereum private key with a custom reqwest client
@@ -153,641 +424,2527 @@ impl Client {
             .map_err(|e| Error::TypeConversionError(e.to_string()))?;
 
         let paradex_private_key = get_paradex_private_key(&eth_signer);
-        let paradex_signing_key = SigningKey::from_secret_scalar(paradex_private_key);
-        let paradex_public_key_hex = paradex_signing_key.verifying_key().scalar().to_hex_string();
-        let paradex_private_key_hex = paradex_private_key.to_hex_string();
-
-        let client = Self::with_client(client, url, Some(paradex_private_key_hex.clone())).await?;
-
-        let mut request = onboarding_request.unwrap_or_default();
-        request.public_key = paradex_public_key_hex;
-
         let ethereum_account = format!("{:#x}", eth_signer.address());
-        client.submit_onboarding(&ethereum_account, request).await?;
 
-        Ok(client)
+        Self::with_client_for_ethereum_account(
+            client,
+            url,
+            ethereum_account,
+            format_key(&paradex_private_key),
+            onboarding_request,
+        )
+        .await
     }
 
-    /// Get the Paradex system configuration
-    ///
-    /// # Returns
-    ///
-    /// A SystemConfig struct representing the system configuration
+    /// Onboarding requires the `onboarding` feature, see `ONBOARDING_SUPPORTED`
     ///
     /// # Errors
     ///
-    /// If the system configuration cannot be retrieved
-    pub async fn system_config(&self) -> Result<SystemConfig> {
-        self.request(Method::Get::<()>(vec![]), "/v1/system/config".into(), None)
-            .await
+    /// Always returns `Error::FeatureDisabled`
+    #[cfg(not(feature = "onboarding"))]
+    pub async fn with_client_from_eth_private_key(
+        _client: reqwest::Client,
+        _url: URL,
+        _eth_private_key_hex_str: String,
+        _onboarding_request: Option<OnboardingRequest>,
+    ) -> Result<Self> {
+        Err(onboarding_disabled())
     }
 
-    /// Get the Paradex system state
+    /// Create a new client instance for an Ethereum account that cannot sign with a local key
+    /// This will submit an onboarding request and should only need to be done once per account
+    ///
+    /// Onboarding only proves ownership of the Stark key, the Ethereum account is sent as the
+    /// address the Paradex account is registered to. Smart contract wallets (Safe, Argent) that
+    /// cannot produce the ECDSA signature the Stark key is normally derived from can therefore
+    /// onboard with a Stark key generated and stored separately, or derived with
+    /// `onboarding::paradex_private_key_from_signature` from a signature produced elsewhere.
+    ///
+    /// # Parameters
+    ///
+    /// * `client` - A reqwest client
+    /// * `url` - A URL struct representing the base URL for the REST API
+    /// * `ethereum_account` - The hex address of the Ethereum account, e.g. the contract wallet
+    /// * `l2_private_key_hex_str` - The Stark private key to onboard for the account
+    /// * `onboarding_request` - Optional marketing, referral and UTM metadata
     ///
     /// # Returns
     ///
-    /// A SystemState struct representing the system state
+    /// A Result with the new Client instance
     ///
     /// # Errors
     ///
-    /// If the system state cannot be retrieved
-    pub async fn system_state(&self) -> Result<SystemState> {
-        self.request(Method::Get::<()>(vec![]), "/v1/system/state".into(), None)
-            .await
+    /// If the private key is invalid or the onboarding request is rejected
+    #[cfg(feature = "onboarding")]
+    pub async fn with_client_for_ethereum_account(
+        client: reqwest::Client,
+        url: URL,
+        ethereum_account: String,
+        l2_private_key_hex_str: String,
+        onboarding_request: Option<OnboardingRequest>,
+    ) -> Result<Self> {
+        let paradex_public_key_hex =
+            format_key(&public_key(&parse_private_key(&l2_private_key_hex_str)?));
+
+        let client = Self::with_client(client, url, Some(l2_private_key_hex_str)).await?;
+
+        let mut request = onboarding_request.unwrap_or_default();
+        request.public_key = paradex_public_key_hex;
+
+        client.submit_onboarding(&ethereum_account, request).await?;
+
+        Ok(client)
     }
 
-    /// Get the Paradex system time
-    ///
-    /// # Returns
-    ///
-    /// A SystemTimeResponse struct representing the system time
+    /// Onboarding requires the `onboarding` feature, see `ONBOARDING_SUPPORTED`
     ///
     /// # Errors
     ///
-    /// If the system time cannot be retrieved
-    pub async fn system_time(&self) -> Result<SystemTimeResponse> {
-        self.request(Method::Get::<()>(vec![]), "/v1/system/time".into(), None)
-            .await
+    /// Always returns `Error::FeatureDisabled`
+    #[cfg(not(feature = "onboarding"))]
+    pub async fn with_client_for_ethereum_account(
+        _client: reqwest::Client,
+        _url: URL,
+        _ethereum_account: String,
+        _l2_private_key_hex_str: String,
+        _onboarding_request: Option<OnboardingRequest>,
+    ) -> Result<Self> {
+        Err(onboarding_disabled())
     }
 
-    /// Get the list of markets on the exchange
+    /// Get the Paradex account address used by this client
     ///
     /// # Returns
     ///
-    /// A vector of MarketSummaryStatic structs representing the markets
-    ///
-    /// # Errors
-    ///
-    /// If the markets cannot be retrieved
-    pub async fn markets(&self) -> Result<Vec<MarketSummaryStatic>> {
-        self.request(Method::Get::<()>(vec![]), "/v1/markets".into(), None)
-            .await
-            .map(
-                |result_container: ResultsContainer<Vec<MarketSummaryStatic>>| {
-                    result_container.results
-                },
-            )
+    /// The account address, or None if the client has no private key
+    pub fn account(&self) -> Option<Felt> {
+        self.l2_chain_private_key_account
+            .as_ref()
+            .map(|(_, _, account)| *account)
     }
 
-    /// Get the list of Klines for a symbol
+    /// Get a client routing every request to another account signed for with the same key,
+    /// e.g. a sub-account from `list_subaccounts`
+    ///
+    /// The returned client shares the HTTP client and rate limits with this one, but
+    /// authenticates separately, so both can be used side by side.
+    ///
+    /// # Parameters
+    ///
+    /// * `account` - The address of the sub-account
     ///
     /// # Returns
     ///
-    /// A vector of Kline structs representing the klines
+    /// A Result with the Client for the sub-account
     ///
     /// # Errors
     ///
-    /// If the klines cannot be retrieved
-    pub async fn klines(&self, params: KlineParams) -> Result<Vec<Kline>> {
-        self.request(
-            Method::Get::<()>(params.into()),
-            "/v1/markets/klines".into(),
-            None,
-        )
-        .await
-        .map(|result_container: ResultsContainer<Vec<Kline>>| result_container.results)
+    /// If the client has no private key, since API tokens are issued for a single account
+    pub fn for_subaccount(&self, account: Felt) -> Result<Self> {
+        let (l2_chain, signer, _) = self
+            .l2_chain_private_key_account
+            .as_ref()
+            .ok_or(Error::MissingPrivateKey)?;
+        Ok(Self {
+            l2_chain_private_key_account: Some((*l2_chain, signer.clone(), account)),
+            jwt: Arc::new(RwLock::new((UNIX_EPOCH, "".to_string()))),
+            ..self.clone()
+        })
     }
 
-    /// Get snapshot of the orderbook for the given market
+    /// Look up the Paradex account associated with an Ethereum wallet
+    ///
+    /// The Paradex key pair and account address are derived from the wallet deterministically, so
+    /// this only needs the system configuration and does not submit an onboarding request. The
+    /// account may not have been onboarded yet. The API does not expose the reverse mapping from a
+    /// Paradex account back to its Ethereum wallet.
+    ///
+    /// # Parameters
+    ///
+    /// * `eth_private_key_hex_str` - The Ethereum private key of the wallet
     ///
     /// # Returns
     ///
-    /// An OrderBookResponse struct representing the orderbook
+    /// The Paradex account address associated with the wallet
     ///
     /// # Errors
     ///
-    /// If the orderbook cannot be retrieved
-    pub async fn orderbook(
-        &self,
-        market: String,
-        params: OrderBookParams,
-    ) -> Result<OrderBookResponse> {
-        self.request(
-            Method::Get::<()>(params.into()),
-            format!("/v1/orderbook/{}", market),
-            None,
-        )
-        .await
+    /// If the Ethereum key is invalid or the system configuration cannot be retrieved
+    #[cfg(feature = "onboarding")]
+    pub async fn account_for_eth_private_key(&self, eth_private_key_hex_str: &str) -> Result<Felt> {
+        let eth_signer = PrivateKeySigner::from_str(eth_private_key_hex_str)
+            .map_err(|e| Error::TypeConversionError(e.to_string()))?;
+        let paradex_public_key = public_key(&get_paradex_private_key(&eth_signer));
+        account_for_public_key(paradex_public_key, &self.system_config().await?)
     }
 
-    /// Returns orderbook including RPI
-    ///
-    /// # Returns
-    ///
-    /// An OrderBookResponse struct representing the orderbook
+    /// Deriving the Stark key from an Ethereum key requires the `onboarding` feature, see
+    /// `ONBOARDING_SUPPORTED`
     ///
     /// # Errors
     ///
-    /// If the orderbook cannot be retrieved
-    pub async fn orderbook_interactive(
+    /// Always returns `Error::FeatureDisabled`
+    #[cfg(not(feature = "onboarding"))]
+    pub async fn account_for_eth_private_key(
         &self,
-        market: String,
-        params: OrderBookParams,
-    ) -> Result<OrderBookInteractiveResponse> {
-        self.request(
-            Method::Get::<()>(params.into()),
-            format!("/v1/orderbook/{market}/interactive"),
-            None,
-        )
-        .await
+        _eth_private_key_hex_str: &str,
+    ) -> Result<Felt> {
+        Err(onboarding_disabled())
     }
 
-    /// Check if the client has a private key set allowing for private API calls
+    /// Reuse order signatures for identical orders created within the same time bucket
+    ///
+    /// Order signature timestamps are truncated to a multiple of `bucket`, so re-quoting the same
+    /// market, side, type, size and price within a bucket skips hashing and signing. `bucket`
+    /// must be well below the recv window accepted by the venue.
+    ///
+    /// # Parameters
+    ///
+    /// * `bucket` - The signature timestamp granularity
     ///
     /// # Returns
     ///
-    /// A boolean indicating if the client has a private key set
-    pub(crate) fn is_private(&self) -> bool {
-        self.l2_chain_private_key_account.is_some()
+    /// The Client with signature caching enabled
+    pub fn with_signature_cache(mut self, bucket: std::time::Duration) -> Self {
+        self.signature_bucket_ms = Some(bucket.as_millis());
+        self
     }
 
-    /// Get the current JWT token
-    /// If the token is expired, it will be refreshed
+    /// Retry requests rejected by the venue's rate limits
     ///
-    /// # Returns
+    /// Rate limited requests are retried with exponential backoff, waiting as long as the
+    /// `Retry-After` header asks for when the response carries one. Without a policy the rate
+    /// limit error is returned straight away.
     ///
-    /// A string representing the current JWT token
+    /// # Parameters
     ///
-    /// # Errors
+    /// * `retry_policy` - The number of retries and backoff between them
     ///
-    /// If the token cannot be refreshed
-    pub async fn jwt(&self) -> Result<String> {
-        // Check if Invalid
-        if self.check_jwt_expired().await {
-            self.refresh_jwt(false).await?;
-        }
-
-        // Return JWT
-        let lock = self.jwt.read().await;
-        let (_ts, jwt) = &*lock;
-        Ok(jwt.clone())
+    /// # Returns
+    ///
+    /// The Client with retries enabled
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
     }
 
-    /// Check if the current JWT token is expired
+    /// Throttle requests of an endpoint class on the client side with a token bucket
+    ///
+    /// Requests wait for a token before being sent, so bursts are smoothed out before they
+    /// reach the venue's limits. The bucket is shared with clones of the client created after
+    /// this call.
+    ///
+    /// # Parameters
+    ///
+    /// * `class` - The endpoints the limit applies to
+    /// * `limit` - The burst size and sustained rate of the bucket
     ///
     /// # Returns
     ///
-    /// A boolean indicating if the token is expired
-    async fn check_jwt_expired(&self) -> bool {
-        // Read Lock to check if JWT is valid
-        let lock = self.jwt.read().await;
-        let (ts, _jwt) = &*lock;
-        SystemTime::now()
-            .duration_since(*ts)
-            .map_or(true, |duration| duration.as_secs() > JWT_UPDATE_INTERVAL)
+    /// The Client with the limit applied
+    pub fn with_rate_limit(mut self, class: EndpointClass, limit: RateLimit) -> Self {
+        self.rate_limiter.set(class, limit);
+        self
     }
 
-    /// Refresh the current JWT token
-    /// Allows for a force update to bypass the check for expired token
+    /// Reject orders violating `guard` locally, before they are signed and sent
+    ///
+    /// Creations, batch creations and modifications are checked against the notional limit,
+    /// market orders at the best ask or bid they would take. Creations count towards the orders
+    /// per minute limit unless they fail to be placed. Violations fail the request with
+    /// `Error::OrderGuardViolation`. The count of recent orders is shared with clones of the
+    /// client created after this call.
     ///
     /// # Parameters
     ///
-    /// * `force_update` - A boolean indicating if the token should be updated regardless of expiration
+    /// * `guard` - The limits to enforce
     ///
-    /// # Errors
+    /// # Returns
     ///
-    /// If the token cannot be refreshed
-    pub async fn refresh_jwt(&self, force_update: bool) -> Result<()> {
-        // Write Lock to update JWT
-        let mut lock = self.jwt.write().await;
-
-        // Recheck if JWT is expired after acquiring write lock to prevent multiple updates at once with async calls
-        let is_jwt_expired = {
-            let (ts, _jwt) = &*lock;
-            SystemTime::now()
-                .duration_since(*ts)
-                .map_or(true, |duration| duration.as_secs() > JWT_UPDATE_INTERVAL)
-        };
-
-        // Update JWT if expired or forced update is requested
-        if is_jwt_expired || force_update {
-            let (l2_chain, signing_key, account) = self
-                .l2_chain_private_key_account
-                .as_ref()
-                .ok_or(Error::MissingPrivateKey)?;
-            let (timestamp, headers) = auth_headers(l2_chain, signing_key, account)?;
-            trace!("Auth Headers {headers:?}");
-            let token = self
-                .request::<&'static str, JWTToken>(
-                    Method::Post(""),
-                    "/v1/auth".into(),
-                    Some(headers),
-                )
-                .await
-                .map(|s| s.jwt_token)?;
-            *lock = (timestamp, token);
-        }
-        Ok(())
-    }
-
-    /// Submit onboarding information for the current client
-    #[cfg(feature = "onboarding")]
-    async fn submit_onboarding(
-        &self,
-        ethereum_account: &str,
-        request: OnboardingRequest,
-    ) -> Result<()> {
-        let (l2_chain, signing_key, account) = self
-            .l2_chain_private_key_account
-            .as_ref()
-            .ok_or(Error::MissingPrivateKey)?;
-        let headers = onboarding_headers(ethereum_account, l2_chain, signing_key, account)?;
-
-        match self
-            .request::<_, Value>(
-                Method::Post(request),
-                "/v1/onboarding".into(),
-                Some(headers),
-            )
-            .await
-        {
-            Ok(_) => Ok(()),
-            Err(Error::RestEmptyResponse) => Ok(()),
-            Err(e) => Err(e),
-        }
+    /// The Client with the guard applied
+    pub fn with_order_guard(mut self, guard: OrderGuard) -> Self {
+        self.order_guard = GuardState::new(guard);
+        self
     }
 
-    /// Get the current BBO for a market
+    /// Only allow withdrawals to L1 addresses in `whitelist`
+    ///
+    /// `withdraw_to_l1` fails with `Error::WithdrawalNotWhitelisted` before anything is signed
+    /// when the recipient is not in the address book, so an empty book blocks every withdrawal
+    /// to L1. Withdrawals from Paraclear to the account's own L2 balance are not affected.
     ///
     /// # Parameters
     ///
-    /// * `market_symbol` - A string representing the market symbol
+    /// * `whitelist` - The addresses withdrawals may be sent to
     ///
     /// # Returns
     ///
-    /// A BBO struct representing the best bid and offer for the market
-    ///
-    /// # Errors
-    ///
-    /// If the BBO cannot be retrieved
-    pub async fn bbo(&self, market_symbol: String) -> Result<BBO> {
-        self.request(
-            Method::Get::<()>(vec![]),
-            format!("/v1/bbo/{market_symbol}"),
-            None,
-        )
-        .await
+    /// The Client with the whitelist applied
+    pub fn with_withdrawal_whitelist(mut self, whitelist: AddressBook) -> Self {
+        self.withdrawal_whitelist = Some(whitelist);
+        self
     }
 
-    /// Create an order on the exchange
+    /// Ask `approver` before every withdrawal, deposit, margin update and cancel of all orders
+    ///
+    /// The operation is only signed and sent once the approver confirms it, and otherwise fails
+    /// with `Error::OperationNotApproved`, or with the approver's error if it could not decide.
     ///
     /// # Parameters
     ///
-    /// * `order_request` - An OrderRequest struct representing the order to be created
+    /// * `approver` - The approver consulted, e.g. one forwarding to an external approval service
     ///
     /// # Returns
     ///
-    /// An OrderUpdate struct representing the order that was created
-    ///
-    /// # Errors
-    ///
-    /// If the order cannot be created
-    pub async fn create_order(&self, order_request: OrderRequest) -> Result<OrderUpdate> {
-        let signature_timestamp_ms = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| Error::TimeError(e.to_string()))?
-            .as_millis();
-
-        let (l2_chain, signing_key, account) = self
-            .l2_chain_private_key_account
-            .as_ref()
-            .ok_or(Error::MissingPrivateKey)?;
-
-        let signature = sign_order(
-            &order_request,
-            signing_key,
-            signature_timestamp_ms,
-            *l2_chain,
-            *account,
-        )?;
-
-        let order = order_request.into_order([signature.r, signature.s], signature_timestamp_ms);
+    /// The Client with the approver applied
+    pub fn with_approver(mut self, approver: impl Approver) -> Self {
+        self.approver = Some(Arc::new(approver));
+        self
+    }
 
-        self.request_auth(Method::Post(order), "/v1/orders".into())
-            .await
+    /// Wait for the approver to confirm the operation built by `operation`, which is only built
+    /// when there is an approver
+    async fn approve(&self, operation: impl FnOnce() -> SensitiveOperation) -> Result<()> {
+        let Some(approver) = &self.approver else {
+            return Ok(());
+        };
+        let operation = operation();
+        if approver.approve(&operation).await? {
+            Ok(())
+        } else {
+            Err(Error::OperationNotApproved(format!("{operation:?}")))
+        }
     }
 
-    /// Create an order on the exchange
+    /// Cache the markets and their summaries, refetching them once older than `ttl`
+    ///
+    /// Same as `with_market_registry` with a new `MarketRegistry::with_ttl(ttl)`.
     ///
     /// # Parameters
     ///
-    /// * `order_request` - An OrderRequest struct representing the order to be created
+    /// * `ttl` - How long fetched markets and summaries are used before being refetched
     ///
     /// # Returns
     ///
-    /// An OrderUpdate struct representing the order that was created
-    ///
-    /// # Errors
-    ///
-    /// If the order cannot be created
-    pub async fn update_account_margin(
-        &self,
-        market: String,
-        account_margin_update: AccountMarginUpdate,
-    ) -> Result<AccountMarginUpdateResponse> {
-        self.request_auth(
-            Method::Post(account_margin_update),
-            format!("/v1/account/margin/{market}"),
-        )
-        .await
-    }
-
-    pub async fn modify_order(
-        &self,
-        modify_order_request: ModifyOrderRequest,
-    ) -> Result<OrderUpdate> {
-        let signature_timestamp_ms = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| Error::TimeError(e.to_string()))?
-            .as_millis();
-
-        let (l2_chain, signing_key, account) = self
-            .l2_chain_private_key_account
-            .as_ref()
-            .ok_or(Error::MissingPrivateKey)?;
-
-        let signature = sign_modify_order(
-            &modify_order_request,
-            signing_key,
-            signature_timestamp_ms,
-            *l2_chain,
-            *account,
-        )?;
-
-        let modify_order = modify_order_request
-            .into_modify_order([signature.r, signature.s], signature_timestamp_ms);
-
-        let path = format!("/v1/orders/{}", modify_order.id);
-        self.request_auth(Method::Put(modify_order), path).await
+    /// The Client with the cache applied
+    pub fn with_market_cache(self, ttl: std::time::Duration) -> Self {
+        self.with_market_registry(MarketRegistry::with_ttl(ttl))
     }
 
-    /// Cancel an order on the exchange by order ID
+    /// Look markets up in `registry`, refetching the markets and their summaries into it when
+    /// it is stale
+    ///
+    /// `cached_market`, `cached_market_summary` and `order_builder` then use the registry, which
+    /// is filled by the first lookup, as does `set_margin`. The registry is shared with clones of the client created after this call, and
+    /// may also be kept current by `MarketRegistry::poll`.
     ///
     /// # Parameters
     ///
-    /// * `order_id` - A string representing the order ID to be cancelled
+    /// * `registry` - The registry the markets are cached in
     ///
     /// # Returns
     ///
-    /// A Result indicating success or failure
-    ///
-    /// # Errors
-    ///
-    /// If the order cannot be cancelled
-    pub async fn cancel_order(&self, order_id: String) -> Result<()> {
-        match self
-            .request_auth::<(), ()>(Method::Delete, format!("/v1/orders/{order_id}"))
-            .await
-        {
-            Ok(_) => Ok(()),
-            Err(Error::RestEmptyResponse) => Ok(()),
-            Err(e) => Err(e),
-        }
+    /// The Client with the registry applied
+    pub fn with_market_registry(mut self, registry: MarketRegistry) -> Self {
+        self.market_registry = Some(registry);
+        self
     }
 
-    /// Cancel an order on the exchange by client ID
+    /// The market registry installed with `with_market_cache` or `with_market_registry`, if any
+    pub fn market_registry(&self) -> Option<&MarketRegistry> {
+        self.market_registry.as_ref()
+    }
+
+    /// Deliver an OrderEvent to `sink` for every order creation, modification and cancel sent
+    ///
+    /// Events carry the terms of the request but never its signature, so they can be kept for
+    /// compliance or debugging. They are delivered as requests are sent, whatever their outcome,
+    /// including those a WebsocketManager sends on behalf of the client. The sink is called on
+    /// the sending task and should not block.
     ///
     /// # Parameters
     ///
-    /// * `client_order_id` - A string representing the client order ID to be cancelled
+    /// * `sink` - Called with each outbound order request
     ///
     /// # Returns
     ///
-    /// A Result indicating success or failure
-    ///
-    /// # Errors
-    ///
-    /// If the order cannot be cancelled
-    pub async fn cancel_order_by_client_id(&self, client_order_id: String) -> Result<()> {
-        match self
-            .request_auth::<(), ()>(
-                Method::Delete,
-                format!("/v1/orders/by_client_id/{client_order_id}"),
-            )
-            .await
-        {
-            Ok(_) => Ok(()),
-            Err(Error::RestEmptyResponse) => Ok(()),
-            Err(e) => Err(e),
-        }
+    /// The Client delivering events to the sink
+    pub fn with_order_log(mut self, sink: impl Fn(&OrderEvent) + Send + Sync + 'static) -> Self {
+        self.order_log = OrderLog::new(Arc::new(sink));
+        self
     }
 
-    /// Cancel all orders on the exchange
+    /// Deliver an outbound order request to the order sink, if any
+    pub(crate) fn log_order(&self, event: impl FnOnce() -> OrderEvent) {
+        self.order_log.record(event);
+    }
+
+    /// Get the Paradex system configuration
     ///
     /// # Returns
     ///
-    /// A vector of strings representing the order IDs that were cancelled
+    /// A SystemConfig struct representing the system configuration
     ///
     /// # Errors
     ///
-    /// If the orders cannot be cancelled
-    pub async fn cancel_all_orders(&self) -> Result<Vec<String>> {
-        self.request_auth(Method::Delete::<()>, "/v1/orders".into())
+    /// If the system configuration cannot be retrieved
+    pub async fn system_config(&self) -> Result<SystemConfig> {
+        self.request(Method::Get(()), "/v1/system/config".into(), None)
             .await
     }
 
-    /// Cancel all orders on the exchange for a specific market
+    /// Get the bridge to deposit a token through
+    ///
+    /// Deposits are made on L1 to the token's `l1_bridge_address`, and credited on Paradex in
+    /// the token's `l2_token_address`.
     ///
     /// # Parameters
     ///
-    /// * `market` - A string representing the market symbol to cancel orders for
+    /// * `token` - The symbol of the token to deposit, e.g. `USDC`
     ///
     /// # Returns
     ///
-    /// A vector of strings representing the order IDs that were cancelled
+    /// The BridgedToken from the system configuration
     ///
     /// # Errors
     ///
-    /// If the orders cannot be cancelled
-    pub async fn cancel_all_orders_for_market(
-        &self,
-        market: String,
-    ) -> Result<CancelByMarketResponse> {
-        self.request_auth(Method::Delete::<()>, format!("/v1/orders/?market={market}"))
-            .await
+    /// * `Error::TokenNotBridged` if the token is not bridged to Paradex
+    /// * If the system configuration cannot be retrieved
+    pub async fn deposit_bridge(&self, token: &str) -> Result<BridgedToken> {
+        self.system_config()
+            .await?
+            .bridged_token(token)
+            .cloned()
+            .ok_or_else(|| Error::TokenNotBridged(token.to_string()))
     }
 
-    /// Get all open orders
-    ///
-    /// # Returns
+    /// Deposit a token from L1 into the account through its StarkGate bridge
     ///
-    /// An OrderUpdates struct representing the open orders
+    /// Approves the token's `l1_bridge_address` if needed, then deposits into Paraclear for
+    /// this client's account, signing both L1 transactions with `eth_signer`. The funds are
+    /// credited once the bridge message is consumed on L2. Requires the `bridge` feature.
     ///
-    /// # Errors
+    /// # Parameters
     ///
-    /// If open orders cannot be retrieved
-    pub async fn open_orders(&self) -> Result<OrderUpdates> {
-        self.request_auth(Method::Get::<()>(vec![]), "/v1/orders".into())
-            .await
-    }
-
-    /// Get the Account Information
+    /// * `token` - The symbol of the token to deposit, e.g. `USDC`
+    /// * `amount` - The amount to deposit
+    /// * `eth_signer` - The Ethereum wallet holding the tokens
+    /// * `l1_rpc_url` - The JSON-RPC endpoint of an Ethereum node on `SystemConfig::l1_chain_id`
     ///
     /// # Returns
     ///
-    /// An AccountInformation struct representing the account information
+    /// The hash of the L1 deposit transaction
     ///
     /// # Errors
     ///
-    /// If the account information cannot be retrieved
-    pub async fn account_information(&self) -> Result<AccountInformation> {
-        self.request_auth(Method::Get::<()>(vec![]), "/v1/account".into())
-            .await
+    /// * `Error::MissingPrivateKey` if the client has no account
+    /// * `Error::OperationNotApproved` if the client's approver rejects the deposit
+    /// * `Error::TokenNotBridged` if the token is not bridged to Paradex
+    /// * `Error::EthereumError` if the node rejects a transaction or the approval reverts
+    /// * If the system configuration cannot be retrieved
+    #[cfg(feature = "bridge")]
+    pub async fn deposit(
+        &self,
+        token: &str,
+        amount: rust_decimal::Decimal,
+        eth_signer: &PrivateKeySigner,
+        l1_rpc_url: &str,
+    ) -> Result<alloy_primitives::B256> {
+        let account = self.account().ok_or(Error::MissingPrivateKey)?;
+        self.approve(|| SensitiveOperation::Deposit {
+            token: token.to_string(),
+            amount,
+            l1_sender: eth_signer.address().to_string(),
+        })
+        .await?;
+        let config = self.system_config().await?;
+        let bridged = config
+            .bridged_token(token)
+            .ok_or_else(|| Error::TokenNotBridged(token.to_string()))?;
+        crate::bridge::deposit(
+            &crate::bridge::EthRpc::new(self.client.clone(), l1_rpc_url),
+            eth_signer,
+            bridged,
+            &config.paraclear_address,
+            account,
+            amount,
+        )
+        .await
     }
 
-    /// Get the Account margin configuration for a specific market
+    /// Withdraw a token from Paraclear to the account's L2 balance
+    ///
+    /// This is the transfer primitive of withdrawals: the funds stay on L2, in the account's
+    /// wallet. Use `withdraw_to_l1` to withdraw to an Ethereum address, which bridges the funds
+    /// on to L1 in the same transaction. Sends a `withdraw` invoke transaction to
+    /// `paraclear_address`, signed with the L2 key and submitted to
+    /// `starknet_fullnode_rpc_url`.
     ///
     /// # Parameters
     ///
-    /// * `market` - A string representing the market symbol
+    /// * `token` - The symbol of the token to withdraw, e.g. `USDC`
+    /// * `amount` - The amount to withdraw
+    /// * `fee_bounds` - The resource bounds to send the transaction with, or None to estimate them
     ///
     /// # Returns
     ///
-    /// An AccountMarginConfigurations struct representing the account margin configuration
+    /// The hash of the submitted transaction
     ///
     /// # Errors
     ///
-    /// If the account information cannot be retrieved
-    pub async fn account_margin_configuration(
+    /// * `Error::MissingPrivateKey` if the client has no private key
+    /// * `Error::OperationNotApproved` if the client's approver rejects the withdrawal
+    /// * `Error::TokenNotBridged` if the token is not bridged to Paradex
+    /// * `Error::StarknetError` if the full node rejects the transaction
+    /// * If the system configuration cannot be retrieved
+    pub async fn withdraw(
         &self,
-        market: String,
-    ) -> Result<AccountMarginConfigurations> {
-        let params = vec![("market".to_string(), market)];
-        self.request_auth(Method::Get::<()>(params), "/v1/account/margin".into())
+        token: &str,
+        amount: rust_decimal::Decimal,
+        fee_bounds: Option<FeeBounds>,
+    ) -> Result<Felt> {
+        self.submit_withdrawal(token, amount, None, fee_bounds)
             .await
     }
 
-    /// Get the balances for the account
+    /// Withdraw a token from Paraclear and bridge it to an L1 address
+    ///
+    /// Sends the `withdraw` of `withdraw` along with an `initiate_token_withdraw` of the token's
+    /// `l2_bridge_address` in one transaction. The funds can be claimed on L1 once the bridge
+    /// message is proven there. If the client has a withdrawal whitelist, the recipient must be
+    /// in it.
+    ///
+    /// # Parameters
+    ///
+    /// * `token` - The symbol of the token to withdraw, e.g. `USDC`
+    /// * `amount` - The amount to withdraw
+    /// * `l1_recipient` - The Ethereum address receiving the funds
+    /// * `fee_bounds` - The resource bounds to send the transaction with, or None to estimate them
     ///
     /// # Returns
     ///
-    /// A Balances struct representing the account balances
+    /// The hash of the submitted transaction
     ///
     /// # Errors
     ///
-    /// If the balances cannot be retrieved
-    pub async fn balance(&self) -> Result<Balances> {
-        self.request_auth(Method::Get::<()>(vec![]), "/v1/balance".into())
+    /// * `Error::WithdrawalNotWhitelisted` if the recipient is not in the withdrawal whitelist
+    /// * `Error::MissingPrivateKey` if the client has no private key
+    /// * `Error::OperationNotApproved` if the client's approver rejects the withdrawal
+    /// * `Error::TokenNotBridged` if the token is not bridged to Paradex
+    /// * `Error::StarknetError` if the full node rejects the transaction
+    /// * If the system configuration cannot be retrieved
+    pub async fn withdraw_to_l1(
+        &self,
+        token: &str,
+        amount: rust_decimal::Decimal,
+        l1_recipient: &str,
+        fee_bounds: Option<FeeBounds>,
+    ) -> Result<Felt> {
+        if let Some(whitelist) = &self.withdrawal_whitelist {
+            whitelist.check(l1_recipient)?;
+        }
+        self.submit_withdrawal(token, amount, Some(l1_recipient), fee_bounds)
             .await
     }
 
-    /// Get the positions for the account
+    /// Withdraw from Paraclear, bridging the funds on to `l1_recipient` if given
+    async fn submit_withdrawal(
+        &self,
+        token: &str,
+        amount: rust_decimal::Decimal,
+        l1_recipient: Option<&str>,
+        fee_bounds: Option<FeeBounds>,
+    ) -> Result<Felt> {
+        let (chain_id, signer, account) = self
+            .l2_chain_private_key_account
+            .clone()
+            .ok_or(Error::MissingPrivateKey)?;
+        self.approve(|| SensitiveOperation::Withdrawal {
+            token: token.to_string(),
+            amount,
+            l1_recipient: l1_recipient.map(str::to_string),
+        })
+        .await?;
+        let config = self.system_config().await?;
+        let bridged = config
+            .bridged_token(token)
+            .ok_or_else(|| Error::TokenNotBridged(token.to_string()))?;
+        let felt = |hex: &str| {
+            Felt::from_hex(hex).map_err(|e| Error::TypeConversionError(format!("{hex}: {e}")))
+        };
+        let mut calls = vec![Call::new(
+            felt(&config.paraclear_address)?,
+            "withdraw",
+            vec![
+                felt(&bridged.l2_token_address)?,
+                scale_amount(amount, config.paraclear_decimals)?,
+            ],
+        )?];
+        if let Some(l1_recipient) = l1_recipient {
+            // The amount is a u256 of the token's own decimals, low and high 128 bits
+            calls.push(Call::new(
+                felt(&bridged.l2_bridge_address)?,
+                "initiate_token_withdraw",
+                vec![
+                    felt(&bridged.l1_token_address)?,
+                    felt(l1_recipient)?,
+                    scale_amount(amount, bridged.decimals)?,
+                    Felt::ZERO,
+                ],
+            )?);
+        }
+        let rpc = StarknetRpc::new(self.client.clone(), &config.starknet_fullnode_rpc_url);
+        let nonce = rpc.nonce(account).await?;
+        let mut transaction = InvokeTransaction::new(account, &calls, nonce, chain_id);
+        transaction.fee_bounds = match fee_bounds {
+            Some(fee_bounds) => fee_bounds,
+            None => rpc.estimate_fee_bounds(&transaction).await?,
+        };
+        let signature = signer.sign(transaction.hash()).await?;
+        rpc.add_invoke_transaction(&transaction, &signature).await
+    }
+
+    /// Get the Paradex system state
     ///
     /// # Returns
     ///
-    /// A Positions struct representing the account positions
+    /// A SystemState struct representing the system state
     ///
     /// # Errors
     ///
-    /// If the positions cannot be retrieved
-    pub async fn positions(&self) -> Result<Positions> {
-        self.request_auth(Method::Get::<()>(vec![]), "/v1/positions".into())
-            .await
+    /// If the system state cannot be retrieved
+    pub async fn system_state(&self) -> Result<SystemState> {
+        let state: SystemState = self
+            .request(Method::Get(()), "/v1/system/state".into(), None)
+            .await?;
+        Ok(state)
     }
 
-    pub async fn fills(
-        &self,
-        market: Option<String>,
-        start: Option<chrono::DateTime<chrono::Utc>>,
-        end: Option<chrono::DateTime<chrono::Utc>>,
-    ) -> Result<Vec<Fill>> {
-        let filters = market.map(|market| vec![("market".to_string(), market)]);
+    /// The latest system status recorded by `poll_system_state`, shared with clones of the
+    /// client
+    pub fn system_status(&self) -> Option<SystemStatus> {
+        self.system_status.get()
+    }
 
-        self.request_cursor("/v1/fills".to_string(), filters, start, end, true)
-            .await
+    /// Watch the system status for transitions, e.g. to pull quotes ahead of maintenance
+    ///
+    /// The receiver holds the latest status recorded by `poll_system_state` on this client or
+    /// its clones, and is notified only when it changes.
+    pub fn watch_system_status(&self) -> tokio::sync::watch::Receiver<Option<SystemStatus>> {
+        self.system_status.watch()
     }
 
-    pub async fn transfers(
+    /// Fetch the system state every `interval` in the background and gate order flow on it
+    ///
+    /// While the poller runs and the last polled status is `CancelOnly` or `Maintenance`, order
+    /// creations and modifications on this client and its clones fail locally with
+    /// `Error::OrderFlowSuspended` without being sent. Cancels are always sent. A polled status
+    /// goes stale after three intervals without a successful fetch, after which orders are sent
+    /// regardless. Failed fetches are logged.
+    ///
+    /// Without a running poller order flow is never gated, leaving the venue to reject orders.
+    ///
+    /// # Parameters
+    ///
+    /// * `interval` - Time between fetches, the first of which is immediate
+    ///
+    /// # Returns
+    ///
+    /// A SystemStatusPoller which stops polling and gating when stopped or dropped
+    pub fn poll_system_state(&self, interval: Duration) -> SystemStatusPoller {
+        let client = self.clone();
+        let ttl = interval * SYSTEM_STATUS_TTL_POLLS;
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                match client.system_state().await {
+                    Ok(state) => {
+                        let previous = client.system_status.record(state.status.clone(), ttl);
+                        if previous.as_ref() != Some(&state.status) {
+                            info!(
+                                "System status changed from {previous:?} to {:?}",
+                                state.status
+                            );
+                        }
+                    }
+                    Err(e) => warn!("Could not poll the system state: {e}"),
+                }
+            }
+        });
+        SystemStatusPoller::new(task, self.system_status.clone())
+    }
+
+    /// Get the Paradex system time
+    ///
+    /// # Returns
+    ///
+    /// A SystemTimeResponse struct representing the system time
+    ///
+    /// # Errors
+    ///
+    /// If the system time cannot be retrieved
+    pub async fn system_time(&self) -> Result<SystemTimeResponse> {
+        self.request(Method::Get(()), "/v1/system/time".into(), None)
+            .await
+    }
+
+    /// Get the list of markets on the exchange
+    ///
+    /// # Returns
+    ///
+    /// A vector of MarketSummaryStatic structs representing the markets
+    ///
+    /// # Errors
+    ///
+    /// If the markets cannot be retrieved
+    pub async fn markets(&self) -> Result<Vec<MarketSummaryStatic>> {
+        self.request(Method::Get(()), "/v1/markets".into(), None)
+            .await
+            .map(
+                |result_container: ResultsContainer<Vec<MarketSummaryStatic>>| {
+                    result_container.results
+                },
+            )
+    }
+
+    /// Get the summaries of every market on the exchange
+    ///
+    /// # Returns
+    ///
+    /// A vector of MarketSummary structs with the prices, volume and funding of each market
+    ///
+    /// # Errors
+    ///
+    /// If the market summaries cannot be retrieved
+    pub async fn markets_summary(&self) -> Result<Vec<MarketSummary>> {
+        let params = [("market", "ALL")];
+        self.request(Method::Get(params), "/v1/markets/summary".into(), None)
+            .await
+            .map(|result_container: ResultsContainer<Vec<MarketSummary>>| result_container.results)
+    }
+
+    /// Get the configuration of a market from the market registry, refreshing it first if it
+    /// is stale
+    ///
+    /// # Parameters
+    ///
+    /// * `symbol` - The market symbol
+    ///
+    /// # Returns
+    ///
+    /// The MarketSummaryStatic of the market, with its tick size, size increment and minimum
+    /// notional
+    ///
+    /// # Errors
+    ///
+    /// * `Error::MissingMarketCache` if the client has no market registry
+    /// * `Error::UnknownMarket` if there is no market `symbol`
+    /// * If the markets cannot be retrieved
+    pub async fn cached_market(&self, symbol: &str) -> Result<MarketSummaryStatic> {
+        self.fresh_market_registry()
+            .await?
+            .get(symbol)
+            .ok_or_else(|| Error::UnknownMarket(symbol.to_string()))
+    }
+
+    /// Get the summary of a market from the market registry, refreshing it first if it is
+    /// stale
+    ///
+    /// # Parameters
+    ///
+    /// * `symbol` - The market symbol
+    ///
+    /// # Returns
+    ///
+    /// The MarketSummary of the market as of the last refresh
+    ///
+    /// # Errors
+    ///
+    /// * `Error::MissingMarketCache` if the client has no market registry
+    /// * `Error::UnknownMarket` if there is no summary of market `symbol`
+    /// * If the market summaries cannot be retrieved
+    pub async fn cached_market_summary(&self, symbol: &str) -> Result<MarketSummary> {
+        self.fresh_market_registry()
+            .await?
+            .summary(symbol)
+            .ok_or_else(|| Error::UnknownMarket(symbol.to_string()))
+    }
+
+    /// Start building an order in a market of the market registry, refreshing it first if it
+    /// is stale
+    ///
+    /// # Parameters
+    ///
+    /// * `symbol` - The market symbol
+    /// * `side` - The side of the order
+    /// * `order_type` - The type of the order
+    /// * `size` - The size of the order
+    ///
+    /// # Returns
+    ///
+    /// An OrderBuilder validating the order against the cached market
+    ///
+    /// # Errors
+    ///
+    /// * `Error::MissingMarketCache` if the client has no market registry
+    /// * `Error::UnknownMarket` if there is no market `symbol`
+    /// * If the markets cannot be retrieved
+    pub async fn order_builder(
+        &self,
+        symbol: &str,
+        side: Side,
+        order_type: OrderType,
+        size: rust_decimal::Decimal,
+    ) -> Result<OrderBuilder<'static>> {
+        let market = self.cached_market(symbol).await?;
+        Ok(OrderBuilder::owned(market, side, order_type, size))
+    }
+
+    /// Refetch the markets and their summaries into the market registry, whether it is stale
+    /// or not
+    ///
+    /// # Errors
+    ///
+    /// * `Error::MissingMarketCache` if the client has no market registry
+    /// * If the markets or their summaries cannot be retrieved
+    pub async fn refresh_market_cache(&self) -> Result<()> {
+        self.required_market_registry()?
+            .refresh_with(|| try_join(self.markets(), self.markets_summary()))
+            .await
+    }
+
+    /// The market registry, refreshed if it is stale
+    async fn fresh_market_registry(&self) -> Result<&MarketRegistry> {
+        let registry = self.required_market_registry()?;
+        registry
+            .refresh_if_stale(|| try_join(self.markets(), self.markets_summary()))
+            .await?;
+        Ok(registry)
+    }
+
+    fn required_market_registry(&self) -> Result<&MarketRegistry> {
+        self.market_registry
+            .as_ref()
+            .ok_or(Error::MissingMarketCache)
+    }
+
+    /// Get the list of Klines for a symbol
+    ///
+    /// # Returns
+    ///
+    /// A vector of Kline structs representing the klines
+    ///
+    /// # Errors
+    ///
+    /// If the klines cannot be retrieved
+    pub async fn klines(&self, params: KlineParams) -> Result<Vec<Kline>> {
+        self.request(Method::Get(params), "/v1/markets/klines".into(), None)
+            .await
+            .map(|result_container: ResultsContainer<Vec<Kline>>| result_container.results)
+    }
+
+    /// Get snapshot of the orderbook for the given market
+    ///
+    /// # Returns
+    ///
+    /// An OrderBookResponse struct representing the orderbook
+    ///
+    /// # Errors
+    ///
+    /// If the orderbook cannot be retrieved
+    pub async fn orderbook(
+        &self,
+        market: String,
+        params: OrderBookParams,
+    ) -> Result<OrderBookResponse> {
+        self.request(
+            Method::Get(params),
+            format!("/v1/orderbook/{}", market),
+            None,
+        )
+        .await
+    }
+
+    /// Returns orderbook including RPI
+    ///
+    /// # Returns
+    ///
+    /// An OrderBookInteractiveResponse struct representing the orderbook along with the best
+    /// levels excluding and including RPI orders
+    ///
+    /// # Errors
+    ///
+    /// If the orderbook cannot be retrieved
+    pub async fn orderbook_interactive(
+        &self,
+        market: String,
+        params: OrderBookParams,
+    ) -> Result<OrderBookInteractiveResponse> {
+        self.request(
+            Method::Get(params),
+            format!("/v1/orderbook/{market}/interactive"),
+            None,
+        )
+        .await
+    }
+
+    /// Check if the client has a private key set allowing for private API calls
+    ///
+    /// # Returns
+    ///
+    /// A boolean indicating if the client has a private key set
+    pub(crate) fn is_private(&self) -> bool {
+        self.l2_chain_private_key_account.is_some() || self.api_token.is_some()
+    }
+
+    /// Get the current JWT token
+    /// If the token is expired, it will be refreshed
+    /// Clients created with `with_api_token` return their API token
+    ///
+    /// # Returns
+    ///
+    /// A string representing the current JWT token
+    ///
+    /// # Errors
+    ///
+    /// If the token cannot be refreshed
+    pub async fn jwt(&self) -> Result<String> {
+        if let Some(api_token) = &self.api_token {
+            return Ok(api_token.read().await.clone());
+        }
+
+        // Check if Invalid
+        if self.check_jwt_expired().await {
+            self.refresh_jwt(false).await?;
+        }
+
+        // Return JWT
+        let lock = self.jwt.read().await;
+        let (_ts, jwt) = &*lock;
+        Ok(jwt.clone())
+    }
+
+    /// Check if the current JWT token is expired
+    ///
+    /// # Returns
+    ///
+    /// A boolean indicating if the token is expired
+    async fn check_jwt_expired(&self) -> bool {
+        // Read Lock to check if JWT is valid
+        let lock = self.jwt.read().await;
+        let (ts, _jwt) = &*lock;
+        SystemTime::now()
+            .duration_since(*ts)
+            .map_or(true, |duration| duration.as_secs() > JWT_UPDATE_INTERVAL)
+    }
+
+    /// Refresh the current JWT token
+    /// Allows for a force update to bypass the check for expired token
+    ///
+    /// # Parameters
+    ///
+    /// * `force_update` - A boolean indicating if the token should be updated regardless of expiration
+    ///
+    /// # Errors
+    ///
+    /// If the token cannot be refreshed
+    pub async fn refresh_jwt(&self, force_update: bool) -> Result<()> {
+        // API tokens are rotated by the caller rather than refreshed
+        if self.api_token.is_some() {
+            return Ok(());
+        }
+
+        // Write Lock to update JWT
+        let mut lock = self.jwt.write().await;
+
+        // Recheck if JWT is expired after acquiring write lock to prevent multiple updates at once with async calls
+        let is_jwt_expired = {
+            let (ts, _jwt) = &*lock;
+            SystemTime::now()
+                .duration_since(*ts)
+                .map_or(true, |duration| duration.as_secs() > JWT_UPDATE_INTERVAL)
+        };
+
+        // Update JWT if expired or forced update is requested
+        if is_jwt_expired || force_update {
+            let (l2_chain, signer, account) = self
+                .l2_chain_private_key_account
+                .as_ref()
+                .ok_or(Error::MissingPrivateKey)?;
+            let (timestamp, headers) =
+                auth_headers_with_signer(l2_chain, signer.as_ref(), account).await?;
+            trace!("Auth Headers {headers:?}");
+            let token = self
+                .request::<&'static str, JWTToken>(
+                    Method::Post(""),
+                    "/v1/auth".into(),
+                    Some(headers),
+                )
+                .await
+                .map(|s| s.jwt_token)?;
+            *lock = (timestamp, token);
+        }
+        Ok(())
+    }
+
+    /// Submit onboarding information for the current client
+    #[cfg(feature = "onboarding")]
+    async fn submit_onboarding(
+        &self,
+        ethereum_account: &str,
+        request: OnboardingRequest,
+    ) -> Result<()> {
+        let (l2_chain, signer, account) = self
+            .l2_chain_private_key_account
+            .as_ref()
+            .ok_or(Error::MissingPrivateKey)?;
+        let headers =
+            onboarding_headers_with_signer(ethereum_account, l2_chain, signer.as_ref(), account)
+                .await?;
+
+        match self
+            .request::<_, Value>(
+                Method::Post(request),
+                "/v1/onboarding".into(),
+                Some(headers),
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(Error::RestEmptyResponse) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get the current BBO for a market
+    ///
+    /// # Parameters
+    ///
+    /// * `market_symbol` - A string representing the market symbol
+    ///
+    /// # Returns
+    ///
+    /// A BBO struct representing the best bid and offer for the market
+    ///
+    /// # Errors
+    ///
+    /// If the BBO cannot be retrieved
+    pub async fn bbo(&self, market_symbol: String) -> Result<BBO> {
+        self.request(Method::Get(()), format!("/v1/bbo/{market_symbol}"), None)
+            .await
+    }
+
+    /// Get the best bid and offer for a market with exact decimal prices and sizes
+    ///
+    /// # Parameters
+    ///
+    /// * `market_symbol` - A string representing the market symbol
+    ///
+    /// # Returns
+    ///
+    /// An exact::BBO struct representing the best bid and offer for the market
+    ///
+    /// # Errors
+    ///
+    /// If the BBO cannot be retrieved
+    pub async fn bbo_exact(&self, market_symbol: &str) -> Result<exact::BBO> {
+        self.request(Method::Get(()), format!("/v1/bbo/{market_symbol}"), None)
+            .await
+    }
+
+    /// Get the current BBOs of many markets with concurrent requests
+    ///
+    /// Paradex only serves the BBO of one market per request. Requests are paced by the public
+    /// rate limit of the client, see `with_rate_limit`.
+    ///
+    /// # Parameters
+    ///
+    /// * `market_symbols` - The markets to get the BBO of
+    /// * `concurrency` - How many requests are in flight at a time, at least one
+    ///
+    /// # Returns
+    ///
+    /// The BBOs in the order of `market_symbols`
+    ///
+    /// # Errors
+    ///
+    /// If the BBO of any of the markets cannot be retrieved
+    pub async fn bbos<I>(&self, market_symbols: I, concurrency: usize) -> Result<Vec<BBO>>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        stream::iter(market_symbols)
+            .map(|market_symbol| self.bbo(market_symbol.into()))
+            .buffered(concurrency.max(1))
+            .try_collect()
+            .await
+    }
+
+    /// Create an order on the exchange
+    ///
+    /// # Parameters
+    ///
+    /// * `order_request` - An OrderRequest struct representing the order to be created
+    ///
+    /// # Returns
+    ///
+    /// An OrderUpdate struct representing the order that was created
+    ///
+    /// # Errors
+    ///
+    /// If the order cannot be created
+    pub async fn create_order(&self, order_request: OrderRequest) -> Result<OrderUpdate> {
+        let order = self.prepare_order(order_request).await?;
+        self.log_order(|| OrderEvent::create(&order, OrderTransport::Rest));
+        let headers = self
+            .auth_header()
+            .await
+            .inspect_err(|_| self.release_orders(1))?;
+        self.request(Method::Post(order), "/v1/orders".into(), Some(headers))
+            .await
+            .inspect_err(|e| self.release_rejected_orders(e, 1))
+    }
+
+    /// Check an order against the system status and the order guard, then sign it
+    ///
+    /// The order is counted by the order guard, see `release_rejected_orders` if it then fails
+    /// to be placed.
+    async fn prepare_order(&self, order_request: OrderRequest) -> Result<Order> {
+        self.system_status.check_order_flow()?;
+        self.check_order_notional(&order_request).await?;
+        self.order_guard.record_orders(1)?;
+        self.sign_order_request(order_request)
+            .await
+            .inspect_err(|_| self.release_orders(1))
+    }
+
+    /// Uncount orders counted by the order guard which failed to be placed
+    fn release_orders(&self, orders: usize) {
+        self.order_guard.release_orders(orders);
+    }
+
+    /// Uncount orders whose request failed with `error` if the venue rejected them, keeping
+    /// orders of unknown fate counted
+    fn release_rejected_orders(&self, error: &Error, orders: usize) {
+        if is_rejection(error) {
+            self.release_orders(orders);
+        }
+    }
+
+    /// Create up to 10 orders on the exchange in a single request
+    ///
+    /// Orders are accepted or rejected individually, so a rejected order does not fail the
+    /// request.
+    ///
+    /// # Parameters
+    ///
+    /// * `order_requests` - The orders to be created
+    ///
+    /// # Returns
+    ///
+    /// A BatchOrdersResponse with the created orders and the reasons others were rejected
+    ///
+    /// # Errors
+    ///
+    /// If an order cannot be signed or the request fails as a whole
+    pub async fn create_orders(
+        &self,
+        order_requests: Vec<OrderRequest>,
+    ) -> Result<BatchOrdersResponse> {
+        self.system_status.check_order_flow()?;
+        for order_request in &order_requests {
+            self.check_order_notional(order_request).await?;
+        }
+        let count = order_requests.len();
+        self.order_guard.record_orders(count)?;
+        let orders: Vec<Order> = try_join_all(
+            order_requests
+                .into_iter()
+                .map(|order_request| self.sign_order_request(order_request)),
+        )
+        .await
+        .inspect_err(|_| self.release_orders(count))?;
+        for order in &orders {
+            self.log_order(|| OrderEvent::create(order, OrderTransport::Rest));
+        }
+        let headers = self
+            .auth_header()
+            .await
+            .inspect_err(|_| self.release_orders(count))?;
+        let response: BatchOrdersResponse = self
+            .request(
+                Method::Post(orders),
+                "/v1/orders/batch".into(),
+                Some(headers),
+            )
+            .await
+            .inspect_err(|e| self.release_rejected_orders(e, count))?;
+        self.release_orders(response.errors.len());
+        Ok(response)
+    }
+
+    /// Check an order against the notional limit of the order guard
+    ///
+    /// Orders without a price or trigger price are priced at the best ask for buys and the
+    /// best bid for sells, which takes a BBO request while a notional limit is set. The market
+    /// registry's summaries are not used as they may be as old as its TTL.
+    async fn check_order_notional(&self, order_request: &OrderRequest) -> Result<()> {
+        if !self.order_guard.limits_notional() {
+            return Ok(());
+        }
+        let price = match order_request.price.or(order_request.trigger_price) {
+            Some(price) => Some(price),
+            None => {
+                let bbo = self.bbo_exact(&order_request.market).await?;
+                let touch = match order_request.side {
+                    Side::BUY => bbo.ask,
+                    Side::SELL => bbo.bid,
+                };
+                (touch > rust_decimal::Decimal::ZERO).then_some(touch)
+            }
+        };
+        self.order_guard
+            .check_notional(&order_request.market, price, order_request.size)
+    }
+
+    /// Sign an order request with the client's key
+    async fn sign_order_request(&self, order_request: OrderRequest) -> Result<Order> {
+        let signature_timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| Error::TimeError(e.to_string()))?
+            .as_millis();
+
+        let (l2_chain, signer, account) = self
+            .l2_chain_private_key_account
+            .as_ref()
+            .ok_or(Error::MissingPrivateKey)?;
+
+        let (signature, signature_timestamp_ms) = match self.signature_bucket_ms {
+            Some(bucket_ms) => {
+                sign_order_bucketed_with_signer(
+                    &order_request,
+                    signer.as_ref(),
+                    signature_timestamp_ms,
+                    bucket_ms,
+                    *l2_chain,
+                    *account,
+                )
+                .await?
+            }
+            None => {
+                let signature = sign_order_with_signer(
+                    &order_request,
+                    signer.as_ref(),
+                    signature_timestamp_ms,
+                    *l2_chain,
+                    *account,
+                )
+                .await?;
+                ([signature.r, signature.s], signature_timestamp_ms)
+            }
+        };
+
+        Ok(order_request.into_order(signature, signature_timestamp_ms))
+    }
+
+    /// Update the leverage and margin type of a market
+    ///
+    /// # Parameters
+    ///
+    /// * `market` - The market symbol
+    /// * `account_margin_update` - The new leverage and margin type
+    ///
+    /// # Returns
+    ///
+    /// An AccountMarginUpdateResponse with the configuration applied
+    ///
+    /// # Errors
+    ///
+    /// * `Error::OperationNotApproved` if the client's approver rejects the update
+    /// * If the margin configuration cannot be updated
+    pub async fn update_account_margin(
+        &self,
+        market: String,
+        account_margin_update: AccountMarginUpdate,
+    ) -> Result<AccountMarginUpdateResponse> {
+        self.approve(|| SensitiveOperation::MarginUpdate {
+            market: market.clone(),
+            leverage: account_margin_update.leverage,
+            margin_type: account_margin_update.margin_type.clone(),
+        })
+        .await?;
+        self.request_auth(
+            Method::Post(account_margin_update),
+            format!("/v1/account/margin/{market}"),
+        )
+        .await
+    }
+
+    /// Set the leverage and margin type of a market, checking them locally first
+    ///
+    /// The leverage must be at least 1 and not exceed the maximum leverage of the market, and
+    /// isolated margin is only accepted for perpetual futures. The market is looked up in the
+    /// market registry if the client has one, see `with_market_registry`, and is otherwise
+    /// fetched. The update is then sent with `update_account_margin`.
+    ///
+    /// # Parameters
+    ///
+    /// * `market` - The market symbol
+    /// * `leverage` - The new leverage
+    /// * `margin_type` - Whether the market is cross or isolated margined
+    ///
+    /// # Returns
+    ///
+    /// An AccountMarginUpdateResponse with the configuration applied
+    ///
+    /// # Errors
+    ///
+    /// * `Error::UnknownMarket` if there is no market `market`
+    /// * `Error::InvalidMarginUpdate` if the update violates the constraints of the market,
+    ///   before anything is sent
+    /// * `Error::OperationNotApproved` if the client's approver rejects the update
+    /// * `Error::MarginUpdateMismatch` if the exchange applied a different configuration,
+    ///   which is then in effect
+    /// * If the market or the margin configuration cannot be retrieved or updated
+    pub async fn set_margin(
+        &self,
+        market: &str,
+        leverage: u64,
+        margin_type: MarginType,
+    ) -> Result<AccountMarginUpdateResponse> {
+        let config = match &self.market_registry {
+            Some(_) => self.cached_market(market).await?,
+            None => self
+                .markets()
+                .await?
+                .into_iter()
+                .find(|config| config.symbol == market)
+                .ok_or_else(|| Error::UnknownMarket(market.to_string()))?,
+        };
+        check_margin_update(market, &config, leverage, margin_type)?;
+        let response = self
+            .update_account_margin(
+                market.to_string(),
+                AccountMarginUpdate {
+                    leverage,
+                    margin_type: margin_type.to_string(),
+                },
+            )
+            .await?;
+        if response.market != market
+            || response.leverage != leverage
+            || !response
+                .margin_type
+                .eq_ignore_ascii_case(margin_type.as_str())
+        {
+            return Err(Error::MarginUpdateMismatch {
+                market: market.to_string(),
+                leverage,
+                margin_type: margin_type.to_string(),
+                applied: Box::new(response),
+            });
+        }
+        Ok(response)
+    }
+
+    /// Create an order, abandoning the attempt if it has not completed within `deadline`
+    ///
+    /// An abandoned order may still have reached the exchange, so strategies should reconcile
+    /// it by its client id before retrying.
+    ///
+    /// # Parameters
+    ///
+    /// * `order_request` - An OrderRequest struct representing the order to be created
+    /// * `deadline` - How long the attempt may take, including rate limit waits and retries
+    ///
+    /// # Returns
+    ///
+    /// An OrderUpdate struct representing the order that was created
+    ///
+    /// # Errors
+    ///
+    /// `Error::DeadlineExceeded` if the deadline passed, or if the order cannot be created
+    pub async fn create_order_with_deadline(
+        &self,
+        order_request: OrderRequest,
+        deadline: Duration,
+    ) -> Result<OrderUpdate> {
+        with_deadline(deadline, self.create_order(order_request)).await
+    }
+
+    /// Create an order, cancelling it by client id if the attempt has not completed within
+    /// `deadline`
+    ///
+    /// A timed out submission leaves it unknown whether the order is live. The follow-up
+    /// `cancel_order_by_client_id` is given the same deadline and cancels the order if it had
+    /// reached the exchange. Even when the cancel is accepted, the abandoned request may still
+    /// arrive afterwards and place the order, so reconcile it by its client id, e.g. with
+    /// `order_by_client_id`, before retrying.
+    ///
+    /// # Parameters
+    ///
+    /// * `order_request` - The order to be created, which must have a client id
+    /// * `deadline` - How long the attempt, and then the cancel, may take
+    ///
+    /// # Returns
+    ///
+    /// An OrderUpdate struct representing the order that was created
+    ///
+    /// # Errors
+    ///
+    /// * `Error::InvalidOrder` if the order has no client id
+    /// * `Error::OrderSubmissionTimedOut` if the deadline passed, with whether the cancel was
+    ///   accepted
+    /// * If the order cannot be created
+    pub async fn create_order_or_cancel(
+        &self,
+        order_request: OrderRequest,
+        deadline: Duration,
+    ) -> Result<OrderUpdate> {
+        let client_id = order_request
+            .client_id
+            .clone()
+            .filter(|client_id| !client_id.is_empty())
+            .ok_or_else(|| {
+                Error::InvalidOrder("a client id is required to cancel on timeout".into())
+            })?;
+        match with_deadline(deadline, self.create_order(order_request)).await {
+            Err(Error::DeadlineExceeded(_)) => {
+                let cancel = self.cancel_order_by_client_id(client_id.clone());
+                let cancelled = match with_deadline(deadline, cancel).await {
+                    Ok(()) => true,
+                    Err(e) => {
+                        warn!("Could not cancel timed out order {client_id}: {e}");
+                        false
+                    }
+                };
+                Err(Error::OrderSubmissionTimedOut {
+                    client_id,
+                    deadline,
+                    cancelled,
+                })
+            }
+            result => result,
+        }
+    }
+
+    /// Modify an order, abandoning the attempt if it has not completed within `deadline`
+    ///
+    /// # Errors
+    ///
+    /// `Error::DeadlineExceeded` if the deadline passed, or if the order cannot be modified
+    pub async fn modify_order_with_deadline(
+        &self,
+        modify_order_request: ModifyOrderRequest,
+        deadline: Duration,
+    ) -> Result<ModifyOrderResult> {
+        with_deadline(deadline, self.modify_order(modify_order_request)).await
+    }
+
+    /// Modify the price or size of an open order
+    ///
+    /// # Parameters
+    ///
+    /// * `modify_order_request` - A ModifyOrderRequest struct with the id of the order and its new
+    ///   price and size
+    ///
+    /// # Returns
+    ///
+    /// A ModifyOrderResult telling whether the order was modified in place or replaced
+    ///
+    /// # Errors
+    ///
+    /// If the order cannot be modified
+    pub async fn modify_order(
+        &self,
+        modify_order_request: ModifyOrderRequest,
+    ) -> Result<ModifyOrderResult> {
+        let modify_order = self.prepare_modify_order(modify_order_request).await?;
+        let path = format!("/v1/orders/{}", modify_order.id);
+        let requested_id = modify_order.id.clone();
+        self.log_order(|| OrderEvent::modify(&modify_order, OrderTransport::Rest));
+        let order = self.request_auth(Method::Put(modify_order), path).await?;
+        Ok(ModifyOrderResult::new(&requested_id, order))
+    }
+
+    /// Check a modification against the system status and the order guard, then sign it
+    async fn prepare_modify_order(
+        &self,
+        modify_order_request: ModifyOrderRequest,
+    ) -> Result<ModifyOrder> {
+        self.system_status.check_order_flow()?;
+        self.order_guard.check_notional(
+            &modify_order_request.market,
+            modify_order_request.price,
+            modify_order_request.size,
+        )?;
+        let signature_timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| Error::TimeError(e.to_string()))?
+            .as_millis();
+
+        let (l2_chain, signer, account) = self
+            .l2_chain_private_key_account
+            .as_ref()
+            .ok_or(Error::MissingPrivateKey)?;
+
+        let signature = sign_modify_order_with_signer(
+            &modify_order_request,
+            signer.as_ref(),
+            signature_timestamp_ms,
+            *l2_chain,
+            *account,
+        )
+        .await?;
+
+        Ok(modify_order_request
+            .into_modify_order([signature.r, signature.s], signature_timestamp_ms))
+    }
+
+    /// Modify an open order identified by its client id
+    ///
+    /// The modification is signed over the exchange id of the order, so the order is looked
+    /// up by its client id first and the `id` of the request is replaced with its exchange id.
+    ///
+    /// # Parameters
+    ///
+    /// * `client_order_id` - The client id of the order to modify
+    /// * `modify_order_request` - The new price and size of the order, `id` is ignored
+    ///
+    /// # Returns
+    ///
+    /// A ModifyOrderResult telling whether the order was modified in place or replaced
+    ///
+    /// # Errors
+    ///
+    /// If the order cannot be found or modified
+    pub async fn modify_order_by_client_id(
+        &self,
+        client_order_id: &str,
+        modify_order_request: ModifyOrderRequest,
+    ) -> Result<ModifyOrderResult> {
+        let order = self.order_by_client_id(client_order_id).await?;
+        self.modify_order(ModifyOrderRequest {
+            id: order.id,
+            ..modify_order_request
+        })
+        .await
+    }
+
+    /// Get an order by its client id
+    ///
+    /// # Parameters
+    ///
+    /// * `client_order_id` - The client id of the order
+    ///
+    /// # Returns
+    ///
+    /// The latest OrderUpdate of the order
+    ///
+    /// # Errors
+    ///
+    /// If the order cannot be retrieved
+    pub async fn order_by_client_id(&self, client_order_id: &str) -> Result<OrderUpdate> {
+        self.request_auth(
+            Method::Get(()),
+            format!("/v1/orders/by_client_id/{}", path_segment(client_order_id)),
+        )
+        .await
+    }
+
+    /// Cancel an order on the exchange by order ID
+    ///
+    /// # Parameters
+    ///
+    /// * `order_id` - A string representing the order ID to be cancelled
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or failure
+    ///
+    /// # Errors
+    ///
+    /// If the order cannot be cancelled
+    pub async fn cancel_order(&self, order_id: String) -> Result<()> {
+        self.log_order(|| OrderEvent::cancel(Some(&order_id), None, OrderTransport::Rest));
+        match self
+            .request_auth::<(), ()>(Method::Delete, format!("/v1/orders/{order_id}"))
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(Error::RestEmptyResponse) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Cancel an order, abandoning the attempt if it has not completed within `deadline`
+    ///
+    /// # Errors
+    ///
+    /// `Error::DeadlineExceeded` if the deadline passed, or if the order cannot be cancelled
+    pub async fn cancel_order_with_deadline(
+        &self,
+        order_id: String,
+        deadline: Duration,
+    ) -> Result<()> {
+        with_deadline(deadline, self.cancel_order(order_id)).await
+    }
+
+    /// Cancel an order on the exchange by client ID
+    ///
+    /// # Parameters
+    ///
+    /// * `client_order_id` - A string representing the client order ID to be cancelled
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or failure
+    ///
+    /// # Errors
+    ///
+    /// If the order cannot be cancelled
+    pub async fn cancel_order_by_client_id(&self, client_order_id: String) -> Result<()> {
+        self.log_order(|| OrderEvent::cancel(None, Some(&client_order_id), OrderTransport::Rest));
+        match self
+            .request_auth::<(), ()>(
+                Method::Delete,
+                format!("/v1/orders/by_client_id/{}", path_segment(&client_order_id)),
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(Error::RestEmptyResponse) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Cancel all orders on the exchange
+    ///
+    /// # Returns
+    ///
+    /// A vector of strings representing the order IDs that were cancelled
+    ///
+    /// # Errors
+    ///
+    /// * `Error::OperationNotApproved` if the client's approver rejects the cancel
+    /// * If the orders cannot be cancelled
+    pub async fn cancel_all_orders(&self) -> Result<Vec<String>> {
+        self.approve(|| SensitiveOperation::CancelAll { market: None })
+            .await?;
+        self.log_order(|| OrderEvent::cancel_all(None));
+        self.request_auth(Method::Delete::<()>, "/v1/orders".into())
+            .await
+    }
+
+    /// Cancel all orders on the exchange for a specific market
+    ///
+    /// # Parameters
+    ///
+    /// * `market` - A string representing the market symbol to cancel orders for
+    ///
+    /// # Returns
+    ///
+    /// A vector of strings representing the order IDs that were cancelled
+    ///
+    /// # Errors
+    ///
+    /// * `Error::OperationNotApproved` if the client's approver rejects the cancel
+    /// * If the orders cannot be cancelled
+    pub async fn cancel_all_orders_for_market(
+        &self,
+        market: String,
+    ) -> Result<CancelByMarketResponse> {
+        self.approve(|| SensitiveOperation::CancelAll {
+            market: Some(market.clone()),
+        })
+        .await?;
+        self.log_order(|| OrderEvent::cancel_all(Some(&market)));
+        self.request_auth(Method::Delete::<()>, format!("/v1/orders/?market={market}"))
+            .await
+    }
+
+    /// Get all open orders
+    ///
+    /// # Returns
+    ///
+    /// An OrderUpdates struct representing the open orders
+    ///
+    /// # Errors
+    ///
+    /// If open orders cannot be retrieved
+    pub async fn open_orders(&self) -> Result<OrderUpdates> {
+        self.request_auth(Method::Get(()), "/v1/orders".into())
+            .await
+    }
+
+    /// Get all open orders, split into resting and untriggered conditional orders
+    ///
+    /// # Returns
+    ///
+    /// An OpenOrders struct with the orders on the book and the conditional orders waiting
+    /// for their trigger price
+    ///
+    /// # Errors
+    ///
+    /// If open orders cannot be retrieved
+    pub async fn open_orders_by_kind(&self) -> Result<OpenOrders> {
+        self.open_orders().await.map(OpenOrders::from)
+    }
+
+    /// Get the untriggered conditional orders, e.g. stop losses and take profits
+    ///
+    /// # Parameters
+    ///
+    /// * `market` - Only return orders for this market symbol, or all markets if None
+    ///
+    /// # Returns
+    ///
+    /// A vector of OrderUpdate structs with status UNTRIGGERED
+    ///
+    /// # Errors
+    ///
+    /// If open orders cannot be retrieved
+    pub async fn untriggered_orders(&self, market: Option<&str>) -> Result<Vec<OrderUpdate>> {
+        Ok(self
+            .open_orders_by_kind()
+            .await?
+            .conditional
+            .into_iter()
+            .filter(|order| market.is_none_or(|market| order.market == market))
+            .collect())
+    }
+
+    /// Cancel the untriggered conditional orders, leaving the resting orders on the book
+    ///
+    /// # Parameters
+    ///
+    /// * `market` - Only cancel orders for this market symbol, or all markets if None
+    ///
+    /// # Returns
+    ///
+    /// A vector of strings representing the order IDs that were cancelled
+    ///
+    /// # Errors
+    ///
+    /// If open orders cannot be retrieved or any of the orders cannot be cancelled. Orders are
+    /// cancelled concurrently, so the other cancellations may still have succeeded.
+    pub async fn cancel_untriggered_orders(&self, market: Option<&str>) -> Result<Vec<String>> {
+        let ids: Vec<String> = self
+            .untriggered_orders(market)
+            .await?
+            .into_iter()
+            .map(|order| order.id)
+            .collect();
+        join_all(ids.iter().map(|id| self.cancel_order(id.clone())))
+            .await
+            .into_iter()
+            .collect::<Result<Vec<()>>>()?;
+        Ok(ids)
+    }
+
+    /// Get the Account Information
+    ///
+    /// # Returns
+    ///
+    /// An AccountInformation struct representing the account information
+    ///
+    /// # Errors
+    ///
+    /// If the account information cannot be retrieved
+    pub async fn account_information(&self) -> Result<AccountInformation> {
+        self.request_auth(Method::Get(()), "/v1/account".into())
+            .await
+    }
+
+    /// Get the account details, including the fee rates of its tier
+    ///
+    /// # Returns
+    ///
+    /// An AccountInfo struct with the account's kind, parent account and fees
+    ///
+    /// # Errors
+    ///
+    /// If the account details cannot be retrieved
+    pub async fn account_info(&self) -> Result<AccountInfo> {
+        self.request_auth(Method::Get(()), "/v1/account/info".into())
+            .await
+    }
+
+    /// Get the profile of the authenticated account
+    ///
+    /// # Returns
+    ///
+    /// An AccountProfile struct with the username, max slippage and referral settings
+    ///
+    /// # Errors
+    ///
+    /// If the profile cannot be retrieved
+    pub async fn account_profile(&self) -> Result<AccountProfile> {
+        self.request_auth(Method::Get(()), "/v1/account/profile".into())
+            .await
+    }
+
+    /// Set the username of the authenticated account
+    ///
+    /// # Parameters
+    ///
+    /// * `username_update` - The new username and whether it is hidden from leaderboards
+    ///
+    /// # Errors
+    ///
+    /// If the username is taken or cannot be updated
+    pub async fn update_username(&self, username_update: UsernameUpdate) -> Result<()> {
+        self.update_profile(username_update, "username").await
+    }
+
+    /// Set the maximum slippage of market orders in a market
+    ///
+    /// # Parameters
+    ///
+    /// * `max_slippage_update` - The market and its new maximum slippage
+    ///
+    /// # Errors
+    ///
+    /// If the maximum slippage cannot be updated
+    pub async fn update_max_slippage(&self, max_slippage_update: MaxSlippageUpdate) -> Result<()> {
+        self.update_profile(max_slippage_update, "max_slippage")
+            .await
+    }
+
+    async fn update_profile<B: serde::Serialize>(&self, update: B, setting: &str) -> Result<()> {
+        match self
+            .request_auth::<B, serde::de::IgnoredAny>(
+                Method::Post(update),
+                format!("/v1/account/profile/{setting}"),
+            )
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(Error::RestEmptyResponse) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// List the accounts referred by the authenticated account with the rewards earned
+    ///
+    /// # Returns
+    ///
+    /// A ReferralSummary for each referred account
+    ///
+    /// # Errors
+    ///
+    /// If the referrals cannot be retrieved
+    pub async fn referrals_summary(&self) -> Result<Vec<ReferralSummary>> {
+        Ok(self
+            .request_auth::<_, ResultsContainer<Vec<ReferralSummary>>>(
+                Method::Get(()),
+                "/v1/referrals/summary".into(),
+            )
+            .await?
+            .results)
+    }
+
+    /// List the sub-accounts of the authenticated account
+    ///
+    /// # Returns
+    ///
+    /// The sub-accounts, oldest first
+    ///
+    /// # Errors
+    ///
+    /// If the sub-accounts cannot be retrieved
+    pub async fn list_subaccounts(&self) -> Result<Vec<SubAccount>> {
+        let mut subaccounts = self
+            .request_auth::<_, ResultsContainer<Vec<SubAccount>>>(
+                Method::Get(()),
+                "/v1/account/subaccounts".into(),
+            )
+            .await?
+            .results;
+        subaccounts.sort_by(|a, b| (a.created_at, &a.account).cmp(&(b.created_at, &b.account)));
+        Ok(subaccounts)
+    }
+
+    /// Get the Account margin configuration for a specific market
+    ///
+    /// # Parameters
+    ///
+    /// * `market` - A string representing the market symbol
+    ///
+    /// # Returns
+    ///
+    /// An AccountMarginConfigurations struct representing the account margin configuration
+    ///
+    /// # Errors
+    ///
+    /// If the account information cannot be retrieved
+    pub async fn account_margin_configuration(
+        &self,
+        market: String,
+    ) -> Result<AccountMarginConfigurations> {
+        let params = [("market", market)];
+        self.request_auth(Method::Get(params), "/v1/account/margin".into())
+            .await
+    }
+
+    /// Get the margin configuration of every market of the account
+    ///
+    /// # Returns
+    ///
+    /// A vector of MarginConfig structs with the leverage and margin type of each market
+    ///
+    /// # Errors
+    ///
+    /// If the margin configurations cannot be retrieved
+    pub async fn list_margin_configurations(&self) -> Result<Vec<MarginConfig>> {
+        self.request_auth(Method::Get(()), "/v1/account/margin".into())
+            .await
+            .map(|configurations: AccountMarginConfigurations| configurations.configs)
+    }
+
+    /// Get the balances for the account
+    ///
+    /// # Returns
+    ///
+    /// A Balances struct representing the account balances
+    ///
+    /// # Errors
+    ///
+    /// If the balances cannot be retrieved
+    pub async fn balance(&self) -> Result<Balances> {
+        self.request_auth(Method::Get(()), "/v1/balance".into())
+            .await
+    }
+
+    /// Get the balances for the account with exact decimal sizes
+    ///
+    /// # Returns
+    ///
+    /// A vector of exact::Balance structs representing the account balances
+    ///
+    /// # Errors
+    ///
+    /// If the balances cannot be retrieved
+    pub async fn balance_exact(&self) -> Result<Vec<exact::Balance>> {
+        self.request_auth(Method::Get(()), "/v1/balance".into())
+            .await
+            .map(|result_container: ResultsContainer<Vec<exact::Balance>>| result_container.results)
+    }
+
+    /// Get the positions for the account
+    ///
+    /// # Returns
+    ///
+    /// A Positions struct representing the account positions
+    ///
+    /// # Errors
+    ///
+    /// If the positions cannot be retrieved
+    pub async fn positions(&self) -> Result<Positions> {
+        self.request_auth(Method::Get(()), "/v1/positions".into())
+            .await
+    }
+
+    /// Get the positions for the account with exact decimal prices, sizes and PnL
+    ///
+    /// # Returns
+    ///
+    /// A vector of exact::Position structs representing the account positions
+    ///
+    /// # Errors
+    ///
+    /// If the positions cannot be retrieved
+    pub async fn positions_exact(&self) -> Result<Vec<exact::Position>> {
+        self.request_auth(Method::Get(()), "/v1/positions".into())
+            .await
+            .map(|result_container: ResultsContainer<Vec<exact::Position>>| {
+                result_container.results
+            })
+    }
+
+    /// Get the performance summary of a vault
+    ///
+    /// # Parameters
+    ///
+    /// * `vault_address` - The address of the vault
+    ///
+    /// # Returns
+    ///
+    /// A VaultSummary struct including the current vault share price
+    ///
+    /// # Errors
+    ///
+    /// If the vault summary cannot be retrieved or the vault does not exist
+    pub async fn vault_summary(&self, vault_address: &str) -> Result<VaultSummary> {
+        let params = [("address", vault_address)];
+        self.request::<_, ResultsContainer<Vec<VaultSummary>>>(
+            Method::Get(params),
+            "/v1/vaults/summary".into(),
+            None,
+        )
+        .await?
+        .results
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::RestError(format!("{vault_address} is not a vault")))
+    }
+
+    /// Get the history of a vault metric, e.g. its share price
+    ///
+    /// # Parameters
+    ///
+    /// * `vault_address` - The address of the vault
+    /// * `history_type` - The metric to retrieve
+    ///
+    /// # Returns
+    ///
+    /// A VaultHistory struct with the sampled values
+    ///
+    /// # Errors
+    ///
+    /// If the vault history cannot be retrieved or the vault does not exist
+    pub async fn vault_history(
+        &self,
+        vault_address: &str,
+        history_type: VaultHistoryType,
+    ) -> Result<VaultHistory> {
+        let params = VaultHistoryParams {
+            address: vault_address,
+            history_type,
+        };
+        self.request::<_, ResultsContainer<Vec<VaultHistory>>>(
+            Method::Get(params),
+            "/v1/vaults/history".into(),
+            None,
+        )
+        .await?
+        .results
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::RestError(format!("{vault_address} is not a vault")))
+    }
+
+    /// Get the holdings of the account in a vault
+    ///
+    /// # Parameters
+    ///
+    /// * `vault_address` - The address of the vault
+    ///
+    /// # Returns
+    ///
+    /// A VaultAccountSummary struct including the vault shares held by the account
+    ///
+    /// # Errors
+    ///
+    /// If the summary cannot be retrieved or the account never invested in the vault
+    pub async fn vault_account_summary(&self, vault_address: &str) -> Result<VaultAccountSummary> {
+        let params = [("address", vault_address)];
+        self.request_auth::<_, ResultsContainer<Vec<VaultAccountSummary>>>(
+            Method::Get(params),
+            "/v1/vaults/account-summary".into(),
+        )
+        .await?
+        .results
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::RestError(format!("no holdings in vault {vault_address}")))
+    }
+
+    /// Get the fills history, following the cursor through every page
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by market and time range
+    ///
+    /// # Returns
+    ///
+    /// A vector of Fill structs
+    ///
+    /// # Errors
+    ///
+    /// If any page of the fills cannot be retrieved
+    pub async fn fills(&self, filter: impl Into<ListParams>) -> Result<Vec<Fill>> {
+        self.request_cursor("/v1/fills".to_string(), filter.into(), true)
+            .await
+    }
+
+    /// Get the fills history with exact decimal prices, fees and PnL, following the cursor
+    /// through every page
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by market and time range
+    ///
+    /// # Returns
+    ///
+    /// A vector of exact::Fill structs
+    ///
+    /// # Errors
+    ///
+    /// If any page of the fills cannot be retrieved
+    pub async fn fills_exact(&self, filter: impl Into<ListParams>) -> Result<Vec<exact::Fill>> {
+        self.request_cursor("/v1/fills".to_string(), filter.into(), true)
+            .await
+    }
+
+    /// Stream the fills history, fetching each page only once the previous one was consumed
+    ///
+    /// Unlike `fills`, the history is never held in memory as a whole, and dropping the stream
+    /// stops fetching pages.
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by market and time range
+    ///
+    /// # Returns
+    ///
+    /// A stream of Fill structs, ending after the first error
+    pub fn fills_stream(
+        &self,
+        filter: impl Into<ListParams>,
+    ) -> impl Stream<Item = Result<Fill>> + Send + 'static {
+        self.request_cursor_stream("/v1/fills".to_string(), filter.into(), true)
+    }
+
+    /// Get the orders history, following the cursor through every page
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by market, side, status, type, client id and time range
+    ///
+    /// # Returns
+    ///
+    /// A vector of OrderUpdate structs
+    ///
+    /// # Errors
+    ///
+    /// If any page of the orders history cannot be retrieved
+    pub async fn orders_history(&self, filter: impl Into<ListParams>) -> Result<Vec<OrderUpdate>> {
+        self.request_cursor("/v1/orders-history".to_string(), filter.into(), true)
+            .await
+    }
+
+    /// Stream the orders history, fetching each page only once the previous one was consumed
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by market, side, status, type, client id and time range
+    ///
+    /// # Returns
+    ///
+    /// A stream of OrderUpdate structs, ending after the first error
+    pub fn orders_history_stream(
+        &self,
+        filter: impl Into<ListParams>,
+    ) -> impl Stream<Item = Result<OrderUpdate>> + Send + 'static {
+        self.request_cursor_stream("/v1/orders-history".to_string(), filter.into(), true)
+    }
+
+    /// Get the transfers history, following the cursor through every page
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by status and time range
+    ///
+    /// # Returns
+    ///
+    /// A vector of Transfer structs
+    ///
+    /// # Errors
+    ///
+    /// If any page of the transfers cannot be retrieved
+    pub async fn transfers(&self, filter: impl Into<ListParams>) -> Result<Vec<Transfer>> {
+        self.request_cursor("/v1/transfers".to_string(), filter.into(), true)
+            .await
+    }
+
+    /// Get the transfers history with exact decimal amounts, following the cursor through every
+    /// page
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by status and time range
+    ///
+    /// # Returns
+    ///
+    /// A vector of exact::Transfer structs
+    ///
+    /// # Errors
+    ///
+    /// If any page of the transfers cannot be retrieved
+    pub async fn transfers_exact(
+        &self,
+        filter: impl Into<ListParams>,
+    ) -> Result<Vec<exact::Transfer>> {
+        self.request_cursor("/v1/transfers".to_string(), filter.into(), true)
+            .await
+    }
+
+    /// Stream the transfers history, fetching each page only once the previous one was consumed
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by status and time range
+    ///
+    /// # Returns
+    ///
+    /// A stream of Transfer structs, ending after the first error
+    pub fn transfers_stream(
+        &self,
+        filter: impl Into<ListParams>,
+    ) -> impl Stream<Item = Result<Transfer>> + Send + 'static {
+        self.request_cursor_stream("/v1/transfers".to_string(), filter.into(), true)
+    }
+
+    /// Get the funding payments history, following the cursor through every page
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by market and time range
+    ///
+    /// # Returns
+    ///
+    /// A vector of FundingPayment structs
+    ///
+    /// # Errors
+    ///
+    /// If any page of the funding payments cannot be retrieved
+    pub async fn funding_payments(
+        &self,
+        filter: impl Into<ListParams>,
+    ) -> Result<Vec<FundingPayment>> {
+        self.request_cursor("/v1/funding/payments".to_string(), filter.into(), true)
+            .await
+    }
+
+    /// Get the funding payments history with exact decimal payments, following the cursor
+    /// through every page
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by market and time range
+    ///
+    /// # Returns
+    ///
+    /// A vector of exact::FundingPayment structs
+    ///
+    /// # Errors
+    ///
+    /// If any page of the funding payments cannot be retrieved
+    pub async fn funding_payments_exact(
+        &self,
+        filter: impl Into<ListParams>,
+    ) -> Result<Vec<exact::FundingPayment>> {
+        self.request_cursor("/v1/funding/payments".to_string(), filter.into(), true)
+            .await
+    }
+
+    /// Stream the funding payments history, fetching each page only once the previous one was
+    /// consumed
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by market and time range
+    ///
+    /// # Returns
+    ///
+    /// A stream of FundingPayment structs, ending after the first error
+    pub fn funding_payments_stream(
+        &self,
+        filter: impl Into<ListParams>,
+    ) -> impl Stream<Item = Result<FundingPayment>> + Send + 'static {
+        self.request_cursor_stream("/v1/funding/payments".to_string(), filter.into(), true)
+    }
+
+    /// Get the funding data history of a market, following the cursor through every page
+    ///
+    /// # Parameters
+    ///
+    /// * `market` - The symbol of a perpetual market
+    /// * `start` - Start of the time range
+    /// * `end` - End of the time range
+    ///
+    /// # Returns
+    ///
+    /// A vector of FundingData structs with the funding index, premium and rate over time
+    ///
+    /// # Errors
+    ///
+    /// If any page of the funding data cannot be retrieved
+    pub async fn funding_data_history(
+        &self,
+        market: &str,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<FundingData>> {
+        self.request_cursor(
+            "/v1/funding/data".to_string(),
+            ListParams::new().market(market).start(start).end(end),
+            false,
+        )
+        .await
+    }
+
+    /// Get the latest funding data of a market at a point in time
+    ///
+    /// The funding accrued by a position of one unit between two times is the difference of
+    /// the funding indices at those times.
+    ///
+    /// # Parameters
+    ///
+    /// * `market` - The symbol of a perpetual market
+    /// * `at` - The point in time
+    ///
+    /// # Returns
+    ///
+    /// The latest FundingData at or before `at`, None if there is none
+    ///
+    /// # Errors
+    ///
+    /// If the funding data cannot be retrieved
+    pub async fn funding_index(
+        &self,
+        market: &str,
+        at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<FundingData>> {
+        let params = ListParams::new()
+            .market(market)
+            .end(at)
+            .page_size(1)
+            .into_query()?;
+        self.request(Method::Get(params), "/v1/funding/data".into(), None)
+            .await
+            .map(|page: CursorResult<FundingData>| page.results.into_iter().next())
+    }
+
+    /// Get the public trade tape, following the cursor through every page
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by market and time range
+    ///
+    /// # Returns
+    ///
+    /// A vector of Trade structs
+    ///
+    /// # Errors
+    ///
+    /// If any page of the trades cannot be retrieved
+    pub async fn trade_tape(&self, filter: impl Into<ListParams>) -> Result<Vec<Trade>> {
+        self.request_cursor("/v1/trades".to_string(), filter.into(), false)
+            .await
+    }
+
+    /// Get the public trades of a market over a time range, e.g. to warm start models fed by
+    /// the trades channel
+    ///
+    /// # Parameters
+    ///
+    /// * `market` - The market symbol
+    /// * `start` - Start of the time range
+    /// * `end` - End of the time range
+    ///
+    /// # Returns
+    ///
+    /// A vector of Trade structs
+    ///
+    /// # Errors
+    ///
+    /// If any page of the trades cannot be retrieved
+    pub async fn trades(
+        &self,
+        market: &str,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Trade>> {
+        self.trade_tape(ListParams::new().market(market).start(start).end(end))
+            .await
+    }
+
+    /// Get the public trade tape with exact decimal prices and sizes, following the cursor
+    /// through every page
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by market and time range
+    ///
+    /// # Returns
+    ///
+    /// A vector of exact::Trade structs
+    ///
+    /// # Errors
+    ///
+    /// If any page of the trades cannot be retrieved
+    pub async fn trade_tape_exact(
         &self,
-        status: Option<TransferStatus>,
-        start: Option<chrono::DateTime<chrono::Utc>>,
-        end: Option<chrono::DateTime<chrono::Utc>>,
-    ) -> Result<Vec<Transfer>> {
-        let filters = status.map(|status| vec![("status".to_string(), format!("{status:?}"))]);
-
-        self.request_cursor("/v1/transfers".to_string(), filters, start, end, true)
+        filter: impl Into<ListParams>,
+    ) -> Result<Vec<exact::Trade>> {
+        self.request_cursor("/v1/trades".to_string(), filter.into(), false)
             .await
     }
 
-    pub async fn funding_payments(
+    /// Stream the public trade tape, fetching each page only once the previous one was consumed
+    ///
+    /// # Parameters
+    ///
+    /// * `filter` - ListParams narrowing the results by market and time range
+    ///
+    /// # Returns
+    ///
+    /// A stream of Trade structs, ending after the first error
+    pub fn trade_tape_stream(
         &self,
-        market: Option<String>,
-        start: Option<chrono::DateTime<chrono::Utc>>,
-        end: Option<chrono::DateTime<chrono::Utc>>,
-    ) -> Result<Vec<FundingPayment>> {
-        let filters = market.map(|market| vec![("market".to_string(), market)]);
+        filter: impl Into<ListParams>,
+    ) -> impl Stream<Item = Result<Trade>> + Send + 'static {
+        self.request_cursor_stream("/v1/trades".to_string(), filter.into(), false)
+    }
 
-        self.request_cursor(
-            "/v1/funding/payments".to_string(),
-            filters,
-            start,
-            end,
-            true,
-        )
-        .await
+    /// Download the trades and klines of the option instruments of an underlying
+    ///
+    /// At most `request.concurrency` instruments are downloaded at once, each request still
+    /// subject to the client's rate limits.
+    ///
+    /// # Parameters
+    ///
+    /// * `request` - An OptionHistoryRequest selecting the instruments, time range and data
+    ///
+    /// # Returns
+    ///
+    /// An OptionHistory with the history of each selected instrument
+    ///
+    /// # Errors
+    ///
+    /// If the markets or the history of any instrument cannot be retrieved
+    pub async fn option_history(&self, request: OptionHistoryRequest) -> Result<OptionHistory> {
+        let markets = self.markets().await?;
+        let instruments = request
+            .chain
+            .select(&markets)
+            .into_iter()
+            .filter_map(OptionInstrument::new);
+        let instruments: Vec<OptionInstrument> = stream::iter(instruments)
+            .map(|mut instrument| {
+                let request = &request;
+                async move {
+                    if request.trades {
+                        instrument.trades = self
+                            .trades(&instrument.symbol, request.start, request.end)
+                            .await?;
+                        instrument.trades.sort_by_key(|trade| trade.created_at);
+                    }
+                    if let Some(resolution) = request.resolution {
+                        instrument.klines = self
+                            .klines(KlineParams {
+                                start_at: request.start.timestamp_millis() as u64,
+                                end_at: request.end.timestamp_millis() as u64,
+                                symbol: instrument.symbol.clone(),
+                                resolution,
+                                price_kind: None,
+                            })
+                            .await?;
+                    }
+                    trace!("Downloaded the history of {}", instrument.symbol);
+                    Ok::<_, Error>(instrument)
+                }
+            })
+            .buffered(request.concurrency.max(1))
+            .try_collect()
+            .await?;
+        Ok(OptionHistory {
+            underlying: request.chain.underlying.clone(),
+            start_at: request.start.timestamp_millis(),
+            end_at: request.end.timestamp_millis(),
+            instruments,
+        })
     }
 
-    pub async fn trade_tape(
+    /// Perform a cursor-based REST API request with optional filters.
+    ///
+    /// Pages are deserialized as they are received rather than buffered whole, keeping peak
+    /// memory low during bulk backfills. Every result is still collected before returning, see
+    /// `request_cursor_stream` to process them as the pages arrive.
+    ///
+    /// * `filters` - Query parameters such as market or time range, as any type serializing to a
+    ///   flat set of key/value pairs, typically ListParams. Pages of 5000 results are requested
+    ///   unless the filters contain a `page_size`, starting from the first page unless they
+    ///   contain a `cursor`.
+    pub async fn request_cursor<
+        T: for<'de> serde::Deserialize<'de> + Send + 'static,
+        F: serde::Serialize,
+    >(
         &self,
-        market: Option<String>,
-        start: Option<chrono::DateTime<chrono::Utc>>,
-        end: Option<chrono::DateTime<chrono::Utc>>,
-    ) -> Result<Vec<Trade>> {
-        let filters = market.map(|market| vec![("market".to_string(), market)]);
-
-        self.request_cursor("/v1/trades".to_string(), filters, start, end, false)
+        path: String,
+        filters: F,
+        use_auth: bool,
+    ) -> Result<Vec<T>> {
+        self.request_cursor_stream(path, filters, use_auth)
+            .try_collect()
             .await
     }
 
-    /// Perform a cursor-based REST API request with optional filters.
+    /// Perform a cursor-based REST API request with optional filters, fetching each page only
+    /// once the results of the previous one have been consumed
     ///
-    /// * `filters` - Additional query parameters such as market.
-    pub async fn request_cursor<T: for<'de> serde::Deserialize<'de>>(
+    /// Dropping the stream stops fetching pages. The stream ends after the first error.
+    ///
+    /// * `filters` - Query parameters as for `request_cursor`
+    pub fn request_cursor_stream<
+        T: for<'de> serde::Deserialize<'de> + Send + 'static,
+        F: serde::Serialize,
+    >(
         &self,
         path: String,
-        filters: Option<Vec<(String, String)>>,
-        start: Option<chrono::DateTime<chrono::Utc>>,
-        end: Option<chrono::DateTime<chrono::Utc>>,
+        filters: F,
         use_auth: bool,
-    ) -> Result<Vec<T>> {
-        let mut result = Vec::new();
-        let mut cursor: Option<String> = None;
-        let filters = filters.unwrap_or_default();
-        loop {
-            let mut params: Vec<(String, String)> =
-                vec![("page_size".to_string(), "5000".to_string())];
-            params.extend(filters.iter().cloned());
-            if let Some(start_time) = start {
-                params.push((
-                    "start_at".to_string(),
-                    start_time.timestamp_millis().to_string(),
-                ));
-            }
-            if let Some(end_time) = end {
-                params.push((
-                    "end_at".to_string(),
-                    end_time.timestamp_millis().to_string(),
-                ));
+    ) -> impl Stream<Item = Result<T>> + Send + 'static {
+        let client = self.clone();
+        let query = filters.into_query().map(|mut filters| {
+            if !filters.iter().any(|(key, _)| key == "page_size") {
+                filters.push(("page_size".to_string(), "5000".to_string()));
             }
-            if let Some(token) = &cursor {
-                params.push(("cursor".to_string(), token.clone()));
-            }
-            let intermediate: CursorResult<T> = if use_auth {
-                self.request_auth(Method::Get::<()>(params), path.clone())
-                    .await?
-            } else {
-                self.request(Method::Get::<()>(params), path.clone(), None)
-                    .await?
-            };
-            result.extend(intermediate.results);
-
-            if let Some(next) = &intermediate.next {
-                cursor = Some(next.clone());
-            } else {
-                break;
+            // A cursor in the filters is where to start, the next ones come from the pages
+            let cursor = filters
+                .iter()
+                .position(|(key, _)| key == "cursor")
+                .map(|index| filters.remove(index).1);
+            (filters, cursor)
+        });
+        let pages = match query {
+            Err(e) => stream::once(async { Err(e) }).left_stream(),
+            Ok((filters, cursor)) => {
+                // None once the last page was fetched
+                stream::try_unfold(Some(cursor), move |cursor| {
+                    let client = client.clone();
+                    let path = path.clone();
+                    let mut params = filters.clone();
+                    async move {
+                        let Some(cursor) = cursor else {
+                            return Ok(None);
+                        };
+                        if let Some(token) = cursor {
+                            params.push(("cursor".to_string(), token));
+                        }
+                        let headers = if use_auth {
+                            Some(client.auth_header().await?)
+                        } else {
+                            None
+                        };
+                        let response = client
+                            .send(&Method::Get(params), &path, headers.as_ref())
+                            .await?;
+                        let page: CursorResult<T> = body::deserialize_streamed(response).await?;
+                        Ok(Some((page.results, page.next.map(Some))))
+                    }
+                })
+                .right_stream()
             }
-        }
-        Ok(result)
+        };
+        pages
+            .map_ok(|results| stream::iter(results.into_iter().map(Ok)))
+            .try_flatten()
     }
 
     /// Perform a REST API request with authentication headers
@@ -810,10 +2967,16 @@ impl Client {
         method: Method<B>,
         path: String,
     ) -> Result<T> {
+        let header_map = self.auth_header().await?;
+        self.request(method, path, Some(header_map)).await
+    }
+
+    /// Authorization header carrying the current JWT
+    async fn auth_header(&self) -> Result<HeaderMap<HeaderValue>> {
         let jwt = self.jwt().await?;
         let mut header_map: HeaderMap<HeaderValue> = HeaderMap::with_capacity(1);
         header_map.insert("Authorization", format!("Bearer {jwt}").parse().unwrap());
-        self.request(method, path, Some(header_map)).await
+        Ok(header_map)
     }
 
     /// Perform a REST API request with optional additional headers
@@ -838,51 +3001,1421 @@ impl Client {
         path: String,
         additional_headers: Option<HeaderMap<HeaderValue>>,
     ) -> Result<T> {
+        let text = self
+            .send(&method, &path, additional_headers.as_ref())
+            .await?
+            .text()
+            .await
+            .map_err(|e| Error::RestError(e.to_string()))?;
+        if text.is_empty() {
+            Err(Error::RestEmptyResponse)
+        } else {
+            Ok(serde_json::from_str::<T>(&text)
+                .map_err(|e| Error::DeserializationError(format!("Text: {text} Error: {e:?}")))?)
+        }
+    }
+
+    /// Send a REST API request, retrying it while rate limited
+    ///
+    /// # Returns
+    ///
+    /// The successful response, with its body left unread
+    ///
+    /// # Errors
+    ///
+    /// If the request cannot be completed or the response status is not a success
+    async fn send<B: serde::Serialize>(
+        &self,
+        method: &Method<B>,
+        path: &str,
+        additional_headers: Option<&HeaderMap<HeaderValue>>,
+    ) -> Result<reqwest::Response> {
         let url = format!("{}{path}", self.url.rest());
+        let class = if path.starts_with("/v1/orders") && !matches!(method, Method::Get(_)) {
+            EndpointClass::Orders
+        } else if additional_headers.is_some() {
+            EndpointClass::Private
+        } else {
+            EndpointClass::Public
+        };
+
+        let mut retry = 0;
+        loop {
+            self.rate_limiter.acquire(class).await;
+
+            let mut request = match method {
+                Method::Get(params) => self.client.get(&url).query(params),
+                Method::Post(body) => self.client.post(&url).json(body),
+                Method::Put(body) => self.client.put(&url).json(body),
+                Method::Delete => self.client.delete(&url),
+            };
+
+            request = request.header("Accept", "application/json");
+
+            if let Some(headers) = additional_headers {
+                request = request.headers(headers.clone());
+            }
+
+            let result = request
+                .send()
+                .await
+                .map_err(|e| Error::RestError(e.to_string()))?;
+            let status = result.status();
+            if status.is_success() {
+                return Ok(result);
+            }
+            let retry_after = retry_after(result.headers());
+            let text = result
+                .text()
+                .await
+                .map_err(|e| Error::RestError(e.to_string()))?;
+
+            if is_rate_limited(status, &text)
+                && let Some(policy) = &self.retry_policy
+                && retry < policy.max_retries
+            {
+                let backoff = policy.backoff(retry, retry_after);
+                warn!("Rate limited on {path}, retrying in {backoff:?}");
+                tokio::time::sleep(backoff).await;
+                retry += 1;
+                continue;
+            }
+
+            return if text.is_empty() {
+                Err(Error::HTTPError {
+                    status_code: status,
+                })
+            } else {
+                let paradex_error = serde_json::from_str::<RestError>(&text).map_err(|e| {
+                    Error::DeserializationError(format!("Text: {text} Error: {e:?}"))
+                })?;
+                Err(Error::ParadexError {
+                    status_code: status,
+                    error: paradex_error.error.as_deref().map(ParadexErrorCode::from),
+                    message: paradex_error.message,
+                })
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::OptionChainFilter;
+    use crate::structs::OptionType;
+
+    #[tokio::test]
+    async fn api_token_auth() {
+        let client = Client::with_api_token(reqwest::Client::new(), URL::Testnet, "token-a".into());
+        assert!(client.is_private());
+        assert_eq!(client.jwt().await.unwrap(), "token-a");
+
+        let clone = client.clone();
+        client.rotate_api_token("token-b".into()).await.unwrap();
+        assert_eq!(clone.jwt().await.unwrap(), "token-b");
+        assert!(clone.refresh_jwt(true).await.is_ok());
+        assert_eq!(clone.account(), None);
+
+        let public = Client::new(URL::Testnet, None).await.unwrap();
+        assert!(matches!(
+            public.rotate_api_token("token-c".into()).await,
+            Err(Error::MissingApiToken)
+        ));
+    }
 
-        let mut request = match method {
-            Method::Get(params) => self.client.get(url).query(&params),
-            Method::Post(body) => self.client.post(url).json(&body),
-            Method::Put(body) => self.client.put(url).json(&body),
-            Method::Delete => self.client.delete(url),
+    #[cfg(not(feature = "onboarding"))]
+    #[tokio::test]
+    async fn onboarding_disabled() {
+        let result = Client::new_with_eth_private_key(URL::Testnet, "0x1".into(), None).await;
+        assert!(matches!(result, Err(Error::FeatureDisabled(feature)) if feature == "onboarding"));
+    }
+
+    #[tokio::test]
+    async fn remote_signer() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Signs with a local key, counting the hashes it was asked to sign
+        struct CountingSigner(SigningKey, AtomicUsize);
+
+        impl StarkSigner for CountingSigner {
+            fn public_key(&self) -> Felt {
+                self.0.verifying_key().scalar()
+            }
+
+            fn sign(
+                &self,
+                hash: Felt,
+            ) -> futures_util::future::BoxFuture<'_, Result<starknet_crypto::Signature>>
+            {
+                self.1.fetch_add(1, Ordering::SeqCst);
+                StarkSigner::sign(&self.0, hash)
+            }
+        }
+
+        let (address, server) = serve(vec![
+            (
+                "200 OK",
+                include_str!("../tests/fixtures/system_config.json"),
+            ),
+            ("200 OK", include_str!("../tests/fixtures/jwt_token.json")),
+        ])
+        .await;
+        let signing_key = SigningKey::from_secret_scalar(Felt::from_hex_unchecked("0x1234"));
+        let signer = Arc::new(CountingSigner(signing_key.clone(), AtomicUsize::new(0)));
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new_with_signer(url, signer.clone()).await.unwrap();
+        let config: SystemConfig =
+            serde_json::from_str(include_str!("../tests/fixtures/system_config.json")).unwrap();
+        assert_eq!(
+            client.account(),
+            Some(account_for_public_key(signer.public_key(), &config).unwrap())
+        );
+
+        client.jwt().await.unwrap();
+        assert_eq!(signer.1.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            server.await.unwrap(),
+            vec!["GET /v1/system/config HTTP/1.1", "POST /v1/auth HTTP/1.1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn cancel_only_gates_order_flow() {
+        use crate::structs::{OrderInstruction, OrderType, Side};
+        use rust_decimal::Decimal;
+
+        let (address, server) = serve(vec![
+            ("200 OK", r#"{"status":"cancel_only"}"#),
+            ("200 OK", r#"{"status":"cancel_only"}"#),
+            ("204 No Content", ""),
+        ])
+        .await;
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::with_api_token(reqwest::Client::new(), url, "token".into());
+        let order = OrderRequest {
+            instruction: OrderInstruction::GTC,
+            market: "BTC-USD-PERP".into(),
+            price: Some(Decimal::from(30000)),
+            side: Side::BUY,
+            size: Decimal::ONE,
+            order_type: OrderType::LIMIT,
+            client_id: None,
+            flags: Vec::new(),
+            recv_window: None,
+            stp: None,
+            trigger_price: None,
         };
 
-        request = request.header("Accept", "application/json");
+        // Fetching the state alone does not gate, failing only for lack of a Stark key
+        client.system_state().await.unwrap();
+        assert_eq!(client.system_status(), None);
+        assert!(matches!(
+            client.create_order(order.clone()).await,
+            Err(Error::MissingPrivateKey)
+        ));
+
+        let mut watcher = client.watch_system_status();
+        let poller = client.poll_system_state(Duration::from_secs(3600));
+        watcher.changed().await.unwrap();
+        assert_eq!(
+            client.clone().system_status(),
+            Some(SystemStatus::CancelOnly)
+        );
+        assert!(matches!(
+            client.create_order(order.clone()).await,
+            Err(Error::OrderFlowSuspended(SystemStatus::CancelOnly))
+        ));
+        assert!(matches!(
+            client
+                .modify_order(ModifyOrderRequest {
+                    id: "abc".into(),
+                    market: order.market.clone(),
+                    price: order.price,
+                    side: order.side,
+                    size: order.size,
+                    order_type: order.order_type,
+                })
+                .await,
+            Err(Error::OrderFlowSuspended(SystemStatus::CancelOnly))
+        ));
+        client.cancel_order("abc".into()).await.unwrap();
+        assert_eq!(
+            server.await.unwrap(),
+            vec![
+                "GET /v1/system/state HTTP/1.1",
+                "GET /v1/system/state HTTP/1.1",
+                "DELETE /v1/orders/abc HTTP/1.1"
+            ]
+        );
+
+        // Stopping the poller stops gating
+        poller.stop();
+        assert!(matches!(
+            client.create_order(order).await,
+            Err(Error::MissingPrivateKey)
+        ));
+    }
+
+    #[tokio::test]
+    async fn order_log() {
+        use crate::structs::{OrderInstruction, OrderType, Side};
+        use rust_decimal::Decimal;
+
+        let (address, server) = serve(vec![
+            (
+                "200 OK",
+                include_str!("../tests/fixtures/order_update.json"),
+            ),
+            ("204 No Content", ""),
+        ])
+        .await;
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        let client = Client::with_api_token(reqwest::Client::new(), url, "token".into())
+            .with_signer_account(
+                Felt::from_hex_unchecked("0x1"),
+                Arc::new(SigningKey::from_secret_scalar(Felt::from_hex_unchecked(
+                    "0x1234",
+                ))),
+                Felt::from_hex_unchecked("0x5678"),
+            )
+            .with_order_log(move |event| sink.lock().unwrap().push(event.clone()));
+
+        client
+            .create_order(OrderRequest {
+                instruction: OrderInstruction::GTC,
+                market: "BTC-USD-PERP".into(),
+                price: Some(Decimal::from(30000)),
+                side: Side::BUY,
+                size: Decimal::ONE,
+                order_type: OrderType::LIMIT,
+                client_id: Some("mm-1".into()),
+                flags: Vec::new(),
+                recv_window: None,
+                stp: None,
+                trigger_price: None,
+            })
+            .await
+            .unwrap();
+        client.cancel_order("abc".into()).await.unwrap();
+        server.await.unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        let created = serde_json::to_value(&events[0]).unwrap();
+        assert_eq!(created["action"], "Create");
+        assert_eq!(created["transport"], "Rest");
+        assert_eq!(created["client_id"], "mm-1");
+        assert_eq!(created["price"], "30000");
+        assert!(created.get("signature").is_none());
+        assert_eq!(events[1].action, OrderAction::Cancel);
+        assert_eq!(events[1].order_id.as_deref(), Some("abc"));
+        assert_eq!(events[1].price, None);
+    }
+
+    #[tokio::test]
+    async fn approver_gates_sensitive_operations() {
+        use futures_util::future::BoxFuture;
+
+        /// Approves cancelling the orders of a single market only, and fails to decide on margin
+        struct MarketCancelsOnly(Arc<std::sync::Mutex<Vec<SensitiveOperation>>>);
 
-        if let Some(headers) = additional_headers {
-            request = request.headers(headers);
+        impl Approver for MarketCancelsOnly {
+            fn approve<'a>(
+                &'a self,
+                operation: &'a SensitiveOperation,
+            ) -> BoxFuture<'a, Result<bool>> {
+                self.0.lock().unwrap().push(operation.clone());
+                Box::pin(async move {
+                    match operation {
+                        SensitiveOperation::CancelAll { market } => Ok(market.is_some()),
+                        SensitiveOperation::MarginUpdate { .. } => {
+                            Err(Error::RestError("approval service unavailable".into()))
+                        }
+                        SensitiveOperation::Withdrawal { .. }
+                        | SensitiveOperation::Deposit { .. } => Ok(false),
+                    }
+                })
+            }
         }
 
-        let result = request
-            .send()
+        let (address, server) = serve(vec![(
+            "200 OK",
+            include_str!("../tests/fixtures/cancel_by_market_response.json"),
+        )])
+        .await;
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let asked = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let client = Client::with_api_token(reqwest::Client::new(), url, "token".into())
+            .with_signer_account(
+                Felt::from_hex_unchecked("0x1"),
+                Arc::new(SigningKey::from_secret_scalar(Felt::from_hex_unchecked(
+                    "0x1234",
+                ))),
+                Felt::from_hex_unchecked("0x5678"),
+            )
+            .with_approver(MarketCancelsOnly(Arc::clone(&asked)));
+
+        assert!(matches!(
+            client.cancel_all_orders().await,
+            Err(Error::OperationNotApproved(_))
+        ));
+        client
+            .cancel_all_orders_for_market("BTC-USD-PERP".into())
             .await
-            .map_err(|e| Error::RestError(e.to_string()))?;
-        let status = result.status();
-        let text = result
-            .text()
+            .unwrap();
+        let update = AccountMarginUpdate {
+            leverage: 10,
+            margin_type: "CROSS".into(),
+        };
+        assert!(matches!(
+            client
+                .update_account_margin("BTC-USD-PERP".into(), update)
+                .await,
+            Err(Error::RestError(_))
+        ));
+        let amount = crate::decimal::parse_decimal("12.5").unwrap();
+        assert!(matches!(
+            client.withdraw("USDC", amount, None).await,
+            Err(Error::OperationNotApproved(_))
+        ));
+        #[cfg(feature = "bridge")]
+        {
+            let eth_signer = PrivateKeySigner::random();
+            assert!(matches!(
+                client
+                    .deposit("USDC", amount, &eth_signer, "http://127.0.0.1:1")
+                    .await,
+                Err(Error::OperationNotApproved(_))
+            ));
+            assert_eq!(
+                asked.lock().unwrap().pop(),
+                Some(SensitiveOperation::Deposit {
+                    token: "USDC".into(),
+                    amount,
+                    l1_sender: eth_signer.address().to_string(),
+                })
+            );
+        }
+        // Only the approved cancel reached the exchange
+        assert_eq!(server.await.unwrap().len(), 1);
+        assert_eq!(
+            asked.lock().unwrap().clone(),
+            vec![
+                SensitiveOperation::CancelAll { market: None },
+                SensitiveOperation::CancelAll {
+                    market: Some("BTC-USD-PERP".into())
+                },
+                SensitiveOperation::MarginUpdate {
+                    market: "BTC-USD-PERP".into(),
+                    leverage: 10,
+                    margin_type: "CROSS".into(),
+                },
+                SensitiveOperation::Withdrawal {
+                    token: "USDC".into(),
+                    amount,
+                    l1_recipient: None,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn order_guard_rejects_before_sending() {
+        use crate::error::GuardViolation;
+        use crate::structs::{OrderInstruction, OrderType, Side};
+        use rust_decimal::Decimal;
+
+        let client = Client::with_api_token(reqwest::Client::new(), URL::Testnet, "token".into())
+            .with_order_guard(
+                OrderGuard::new()
+                    .max_notional(Decimal::from(1000))
+                    .max_orders_per_minute(1),
+            );
+        let order = |price: i64| OrderRequest {
+            instruction: OrderInstruction::GTC,
+            market: "BTC-USD-PERP".into(),
+            price: Some(Decimal::from(price)),
+            side: Side::BUY,
+            size: Decimal::ONE,
+            order_type: OrderType::LIMIT,
+            client_id: None,
+            flags: Vec::new(),
+            recv_window: None,
+            stp: None,
+            trigger_price: None,
+        };
+        assert!(matches!(
+            client.create_order(order(30000)).await,
+            Err(Error::OrderGuardViolation(
+                GuardViolation::MaxNotional { .. }
+            ))
+        ));
+        // Within the limits the order fails only for lack of a Stark key, so it is not counted
+        for _ in 0..2 {
+            assert!(matches!(
+                client.create_order(order(500)).await,
+                Err(Error::MissingPrivateKey)
+            ));
+        }
+
+        assert!(matches!(
+            Client::with_api_token(reqwest::Client::new(), URL::Testnet, "token".into())
+                .cached_market("BTC-USD-PERP")
+                .await,
+            Err(Error::MissingMarketCache)
+        ));
+    }
+
+    #[tokio::test]
+    async fn order_guard_prices_market_orders_at_the_bbo() {
+        use crate::error::GuardViolation;
+        use crate::structs::{OrderInstruction, OrderType, Side};
+        use rust_decimal::Decimal;
+
+        let (address, server) =
+            serve(vec![("200 OK", include_str!("../tests/fixtures/bbo.json"))]).await;
+        // The cached summary has an ask of 30130.15 where the BBO has moved to 31000.12
+        let registry = MarketRegistry::new();
+        let markets: ResultsContainer<Vec<MarketSummaryStatic>> =
+            serde_json::from_str(include_str!("../tests/fixtures/markets.json")).unwrap();
+        registry.update(markets.results);
+        registry.update_summaries(vec![
+            serde_json::from_str(include_str!("../tests/fixtures/market_summary.json")).unwrap(),
+        ]);
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, None)
             .await
-            .map_err(|e| Error::RestError(e.to_string()))?;
+            .unwrap()
+            .with_market_registry(registry)
+            .with_order_guard(OrderGuard::new().max_notional(Decimal::from(1000)));
+        let order = OrderRequest {
+            instruction: OrderInstruction::GTC,
+            market: "BTC-USD-PERP".into(),
+            price: None,
+            side: Side::BUY,
+            size: Decimal::ONE,
+            order_type: OrderType::MARKET,
+            client_id: None,
+            flags: Vec::new(),
+            recv_window: None,
+            stp: None,
+            trigger_price: None,
+        };
+        assert!(matches!(
+            client.create_order(order).await,
+            Err(Error::OrderGuardViolation(GuardViolation::MaxNotional { notional, .. }))
+                if notional == crate::decimal::parse_decimal("31000.12").unwrap()
+        ));
+        assert_eq!(server.await.unwrap(), ["GET /v1/bbo/BTC-USD-PERP HTTP/1.1"]);
+    }
 
-        if status.is_success() {
-            if text.is_empty() {
-                Err(Error::RestEmptyResponse)
-            } else {
-                Ok(serde_json::from_str::<T>(&text).map_err(|e| {
-                    Error::DeserializationError(format!("Text: {text} Error: {e:?}"))
-                })?)
+    #[tokio::test]
+    async fn order_guard_counts_placed_orders() {
+        use crate::error::GuardViolation;
+        use crate::structs::{OrderInstruction, OrderType, Side};
+        use rust_decimal::Decimal;
+
+        let (address, server) = serve(vec![
+            (
+                "200 OK",
+                include_str!("../tests/fixtures/system_config.json"),
+            ),
+            ("200 OK", include_str!("../tests/fixtures/bbo.json")),
+            ("200 OK", include_str!("../tests/fixtures/jwt_token.json")),
+            (
+                "400 Bad Request",
+                r#"{"error":"VALIDATION_ERROR","message":"invalid order"}"#,
+            ),
+            (
+                "200 OK",
+                include_str!("../tests/fixtures/order_update.json"),
+            ),
+        ])
+        .await;
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, Some("0x1234".into()))
+            .await
+            .unwrap()
+            .with_order_guard(
+                OrderGuard::new()
+                    .max_notional(Decimal::from(1000))
+                    .max_orders_per_minute(1),
+            );
+        let order = |order_type, price| OrderRequest {
+            instruction: OrderInstruction::GTC,
+            market: "BTC-USD-PERP".into(),
+            price,
+            side: Side::BUY,
+            size: Decimal::ONE,
+            order_type,
+            client_id: None,
+            flags: Vec::new(),
+            recv_window: None,
+            stp: None,
+            trigger_price: None,
+        };
+        let limit = || order(OrderType::LIMIT, Some(Decimal::from(500)));
+
+        // Market orders are priced at the ask they would take
+        assert!(matches!(
+            client.create_order(order(OrderType::MARKET, None)).await,
+            Err(Error::OrderGuardViolation(
+                GuardViolation::MaxNotional { .. }
+            ))
+        ));
+        // A rejected order does not use up the limit
+        assert!(client.create_order(limit()).await.is_err());
+        client.create_order(limit()).await.unwrap();
+        assert!(matches!(
+            client.create_orders(vec![limit()]).await,
+            Err(Error::OrderGuardViolation(
+                GuardViolation::MaxOrdersPerMinute { limit: 1 }
+            ))
+        ));
+        assert_eq!(
+            server.await.unwrap()[1..],
+            [
+                "GET /v1/bbo/BTC-USD-PERP HTTP/1.1",
+                "POST /v1/auth HTTP/1.1",
+                "POST /v1/orders HTTP/1.1",
+                "POST /v1/orders HTTP/1.1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn order_guard_keeps_orders_of_unknown_fate() {
+        use crate::error::GuardViolation;
+        use crate::structs::{OrderInstruction, OrderType, Side};
+        use rust_decimal::Decimal;
+
+        let (address, server) = serve(vec![
+            (
+                "200 OK",
+                include_str!("../tests/fixtures/system_config.json"),
+            ),
+            ("200 OK", include_str!("../tests/fixtures/jwt_token.json")),
+            ("", ""),
+        ])
+        .await;
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, Some("0x1234".into()))
+            .await
+            .unwrap()
+            .with_order_guard(OrderGuard::new().max_orders_per_minute(1));
+        let order = || OrderRequest {
+            instruction: OrderInstruction::GTC,
+            market: "BTC-USD-PERP".into(),
+            price: Some(Decimal::from(500)),
+            side: Side::BUY,
+            size: Decimal::ONE,
+            order_type: OrderType::LIMIT,
+            client_id: None,
+            flags: Vec::new(),
+            recv_window: None,
+            stp: None,
+            trigger_price: None,
+        };
+
+        // The connection drops after the order was sent, so it may rest and stays counted
+        assert!(matches!(
+            client.create_order(order()).await,
+            Err(Error::RestError(_))
+        ));
+        assert!(matches!(
+            client.create_order(order()).await,
+            Err(Error::OrderGuardViolation(
+                GuardViolation::MaxOrdersPerMinute { limit: 1 }
+            ))
+        ));
+        assert_eq!(
+            server.await.unwrap()[1..],
+            ["POST /v1/auth HTTP/1.1", "POST /v1/orders HTTP/1.1"]
+        );
+    }
+
+    /// Serve one canned response per connection, returning the request lines received
+    ///
+    /// An empty status drops the connection after reading the request, without answering.
+    async fn serve<B: AsRef<str> + Send + 'static>(
+        responses: Vec<(&'static str, B)>,
+    ) -> (std::net::SocketAddr, tokio::task::JoinHandle<Vec<String>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let body = body.as_ref();
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 1024];
+                let read = stream.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..read]).into_owned();
+                requests.push(request.lines().next().unwrap_or_default().to_string());
+                if status.is_empty() {
+                    // Drop the connection without answering
+                    continue;
+                }
+                let response = format!(
+                    "HTTP/1.1 {status}\r\ncontent-type: application/json\r\nretry-after: 0\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
             }
-        } else if text.is_empty() {
-            Err(Error::HTTPError {
-                status_code: status,
-            })
-        } else {
-            let paradex_error = serde_json::from_str::<RestError>(&text)
-                .map_err(|e| Error::DeserializationError(format!("Text: {text} Error: {e:?}")))?;
+            requests
+        });
+        (address, server)
+    }
+
+    #[tokio::test]
+    async fn custom_url() {
+        let (address, server) = serve(vec![("200 OK", r#"{"server_time":"1681493415023"}"#)]).await;
+
+        let url = URL::custom(format!("http://{address}/"), format!("ws://{address}/v1"));
+        assert_eq!(url.websocket(), format!("ws://{address}/v1"));
+        let client = Client::new(url, None).await.unwrap();
+        let time = client.system_time().await.unwrap();
+        assert_eq!(time.server_time, 1681493415023);
+        assert_eq!(server.await.unwrap(), vec!["GET /v1/system/time HTTP/1.1"]);
+    }
+
+    #[tokio::test]
+    async fn deposit_bridge() {
+        let config = include_str!("../tests/fixtures/system_config.json");
+        let (address, server) = serve(vec![("200 OK", config); 2]).await;
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, None).await.unwrap();
+        let bridged = client.deposit_bridge("usdc").await.unwrap();
+        assert_eq!(bridged.symbol, "USDC");
+        assert!(matches!(
+            client.deposit_bridge("BTC").await,
+            Err(Error::TokenNotBridged(token)) if token == "BTC"
+        ));
+        assert_eq!(server.await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn option_history() {
+        let (address, server) = serve(vec![
+            ("200 OK", include_str!("../tests/fixtures/markets.json")),
+            (
+                "200 OK",
+                concat!(
+                    r#"{"next":null,"results":["#,
+                    include_str!("../tests/fixtures/trade.json"),
+                    "]}"
+                ),
+            ),
+            ("200 OK", include_str!("../tests/fixtures/klines.json")),
+        ])
+        .await;
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, None).await.unwrap();
+        let start = chrono::DateTime::from_timestamp_millis(1681493400000).unwrap();
+        let end = chrono::DateTime::from_timestamp_millis(1681497000000).unwrap();
+        let request = OptionHistoryRequest::new(OptionChainFilter::new("ETH"), start, end)
+            .klines(crate::structs::KlineResolution::Min1)
+            .concurrency(1);
+        let history = client.option_history(request).await.unwrap();
+        assert_eq!(history.instruments.len(), 1);
+        let instrument = &history.instruments[0];
+        assert_eq!(instrument.symbol, "ETH-USD-3000-C");
+        assert_eq!(instrument.strike_price, 3000.);
+        assert_eq!((instrument.trades.len(), instrument.klines.len()), (1, 2));
+        assert_eq!(history.trades()[0].option_type, OptionType::CALL);
+        assert_eq!(
+            server.await.unwrap(),
+            vec![
+                "GET /v1/markets HTTP/1.1",
+                "GET /v1/trades?market=ETH-USD-3000-C&start_at=1681493400000&end_at=1681497000000&page_size=5000 HTTP/1.1",
+                "GET /v1/markets/klines?start_at=1681493400000&end_at=1681497000000&symbol=ETH-USD-3000-C&resolution=1 HTTP/1.1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn funding_index() {
+        let (address, server) = serve(vec![(
+            "200 OK",
+            r#"{"next":"abc","prev":null,"results":[{"created_at":1681375481000,"funding_index":"100.0","funding_premium":"22.4","funding_rate":"0.00034","market":"BTC-USD-PERP"}]}"#,
+        )])
+        .await;
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, None).await.unwrap();
+        let at = chrono::DateTime::from_timestamp_millis(1681375500000).unwrap();
+        let funding = client.funding_index("BTC-USD-PERP", at).await.unwrap();
+        assert_eq!(funding.unwrap().funding_index, 100.0);
+        // Only the first page is requested
+        assert_eq!(
+            server.await.unwrap(),
+            vec![
+                "GET /v1/funding/data?market=BTC-USD-PERP&end_at=1681375500000&page_size=1 HTTP/1.1"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn trades_follow_cursor() {
+        let (address, server) = serve(vec![
+            (
+                "200 OK",
+                r#"{"next":"page2","prev":null,"results":[{"created_at":1681497002041,"id":"1","market":"BTC-USD-PERP","price":"30001.2","side":"BUY","size":"0.01","trade_type":"FILL"}]}"#,
+            ),
+            (
+                "200 OK",
+                r#"{"next":null,"prev":"page1","results":[{"created_at":1681497002000,"id":"2","market":"BTC-USD-PERP","price":"30001.1","side":"SELL","size":"0.02","trade_type":"FILL"}]}"#,
+            ),
+        ])
+        .await;
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, None).await.unwrap();
+        let start = chrono::DateTime::from_timestamp_millis(1681497000000).unwrap();
+        let end = chrono::DateTime::from_timestamp_millis(1681497010000).unwrap();
+        let trades = client.trades("BTC-USD-PERP", start, end).await.unwrap();
+        let ids: Vec<&str> = trades.iter().map(|trade| trade.id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "2"]);
+        assert_eq!(
+            server.await.unwrap(),
+            vec![
+                "GET /v1/trades?market=BTC-USD-PERP&start_at=1681497000000&end_at=1681497010000&page_size=5000 HTTP/1.1",
+                "GET /v1/trades?market=BTC-USD-PERP&start_at=1681497000000&end_at=1681497010000&page_size=5000&cursor=page2 HTTP/1.1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn trade_tape_stream() {
+        let (address, server) = serve(vec![
+            (
+                "200 OK",
+                r#"{"next":"page2","prev":null,"results":[{"created_at":1681497002041,"id":"1","market":"BTC-USD-PERP","price":"30001.2","side":"BUY","size":"0.01","trade_type":"FILL"},{"created_at":1681497002042,"id":"2","market":"BTC-USD-PERP","price":"30001.2","side":"BUY","size":"0.01","trade_type":"FILL"}]}"#,
+            ),
+            (
+                "200 OK",
+                r#"{"next":"page3","prev":"page1","results":[{"created_at":1681497002000,"id":"3","market":"BTC-USD-PERP","price":"30001.1","side":"SELL","size":"0.02","trade_type":"FILL"}]}"#,
+            ),
+            (
+                "400 Bad Request",
+                r#"{"error":"INVALID_REQUEST_PARAMETER","message":"invalid cursor"}"#,
+            ),
+        ])
+        .await;
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, None).await.unwrap();
+        let trades = client.trade_tape_stream(ListParams::new().market("BTC-USD-PERP"));
+        futures_util::pin_mut!(trades);
+        for id in ["1", "2", "3"] {
+            assert_eq!(trades.next().await.unwrap().unwrap().id, id);
+        }
+        // The stream ends after the failed page
+        assert!(trades.next().await.unwrap().is_err());
+        assert!(trades.next().await.is_none());
+        assert_eq!(
+            server.await.unwrap(),
+            vec![
+                "GET /v1/trades?market=BTC-USD-PERP&page_size=5000 HTTP/1.1",
+                "GET /v1/trades?market=BTC-USD-PERP&page_size=5000&cursor=page2 HTTP/1.1",
+                "GET /v1/trades?market=BTC-USD-PERP&page_size=5000&cursor=page3 HTTP/1.1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn list_params_start_from_cursor() {
+        let (address, server) = serve(vec![(
+            "200 OK",
+            r#"{"next":null,"prev":"page1","results":[{"created_at":1681497002000,"id":"2","market":"BTC-USD-PERP","price":"30001.1","side":"SELL","size":"0.02","trade_type":"FILL"}]}"#,
+        )])
+        .await;
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, None).await.unwrap();
+        let params = ListParams::new()
+            .market("BTC-USD-PERP")
+            .page_size(1)
+            .cursor("page2");
+        let trades: Vec<Trade> = client
+            .request_cursor("/v1/trades".to_string(), params, false)
+            .await
+            .unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(
+            server.await.unwrap(),
+            vec!["GET /v1/trades?market=BTC-USD-PERP&page_size=1&cursor=page2 HTTP/1.1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn bbos() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Answers each request with the bbo of the requested market, the earlier markets of the
+        // list answered later, so that results complete out of order
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let markets = ["BTC-USD-PERP", "ETH-USD-PERP", "SOL-USD-PERP"];
+        let server = tokio::spawn(async move {
+            let mut answers = Vec::new();
+            for _ in markets {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                answers.push(tokio::spawn(async move {
+                    let mut request = vec![0; 1024];
+                    let read = stream.read(&mut request).await.unwrap();
+                    let request = String::from_utf8_lossy(&request[..read]).into_owned();
+                    let request = request.lines().next().unwrap_or_default().to_string();
+                    let market = markets
+                        .into_iter()
+                        .find(|market| request.contains(market))
+                        .unwrap();
+                    let delay = match market {
+                        "BTC-USD-PERP" => 150,
+                        "ETH-USD-PERP" => 75,
+                        _ => 0,
+                    };
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    let body = include_str!("../tests/fixtures/bbo.json")
+                        .replace("BTC-USD-PERP", market);
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                    stream.write_all(response.as_bytes()).await.unwrap();
+                    request
+                }));
+            }
+            let mut requests = Vec::new();
+            for answer in answers {
+                requests.push(answer.await.unwrap());
+            }
+            requests
+        });
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, None).await.unwrap();
+        let bbos = client.bbos(markets, 3).await.unwrap();
+        assert_eq!(
+            bbos.iter()
+                .map(|bbo| bbo.market.as_str())
+                .collect::<Vec<_>>(),
+            markets
+        );
+        // Requests run concurrently, so they may arrive in any order
+        let mut requests = server.await.unwrap();
+        requests.sort();
+        assert_eq!(
+            requests,
+            markets.map(|market| format!("GET /v1/bbo/{market} HTTP/1.1"))
+        );
+    }
+
+    #[tokio::test]
+    async fn markets_summary() {
+        let (address, server) = serve(vec![(
+            "200 OK",
+            concat!(
+                r#"{"results":["#,
+                include_str!("../tests/fixtures/market_summary.json"),
+                "]}"
+            ),
+        )])
+        .await;
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, None).await.unwrap();
+        let summaries = client.markets_summary().await.unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].symbol, "BTC-USD-PERP");
+        assert_eq!(
+            server.await.unwrap(),
+            vec!["GET /v1/markets/summary?market=ALL HTTP/1.1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn set_margin() {
+        let (address, server) = serve(vec![
+            (
+                "200 OK",
+                r#"{"account":"0x1","leverage":5,"margin_type":"ISOLATED","market":"BTC-USD-PERP"}"#,
+            ),
+            (
+                "200 OK",
+                include_str!("../tests/fixtures/account_margin_update_response.json"),
+            ),
+            (
+                "200 OK",
+                include_str!("../tests/fixtures/account_margin_configurations.json"),
+            ),
+            ("200 OK", include_str!("../tests/fixtures/markets.json")),
+        ])
+        .await;
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let uncached = Client::with_api_token(reqwest::Client::new(), url, "token".into());
+        let client = uncached.clone().with_market_cache(Duration::from_secs(60));
+        let markets: ResultsContainer<Vec<MarketSummaryStatic>> =
+            serde_json::from_str(include_str!("../tests/fixtures/markets.json")).unwrap();
+        client.market_registry().unwrap().update(markets.results);
+        assert!(matches!(
+            client.set_margin("DOGE-USD-PERP", 5, MarginType::CROSS).await,
+            Err(Error::UnknownMarket(market)) if market == "DOGE-USD-PERP"
+        ));
+        let order = client
+            .order_builder(
+                "BTC-USD-PERP",
+                Side::BUY,
+                OrderType::LIMIT,
+                crate::decimal::parse_decimal("0.001").unwrap(),
+            )
+            .await
+            .unwrap()
+            .price(crate::decimal::parse_decimal("30000.05").unwrap())
+            .build();
+        assert!(order.is_err());
+
+        for (market, leverage, margin_type) in [
+            ("BTC-USD-PERP", 0, MarginType::CROSS),
+            ("BTC-USD-PERP", 10, MarginType::ISOLATED),
+            ("ETH-USD-3000-C", 1, MarginType::ISOLATED),
+        ] {
+            assert!(matches!(
+                client.set_margin(market, leverage, margin_type).await,
+                Err(Error::InvalidMarginUpdate(_))
+            ));
+        }
+        let applied = client
+            .set_margin("BTC-USD-PERP", 5, MarginType::ISOLATED)
+            .await
+            .unwrap();
+        assert_eq!(applied.margin_type, "ISOLATED");
+        // The exchange answers with 10x cross margin, which is then in effect
+        assert!(matches!(
+            client
+                .set_margin("BTC-USD-PERP", 9, MarginType::CROSS)
+                .await,
+            Err(Error::MarginUpdateMismatch { leverage: 9, applied, .. }) if applied.leverage == 10
+        ));
+
+        let configs = client.list_margin_configurations().await.unwrap();
+        assert_eq!(configs.len(), 2);
+        assert_eq!(
+            configs[1].margin_type.parse::<MarginType>().unwrap(),
+            MarginType::ISOLATED
+        );
+        // Without a market registry the market is fetched to check the update against
+        assert!(matches!(
+            uncached
+                .set_margin("BTC-USD-PERP", 10, MarginType::ISOLATED)
+                .await,
+            Err(Error::InvalidMarginUpdate(_))
+        ));
+        assert_eq!(
+            server.await.unwrap(),
+            vec![
+                "POST /v1/account/margin/BTC-USD-PERP HTTP/1.1",
+                "POST /v1/account/margin/BTC-USD-PERP HTTP/1.1",
+                "GET /v1/account/margin HTTP/1.1",
+                "GET /v1/markets HTTP/1.1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn compressed_responses() {
+        use std::io::Write;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(br#"{"server_time":"1681493415023"}"#)
+            .unwrap();
+        let body = encoder.finish().unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 1024];
+            let read = stream.read(&mut request).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..read]).to_lowercase();
+            let header = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-encoding: gzip\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(header.as_bytes()).await.unwrap();
+            stream.write_all(&body).await.unwrap();
+            request
+        });
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, None).await.unwrap();
+        let time = client.system_time().await.unwrap();
+        assert_eq!(time.server_time, 1681493415023);
+        let request = server.await.unwrap();
+        let accept_encoding = request
+            .lines()
+            .find_map(|line| line.strip_prefix("accept-encoding: "))
+            .unwrap();
+        for encoding in ["gzip", "br", "deflate"] {
+            assert!(accept_encoding.contains(encoding), "{accept_encoding}");
+        }
+    }
+
+    #[tokio::test]
+    async fn deadline_cancels_request() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        // Accept the connection but never answer
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            drop(stream);
+        });
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, None).await.unwrap();
+        let started = std::time::Instant::now();
+        let deadline = Duration::from_millis(100);
+        assert!(matches!(
+            with_deadline(deadline, client.system_time()).await,
+            Err(Error::DeadlineExceeded(elapsed)) if elapsed == deadline
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn cancel_on_timeout() {
+        use crate::structs::{OrderInstruction, OrderType, Side};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        // Answer everything but order submissions, which are left hanging
+        let server = tokio::spawn(async move {
+            let mut hanging = Vec::new();
+            let mut requests = Vec::new();
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 4096];
+                let read = stream.read(&mut request).await.unwrap();
+                let line = String::from_utf8_lossy(&request[..read])
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+                let body = if line.starts_with("POST /v1/orders ") {
+                    hanging.push(stream);
+                    continue;
+                } else if line.starts_with("GET /v1/system/config") {
+                    include_str!("../tests/fixtures/system_config.json")
+                } else if line.starts_with("POST /v1/auth") {
+                    include_str!("../tests/fixtures/jwt_token.json")
+                } else {
+                    ""
+                };
+                let cancelled = line.starts_with("DELETE");
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                requests.push(line);
+                if cancelled {
+                    return requests;
+                }
+            }
+        });
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, Some("0x1234".into())).await.unwrap();
+        let order = |client_id: Option<&str>| OrderRequest {
+            instruction: OrderInstruction::GTC,
+            market: "BTC-USD-PERP".into(),
+            price: Some(crate::decimal::parse_decimal("30000").unwrap()),
+            side: Side::BUY,
+            size: crate::decimal::parse_decimal("0.1").unwrap(),
+            order_type: OrderType::LIMIT,
+            client_id: client_id.map(Into::into),
+            flags: Vec::new(),
+            recv_window: None,
+            stp: None,
+            trigger_price: None,
+        };
+        let deadline = Duration::from_millis(200);
+        assert!(matches!(
+            client.create_order_or_cancel(order(None), deadline).await,
+            Err(Error::InvalidOrder(_))
+        ));
+        match client
+            .create_order_or_cancel(order(Some("x1")), deadline)
+            .await
+        {
+            Err(Error::OrderSubmissionTimedOut {
+                client_id,
+                cancelled,
+                ..
+            }) => {
+                assert_eq!(client_id, "x1");
+                assert!(cancelled);
+            }
+            other => panic!("expected a timed out submission, got {other:?}"),
+        }
+        assert_eq!(
+            server.await.unwrap().last().unwrap(),
+            "DELETE /v1/orders/by_client_id/x1 HTTP/1.1"
+        );
+    }
+
+    #[tokio::test]
+    async fn streamed_pages() {
+        let trade = r#"{"created_at":1681497002041,"id":"1","market":"BTC-USD-PERP","price":"30001.2","side":"BUY","size":"0.01","trade_type":"FILL"}"#;
+        let page = format!(
+            r#"{{"next":null,"prev":null,"results":[{}]}}"#,
+            vec![trade; 20_000].join(",")
+        );
+        let truncated = page[..page.len() / 2].to_string();
+        let (address, server) = serve(vec![("200 OK", page), ("200 OK", truncated)]).await;
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, None).await.unwrap();
+        let start = chrono::DateTime::from_timestamp_millis(1681497000000).unwrap();
+        let end = chrono::DateTime::from_timestamp_millis(1681497010000).unwrap();
+        let trades = client.trades("BTC-USD-PERP", start, end).await.unwrap();
+        assert_eq!(trades.len(), 20_000);
+        assert!(matches!(
+            client.trades("BTC-USD-PERP", start, end).await,
+            Err(Error::DeserializationError(_))
+        ));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn withdraw_submits_signed_transaction() {
+        let (rpc_address, rpc) = serve(vec![
+            ("200 OK", r#"{"jsonrpc":"2.0","id":1,"result":"0x4"}"#),
+            (
+                "200 OK",
+                r#"{"jsonrpc":"2.0","id":1,"result":[{"l1_gas_consumed":"0x0","l1_gas_price":"0x10","l2_gas_consumed":"0x1000","l2_gas_price":"0x20","l1_data_gas_consumed":"0x80","l1_data_gas_price":"0x1","overall_fee":"0x20080","unit":"FRI"}]}"#,
+            ),
+            (
+                "200 OK",
+                r#"{"jsonrpc":"2.0","id":1,"result":{"transaction_hash":"0xabc"}}"#,
+            ),
+            ("200 OK", r#"{"jsonrpc":"2.0","id":1,"result":"0x5"}"#),
+            (
+                "200 OK",
+                r#"{"jsonrpc":"2.0","id":1,"error":{"code":55,"message":"Account validation failed"}}"#,
+            ),
+        ])
+        .await;
+        let config = include_str!("../tests/fixtures/system_config.json").replace(
+            "https://pathfinder.api.prod.paradex.trade/rpc/v0_7",
+            &format!("http://{rpc_address}/rpc"),
+        );
+        let (address, server) = serve(vec![("200 OK", config); 4]).await;
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let amount = crate::decimal::parse_decimal("12.5").unwrap();
+        assert!(matches!(
+            Client::new(url.clone(), None)
+                .await
+                .unwrap()
+                .withdraw("USDC", amount, None)
+                .await,
+            Err(Error::MissingPrivateKey)
+        ));
+        let client = Client::new(url, Some("0x1234".into())).await.unwrap();
+        let hash = client.withdraw("USDC", amount, None).await.unwrap();
+        assert_eq!(hash, Felt::from_hex_unchecked("0xabc"));
+        assert!(matches!(
+            client.withdraw("ETH", amount, None).await,
+            Err(Error::TokenNotBridged(token)) if token == "ETH"
+        ));
+        // Given bounds skip the estimate
+        assert!(matches!(
+            client
+                .withdraw("USDC", amount, Some(FeeBounds::default()))
+                .await,
+            Err(Error::StarknetError(_))
+        ));
+        server.await.unwrap();
+        assert_eq!(rpc.await.unwrap(), vec!["POST /rpc HTTP/1.1"; 5]);
+    }
+
+    #[tokio::test]
+    async fn withdraw_to_l1_whitelist() {
+        let (rpc_address, rpc) = serve(vec![
+            ("200 OK", r#"{"jsonrpc":"2.0","id":1,"result":"0x4"}"#),
+            (
+                "200 OK",
+                r#"{"jsonrpc":"2.0","id":1,"result":{"transaction_hash":"0xdef"}}"#,
+            ),
+        ])
+        .await;
+        let config = include_str!("../tests/fixtures/system_config.json").replace(
+            "https://pathfinder.api.prod.paradex.trade/rpc/v0_7",
+            &format!("http://{rpc_address}/rpc"),
+        );
+        let (address, server) = serve(vec![("200 OK", config); 2]).await;
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let treasury = "0xAb5801a7D398351b8bE11C439e05C5B3259aeC9B";
+        let client = Client::new(url, Some("0x1234".into()))
+            .await
+            .unwrap()
+            .with_withdrawal_whitelist(AddressBook::new().address("treasury", treasury));
+        let amount = crate::decimal::parse_decimal("12.5").unwrap();
+        // Rejected before the configuration is fetched or anything is signed
+        assert!(matches!(
+            client
+                .withdraw_to_l1("USDC", amount, "0x1234", Some(FeeBounds::default()))
+                .await,
+            Err(Error::WithdrawalNotWhitelisted(recipient)) if recipient == "0x1234"
+        ));
+        let hash = client
+            .withdraw_to_l1(
+                "USDC",
+                amount,
+                &treasury.to_lowercase(),
+                Some(FeeBounds::default()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(hash, Felt::from_hex_unchecked("0xdef"));
+        server.await.unwrap();
+        assert_eq!(rpc.await.unwrap(), vec!["POST /rpc HTTP/1.1"; 2]);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_retry() {
+        const RATE_LIMITED: (&str, &str) = (
+            "429 Too Many Requests",
+            r#"{"error":"RATE_LIMIT_EXCEEDED","message":"rate limit exceeded"}"#,
+        );
+        let (address, server) = serve(vec![
+            RATE_LIMITED,
+            RATE_LIMITED,
+            ("200 OK", r#"{"server_time":"1681493415023"}"#),
+            RATE_LIMITED,
+            RATE_LIMITED,
+        ])
+        .await;
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, None)
+            .await
+            .unwrap()
+            .with_retry_policy(RetryPolicy {
+                max_retries: 1,
+                ..RetryPolicy::default()
+            });
+        // Retry-After: 0 takes precedence over the backoff
+        assert!(client.system_time().await.is_err());
+        assert_eq!(
+            client.system_time().await.unwrap().server_time,
+            1681493415023
+        );
+        match client.system_time().await {
             Err(Error::ParadexError {
-                status_code: status,
-                error: paradex_error.error,
-                message: paradex_error.message,
-            })
+                status_code,
+                error: Some(ParadexErrorCode::RATE_LIMIT_EXCEEDED),
+                ..
+            }) => assert_eq!(status_code, reqwest::StatusCode::TOO_MANY_REQUESTS),
+            other => panic!("expected rate limit error, got {other:?}"),
+        }
+        assert_eq!(server.await.unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn modify_by_client_id() {
+        const ORDER: &str = include_str!("../tests/fixtures/order_update.json");
+        let (address, server) = serve(vec![
+            (
+                "200 OK",
+                include_str!("../tests/fixtures/system_config.json").to_string(),
+            ),
+            (
+                "200 OK",
+                include_str!("../tests/fixtures/jwt_token.json").to_string(),
+            ),
+            ("200 OK", ORDER.to_string()),
+            ("200 OK", ORDER.replace("123456", "123457")),
+        ])
+        .await;
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new(url, Some("0x1234".into())).await.unwrap();
+        let result = client
+            .modify_order_by_client_id(
+                "x1234/a b",
+                ModifyOrderRequest {
+                    id: String::new(),
+                    market: "BTC-USD-PERP".into(),
+                    price: Some(crate::decimal::parse_decimal("29500").unwrap()),
+                    side: crate::structs::Side::BUY,
+                    size: crate::decimal::parse_decimal("0.1").unwrap(),
+                    order_type: crate::structs::OrderType::LIMIT,
+                },
+            )
+            .await
+            .unwrap();
+        match &result {
+            ModifyOrderResult::Replaced { replaced_id, order } => {
+                assert_eq!(replaced_id, "123456");
+                assert_eq!(order.id, "123457");
+            }
+            other => panic!("expected a replacement, got {other:?}"),
         }
+        assert_eq!(
+            server.await.unwrap()[2..],
+            [
+                "GET /v1/orders/by_client_id/x1234%2Fa%20b HTTP/1.1",
+                "PUT /v1/orders/123456 HTTP/1.1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn subaccount() {
+        let (address, server) = serve(vec![
+            ("200 OK", include_str!("../tests/fixtures/system_config.json")),
+            ("200 OK", include_str!("../tests/fixtures/jwt_token.json")),
+            (
+                "200 OK",
+                r#"{"results":[
+                    {"account":"0x5b2","parent_account":"0x1","public_key":"0x2","created_at":1700000000002},
+                    {"account":"0x5b1","parent_account":"0x1","public_key":"0x2","created_at":1700000000001}
+                ]}"#,
+            ),
+        ])
+        .await;
+
+        let url = URL::custom(format!("http://{address}"), format!("ws://{address}/v1"));
+        let client = Client::new_with_subaccount(url, "0x1234".into(), 1)
+            .await
+            .unwrap();
+        assert_eq!(client.account(), Some(Felt::from_hex_unchecked("0x5b2")));
+        assert_eq!(
+            server.await.unwrap(),
+            vec![
+                "GET /v1/system/config HTTP/1.1",
+                "POST /v1/auth HTTP/1.1",
+                "GET /v1/account/subaccounts HTTP/1.1",
+            ]
+        );
+
+        let api_token = Client::with_api_token(reqwest::Client::new(), URL::Testnet, "t".into());
+        assert!(matches!(
+            api_token.for_subaccount(Felt::ONE),
+            Err(Error::MissingPrivateKey)
+        ));
     }
 }
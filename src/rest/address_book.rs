@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+
+use starknet_core::types::Felt;
+
+use crate::error::{Error, Result};
+
+/// Destinations funds may be withdrawn to, see `Client::with_withdrawal_whitelist`
+///
+/// Addresses are compared as numbers, so their case and leading zeros do not matter.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AddressBook {
+    /// Labels by normalized address
+    entries: BTreeMap<String, String>,
+}
+
+impl AddressBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an address under a label, e.g. the name of the wallet
+    pub fn address(mut self, label: impl Into<String>, address: &str) -> Self {
+        self.entries.insert(normalize(address), label.into());
+        self
+    }
+
+    /// Label of an address in the book
+    pub fn label(&self, address: &str) -> Option<&str> {
+        self.entries.get(&normalize(address)).map(String::as_str)
+    }
+
+    pub fn contains(&self, address: &str) -> bool {
+        self.label(address).is_some()
+    }
+
+    /// Fail with `Error::WithdrawalNotWhitelisted` unless the address is in the book
+    pub(crate) fn check(&self, address: &str) -> Result<()> {
+        if self.contains(address) {
+            Ok(())
+        } else {
+            Err(Error::WithdrawalNotWhitelisted(address.to_string()))
+        }
+    }
+}
+
+fn normalize(address: &str) -> String {
+    let address = address.trim();
+    Felt::from_hex(address).map_or_else(|_| address.to_lowercase(), |felt| format!("{felt:#x}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitelist() {
+        let book = AddressBook::new()
+            .address(
+                "cold wallet",
+                "0x00Ab5801a7D398351b8bE11C439e05C5B3259aeC9B",
+            )
+            .address("treasury", "0x1234");
+        assert_eq!(
+            book.label("0xab5801a7d398351b8be11c439e05c5b3259aec9b"),
+            Some("cold wallet")
+        );
+        assert!(book.contains(" 0x0000000000000000000000000000000000001234"));
+        assert!(book.check("0x1234").is_ok());
+        assert!(matches!(
+            book.check("0x1235"),
+            Err(Error::WithdrawalNotWhitelisted(address)) if address == "0x1235"
+        ));
+        assert!(AddressBook::new().check("0x1234").is_err());
+    }
+}
@@ -0,0 +1,45 @@
+use futures_util::future::BoxFuture;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::error::Result;
+
+/// An operation which must be approved before it is sent, see `Client::with_approver`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum SensitiveOperation {
+    /// Withdrawal from Paraclear, to the account's L2 balance or bridged on to `l1_recipient`
+    Withdrawal {
+        token: String,
+        amount: Decimal,
+        l1_recipient: Option<String>,
+    },
+    /// Deposit from `l1_sender` on Ethereum into Paraclear through the token's bridge
+    Deposit {
+        token: String,
+        amount: Decimal,
+        l1_sender: String,
+    },
+    /// Change of the leverage or margin type of a market
+    MarginUpdate {
+        market: String,
+        leverage: u64,
+        margin_type: String,
+    },
+    /// Cancel every open order, or every open order of `market`
+    CancelAll { market: Option<String> },
+}
+
+/// Decides whether sensitive operations may proceed
+///
+/// Implementations typically forward the operation to an external approval service and wait
+/// for a second person to confirm it, so that no single key holder can move funds or change
+/// risk settings alone.
+pub trait Approver: Send + Sync + 'static {
+    /// Approve an operation, Ok(false) rejecting it
+    ///
+    /// # Errors
+    ///
+    /// If the approval cannot be obtained, e.g. when the approval service is unreachable, which
+    /// fails the operation with this error
+    fn approve<'a>(&'a self, operation: &'a SensitiveOperation) -> BoxFuture<'a, Result<bool>>;
+}
@@ -0,0 +1,85 @@
+use std::io::{BufReader, Read};
+
+use bytes::Bytes;
+use tokio::sync::mpsc::{Receiver, channel};
+
+use crate::error::{Error, Result};
+
+/// Chunks of the body kept in flight between the connection and the deserializer
+const CHUNKS_IN_FLIGHT: usize = 16;
+
+/// Blocking reader over the chunks of a response body received on a channel
+///
+/// serde_json reads a byte at a time, so the reader is wrapped in a `BufReader`.
+struct ChunkReader {
+    chunks: Receiver<Bytes>,
+    current: Bytes,
+    read: usize,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.current.is_empty() {
+            match self.chunks.blocking_recv() {
+                Some(chunk) => self.current = chunk,
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.current.len());
+        buf[..len].copy_from_slice(&self.current.split_to(len));
+        self.read += len;
+        Ok(len)
+    }
+}
+
+/// Deserialize a successful response as its body arrives instead of buffering it whole
+///
+/// Parsing runs on the blocking thread pool, holding at most a few chunks of the body in
+/// memory besides the value being built.
+///
+/// # Errors
+///
+/// * `Error::RestError` if the body cannot be received
+/// * `Error::RestEmptyResponse` if the body is empty
+/// * `Error::DeserializationError` if the body is not a valid `T`
+pub(crate) async fn deserialize_streamed<T>(mut response: reqwest::Response) -> Result<T>
+where
+    T: for<'de> serde::Deserialize<'de> + Send + 'static,
+{
+    let (sender, chunks) = channel(CHUNKS_IN_FLIGHT);
+    let parser = tokio::task::spawn_blocking(move || {
+        let mut reader = BufReader::new(ChunkReader {
+            chunks,
+            current: Bytes::new(),
+            read: 0,
+        });
+        let result = serde_json::from_reader::<_, T>(&mut reader);
+        (result, reader.get_ref().read)
+    });
+    let mut received = Ok(());
+    loop {
+        match response.chunk().await {
+            // The parser stops reading once it fails, dropping the receiver
+            Ok(Some(chunk)) => {
+                if sender.send(chunk).await.is_err() {
+                    break;
+                }
+            }
+            Ok(None) => break,
+            Err(e) => {
+                received = Err(Error::RestError(e.to_string()));
+                break;
+            }
+        }
+    }
+    drop(sender);
+    let (result, read) = parser.await.map_err(|e| Error::RestError(e.to_string()))?;
+    received?;
+    match result {
+        Ok(value) => Ok(value),
+        Err(_) if read == 0 => Err(Error::RestEmptyResponse),
+        Err(e) => Err(Error::DeserializationError(format!(
+            "Streamed body of {read} bytes Error: {e:?}"
+        ))),
+    }
+}
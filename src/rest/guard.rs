@@ -0,0 +1,190 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rust_decimal::Decimal;
+
+use crate::error::{Error, GuardViolation, Result};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits checked on the client before orders are signed and sent
+///
+/// Guards protect against bugs in strategy code, such as a misplaced decimal point or an order
+/// loop gone wild, by failing the request with `Error::OrderGuardViolation` instead of sending
+/// it. They are not a substitute for the venue's own risk checks.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrderGuard {
+    /// Largest price times size of a single order, in quote currency
+    pub max_notional: Option<Decimal>,
+    /// Order creations allowed within any 60 second window
+    pub max_orders_per_minute: Option<u32>,
+}
+
+impl OrderGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_notional(mut self, max_notional: Decimal) -> Self {
+        self.max_notional = Some(max_notional);
+        self
+    }
+
+    pub fn max_orders_per_minute(mut self, max_orders_per_minute: u32) -> Self {
+        self.max_orders_per_minute = Some(max_orders_per_minute);
+        self
+    }
+}
+
+/// An OrderGuard along with the times of recent orders, shared between clones of a Client
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GuardState {
+    guard: OrderGuard,
+    sent: Arc<Mutex<VecDeque<Instant>>>,
+}
+
+impl GuardState {
+    pub(crate) fn new(guard: OrderGuard) -> Self {
+        Self {
+            guard,
+            sent: Arc::default(),
+        }
+    }
+
+    /// Whether orders are checked against a notional limit, so they need a price
+    pub(crate) fn limits_notional(&self) -> bool {
+        self.guard.max_notional.is_some()
+    }
+
+    /// Check the notional of an order priced at `price`
+    ///
+    /// Orders without a price are rejected while a notional limit is set, since they cannot be
+    /// checked against it.
+    pub(crate) fn check_notional(
+        &self,
+        market: &str,
+        price: Option<Decimal>,
+        size: Decimal,
+    ) -> Result<()> {
+        let Some(limit) = self.guard.max_notional else {
+            return Ok(());
+        };
+        let Some(price) = price else {
+            return Err(Error::OrderGuardViolation(GuardViolation::Unpriced {
+                market: market.to_string(),
+            }));
+        };
+        let notional = price.checked_mul(size).map_or(Decimal::MAX, |n| n.abs());
+        if notional > limit {
+            return Err(Error::OrderGuardViolation(GuardViolation::MaxNotional {
+                market: market.to_string(),
+                notional,
+                limit,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Count `orders` new orders against the rate limit, all or none of them
+    ///
+    /// Orders are counted before they are signed and sent, so concurrent requests cannot
+    /// exceed the limit together. Orders which then fail to be signed or are rejected by the
+    /// venue are uncounted with `release_orders`, see `is_rejection`.
+    pub(crate) fn record_orders(&self, orders: usize) -> Result<()> {
+        self.record_orders_at(orders, Instant::now())
+    }
+
+    /// Uncount `orders` of the most recently counted orders, after they failed to be placed
+    pub(crate) fn release_orders(&self, orders: usize) {
+        if self.guard.max_orders_per_minute.is_none() {
+            return;
+        }
+        let mut sent = self.sent.lock().unwrap();
+        let kept = sent.len().saturating_sub(orders);
+        sent.truncate(kept);
+    }
+
+    fn record_orders_at(&self, orders: usize, now: Instant) -> Result<()> {
+        let Some(limit) = self.guard.max_orders_per_minute else {
+            return Ok(());
+        };
+        let mut sent = self.sent.lock().unwrap();
+        while sent
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= WINDOW)
+        {
+            sent.pop_front();
+        }
+        if sent.len() + orders > limit as usize {
+            return Err(Error::OrderGuardViolation(
+                GuardViolation::MaxOrdersPerMinute { limit },
+            ));
+        }
+        sent.extend(std::iter::repeat_n(now, orders));
+        Ok(())
+    }
+}
+
+/// Whether an order request failed because the venue rejected it, so the order is known not
+/// to rest and may be uncounted with `release_orders`
+///
+/// Transport errors and timeouts leave the fate of the order unknown, and server errors may
+/// come after it was placed, so these keep it counted.
+pub(crate) fn is_rejection(error: &Error) -> bool {
+    match error {
+        Error::ParadexError { status_code, .. } | Error::HTTPError { status_code } => {
+            status_code.is_client_error()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notional() {
+        let state = GuardState::new(OrderGuard::new().max_notional(Decimal::from(1000)));
+        assert!(
+            state
+                .check_notional("BTC-USD-PERP", Some(Decimal::from(100)), Decimal::from(10))
+                .is_ok()
+        );
+        assert_eq!(
+            state
+                .check_notional("BTC-USD-PERP", Some(Decimal::from(100)), Decimal::from(11))
+                .unwrap_err()
+                .to_string(),
+            "Order Guard Violation: notional 1100 of order on BTC-USD-PERP exceeds the limit of 1000"
+        );
+        assert!(matches!(
+            state.check_notional("BTC-USD-PERP", None, Decimal::from(11)),
+            Err(Error::OrderGuardViolation(GuardViolation::Unpriced { .. }))
+        ));
+        assert!(
+            GuardState::default()
+                .check_notional("BTC-USD-PERP", None, Decimal::MAX)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn orders_per_minute() {
+        let state = GuardState::new(OrderGuard::new().max_orders_per_minute(3));
+        let start = Instant::now();
+        state.record_orders_at(2, start).unwrap();
+        assert!(matches!(
+            state.record_orders_at(2, start),
+            Err(Error::OrderGuardViolation(
+                GuardViolation::MaxOrdersPerMinute { limit: 3 }
+            ))
+        ));
+        state.clone().record_orders_at(1, start).unwrap();
+        assert!(state.record_orders_at(1, start + WINDOW / 2).is_err());
+        state.release_orders(1);
+        state.record_orders_at(1, start + WINDOW / 2).unwrap();
+        state.record_orders_at(2, start + WINDOW).unwrap();
+    }
+}
@@ -0,0 +1,144 @@
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::structs::{ModifyOrder, Order, OrderFlags, OrderInstruction, OrderType, Side};
+
+/// Kind of an outbound order request
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum OrderAction {
+    Create,
+    Modify,
+    Cancel,
+    /// Cancel every open order, or every open order of `OrderEvent::market`
+    CancelAll,
+}
+
+/// Connection an order request was sent over
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum OrderTransport {
+    Rest,
+}
+
+/// An outbound order request, without its signature, see `Client::with_order_log`
+///
+/// Fields which do not apply to the action, such as the price of a cancel, are None.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OrderEvent {
+    pub action: OrderAction,
+    pub transport: OrderTransport,
+    /// When the request was sent, in milliseconds since the epoch
+    pub sent_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub market: Option<String>,
+    /// Id of the order modified or cancelled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side: Option<Side>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_type: Option<OrderType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instruction: Option<OrderInstruction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trigger_price: Option<Decimal>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<OrderFlags>,
+}
+
+impl OrderEvent {
+    fn new(action: OrderAction, transport: OrderTransport) -> Self {
+        let sent_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        Self {
+            action,
+            transport,
+            sent_at,
+            market: None,
+            order_id: None,
+            client_id: None,
+            side: None,
+            order_type: None,
+            instruction: None,
+            price: None,
+            size: None,
+            trigger_price: None,
+            flags: Vec::new(),
+        }
+    }
+
+    pub(crate) fn create(order: &Order, transport: OrderTransport) -> Self {
+        Self {
+            market: Some(order.market.clone()),
+            client_id: order.client_id.clone(),
+            side: Some(order.side),
+            order_type: Some(order.order_type),
+            instruction: Some(order.instruction.clone()),
+            price: order.price,
+            size: Some(order.size),
+            trigger_price: order.trigger_price,
+            flags: order.flags.clone(),
+            ..Self::new(OrderAction::Create, transport)
+        }
+    }
+
+    pub(crate) fn modify(order: &ModifyOrder, transport: OrderTransport) -> Self {
+        Self {
+            market: Some(order.market.clone()),
+            order_id: Some(order.id.clone()),
+            side: Some(order.side),
+            order_type: Some(order.order_type),
+            price: order.price,
+            size: Some(order.size),
+            ..Self::new(OrderAction::Modify, transport)
+        }
+    }
+
+    pub(crate) fn cancel(
+        order_id: Option<&str>,
+        client_id: Option<&str>,
+        transport: OrderTransport,
+    ) -> Self {
+        Self {
+            order_id: order_id.map(str::to_string),
+            client_id: client_id.map(str::to_string),
+            ..Self::new(OrderAction::Cancel, transport)
+        }
+    }
+
+    pub(crate) fn cancel_all(market: Option<&str>) -> Self {
+        Self {
+            market: market.map(str::to_string),
+            ..Self::new(OrderAction::CancelAll, OrderTransport::Rest)
+        }
+    }
+}
+
+/// Sink receiving an OrderEvent for every outbound order request
+pub type OrderSink = Arc<dyn Fn(&OrderEvent) + Send + Sync>;
+
+/// The optional order sink of a Client, shared between its clones
+#[derive(Clone, Default)]
+pub(crate) struct OrderLog(Option<OrderSink>);
+
+impl OrderLog {
+    pub(crate) fn new(sink: OrderSink) -> Self {
+        Self(Some(sink))
+    }
+
+    /// Deliver the event built by `event`, which is only built when there is a sink
+    pub(crate) fn record(&self, event: impl FnOnce() -> OrderEvent) {
+        if let Some(sink) = &self.0 {
+            sink(&event());
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::error::{Error, Result};
+
+/// How rate limited requests are retried
+///
+/// A rate limited request was rejected before being processed, so retrying it is safe for
+/// every endpoint including order creation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt before the rate limit error is returned
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for every retry after it
+    pub initial_backoff: Duration,
+    /// Upper bound of the wait between retries, including waits requested by `Retry-After`
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before retry number `retry`, counted from 0
+    pub(crate) fn backoff(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+        retry_after
+            .unwrap_or_else(|| {
+                self.initial_backoff
+                    .saturating_mul(2u32.saturating_pow(retry))
+            })
+            .min(self.max_backoff)
+    }
+}
+
+/// Whether a response rejected the request for exceeding a rate limit
+pub(crate) fn is_rate_limited(status: StatusCode, text: &str) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || text.to_lowercase().contains("rate limit exceeded")
+}
+
+/// Wait requested by the `Retry-After` header, in seconds
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.)
+        .map(Duration::from_secs_f64)
+}
+
+/// Groups of endpoints sharing a rate limit on the venue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointClass {
+    /// Unauthenticated market data and system endpoints
+    Public,
+    /// Authenticated account endpoints other than order entry
+    Private,
+    /// Creating, modifying and cancelling orders
+    Orders,
+}
+
+/// Token bucket limit of an endpoint class
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    burst: u32,
+    per_second: f64,
+}
+
+impl RateLimit {
+    /// # Parameters
+    ///
+    /// * `burst` - Requests that can be sent back to back after a quiet period
+    /// * `per_second` - Sustained requests per second
+    ///
+    /// # Errors
+    ///
+    /// `Error::InvalidConfiguration` if `burst` is zero or `per_second` is not a positive
+    /// number
+    pub fn new(burst: u32, per_second: f64) -> Result<Self> {
+        if burst == 0 {
+            return Err(Error::InvalidConfiguration(
+                "rate limit burst must be at least 1".into(),
+            ));
+        }
+        if !(per_second.is_finite() && per_second > 0.) {
+            return Err(Error::InvalidConfiguration(format!(
+                "rate limit of {per_second} requests per second is not positive"
+            )));
+        }
+        Ok(Self { burst, per_second })
+    }
+
+    /// Requests that can be sent back to back after a quiet period
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// Sustained requests per second
+    pub fn per_second(&self) -> f64 {
+        self.per_second
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// Take a token, returning how long to wait until it is available
+    ///
+    /// Tokens are taken even when they are not available yet, so concurrent callers queue up
+    /// behind each other instead of all waking at the same time.
+    fn take(&mut self, now: Instant) -> Duration {
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.limit.per_second).min(f64::from(self.limit.burst));
+        self.refilled_at = now;
+        self.tokens -= 1.;
+        if self.tokens >= 0. {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.limit.per_second)
+        }
+    }
+}
+
+/// Client side token buckets keyed per endpoint class, shared between clones of a Client
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RateLimiter {
+    buckets: HashMap<EndpointClass, Arc<Mutex<TokenBucket>>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn set(&mut self, class: EndpointClass, limit: RateLimit) {
+        self.buckets.insert(
+            class,
+            Arc::new(Mutex::new(TokenBucket {
+                limit,
+                tokens: f64::from(limit.burst),
+                refilled_at: Instant::now(),
+            })),
+        );
+    }
+
+    /// Wait until a request of `class` may be sent
+    pub(crate) async fn acquire(&self, class: EndpointClass) {
+        let Some(bucket) = self.buckets.get(&class) else {
+            return;
+        };
+        let wait = bucket.lock().await.take(Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+        assert_eq!(policy.backoff(0, None), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2, None), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4, None), Duration::from_secs(1));
+        assert_eq!(
+            policy.backoff(0, Some(Duration::from_secs(30))),
+            Duration::from_secs(1)
+        );
+        assert_eq!(policy.backoff(3, Some(Duration::ZERO)), Duration::ZERO);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "2".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(2)));
+        headers.insert(
+            RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn token_bucket() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket {
+            limit: RateLimit::new(2, 4.).unwrap(),
+            tokens: 2.,
+            refilled_at: start,
+        };
+        assert_eq!(bucket.take(start), Duration::ZERO);
+        assert_eq!(bucket.take(start), Duration::ZERO);
+        // Queued behind each other while the bucket is empty
+        assert_eq!(bucket.take(start), Duration::from_millis(250));
+        assert_eq!(bucket.take(start), Duration::from_millis(500));
+        // Refilled up to the burst size only
+        let later = start + Duration::from_secs(10);
+        assert_eq!(bucket.take(later), Duration::ZERO);
+        assert_eq!(bucket.take(later), Duration::ZERO);
+        assert_eq!(bucket.take(later), Duration::from_millis(250));
+
+        for (burst, per_second) in [
+            (0, 1.),
+            (1, 0.),
+            (1, -1.),
+            (1, f64::NAN),
+            (1, f64::INFINITY),
+        ] {
+            assert!(matches!(
+                RateLimit::new(burst, per_second),
+                Err(Error::InvalidConfiguration(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::error::{Error, Result};
+use crate::structs::SystemStatus;
+
+/// Polls the system state on a fixed interval until stopped or dropped, see
+/// `Client::poll_system_state`
+///
+/// Order flow is only gated on the system status while a poller is running.
+pub struct SystemStatusPoller {
+    task: JoinHandle<()>,
+    gate: StatusGate,
+}
+
+impl SystemStatusPoller {
+    pub(crate) fn new(task: JoinHandle<()>, gate: StatusGate) -> Self {
+        Self { task, gate }
+    }
+
+    /// Stop polling, same as dropping the poller
+    pub fn stop(self) {}
+}
+
+impl Drop for SystemStatusPoller {
+    fn drop(&mut self) {
+        self.task.abort();
+        self.gate.clear();
+    }
+}
+
+/// A polled status and the instant it goes stale
+#[derive(Debug)]
+struct Polled {
+    status: SystemStatus,
+    expires_at: Instant,
+}
+
+/// The latest polled system status, shared between clones of a Client
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StatusGate {
+    status: Arc<watch::Sender<Option<SystemStatus>>>,
+    /// The status order flow is checked against, set only by a running poller
+    polled: Arc<Mutex<Option<Polled>>>,
+}
+
+impl StatusGate {
+    pub(crate) fn get(&self) -> Option<SystemStatus> {
+        self.status.borrow().clone()
+    }
+
+    /// Record a polled status which gates order flow for `ttl`, returning the previous status
+    ///
+    /// Watchers are only notified when the status changed.
+    pub(crate) fn record(&self, status: SystemStatus, ttl: Duration) -> Option<SystemStatus> {
+        self.record_at(status, Instant::now() + ttl)
+    }
+
+    fn record_at(&self, status: SystemStatus, expires_at: Instant) -> Option<SystemStatus> {
+        *self.polled.lock().unwrap() = Some(Polled {
+            status: status.clone(),
+            expires_at,
+        });
+        let mut previous = None;
+        self.status.send_if_modified(|current| {
+            previous = current.replace(status.clone());
+            previous.as_ref() != Some(&status)
+        });
+        previous
+    }
+
+    /// Stop gating order flow, keeping the last status for watchers
+    pub(crate) fn clear(&self) {
+        self.polled.lock().unwrap().take();
+    }
+
+    pub(crate) fn watch(&self) -> watch::Receiver<Option<SystemStatus>> {
+        self.status.subscribe()
+    }
+
+    /// Fail order creations and modifications while the venue does not accept them
+    ///
+    /// Passes when no poller is running and once the polled status went stale, leaving the
+    /// venue to decide.
+    pub(crate) fn check_order_flow(&self) -> Result<()> {
+        self.check_order_flow_at(Instant::now())
+    }
+
+    fn check_order_flow_at(&self, now: Instant) -> Result<()> {
+        match self.polled.lock().unwrap().as_ref() {
+            Some(Polled {
+                status: status @ (SystemStatus::CancelOnly | SystemStatus::Maintenance),
+                expires_at,
+            }) if now < *expires_at => Err(Error::OrderFlowSuspended(status.clone())),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_only() {
+        let gate = StatusGate::default();
+        let start = Instant::now();
+        let ttl = Duration::from_secs(90);
+        assert!(gate.check_order_flow_at(start).is_ok());
+        gate.record_at(SystemStatus::CancelOnly, start + ttl);
+        assert!(matches!(
+            gate.clone().check_order_flow_at(start),
+            Err(Error::OrderFlowSuspended(SystemStatus::CancelOnly))
+        ));
+        assert_eq!(
+            gate.record_at(SystemStatus::Ok, start + ttl),
+            Some(SystemStatus::CancelOnly)
+        );
+        assert!(gate.check_order_flow_at(start).is_ok());
+
+        gate.record_at(SystemStatus::Maintenance, start + ttl);
+        assert!(gate.check_order_flow_at(start).is_err());
+        // A stale status no longer gates
+        assert!(gate.check_order_flow_at(start + ttl).is_ok());
+
+        gate.record_at(SystemStatus::Maintenance, start + ttl);
+        gate.clear();
+        assert!(gate.check_order_flow_at(start).is_ok());
+        assert_eq!(gate.get(), Some(SystemStatus::Maintenance));
+    }
+
+    #[test]
+    fn transitions() {
+        let gate = StatusGate::default();
+        let ttl = Duration::from_secs(90);
+        let mut watcher = gate.watch();
+        gate.record(SystemStatus::Ok, ttl);
+        assert!(watcher.has_changed().unwrap());
+        assert_eq!(*watcher.borrow_and_update(), Some(SystemStatus::Ok));
+        // Polling the same status again is not a transition
+        gate.record(SystemStatus::Ok, ttl);
+        assert!(!watcher.has_changed().unwrap());
+        gate.record(SystemStatus::CancelOnly, ttl);
+        assert_eq!(*watcher.borrow_and_update(), Some(SystemStatus::CancelOnly));
+    }
+}
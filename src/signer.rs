@@ -0,0 +1,38 @@
+//! Signing of Paradex messages with keys held outside the process.
+//!
+//! The [`Client`](crate::rest::Client) signs orders, auth requests, onboarding and
+//! transactions through a [`StarkSigner`], which only has to turn a message hash into a Stark
+//! signature. A local `SigningKey` is one, and `Client::new_with_signer` accepts any other,
+//! e.g. one forwarding the hash to a KMS, an HSM or a remote co-signer.
+
+use futures_util::future::BoxFuture;
+use starknet_core::types::Felt;
+use starknet_crypto::Signature;
+use starknet_signers::SigningKey;
+
+use crate::error::{Error, Result};
+
+/// Signs message hashes with a Stark key pair
+pub trait StarkSigner: Send + Sync + 'static {
+    /// Public key of the key pair, from which the account address is derived
+    fn public_key(&self) -> Felt;
+
+    /// Sign a message hash
+    ///
+    /// # Errors
+    ///
+    /// If the hash cannot be signed, e.g. when a remote signer is unreachable or refuses it
+    fn sign(&self, hash: Felt) -> BoxFuture<'_, Result<Signature>>;
+}
+
+impl StarkSigner for SigningKey {
+    fn public_key(&self) -> Felt {
+        self.verifying_key().scalar()
+    }
+
+    fn sign(&self, hash: Felt) -> BoxFuture<'_, Result<Signature>> {
+        let signature =
+            SigningKey::sign(self, &hash).map_err(|e| Error::StarknetError(e.to_string()));
+        Box::pin(async move { signature })
+    }
+}
@@ -0,0 +1,313 @@
+//! Simulated execution behind the [`ParadexApi`] trait.
+//!
+//! [`SimClient`] fills orders locally with a [`PaperExchange`], fed from the BBO or order book
+//! deltas channel and the trades channel of each market, so strategy code written against
+//! [`ParadexApi`] can be pointed at simulated execution without changes. The simulated order
+//! and fill updates are delivered to listeners registered with [`SimClient::on_event`] instead
+//! of the private websocket channels.
+//!
+//! The matching is that of [`PaperExchange`], which
+//! [`BacktestExchange`](crate::backtest::BacktestExchange) drives from a recording instead:
+//! use the latter to replay market data, and [`SimClient`] to trade against live market data.
+
+use std::sync::{Arc, Mutex};
+
+use log::warn;
+
+use crate::api::ParadexApi;
+use crate::error::Result;
+use crate::exchange::Exchange;
+use crate::listeners::Listeners;
+use crate::paper::{PaperEvent, PaperExchange};
+use crate::structs::{
+    BBO, CancelByMarketResponse, MarketSummaryStatic, ModifyOrderRequest, ModifyOrderResult,
+    OrderBook, OrderBookResponse, OrderRequest, OrderUpdate, Trade,
+};
+use crate::ws::{
+    BboSubscription, ChannelEvent, Identifier, OrderBookDeltasSubscription, TradesSubscription,
+};
+
+/// Market data channel the simulated books are built from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BookFeed {
+    /// Top of book only, resting orders queue behind the whole size shown at their price
+    #[default]
+    Bbo,
+    /// Full depth from the order book deltas channel
+    OrderBook,
+}
+
+/// Client filling orders against locally simulated order books
+///
+/// Clones share the same simulated venue.
+#[derive(Clone)]
+pub struct SimClient {
+    paper: Arc<Mutex<PaperExchange>>,
+    markets: Arc<Vec<MarketSummaryStatic>>,
+    listeners: Listeners<PaperEvent>,
+}
+
+impl SimClient {
+    /// Simulate trading on `markets`, which are returned by `markets()`
+    pub fn new(paper: PaperExchange, markets: Vec<MarketSummaryStatic>) -> Self {
+        Self {
+            paper: Arc::new(Mutex::new(paper)),
+            markets: Arc::new(markets),
+            listeners: Listeners::default(),
+        }
+    }
+
+    /// Call `callback` with every simulated order and fill update
+    pub fn on_event(&self, callback: impl Fn(&PaperEvent) + Send + Sync + 'static) {
+        self.listeners.push(callback);
+    }
+
+    /// Inspect the simulated venue
+    pub fn with_paper<R>(&self, f: impl FnOnce(&PaperExchange) -> R) -> R {
+        f(&self.paper.lock().unwrap())
+    }
+
+    /// Feed the book and trades channels of `markets` into the simulation
+    ///
+    /// # Returns
+    ///
+    /// The identifiers of the subscriptions
+    ///
+    /// # Errors
+    ///
+    /// If any subscription cannot be made
+    pub async fn attach<E: Exchange>(
+        &self,
+        exchange: &E,
+        markets: &[&str],
+        feed: BookFeed,
+    ) -> Result<Vec<Identifier>> {
+        let mut identifiers = Vec::new();
+        for market in markets {
+            let sim = self.clone();
+            identifiers.push(match feed {
+                BookFeed::Bbo => {
+                    exchange
+                        .subscribe_typed(BboSubscription::new(*market), move |event| {
+                            if let ChannelEvent::Data(bbo) = event
+                                && let Err(e) = sim.on_bbo(bbo)
+                            {
+                                warn!("Could not apply BBO to simulated book: {e}");
+                            }
+                        })
+                        .await?
+                }
+                BookFeed::OrderBook => {
+                    exchange
+                        .subscribe_typed(OrderBookDeltasSubscription::new(*market), move |event| {
+                            if let ChannelEvent::Data(update) = event
+                                && let Err(e) = sim.on_order_book(update)
+                            {
+                                warn!("Could not apply order book update to simulated book: {e}");
+                            }
+                        })
+                        .await?
+                }
+            });
+            let sim = self.clone();
+            identifiers.push(
+                exchange
+                    .subscribe_typed(TradesSubscription::new(*market), move |event| {
+                        if let ChannelEvent::Data(trade) = event {
+                            sim.on_trade(trade);
+                        }
+                    })
+                    .await?,
+            );
+        }
+        Ok(identifiers)
+    }
+
+    /// Replace a market's book with the top of book
+    ///
+    /// # Errors
+    ///
+    /// If a price or size is not a number
+    pub fn on_bbo(&self, bbo: &BBO) -> Result<()> {
+        let level = |price: f64, size: f64| {
+            (price.is_finite() && size > 0.).then(|| (price.to_string(), size.to_string()))
+        };
+        let snapshot = OrderBookResponse {
+            asks: level(bbo.ask, bbo.ask_size).into_iter().collect(),
+            bids: level(bbo.bid, bbo.bid_size).into_iter().collect(),
+            last_updated_at: bbo.last_updated_at,
+            market: bbo.market.clone(),
+            seq_no: bbo.last_updated_at,
+        };
+        self.apply(|paper| paper.on_order_book_snapshot(&snapshot))
+    }
+
+    /// Apply an update from the order book deltas channel
+    ///
+    /// # Errors
+    ///
+    /// `Error::OrderBookSequenceGap` if the update does not follow the last applied one
+    pub fn on_order_book(&self, update: &OrderBook) -> Result<()> {
+        self.apply(|paper| paper.on_order_book(update))
+    }
+
+    /// Match resting orders against a trade
+    pub fn on_trade(&self, trade: &Trade) {
+        self.apply(|paper| paper.on_trade(trade));
+    }
+
+    /// Run `f` on the simulated venue, then deliver the updates it produced
+    fn apply<R>(&self, f: impl FnOnce(&mut PaperExchange) -> R) -> R {
+        let (result, events) = {
+            let mut paper = self.paper.lock().unwrap();
+            let result = f(&mut paper);
+            (result, paper.take_events())
+        };
+        for event in &events {
+            self.listeners.notify(event);
+        }
+        result
+    }
+}
+
+impl ParadexApi for SimClient {
+    async fn create_order(&self, order_request: OrderRequest) -> Result<OrderUpdate> {
+        self.apply(|paper| paper.place_order(order_request))
+    }
+
+    /// The simulated venue replaces the order with a new one keeping its client id, see
+    /// `PaperExchange::modify_order`
+    async fn modify_order(
+        &self,
+        modify_order_request: ModifyOrderRequest,
+    ) -> Result<ModifyOrderResult> {
+        self.apply(|paper| paper.modify_order(modify_order_request))
+    }
+
+    async fn cancel_order(&self, order_id: String) -> Result<()> {
+        self.apply(|paper| paper.cancel_order(&order_id).map(|_| ()))
+    }
+
+    async fn cancel_all_orders(&self) -> Result<Vec<String>> {
+        let cancelled = self.apply(|paper| paper.cancel_all_orders(None));
+        Ok(cancelled.into_iter().map(|order| order.id).collect())
+    }
+
+    async fn cancel_all_orders_for_market(&self, market: String) -> Result<CancelByMarketResponse> {
+        let cancelled = self.apply(|paper| paper.cancel_all_orders(Some(&market)));
+        Ok(CancelByMarketResponse {
+            message: format!("{} orders cancelled", cancelled.len()),
+            market,
+        })
+    }
+
+    async fn open_orders(&self) -> Result<Vec<OrderUpdate>> {
+        Ok(self.paper.lock().unwrap().open_orders(None))
+    }
+
+    async fn bbo(&self, market_symbol: String) -> Result<BBO> {
+        self.paper.lock().unwrap().bbo(&market_symbol)
+    }
+
+    async fn markets(&self) -> Result<Vec<MarketSummaryStatic>> {
+        Ok(self.markets.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::parse_decimal;
+    use crate::structs::{OrderInstruction, OrderStatus, OrderType, Side};
+
+    const MARKET: &str = "BTC-USD-PERP";
+
+    fn bbo(bid: f64, ask: f64, last_updated_at: u64) -> BBO {
+        BBO {
+            bid,
+            bid_size: 1.,
+            ask,
+            ask_size: 1.,
+            market: MARKET.into(),
+            last_updated_at,
+            seq_no: 0,
+        }
+    }
+
+    /// Strategy code only knowing about the trait
+    async fn join_bid<A: ParadexApi>(api: &A) -> Result<OrderUpdate> {
+        let bbo = api.bbo(MARKET.into()).await?;
+        api.create_order(OrderRequest {
+            instruction: OrderInstruction::POST_ONLY,
+            market: MARKET.into(),
+            price: Some(parse_decimal(&bbo.bid.to_string())?),
+            side: Side::BUY,
+            size: parse_decimal("0.5")?,
+            order_type: OrderType::LIMIT,
+            client_id: Some("bid".into()),
+            flags: vec![],
+            recv_window: None,
+            stp: None,
+            trigger_price: None,
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn fills_against_streamed_data() {
+        let sim = SimClient::new(PaperExchange::new(), Vec::new());
+        let fills = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&fills);
+        sim.on_event(move |event| {
+            if let PaperEvent::Fill(fill) = event {
+                recorded.lock().unwrap().push((fill.price, fill.size));
+            }
+        });
+        assert!(join_bid(&sim).await.is_err());
+
+        sim.on_bbo(&bbo(99., 100., 1000)).unwrap();
+        let order = join_bid(&sim).await.unwrap();
+        assert_eq!(order.status, OrderStatus::OPEN);
+        assert_eq!(sim.bbo(MARKET.into()).await.unwrap().ask, 100.);
+
+        let modified = sim
+            .modify_order(ModifyOrderRequest {
+                id: order.id.clone(),
+                market: MARKET.into(),
+                price: Some(parse_decimal("98").unwrap()),
+                side: Side::BUY,
+                size: parse_decimal("0.5").unwrap(),
+                order_type: OrderType::LIMIT,
+            })
+            .await
+            .unwrap();
+        assert!(modified.is_replaced());
+        assert_eq!(modified.order().client_id, "bid");
+
+        // A rejected replacement leaves the order resting
+        let rejected = sim
+            .modify_order(ModifyOrderRequest {
+                id: modified.order().id.clone(),
+                market: MARKET.into(),
+                price: None,
+                side: Side::BUY,
+                size: parse_decimal("0.5").unwrap(),
+                order_type: OrderType::LIMIT,
+            })
+            .await;
+        assert!(rejected.is_err());
+        let open = sim.open_orders().await.unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].id, modified.order().id);
+
+        let mut trade: Trade =
+            serde_json::from_str(include_str!("../tests/fixtures/trade.json")).unwrap();
+        trade.market = MARKET.into();
+        trade.side = Side::SELL;
+        trade.price = 97.;
+        sim.on_trade(&trade);
+        assert_eq!(*fills.lock().unwrap(), vec![(98., 0.5)]);
+        assert!(sim.open_orders().await.unwrap().is_empty());
+        assert!(sim.cancel_all_orders().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,80 @@
+//! Stark curve key helpers shared by the client, onboarding and user code.
+
+use starknet_crypto::{Felt, get_public_key};
+
+use crate::error::{Error, Result};
+
+/// Order of the STARK curve, as a big-endian hex string
+pub(crate) const STARK_EC_ORDER_HEX: &str =
+    "0800000000000010ffffffffffffffffb781126dcae7b2321e66a241adc64d2f";
+
+/// Order of the STARK curve. Valid private keys lie in `[1, STARK_EC_ORDER)`.
+pub const STARK_EC_ORDER: Felt = Felt::from_hex_unchecked(STARK_EC_ORDER_HEX);
+
+/// Whether a private key is a valid, non-zero scalar below the curve order
+pub fn is_valid_private_key(private_key: &Felt) -> bool {
+    // Compare the canonical big-endian bytes, Felt's Ord is not guaranteed to be numeric
+    *private_key != Felt::ZERO && private_key.to_bytes_be() < STARK_EC_ORDER.to_bytes_be()
+}
+
+/// Parse a hex encoded private key, checking it is within the curve order
+///
+/// # Errors
+///
+/// If the string is not valid hex or the key is zero or not below the curve order
+pub fn parse_private_key(hex_str: &str) -> Result<Felt> {
+    let private_key = Felt::from_hex(hex_str).map_err(|e| Error::StarknetError(e.to_string()))?;
+    if is_valid_private_key(&private_key) {
+        Ok(private_key)
+    } else {
+        Err(Error::StarknetError(
+            "private key must be non-zero and below the STARK curve order".into(),
+        ))
+    }
+}
+
+/// Compute the Stark public key for a private key
+pub fn public_key(private_key: &Felt) -> Felt {
+    get_public_key(private_key)
+}
+
+/// Format a key as Paradex expects it: lowercase `0x` prefixed hex without leading zeros
+pub fn format_key(key: &Felt) -> String {
+    key.to_hex_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn private_key_range() {
+        assert!(!is_valid_private_key(&Felt::ZERO));
+        assert!(is_valid_private_key(&Felt::ONE));
+        assert!(is_valid_private_key(&(STARK_EC_ORDER - Felt::ONE)));
+        assert!(!is_valid_private_key(&STARK_EC_ORDER));
+        assert!(!is_valid_private_key(&Felt::MAX));
+
+        assert!(parse_private_key("0x0").is_err());
+        assert!(parse_private_key("not hex").is_err());
+        assert!(parse_private_key(&format_key(&STARK_EC_ORDER)).is_err());
+    }
+
+    #[test]
+    fn public_key_and_format() {
+        let private_key =
+            parse_private_key("0x549aa9cb8328a12b1394f99f9430ba2dbc2b5c26b8a4c3b9d2b3ca3765669b2")
+                .unwrap();
+        assert_eq!(
+            format_key(&private_key),
+            "0x549aa9cb8328a12b1394f99f9430ba2dbc2b5c26b8a4c3b9d2b3ca3765669b2"
+        );
+        assert_eq!(
+            public_key(&private_key),
+            starknet_signers::SigningKey::from_secret_scalar(private_key)
+                .verifying_key()
+                .scalar()
+        );
+        assert_eq!(format_key(&Felt::from(255)), "0xff");
+    }
+}
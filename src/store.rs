@@ -0,0 +1,218 @@
+//! Durable storage for SDK state.
+//!
+//! Components which need to pick up where they left off after a restart, such as the
+//! [`FillProcessor`](crate::backfill::FillProcessor) offset or the orders of an
+//! [`OrderTracker`](crate::tracker::OrderTracker), keep their state in a [`StateStore`] as
+//! blobs under a namespace and key. [`MemoryStateStore`] and [`FileStateStore`] are provided;
+//! embedders can implement the trait over Redis, Postgres or similar.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::error::{Error, Result};
+
+/// Key/value storage of namespaced blobs
+pub trait StateStore: Send + Sync + 'static {
+    /// The blob stored under the key, None if there is none
+    fn get(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>>> + Send;
+
+    /// Store a blob under the key, replacing any previous blob
+    fn put(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: Vec<u8>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn delete(&self, namespace: &str, key: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// Get a blob and deserialize it from JSON
+    fn get_json<T: DeserializeOwned>(
+        &self,
+        namespace: &str,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<T>>> + Send {
+        async move {
+            match self.get(namespace, key).await? {
+                Some(blob) => serde_json::from_slice(&blob)
+                    .map(Some)
+                    .map_err(|e| Error::DeserializationError(e.to_string())),
+                None => Ok(None),
+            }
+        }
+    }
+
+    /// Serialize a value to JSON and put it
+    fn put_json<T: Serialize + Sync>(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &T,
+    ) -> impl Future<Output = Result<()>> + Send {
+        async move {
+            let blob =
+                serde_json::to_vec(value).map_err(|e| Error::JsonParseError(e.to_string()))?;
+            self.put(namespace, key, blob).await
+        }
+    }
+}
+
+type Blobs = HashMap<(String, String), Vec<u8>>;
+
+/// State kept in memory, for tests and processes that do not need to resume
+///
+/// Clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStateStore(Arc<Mutex<Blobs>>);
+
+impl MemoryStateStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StateStore for MemoryStateStore {
+    async fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .0
+            .lock()
+            .unwrap()
+            .get(&(namespace.to_string(), key.to_string()))
+            .cloned())
+    }
+
+    async fn put(&self, namespace: &str, key: &str, value: Vec<u8>) -> Result<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .insert((namespace.to_string(), key.to_string()), value);
+        Ok(())
+    }
+
+    async fn delete(&self, namespace: &str, key: &str) -> Result<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .remove(&(namespace.to_string(), key.to_string()));
+        Ok(())
+    }
+}
+
+/// State stored as one file per key in a directory per namespace, replaced atomically on
+/// every put
+#[derive(Clone, Debug)]
+pub struct FileStateStore {
+    directory: PathBuf,
+}
+
+impl FileStateStore {
+    /// Store state below `directory`, which is created on the first put
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// Namespaces and keys become path components, so they are restricted to ASCII
+    /// alphanumerics, `-`, `_` and `.` and must not start with a `.`
+    fn path(&self, namespace: &str, key: &str) -> Result<PathBuf> {
+        for component in [namespace, key] {
+            if component.is_empty()
+                || component.starts_with('.')
+                || !component
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            {
+                return Err(Error::StateStoreError(format!(
+                    "Invalid namespace or key {component:?}"
+                )));
+            }
+        }
+        Ok(self.directory.join(namespace).join(key))
+    }
+}
+
+impl StateStore for FileStateStore {
+    async fn get(&self, namespace: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        match tokio::fs::read(self.path(namespace, key)?).await {
+            Ok(blob) => Ok(Some(blob)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::StateStoreError(e.to_string())),
+        }
+    }
+
+    async fn put(&self, namespace: &str, key: &str, value: Vec<u8>) -> Result<()> {
+        let path = self.path(namespace, key)?;
+        let temporary = path.with_file_name(format!(".{key}.tmp"));
+        tokio::fs::create_dir_all(self.directory.join(namespace))
+            .await
+            .map_err(|e| Error::StateStoreError(e.to_string()))?;
+        tokio::fs::write(&temporary, value)
+            .await
+            .map_err(|e| Error::StateStoreError(e.to_string()))?;
+        tokio::fs::rename(&temporary, &path)
+            .await
+            .map_err(|e| Error::StateStoreError(e.to_string()))
+    }
+
+    async fn delete(&self, namespace: &str, key: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.path(namespace, key)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(Error::StateStoreError(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn exercise(store: impl StateStore) {
+        assert_eq!(store.get("fills", "offset").await.unwrap(), None);
+        store.put("fills", "offset", b"1".to_vec()).await.unwrap();
+        store
+            .put_json("orders", "offset", &vec![1, 2])
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get("fills", "offset").await.unwrap(),
+            Some(b"1".to_vec())
+        );
+        assert_eq!(
+            store
+                .get_json::<Vec<u32>>("orders", "offset")
+                .await
+                .unwrap(),
+            Some(vec![1, 2])
+        );
+        store.delete("fills", "offset").await.unwrap();
+        store.delete("fills", "offset").await.unwrap();
+        assert_eq!(store.get("fills", "offset").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn memory_store() {
+        exercise(MemoryStateStore::new()).await;
+    }
+
+    #[tokio::test]
+    async fn file_store() {
+        let directory = std::env::temp_dir().join(format!("paradex-state-{}", std::process::id()));
+        let store = FileStateStore::new(&directory);
+        exercise(store.clone()).await;
+        assert!(store.put("../fills", "offset", Vec::new()).await.is_err());
+        assert!(store.get("fills", ".offset.tmp").await.is_err());
+        assert!(store.get("fills", "").await.is_err());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
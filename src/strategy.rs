@@ -0,0 +1,300 @@
+//! Event loop scaffold for trading strategies.
+//!
+//! Implement [`Strategy`] and hand it to a [`StrategyRunner`], which subscribes to the
+//! configured channels on an [`Exchange`], dispatches their messages to the strategy one at a
+//! time, fires a periodic timer and unsubscribes again once the shutdown future completes.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{info, warn};
+use tokio::sync::mpsc::unbounded_channel;
+
+use crate::error::Result;
+use crate::exchange::Exchange;
+use crate::structs::{Fill, OrderUpdate};
+use crate::ws::{CallbackFn, Channel, Message};
+
+/// Callbacks of a trading strategy, all of which default to doing nothing
+///
+/// Returning an error from any callback stops the runner.
+pub trait Strategy<E: Exchange>: Send {
+    /// Market data message from one of the subscribed public channels
+    fn on_tick(
+        &mut self,
+        _exchange: &E,
+        _message: &Message,
+    ) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    fn on_order_update(
+        &mut self,
+        _exchange: &E,
+        _update: &OrderUpdate,
+    ) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    fn on_fill(&mut self, _exchange: &E, _fill: &Fill) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    /// Called every timer interval, see `StrategyRunner::timer`
+    fn on_timer(&mut self, _exchange: &E) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+}
+
+/// Drives a Strategy from the subscriptions of an Exchange
+pub struct StrategyRunner<E: Exchange> {
+    exchange: E,
+    channels: Vec<Channel>,
+    timer_interval: Option<Duration>,
+}
+
+impl<E: Exchange> StrategyRunner<E> {
+    pub fn new(exchange: E) -> Self {
+        Self {
+            exchange,
+            channels: Vec::new(),
+            timer_interval: None,
+        }
+    }
+
+    /// Subscribe to a channel while running. Orders and fills channels are dispatched to
+    /// `on_order_update` and `on_fill`, every other channel to `on_tick`.
+    pub fn subscribe(mut self, channel: Channel) -> Self {
+        self.channels.push(channel);
+        self
+    }
+
+    /// Call `on_timer` every `interval`
+    pub fn timer(mut self, interval: Duration) -> Self {
+        self.timer_interval = Some(interval);
+        self
+    }
+
+    /// Run the strategy until `shutdown` completes or a callback returns an error
+    ///
+    /// Messages are dispatched in the order they were received, and a callback is never
+    /// called while another one is still running. Queued messages are handled before
+    /// `shutdown` is polled, so when `shutdown` drives the data itself, e.g. a
+    /// `BacktestExchange::replay`, each replayed message is handled before the next one.
+    ///
+    /// # Returns
+    ///
+    /// The strategy, so its final state can be inspected
+    ///
+    /// # Errors
+    ///
+    /// If subscribing fails or a strategy callback returns an error. Subscriptions made by the
+    /// runner are removed in either case.
+    pub async fn run<S: Strategy<E>>(
+        self,
+        mut strategy: S,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<S> {
+        let (sender, mut receiver) = unbounded_channel();
+        let mut identifiers = Vec::new();
+        let mut result = Ok(());
+        for channel in self.channels.iter().cloned() {
+            let sender = sender.clone();
+            let callback: CallbackFn = Arc::new(move |message: &Message| {
+                // The receiver is only dropped once the runner has stopped
+                let _ = sender.send(message.clone());
+            });
+            match self.exchange.subscribe(channel, callback).await {
+                Ok(identifier) => identifiers.push(identifier),
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+
+        if result.is_ok() {
+            let mut timer = self.timer_interval.map(|interval| {
+                let mut timer = tokio::time::interval(interval);
+                timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                timer
+            });
+            tokio::pin!(shutdown);
+            result = loop {
+                let step = tokio::select! {
+                    biased;
+
+                    Some(message) = receiver.recv() => {
+                        Self::dispatch(&self.exchange, &mut strategy, &message).await
+                    }
+                    _ = &mut shutdown => {
+                        info!("Stopping strategy");
+                        break Ok(());
+                    }
+                    _ = async {
+                        match timer.as_mut() {
+                            Some(timer) => timer.tick().await,
+                            None => std::future::pending().await,
+                        }
+                    } => strategy.on_timer(&self.exchange).await,
+                };
+                if let Err(e) = step {
+                    warn!("Stopping strategy after error {e:?}");
+                    break Err(e);
+                }
+            };
+        }
+
+        for identifier in identifiers {
+            if let Err(e) = self.exchange.unsubscribe(identifier).await {
+                warn!("Could not unsubscribe {identifier:?} while stopping strategy: {e:?}");
+            }
+        }
+        result.map(|_| strategy)
+    }
+
+    async fn dispatch<S: Strategy<E>>(
+        exchange: &E,
+        strategy: &mut S,
+        message: &Message,
+    ) -> Result<()> {
+        match message {
+            Message::Connected
+            | Message::Disconnected
+            | Message::Unsubscribed
+            | Message::AuthRefreshed
+            | Message::Reconnecting { .. } => Ok(()),
+            Message::Error(e) | Message::AuthFailed(e) | Message::ConnectionFailed(e) => {
+                warn!("Subscription error {e:?}");
+                Ok(())
+            }
+            Message::Orders(update) => strategy.on_order_update(exchange, update).await,
+            Message::Fills(fill) => strategy.on_fill(exchange, fill).await,
+            _ => strategy.on_tick(exchange, message).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::ParadexApi;
+    use crate::backtest::{BacktestExchange, RecordedMessage};
+    use crate::decimal::parse_decimal;
+    use crate::paper::PaperExchange;
+    use crate::structs::{OrderInstruction, OrderRequest, OrderStatus, OrderType, Side};
+    use serde_json::json;
+
+    const MARKET: &str = "BTC-USD-PERP";
+
+    /// Joins the bid on the first book update and counts what happens to the order
+    #[derive(Default)]
+    struct JoinBid {
+        ticks: usize,
+        order_updates: Vec<OrderStatus>,
+        filled: f64,
+    }
+
+    impl Strategy<BacktestExchange> for JoinBid {
+        async fn on_tick(&mut self, exchange: &BacktestExchange, _message: &Message) -> Result<()> {
+            self.ticks += 1;
+            if self.ticks == 1 {
+                exchange
+                    .create_order(OrderRequest {
+                        instruction: OrderInstruction::GTC,
+                        market: MARKET.into(),
+                        price: Some(parse_decimal("99").unwrap()),
+                        side: Side::BUY,
+                        size: parse_decimal("1").unwrap(),
+                        order_type: OrderType::LIMIT,
+                        client_id: None,
+                        flags: vec![],
+                        recv_window: None,
+                        stp: None,
+                        trigger_price: None,
+                    })
+                    .await?;
+            }
+            Ok(())
+        }
+
+        async fn on_order_update(
+            &mut self,
+            _exchange: &BacktestExchange,
+            update: &OrderUpdate,
+        ) -> Result<()> {
+            self.order_updates.push(update.status.clone());
+            Ok(())
+        }
+
+        async fn on_fill(&mut self, _exchange: &BacktestExchange, fill: &Fill) -> Result<()> {
+            self.filled += fill.size;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn backtest_strategy() {
+        let exchange = BacktestExchange::new(PaperExchange::new());
+        let recording = vec![
+            RecordedMessage {
+                received_at: 1000,
+                channel: "order_book.BTC-USD-PERP.deltas".into(),
+                data: json!({
+                    "seq_no": 1,
+                    "market": MARKET,
+                    "last_updated_at": 1000,
+                    "update_type": "s",
+                    "deletes": [],
+                    "inserts": [
+                        {"side": "BUY", "price": "99", "size": "2"},
+                        {"side": "SELL", "price": "100", "size": "1"},
+                    ],
+                    "updates": [],
+                }),
+            },
+            RecordedMessage {
+                received_at: 2000,
+                channel: "trades.BTC-USD-PERP".into(),
+                data: json!({
+                    "created_at": 2000,
+                    "id": "1",
+                    "market": MARKET,
+                    "price": "98",
+                    "side": "SELL",
+                    "size": "3",
+                    "trade_type": "FILL",
+                }),
+            },
+        ];
+        let runner = StrategyRunner::new(exchange.clone())
+            .subscribe(Channel::OrderBookDeltas {
+                market_symbol: MARKET.into(),
+            })
+            .subscribe(Channel::Trades {
+                market_symbol: MARKET.into(),
+            })
+            .subscribe(Channel::Orders {
+                market_symbol: None,
+            })
+            .subscribe(Channel::Fills {
+                market_symbol: None,
+            });
+        let replay = exchange.clone();
+        let strategy = runner
+            .run(JoinBid::default(), async move {
+                replay.replay(recording.into_iter().map(Ok)).await.unwrap();
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(strategy.ticks, 2);
+        assert_eq!(
+            strategy.order_updates,
+            vec![OrderStatus::OPEN, OrderStatus::CLOSED]
+        );
+        assert_eq!(strategy.filled, 1.);
+        assert!(exchange.open_orders().await.unwrap().is_empty());
+    }
+}
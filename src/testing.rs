@@ -0,0 +1,49 @@
+//! Fixture-based values shared by the unit tests of the account and order trackers.
+
+use crate::structs::{
+    AccountInformation, AccountStatus, Fill, OrderStatus, OrderUpdate, Position, Positions,
+};
+
+/// Account with a maintenance margin requirement of 1000
+pub(crate) fn account(
+    seq_no: u64,
+    account_value: f64,
+    status: AccountStatus,
+) -> AccountInformation {
+    let mut account: AccountInformation =
+        serde_json::from_str(include_str!("../tests/fixtures/account_information.json")).unwrap();
+    account.seq_no = seq_no;
+    account.account_value = account_value;
+    account.maintenance_margin_requirement = 1000.;
+    account.margin_cushion = account_value - 1000.;
+    account.status = status;
+    account
+}
+
+/// The first position of the positions fixture
+pub(crate) fn position(seq_no: u64, size: f64) -> Position {
+    let positions: Positions =
+        serde_json::from_str(include_str!("../tests/fixtures/positions.json")).unwrap();
+    let mut position = positions.results[0].clone();
+    position.seq_no = seq_no;
+    position.size = size;
+    position
+}
+
+/// The order update fixture with another id, sequence number and status
+pub(crate) fn order_update(id: &str, seq_no: u64, status: OrderStatus) -> OrderUpdate {
+    let mut update: OrderUpdate =
+        serde_json::from_str(include_str!("../tests/fixtures/order_update.json")).unwrap();
+    update.id = id.into();
+    update.seq_no = seq_no;
+    update.status = status;
+    update
+}
+
+/// The fill fixture with another id and creation time
+pub(crate) fn fill(id: &str, created_at: u64) -> Fill {
+    let mut fill: Fill = serde_json::from_str(include_str!("../tests/fixtures/fill.json")).unwrap();
+    fill.id = id.into();
+    fill.created_at = created_at;
+    fill
+}
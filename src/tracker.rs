@@ -0,0 +1,579 @@
+//! Consistent view of the account's orders.
+//!
+//! [`OrderTracker`] merges open order snapshots from the rest API with the orders and fills
+//! websocket channels. Updates are applied per order in `seq_no` order, falling back to
+//! `last_updated_at` for equal sequence numbers, so duplicates and updates arriving after a
+//! newer one are dropped, and fills arriving before their order are held back until the order
+//! is known. Closing is final: once an order is closed, or forgotten after closing, no update
+//! reopens it.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{trace, warn};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::exchange::Exchange;
+use crate::listeners::Listeners;
+use crate::store::StateStore;
+use crate::structs::{Fill, OrderStatus, OrderUpdate};
+use crate::ws::{ChannelEvent, FillsSubscription, Identifier, OrdersSubscription};
+
+/// Namespace of the saved orders in the state store
+pub const ORDERS_NAMESPACE: &str = "orders";
+
+/// Ids of forgotten closed orders remembered to drop late updates for them
+const FORGOTTEN_CAPACITY: usize = 10_000;
+
+/// Lifecycle state of an order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OrderState {
+    /// Accepted, but not yet on the book
+    New,
+    /// Conditional order waiting for its trigger price
+    Untriggered,
+    Open,
+    /// On the book with part of its size filled
+    PartiallyFilled,
+    Closed,
+}
+
+impl OrderState {
+    fn of(update: &OrderUpdate) -> Self {
+        match update.status {
+            OrderStatus::NEW => OrderState::New,
+            OrderStatus::UNTRIGGERED => OrderState::Untriggered,
+            OrderStatus::OPEN if update.remaining_size < update.size => OrderState::PartiallyFilled,
+            OrderStatus::OPEN => OrderState::Open,
+            OrderStatus::CLOSED => OrderState::Closed,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        *self == OrderState::Closed
+    }
+}
+
+/// An order along with the fills received for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedOrder {
+    pub state: OrderState,
+    /// Latest update of the order
+    pub update: OrderUpdate,
+    pub fills: Vec<Fill>,
+}
+
+impl TrackedOrder {
+    /// Size filled according to the latest order update
+    pub fn filled_size(&self) -> Decimal {
+        self.update.size - self.update.remaining_size
+    }
+}
+
+#[derive(Default)]
+struct TrackerState {
+    orders: HashMap<String, TrackedOrder>,
+    /// Fills of orders which have not been seen yet, by order id
+    pending_fills: HashMap<String, Vec<Fill>>,
+    fill_ids: HashSet<String>,
+    /// Closed orders removed by `remove_closed`, oldest first
+    forgotten: VecDeque<String>,
+    forgotten_ids: HashSet<String>,
+}
+
+/// Tracks the state of the account's orders from rest snapshots and websocket updates
+///
+/// Clones share the same state.
+#[derive(Clone, Default)]
+pub struct OrderTracker {
+    state: Arc<Mutex<TrackerState>>,
+    listeners: Listeners<TrackedOrder>,
+    /// Held while listeners are called, so changes of an order are reported in order
+    delivery: Arc<Mutex<()>>,
+}
+
+impl OrderTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `callback` with the new state of an order every time it changes
+    ///
+    /// Changes of an order are reported in the order they were applied, also when updates
+    /// are fed from several threads; a change superseded by a newer one before it could be
+    /// reported is skipped. The callback must not feed updates into the tracker itself.
+    pub fn on_change(&self, callback: impl Fn(&TrackedOrder) + Send + Sync + 'static) {
+        self.listeners.push(callback);
+    }
+
+    /// Feed the orders and fills channels of all markets into the tracker
+    ///
+    /// # Returns
+    ///
+    /// The identifiers of the orders and fills subscriptions
+    ///
+    /// # Errors
+    ///
+    /// If either subscription cannot be made
+    pub async fn attach<E: Exchange>(&self, exchange: &E) -> Result<Vec<Identifier>> {
+        let tracker = self.clone();
+        let orders = exchange
+            .subscribe_typed(OrdersSubscription::all(), move |event| match event {
+                ChannelEvent::Data(update) => tracker.on_order_update(update),
+                ChannelEvent::Error(e) => warn!("Orders subscription error {e:?}"),
+                _ => {}
+            })
+            .await?;
+        let tracker = self.clone();
+        let fills = exchange
+            .subscribe_typed(FillsSubscription::all(), move |event| match event {
+                ChannelEvent::Data(fill) => tracker.on_fill(fill),
+                ChannelEvent::Error(e) => warn!("Fills subscription error {e:?}"),
+                _ => {}
+            })
+            .await?;
+        Ok(vec![orders, fills])
+    }
+
+    /// Reconcile the tracker with the open orders of the exchange
+    ///
+    /// Call after `attach` and after every reconnect, since updates sent while disconnected
+    /// are lost.
+    ///
+    /// # Errors
+    ///
+    /// If the open orders cannot be retrieved
+    pub async fn sync<E: Exchange>(&self, exchange: &E) -> Result<()> {
+        let requested_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| Error::TimeError(e.to_string()))?
+            .as_millis() as u64;
+        let open_orders = exchange.open_orders().await?;
+        self.apply_snapshot(&open_orders, requested_at);
+        Ok(())
+    }
+
+    /// Apply a snapshot of all open orders taken at `requested_at`, in milliseconds
+    ///
+    /// Orders in the snapshot are applied like websocket updates. Tracked orders which are
+    /// still open but missing from the snapshot were closed while the tracker was not
+    /// listening and are marked closed, unless they were updated after `requested_at`.
+    pub fn apply_snapshot(&self, open_orders: &[OrderUpdate], requested_at: u64) {
+        let mut changed = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            for update in open_orders {
+                changed.extend(state.apply(update));
+            }
+            let open: HashSet<&str> = open_orders.iter().map(|order| order.id.as_str()).collect();
+            for order in state.orders.values_mut() {
+                if !order.state.is_closed()
+                    && !open.contains(order.update.id.as_str())
+                    && order.update.last_updated_at < requested_at
+                {
+                    order.state = OrderState::Closed;
+                    order.update.status = OrderStatus::CLOSED;
+                    changed.push(order.clone());
+                }
+            }
+        }
+        self.notify(&changed);
+    }
+
+    /// Apply an order update, ignoring it if a newer update of the order was applied already
+    pub fn on_order_update(&self, update: &OrderUpdate) {
+        let changed = self.state.lock().unwrap().apply(update);
+        self.notify(changed.as_slice());
+    }
+
+    /// Record a fill, ignoring fills which were recorded already
+    pub fn on_fill(&self, fill: &Fill) {
+        let changed = {
+            let mut state = self.state.lock().unwrap();
+            if !state.fill_ids.insert(fill.id.clone()) {
+                return;
+            }
+            match state.orders.get_mut(&fill.order_id) {
+                Some(order) => {
+                    order.fills.push(fill.clone());
+                    order.clone()
+                }
+                None => {
+                    state
+                        .pending_fills
+                        .entry(fill.order_id.clone())
+                        .or_default()
+                        .push(fill.clone());
+                    return;
+                }
+            }
+        };
+        self.notify(&[changed]);
+    }
+
+    /// Save the tracked orders to `store` under `key`, e.g. the account address
+    ///
+    /// # Errors
+    ///
+    /// If the orders cannot be stored
+    pub async fn save<S: StateStore>(&self, store: &S, key: &str) -> Result<()> {
+        let orders: Vec<TrackedOrder> = self
+            .state
+            .lock()
+            .unwrap()
+            .orders
+            .values()
+            .cloned()
+            .collect();
+        store.put_json(ORDERS_NAMESPACE, key, &orders).await
+    }
+
+    /// Restore the orders saved under `key`, keeping tracked orders with newer updates
+    ///
+    /// Call `sync` afterwards, since orders may have changed while they were not tracked.
+    ///
+    /// # Returns
+    ///
+    /// The number of restored orders
+    ///
+    /// # Errors
+    ///
+    /// If the orders cannot be loaded
+    pub async fn restore<S: StateStore>(&self, store: &S, key: &str) -> Result<usize> {
+        let orders: Vec<TrackedOrder> = store
+            .get_json(ORDERS_NAMESPACE, key)
+            .await?
+            .unwrap_or_default();
+        let changed: Vec<TrackedOrder> = {
+            let mut state = self.state.lock().unwrap();
+            orders
+                .into_iter()
+                .filter_map(|order| state.restore(order))
+                .collect()
+        };
+        self.notify(&changed);
+        Ok(changed.len())
+    }
+
+    pub fn order(&self, order_id: &str) -> Option<TrackedOrder> {
+        self.state.lock().unwrap().orders.get(order_id).cloned()
+    }
+
+    pub fn order_by_client_id(&self, client_id: &str) -> Option<TrackedOrder> {
+        self.state
+            .lock()
+            .unwrap()
+            .orders
+            .values()
+            .filter(|order| order.update.client_id == client_id)
+            .max_by_key(|order| order.update.created_at)
+            .cloned()
+    }
+
+    /// Orders of a market, both open and closed, oldest first
+    pub fn orders_for_market(&self, market: &str) -> Vec<TrackedOrder> {
+        self.collect(|order| order.update.market == market)
+    }
+
+    /// Orders which are not closed, oldest first
+    pub fn open_orders(&self) -> Vec<TrackedOrder> {
+        self.collect(|order| !order.state.is_closed())
+    }
+
+    /// Orders of a market which are not closed, oldest first
+    pub fn open_orders_for_market(&self, market: &str) -> Vec<TrackedOrder> {
+        self.collect(|order| !order.state.is_closed() && order.update.market == market)
+    }
+
+    /// Forget closed orders, returning how many were removed
+    pub fn remove_closed(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let TrackerState {
+            orders, fill_ids, ..
+        } = &mut *state;
+        let before = orders.len();
+        let mut removed = Vec::new();
+        orders.retain(|id, order| {
+            if order.state.is_closed() {
+                for fill in &order.fills {
+                    fill_ids.remove(&fill.id);
+                }
+                removed.push(id.clone());
+            }
+            !order.state.is_closed()
+        });
+        let count = before - orders.len();
+        for id in removed {
+            state.forget(id);
+        }
+        count
+    }
+
+    fn collect(&self, filter: impl Fn(&TrackedOrder) -> bool) -> Vec<TrackedOrder> {
+        let mut orders: Vec<TrackedOrder> = self
+            .state
+            .lock()
+            .unwrap()
+            .orders
+            .values()
+            .filter(|order| filter(order))
+            .cloned()
+            .collect();
+        orders.sort_by_key(|order| (order.update.created_at, order.update.seq_no));
+        orders
+    }
+
+    fn notify(&self, changed: &[TrackedOrder]) {
+        if changed.is_empty() {
+            return;
+        }
+        let _delivery = self.delivery.lock().unwrap();
+        for order in changed {
+            let current = self
+                .state
+                .lock()
+                .unwrap()
+                .orders
+                .get(&order.update.id)
+                .is_some_and(|tracked| {
+                    tracked.update.seq_no == order.update.seq_no && tracked.state == order.state
+                });
+            // A newer change of the order was applied and is reported by its own caller
+            if !current {
+                continue;
+            }
+            self.listeners.notify(order);
+        }
+    }
+}
+
+impl TrackerState {
+    /// Apply an update, returning the new state of the order if it changed
+    fn apply(&mut self, update: &OrderUpdate) -> Option<TrackedOrder> {
+        if self.forgotten_ids.contains(&update.id) {
+            trace!(
+                "Dropping update {} of forgotten order {}",
+                update.seq_no, update.id
+            );
+            return None;
+        }
+        if let Some(order) = self.orders.get_mut(&update.id) {
+            if (update.seq_no, update.last_updated_at)
+                <= (order.update.seq_no, order.update.last_updated_at)
+            {
+                return None;
+            }
+            if order.state.is_closed() && update.status != OrderStatus::CLOSED {
+                warn!(
+                    "Dropping {:?} update {} of closed order {}",
+                    update.status, update.seq_no, update.id
+                );
+                return None;
+            }
+            order.state = OrderState::of(update);
+            order.update = update.clone();
+            return Some(order.clone());
+        }
+        let order = TrackedOrder {
+            state: OrderState::of(update),
+            update: update.clone(),
+            fills: self.pending_fills.remove(&update.id).unwrap_or_default(),
+        };
+        self.orders.insert(update.id.clone(), order.clone());
+        Some(order)
+    }
+
+    /// Remember a removed closed order so that late updates do not bring it back
+    fn forget(&mut self, id: String) {
+        if self.forgotten_ids.insert(id.clone()) {
+            self.forgotten.push_back(id);
+        }
+        while self.forgotten.len() > FORGOTTEN_CAPACITY {
+            if let Some(oldest) = self.forgotten.pop_front() {
+                self.forgotten_ids.remove(&oldest);
+            }
+        }
+    }
+
+    /// Insert a saved order unless a newer update of it was applied already
+    fn restore(&mut self, mut order: TrackedOrder) -> Option<TrackedOrder> {
+        let id = order.update.id.clone();
+        if self
+            .orders
+            .get(&id)
+            .is_some_and(|tracked| tracked.update.seq_no >= order.update.seq_no)
+        {
+            return None;
+        }
+        for fill in self.pending_fills.remove(&id).unwrap_or_default() {
+            if !order.fills.iter().any(|known| known.id == fill.id) {
+                order.fills.push(fill);
+            }
+        }
+        self.fill_ids
+            .extend(order.fills.iter().map(|fill| fill.id.clone()));
+        self.orders.insert(id, order.clone());
+        Some(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::parse_decimal;
+    use crate::testing;
+
+    fn update(id: &str, seq_no: u64, status: OrderStatus, remaining_size: &str) -> OrderUpdate {
+        OrderUpdate {
+            client_id: format!("client-{id}"),
+            last_updated_at: seq_no,
+            size: parse_decimal("1").unwrap(),
+            remaining_size: parse_decimal(remaining_size).unwrap(),
+            ..testing::order_update(id, seq_no, status)
+        }
+    }
+
+    fn fill(id: &str, order_id: &str) -> Fill {
+        Fill {
+            order_id: order_id.into(),
+            ..testing::fill(id, 1)
+        }
+    }
+
+    #[test]
+    fn lifecycle() {
+        let tracker = OrderTracker::new();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = changes.clone();
+        tracker.on_change(move |order| {
+            recorded
+                .lock()
+                .unwrap()
+                .push((order.update.id.clone(), order.state))
+        });
+
+        // The fill arrives before the order it belongs to
+        tracker.on_fill(&fill("f1", "1"));
+        tracker.on_order_update(&update("1", 1, OrderStatus::NEW, "1"));
+        tracker.on_order_update(&update("1", 3, OrderStatus::OPEN, "0.4"));
+        // Stale and duplicate updates are dropped
+        tracker.on_order_update(&update("1", 2, OrderStatus::OPEN, "1"));
+        tracker.on_order_update(&update("1", 3, OrderStatus::OPEN, "0.4"));
+        tracker.on_fill(&fill("f1", "1"));
+        tracker.on_fill(&fill("f2", "1"));
+
+        let order = tracker.order_by_client_id("client-1").unwrap();
+        assert_eq!(order.state, OrderState::PartiallyFilled);
+        assert_eq!(order.filled_size(), parse_decimal("0.6").unwrap());
+        assert_eq!(order.fills.len(), 2);
+        assert_eq!(
+            *changes.lock().unwrap(),
+            vec![
+                ("1".to_string(), OrderState::New),
+                ("1".to_string(), OrderState::PartiallyFilled),
+                ("1".to_string(), OrderState::PartiallyFilled),
+            ]
+        );
+
+        tracker.on_order_update(&update("1", 4, OrderStatus::CLOSED, "0"));
+        assert!(tracker.open_orders().is_empty());
+        assert_eq!(tracker.orders_for_market("BTC-USD-PERP").len(), 1);
+        assert_eq!(tracker.remove_closed(), 1);
+        assert!(tracker.order("1").is_none());
+    }
+
+    #[test]
+    fn snapshot_reconciliation() {
+        let tracker = OrderTracker::new();
+        tracker.on_order_update(&update("1", 10, OrderStatus::OPEN, "1"));
+        tracker.on_order_update(&update("2", 20, OrderStatus::OPEN, "1"));
+        tracker.on_order_update(&update("3", 40, OrderStatus::NEW, "1"));
+
+        // Order 2 closed while disconnected, order 3 was created after the snapshot was taken
+        tracker.apply_snapshot(
+            &[
+                update("1", 15, OrderStatus::OPEN, "0.5"),
+                update("4", 25, OrderStatus::OPEN, "1"),
+            ],
+            30,
+        );
+
+        assert_eq!(
+            tracker.order("1").unwrap().state,
+            OrderState::PartiallyFilled
+        );
+        assert_eq!(tracker.order("2").unwrap().state, OrderState::Closed);
+        assert_eq!(tracker.order("3").unwrap().state, OrderState::New);
+        let open: Vec<String> = tracker
+            .open_orders()
+            .into_iter()
+            .map(|order| order.update.id)
+            .collect();
+        assert_eq!(open, vec!["1", "4", "3"]);
+    }
+
+    #[test]
+    fn closed_orders_stay_closed() {
+        let tracker = OrderTracker::new();
+        tracker.on_order_update(&update("1", 1, OrderStatus::OPEN, "1"));
+        tracker.on_order_update(&update("1", 2, OrderStatus::CLOSED, "1"));
+        // A late OPEN with a newer sequence number, e.g. from a reconnected stream
+        tracker.on_order_update(&update("1", 3, OrderStatus::OPEN, "1"));
+        let mut tie = update("1", 2, OrderStatus::OPEN, "1");
+        tie.last_updated_at = 1;
+        tracker.on_order_update(&tie);
+        assert_eq!(tracker.order("1").unwrap().state, OrderState::Closed);
+
+        assert_eq!(tracker.remove_closed(), 1);
+        tracker.on_order_update(&update("1", 1, OrderStatus::OPEN, "1"));
+        tracker.apply_snapshot(&[update("1", 1, OrderStatus::OPEN, "1")], 0);
+        assert!(tracker.order("1").is_none());
+
+        // Changes of an order fed from several threads are reported in sequence
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        tracker.on_change(move |order| {
+            if order.update.id == "2" {
+                recorded.lock().unwrap().push(order.update.seq_no);
+            }
+        });
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let tracker = &tracker;
+                scope.spawn(move || {
+                    for seq_no in (1..=200).filter(|seq_no| seq_no % 4 == thread) {
+                        tracker.on_order_update(&update("2", seq_no, OrderStatus::OPEN, "1"));
+                    }
+                });
+            }
+        });
+        let seen = seen.lock().unwrap();
+        assert!(seen.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(seen.last(), Some(&200));
+    }
+
+    #[tokio::test]
+    async fn save_and_restore() {
+        let store = crate::store::MemoryStateStore::new();
+        let tracker = OrderTracker::new();
+        tracker.on_order_update(&update("1", 10, OrderStatus::OPEN, "1"));
+        tracker.on_order_update(&update("2", 20, OrderStatus::OPEN, "0.5"));
+        tracker.on_fill(&fill("f1", "2"));
+        tracker.save(&store, "account").await.unwrap();
+
+        let restored = OrderTracker::new();
+        // An update received before restoring is newer than the saved order
+        restored.on_order_update(&update("1", 11, OrderStatus::CLOSED, "0"));
+        restored.on_fill(&fill("f2", "2"));
+        assert_eq!(restored.restore(&store, "account").await.unwrap(), 1);
+        assert_eq!(restored.order("1").unwrap().state, OrderState::Closed);
+        assert_eq!(restored.order("2").unwrap().fills.len(), 2);
+        // Fills of restored orders are not recorded twice
+        restored.on_fill(&fill("f1", "2"));
+        assert_eq!(restored.order("2").unwrap().fills.len(), 2);
+        assert_eq!(restored.restore(&store, "other").await.unwrap(), 0);
+    }
+}
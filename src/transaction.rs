@@ -0,0 +1,520 @@
+//! Starknet invoke transactions signed with the L2 key.
+//!
+//! Moving funds out of Paraclear is an on-chain transaction sent by the Paradex account rather
+//! than a REST request. [`InvokeTransaction`] builds and signs a version 3 invoke of the
+//! account's `__execute__` with a list of [`Call`]s, and [`StarknetRpc`] fetches the nonce,
+//! estimates the fee and submits it through the full node at
+//! `SystemConfig::starknet_fullnode_rpc_url`. `Client::withdraw` and
+//! `Client::withdraw_to_l1` put them together.
+
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use serde_json::{Value, json};
+use starknet_core::types::Felt;
+use starknet_core::utils::{cairo_short_string_to_felt, get_selector_from_name};
+use starknet_crypto::{Signature, poseidon_hash_many};
+use starknet_signers::SigningKey;
+
+use crate::error::{Error, Result};
+
+/// Version of the transactions built by this module
+pub const TRANSACTION_VERSION: Felt = Felt::THREE;
+
+/// Version of a transaction which is only simulated, so that it cannot be replayed on chain
+const QUERY_VERSION: Felt = Felt::from_hex_unchecked("0x100000000000000000000000000000003");
+
+/// Margin added on top of the estimated fee, in percent
+const FEE_MARGIN_PERCENT: u128 = 50;
+
+/// A contract call executed by the account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub to: Felt,
+    pub selector: Felt,
+    pub calldata: Vec<Felt>,
+}
+
+impl Call {
+    /// Call the entrypoint `entrypoint` of the contract at `to`
+    ///
+    /// # Errors
+    ///
+    /// If `entrypoint` is not a valid entrypoint name
+    pub fn new(to: Felt, entrypoint: &str, calldata: Vec<Felt>) -> Result<Self> {
+        let selector = get_selector_from_name(entrypoint)
+            .map_err(|e| Error::StarknetError(format!("Invalid entrypoint {entrypoint:?}: {e}")))?;
+        Ok(Self {
+            to,
+            selector,
+            calldata,
+        })
+    }
+}
+
+/// Encode calls as the calldata of the account's `__execute__`
+pub fn execute_calldata(calls: &[Call]) -> Vec<Felt> {
+    let mut calldata = vec![Felt::from(calls.len())];
+    for call in calls {
+        calldata.push(call.to);
+        calldata.push(call.selector);
+        calldata.push(Felt::from(call.calldata.len()));
+        calldata.extend_from_slice(&call.calldata);
+    }
+    calldata
+}
+
+/// Scale a token amount into the integer the Paraclear contract expects
+///
+/// # Errors
+///
+/// If the amount is negative, has more than `decimals` decimal places or does not fit in 128 bits
+pub fn scale_amount(amount: Decimal, decimals: u32) -> Result<Felt> {
+    let scale = 10_u128
+        .checked_pow(decimals)
+        .and_then(Decimal::from_u128)
+        .ok_or_else(|| Error::TypeConversionError(format!("Unsupported decimals {decimals}")))?;
+    amount
+        .checked_mul(scale)
+        .filter(|scaled| scaled.fract().is_zero())
+        .and_then(|scaled| scaled.to_u128())
+        .map(Felt::from)
+        .ok_or_else(|| {
+            Error::TypeConversionError(format!(
+                "Could not convert {amount} to an amount with {decimals} decimals"
+            ))
+        })
+}
+
+/// Maximum amount and price of a resource the transaction may use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceBounds {
+    pub max_amount: u64,
+    pub max_price_per_unit: u128,
+}
+
+impl ResourceBounds {
+    /// The bounds packed with the resource name as they enter the transaction hash
+    fn felt(&self, resource: &str) -> Felt {
+        let name = cairo_short_string_to_felt(resource).expect("valid resource name");
+        name * Felt::TWO.pow(192_u32)
+            + Felt::from(self.max_amount) * Felt::TWO.pow(128_u32)
+            + Felt::from(self.max_price_per_unit)
+    }
+
+    fn to_json(self) -> Value {
+        json!({
+            "max_amount": format!("{:#x}", self.max_amount),
+            "max_price_per_unit": format!("{:#x}", self.max_price_per_unit),
+        })
+    }
+
+    /// The bounds for `consumed` units at `price`, with `FEE_MARGIN_PERCENT` added to both
+    fn with_margin(consumed: u128, price: u128) -> Self {
+        let margin = |value: u128| value.saturating_mul(100 + FEE_MARGIN_PERCENT) / 100;
+        Self {
+            max_amount: margin(consumed).try_into().unwrap_or(u64::MAX),
+            max_price_per_unit: margin(price),
+        }
+    }
+}
+
+/// Resource bounds of a version 3 transaction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeBounds {
+    pub l1_gas: ResourceBounds,
+    pub l2_gas: ResourceBounds,
+    /// Only understood by full nodes serving version 0.8 of the JSON-RPC API and later, which
+    /// require it
+    pub l1_data_gas: Option<ResourceBounds>,
+}
+
+/// A version 3 invoke transaction of the account's `__execute__`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvokeTransaction {
+    pub sender_address: Felt,
+    /// Calldata of `__execute__`, see `execute_calldata`
+    pub calldata: Vec<Felt>,
+    pub nonce: Felt,
+    pub chain_id: Felt,
+    pub fee_bounds: FeeBounds,
+    pub tip: u64,
+}
+
+impl InvokeTransaction {
+    /// Invoke `calls` from `sender_address`
+    pub fn new(sender_address: Felt, calls: &[Call], nonce: Felt, chain_id: Felt) -> Self {
+        Self {
+            sender_address,
+            calldata: execute_calldata(calls),
+            nonce,
+            chain_id,
+            fee_bounds: FeeBounds::default(),
+            tip: 0,
+        }
+    }
+
+    /// The hash of the transaction, which is signed by the account's key
+    pub fn hash(&self) -> Felt {
+        self.hash_for(TRANSACTION_VERSION)
+    }
+
+    fn hash_for(&self, version: Felt) -> Felt {
+        let mut fee_fields = vec![
+            Felt::from(self.tip),
+            self.fee_bounds.l1_gas.felt("L1_GAS"),
+            self.fee_bounds.l2_gas.felt("L2_GAS"),
+        ];
+        if let Some(l1_data_gas) = self.fee_bounds.l1_data_gas {
+            fee_fields.push(l1_data_gas.felt("L1_DATA"));
+        }
+        poseidon_hash_many(&[
+            cairo_short_string_to_felt("invoke").expect("valid prefix"),
+            version,
+            self.sender_address,
+            poseidon_hash_many(&fee_fields),
+            // Paymaster data
+            poseidon_hash_many(&[]),
+            self.chain_id,
+            self.nonce,
+            // Nonce and fee data availability modes, both L1
+            Felt::ZERO,
+            // Account deployment data
+            poseidon_hash_many(&[]),
+            poseidon_hash_many(&self.calldata),
+        ])
+    }
+
+    /// Sign the transaction hash
+    ///
+    /// # Errors
+    ///
+    /// If the hash cannot be signed
+    pub fn sign(&self, signing_key: &SigningKey) -> Result<Signature> {
+        signing_key
+            .sign(&self.hash())
+            .map_err(|e| Error::StarknetError(e.to_string()))
+    }
+
+    /// The transaction in the shape of the JSON-RPC `BROADCASTED_INVOKE_TXN`
+    fn to_json(&self, version: Felt, signature: &[Felt]) -> Value {
+        let mut resource_bounds = json!({
+            "l1_gas": self.fee_bounds.l1_gas.to_json(),
+            "l2_gas": self.fee_bounds.l2_gas.to_json(),
+        });
+        if let Some(l1_data_gas) = self.fee_bounds.l1_data_gas {
+            resource_bounds["l1_data_gas"] = l1_data_gas.to_json();
+        }
+        json!({
+            "type": "INVOKE",
+            "version": format!("{version:#x}"),
+            "sender_address": format!("{:#x}", self.sender_address),
+            "calldata": hex_felts(&self.calldata),
+            "signature": hex_felts(signature),
+            "nonce": format!("{:#x}", self.nonce),
+            "resource_bounds": resource_bounds,
+            "tip": format!("{:#x}", self.tip),
+            "paymaster_data": [],
+            "account_deployment_data": [],
+            "nonce_data_availability_mode": "L1",
+            "fee_data_availability_mode": "L1",
+        })
+    }
+}
+
+fn hex_felts(felts: &[Felt]) -> Vec<String> {
+    felts.iter().map(|felt| format!("{felt:#x}")).collect()
+}
+
+/// Client of the JSON-RPC API of a Starknet full node
+#[derive(Debug, Clone)]
+pub struct StarknetRpc {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl StarknetRpc {
+    pub fn new(client: reqwest::Client, url: impl Into<String>) -> Self {
+        Self {
+            client,
+            url: url.into(),
+        }
+    }
+
+    /// Get the nonce of an account, including pending transactions
+    ///
+    /// # Errors
+    ///
+    /// If the request fails or the node returns an error
+    pub async fn nonce(&self, address: Felt) -> Result<Felt> {
+        let nonce = self
+            .call(
+                "starknet_getNonce",
+                json!({"block_id": "pending", "contract_address": format!("{address:#x}")}),
+            )
+            .await?;
+        parse_felt(&nonce)
+    }
+
+    /// Simulate a transaction to get the fee bounds to send it with
+    ///
+    /// The estimate is made without validating the signature, and `FEE_MARGIN_PERCENT` is added
+    /// to the amounts and prices to absorb changes until the transaction is included.
+    ///
+    /// # Errors
+    ///
+    /// If the request fails, the node returns an error or the transaction would revert
+    pub async fn estimate_fee_bounds(&self, transaction: &InvokeTransaction) -> Result<FeeBounds> {
+        let estimates = self
+            .call(
+                "starknet_estimateFee",
+                json!({
+                    "request": [transaction.to_json(QUERY_VERSION, &[])],
+                    "simulation_flags": ["SKIP_VALIDATE"],
+                    "block_id": "pending",
+                }),
+            )
+            .await?;
+        let estimate = estimates
+            .get(0)
+            .ok_or_else(|| Error::StarknetError(format!("No fee estimate in {estimates}")))?;
+        let field = |names: &[&str]| -> Result<Option<u128>> {
+            names
+                .iter()
+                .find_map(|name| estimate.get(*name))
+                .map(|value| {
+                    u128::try_from(parse_felt(value)?)
+                        .map_err(|e| Error::StarknetError(format!("Invalid fee estimate: {e}")))
+                })
+                .transpose()
+        };
+        let required = |names: &[&str]| {
+            field(names)?.ok_or_else(|| Error::StarknetError(format!("No {names:?} in {estimate}")))
+        };
+        // Nodes serving 0.8 and later account for L2 gas and L1 data gas separately
+        match field(&["l2_gas_consumed"])? {
+            Some(l2_gas_consumed) => Ok(FeeBounds {
+                l1_gas: ResourceBounds::with_margin(
+                    required(&["l1_gas_consumed"])?,
+                    required(&["l1_gas_price"])?,
+                ),
+                l2_gas: ResourceBounds::with_margin(l2_gas_consumed, required(&["l2_gas_price"])?),
+                l1_data_gas: Some(ResourceBounds::with_margin(
+                    required(&["l1_data_gas_consumed"])?,
+                    required(&["l1_data_gas_price"])?,
+                )),
+            }),
+            None => Ok(FeeBounds {
+                l1_gas: ResourceBounds::with_margin(
+                    // The data gas is paid in L1 gas
+                    required(&["overall_fee"])? / required(&["gas_price"])?.max(1),
+                    required(&["gas_price"])?,
+                ),
+                l2_gas: ResourceBounds::default(),
+                l1_data_gas: None,
+            }),
+        }
+    }
+
+    /// Submit a signed transaction
+    ///
+    /// # Returns
+    ///
+    /// The hash of the transaction
+    ///
+    /// # Errors
+    ///
+    /// If the request fails or the node rejects the transaction
+    pub async fn add_invoke_transaction(
+        &self,
+        transaction: &InvokeTransaction,
+        signature: &Signature,
+    ) -> Result<Felt> {
+        let result = self
+            .call(
+                "starknet_addInvokeTransaction",
+                json!({
+                    "invoke_transaction":
+                        transaction.to_json(TRANSACTION_VERSION, &[signature.r, signature.s]),
+                }),
+            )
+            .await?;
+        parse_felt(&result["transaction_hash"])
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        json_rpc(
+            &self.client,
+            &self.url,
+            method,
+            params,
+            Error::StarknetError,
+        )
+        .await
+    }
+}
+
+/// Send a JSON-RPC request, mapping an error returned by the node with `node_error`
+pub(crate) async fn json_rpc(
+    client: &reqwest::Client,
+    url: &str,
+    method: &str,
+    params: Value,
+    node_error: fn(String) -> Error,
+) -> Result<Value> {
+    let response: Value = client
+        .post(url)
+        .json(&json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}))
+        .send()
+        .await
+        .map_err(|e| Error::RestError(e.to_string()))?
+        .json()
+        .await
+        .map_err(|e| Error::DeserializationError(e.to_string()))?;
+    if let Some(error) = response.get("error") {
+        return Err(node_error(format!("{method} failed: {error}")));
+    }
+    response
+        .get("result")
+        .cloned()
+        .ok_or_else(|| node_error(format!("{method} returned no result")))
+}
+
+fn parse_felt(value: &Value) -> Result<Felt> {
+    value
+        .as_str()
+        .and_then(|hex| Felt::from_hex(hex).ok())
+        .ok_or_else(|| Error::StarknetError(format!("Expected a hex felt, got {value}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use starknet_crypto::verify;
+
+    #[test]
+    fn signed_invoke() {
+        let paraclear = Felt::from_hex_unchecked("0x5d4c");
+        let token = Felt::from_hex_unchecked("0x7348");
+        let amount = scale_amount(Decimal::new(125, 1), 8).unwrap();
+        assert_eq!(amount, Felt::from(1_250_000_000_u64));
+        assert!(scale_amount(Decimal::new(1, 9), 8).is_err());
+        assert!(scale_amount(Decimal::new(-1, 0), 8).is_err());
+
+        let call = Call::new(paraclear, "withdraw", vec![token, amount]).unwrap();
+        let mut transaction = InvokeTransaction::new(
+            Felt::from_hex_unchecked("0xacc"),
+            std::slice::from_ref(&call),
+            Felt::from(4_u8),
+            cairo_short_string_to_felt("PRIVATE_SN_PARACLEAR_MAINNET").unwrap(),
+        );
+        assert_eq!(
+            transaction.calldata,
+            vec![
+                Felt::ONE,
+                paraclear,
+                get_selector_from_name("withdraw").unwrap(),
+                Felt::TWO,
+                token,
+                amount
+            ]
+        );
+
+        // Every signed field changes the hash
+        let unbounded = transaction.hash();
+        assert_ne!(transaction.hash_for(QUERY_VERSION), unbounded);
+        transaction.fee_bounds.l1_gas = ResourceBounds {
+            max_amount: 1000,
+            max_price_per_unit: 10,
+        };
+        let l1_gas_bounded = transaction.hash();
+        assert_ne!(l1_gas_bounded, unbounded);
+        transaction.fee_bounds.l1_data_gas = Some(ResourceBounds::default());
+        assert_ne!(transaction.hash(), l1_gas_bounded);
+        assert_eq!(
+            ResourceBounds::with_margin(1000, 10),
+            ResourceBounds {
+                max_amount: 1500,
+                max_price_per_unit: 15
+            }
+        );
+
+        let signing_key = SigningKey::from_secret_scalar(Felt::from_hex_unchecked("0x1234"));
+        let signature = transaction.sign(&signing_key).unwrap();
+        assert!(
+            verify(
+                &signing_key.verifying_key().scalar(),
+                &transaction.hash(),
+                &signature.r,
+                &signature.s
+            )
+            .unwrap()
+        );
+
+        let json = transaction.to_json(TRANSACTION_VERSION, &[signature.r, signature.s]);
+        assert_eq!(json["version"], "0x3");
+        assert_eq!(json["nonce"], "0x4");
+        assert_eq!(json["calldata"][2], format!("{:#x}", call.selector));
+        assert_eq!(json["resource_bounds"]["l1_gas"]["max_amount"], "0x3e8");
+        assert_eq!(json["resource_bounds"]["l1_data_gas"]["max_amount"], "0x0");
+    }
+
+    #[test]
+    fn invoke_hash_known_answer() {
+        // Resource bounds packed as the 60 bit resource name, 64 bit amount and 128 bit price
+        let bounds = ResourceBounds {
+            max_amount: 1000,
+            max_price_per_unit: 10,
+        };
+        assert_eq!(
+            bounds.felt("L1_GAS"),
+            Felt::from_hex_unchecked(
+                "0x4c315f47415300000000000003e80000000000000000000000000000000a"
+            )
+        );
+        assert_eq!(
+            ResourceBounds::default().felt("L1_DATA"),
+            Felt::from_hex_unchecked(
+                "0x4c315f44415441000000000000000000000000000000000000000000000000"
+            )
+        );
+
+        let transaction = InvokeTransaction {
+            sender_address: Felt::from_hex_unchecked("0xacc"),
+            calldata: vec![Felt::ONE, Felt::TWO],
+            nonce: Felt::from(4_u8),
+            chain_id: Felt::from_hex_unchecked("0x534e5f5345504f4c4941"),
+            fee_bounds: FeeBounds {
+                l1_gas: bounds,
+                l2_gas: ResourceBounds::default(),
+                l1_data_gas: Some(ResourceBounds::default()),
+            },
+            tip: 0,
+        };
+        // The field list of a version 3 invoke hash, with the short strings spelled out
+        let expected = poseidon_hash_many(&[
+            // "invoke"
+            Felt::from_hex_unchecked("0x696e766f6b65"),
+            Felt::THREE,
+            Felt::from_hex_unchecked("0xacc"),
+            poseidon_hash_many(&[
+                Felt::ZERO,
+                bounds.felt("L1_GAS"),
+                ResourceBounds::default().felt("L2_GAS"),
+                ResourceBounds::default().felt("L1_DATA"),
+            ]),
+            poseidon_hash_many(&[]),
+            Felt::from_hex_unchecked("0x534e5f5345504f4c4941"),
+            Felt::from(4_u8),
+            Felt::ZERO,
+            poseidon_hash_many(&[]),
+            poseidon_hash_many(&[Felt::ONE, Felt::TWO]),
+        ]);
+        assert_eq!(transaction.hash(), expected);
+        assert_eq!(
+            transaction.hash(),
+            Felt::from_hex_unchecked(
+                "0x316b66a03f50fe74b6e7cd05fb9d18c16bf0243541304fda45adea1b1b61837"
+            )
+        );
+    }
+}
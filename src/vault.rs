@@ -0,0 +1,177 @@
+//! Accounting for investments in third-party vaults.
+//!
+//! [`VaultInvestment`] reconstructs what an account paid into a vault and took out of it from
+//! its [`Transfer`] history, values the vault shares it still holds at the current share price,
+//! and reports withdrawals that are still waiting for the vault to unwind positions.
+
+use starknet_crypto::Felt;
+
+use crate::structs::{Transfer, TransferKind, TransferStatus, VaultSummary};
+
+/// Withdrawal from a vault which has not been paid out yet
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingUnwind {
+    pub transfer_id: String,
+    pub amount: f64,
+    /// Progress of the vault unwinding positions to pay out the withdrawal, as reported
+    pub completion_percentage: f64,
+    pub created_at: u64,
+}
+
+/// Position of an account in a single vault
+#[derive(Clone, Debug, PartialEq)]
+pub struct VaultInvestment {
+    pub vault_address: String,
+    /// Sum of the completed deposits into the vault
+    pub deposited: f64,
+    /// Sum of the completed withdrawals from the vault
+    pub withdrawn: f64,
+    /// Vault shares held by the account
+    pub shares: f64,
+    /// Current price of one vault share
+    pub share_price: f64,
+    pub pending_unwinds: Vec<PendingUnwind>,
+}
+
+impl VaultInvestment {
+    /// Reconstruct the investment from the account's transfers
+    ///
+    /// `shares` is the vtoken amount from `Client::vault_account_summary`, and `transfers` the
+    /// account's transfer history, e.g. from `Client::transfers`. Transfers of other vaults and
+    /// other kinds are ignored, as are failed ones.
+    pub fn new(vault: &VaultSummary, shares: f64, transfers: &[Transfer]) -> Self {
+        let mut investment = Self {
+            vault_address: vault.address.clone(),
+            deposited: 0.,
+            withdrawn: 0.,
+            shares,
+            share_price: vault.vtoken_price,
+            pending_unwinds: Vec::new(),
+        };
+        for transfer in transfers {
+            if !same_address(&transfer.vault_address, &vault.address) {
+                continue;
+            }
+            match (&transfer.kind, &transfer.status) {
+                (_, TransferStatus::FAILED) => {}
+                (TransferKind::VAULT_DEPOSIT, TransferStatus::COMPLETED) => {
+                    investment.deposited += transfer.amount
+                }
+                (TransferKind::VAULT_WITHDRAWAL, TransferStatus::COMPLETED) => {
+                    investment.withdrawn += transfer.amount
+                }
+                (
+                    TransferKind::VAULT_WITHDRAWAL | TransferKind::UNWINDING,
+                    TransferStatus::PENDING | TransferStatus::AVAILABLE,
+                ) => investment.pending_unwinds.push(PendingUnwind {
+                    transfer_id: transfer.id.clone(),
+                    amount: transfer.amount,
+                    completion_percentage: transfer.vault_unwind_completion_percentage,
+                    created_at: transfer.created_at,
+                }),
+                _ => {}
+            }
+        }
+        investment
+    }
+
+    /// Value of the shares held at the current share price
+    pub fn value(&self) -> f64 {
+        self.shares * self.share_price
+    }
+
+    /// Profit of the investment so far: value held plus withdrawn, less deposited
+    ///
+    /// Pending withdrawals are neither part of the value nor withdrawn yet, so the PnL dips
+    /// while a withdrawal unwinds.
+    pub fn pnl(&self) -> f64 {
+        self.value() + self.withdrawn - self.deposited
+    }
+
+    /// PnL relative to the amount deposited, zero without deposits
+    pub fn roi(&self) -> f64 {
+        if self.deposited > 0. {
+            self.pnl() / self.deposited
+        } else {
+            0.
+        }
+    }
+
+    /// Whether a withdrawal is still waiting for the vault to unwind
+    pub fn is_unwinding(&self) -> bool {
+        !self.pending_unwinds.is_empty()
+    }
+}
+
+/// Compare Starknet addresses, which may be formatted with or without leading zeros
+fn same_address(a: &str, b: &str) -> bool {
+    match (Felt::from_hex(a), Felt::from_hex(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.eq_ignore_ascii_case(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const VAULT: &str = "0x5f1";
+
+    fn transfer(id: &str, kind: &str, status: &str, amount: &str, vault: &str) -> Transfer {
+        serde_json::from_value(json!({
+            "account": "0x1",
+            "amount": amount,
+            "auto_withdrawal_fee": "0",
+            "bridge": "STARKGATE",
+            "counterparty": "",
+            "created_at": 1681497002041u64,
+            "direction": if kind == "VAULT_DEPOSIT" { "OUT" } else { "IN" },
+            "external_account": "",
+            "external_chain": "",
+            "external_txn_hash": "",
+            "failure_reason": "",
+            "id": id,
+            "kind": kind,
+            "last_updated_at": 1681497002041u64,
+            "socialized_loss_factor": "0",
+            "status": status,
+            "token": "USDC",
+            "txn_hash": "",
+            "vault_address": vault,
+            "vault_unwind_completion_percentage": "40",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn investment() {
+        let vault: VaultSummary = serde_json::from_value(json!({
+            "address": VAULT,
+            "vtoken_price": "1.2",
+            "vtoken_supply": "100000",
+            "tvl": "120000",
+            "total_pnl": "20000",
+            "total_roi": "0.2",
+        }))
+        .unwrap();
+        let transfers = [
+            transfer("1", "VAULT_DEPOSIT", "COMPLETED", "1000", "0x05f1"),
+            transfer("2", "VAULT_DEPOSIT", "FAILED", "500", VAULT),
+            transfer("3", "VAULT_WITHDRAWAL", "COMPLETED", "300", VAULT),
+            transfer("4", "VAULT_WITHDRAWAL", "PENDING", "200", VAULT),
+            transfer("5", "VAULT_DEPOSIT", "COMPLETED", "700", "0x5f2"),
+            transfer("6", "DEPOSIT", "COMPLETED", "5000", ""),
+        ];
+        let investment = VaultInvestment::new(&vault, 600., &transfers);
+
+        assert_eq!(investment.deposited, 1000.);
+        assert_eq!(investment.withdrawn, 300.);
+        assert!((investment.value() - 720.).abs() < 1e-9);
+        assert!((investment.pnl() - 20.).abs() < 1e-9);
+        assert!((investment.roi() - 0.02).abs() < 1e-9);
+        assert!(investment.is_unwinding());
+        assert_eq!(investment.pending_unwinds[0].transfer_id, "4");
+        assert_eq!(investment.pending_unwinds[0].completion_percentage, 40.);
+    }
+}
@@ -2,6 +2,7 @@ use crate::url::URL;
 use crate::{
     error::{Error, Result},
     rest::Client,
+    structs::{MarketSummary, SocializedLoss},
 };
 use futures_util::{SinkExt, stream::StreamExt};
 use jsonrpsee_core::{params::ObjectParams, traits::ToRpcParams};
@@ -10,78 +11,405 @@ use log::{info, trace, warn};
 use serde_json::Value;
 use std::{
     borrow::Cow,
-    collections::{HashMap, hash_map::Entry},
+    collections::{HashMap, HashSet, hash_map::Entry},
     sync::{Arc, atomic::AtomicU64},
     time::Duration,
 };
 use tokio::{
-    net::TcpStream,
-    sync::mpsc::{UnboundedReceiver, UnboundedSender},
-    task::spawn,
-};
-use tokio_tungstenite::{
-    MaybeTlsStream, WebSocketStream, connect_async_with_config,
-    tungstenite::{client::IntoClientRequest, http::Uri},
+    runtime::Handle,
+    sync::{
+        mpsc::{UnboundedReceiver, UnboundedSender},
+        oneshot,
+    },
+    task::AbortHandle,
 };
 
+mod builder;
+mod connector;
+mod delivery;
+mod health;
+mod hooks;
+mod reconnect;
+mod rpc;
+mod stream;
 mod subscription;
+mod summary_diff;
 mod types;
 
+use health::{HealthMonitor, PingTimer};
+use rpc::{PendingRequests, RequestKind};
+
+pub use builder::{Shutdown, WebsocketConfig, WebsocketManagerBuilder};
+pub use connector::{BoxedWebsocketStream, Connector, TungsteniteConnector, WebsocketStream};
+pub use health::{ConnectionState, Health};
+pub use hooks::{ConnectionInfo, HookFn, LifecycleHooks};
+pub use reconnect::ReconnectPolicy;
+pub use stream::OverflowPolicy;
 pub use subscription::{
     AccountSubscription, BalanceEventsSubscription, BboSubscription, ChannelEvent,
-    FillsSubscription, FundingDataSubscription, FundingPaymentsSubscription,
-    MarketSummarySubscription, OrderBookDeltasSubscription, OrderBookSubscription,
-    OrdersSubscription, PositionSubscription, SubscriptionSpec, TradesSubscription,
+    FillsSubscription, FundingDataSubscription, FundingPaymentsSubscription, KlinesSubscription,
+    MarketSummarySubscription, OrderBookDeltasSubscription, OrderBookInteractiveSubscription,
+    OrderBookSubscription, OrdersSubscription, PositionSubscription, SubscriptionSpec,
+    TradesSubscription, TransfersSubscription,
 };
-pub use types::{Channel, Identifier, Message};
+pub use summary_diff::{MarketSummaryDiffer, SummaryThresholds};
+pub use types::{Channel, Identifier, Message, RefreshRate, SubscriptionStats};
 
 enum WebsocketOperation {
-    Subscribe(Channel, CallbackFn, Identifier),
+    Subscribe(Channel, CallbackFn, Identifier, Option<Acknowledgement>),
     Unsubscribe(Identifier),
+    SetHooks(LifecycleHooks),
     Stop,
+    /// Unsubscribe every channel and close the connection before stopping
+    Shutdown,
+}
+
+/// Aborts the read loop once the last clone of the manager is dropped
+struct ReaderGuard {
+    abort: AbortHandle,
+    health: Arc<HealthMonitor>,
 }
 
+impl ReaderGuard {
+    fn abort(&self) {
+        if !self.abort.is_finished() {
+            self.abort.abort();
+            self.health.set_state(ConnectionState::Stopped);
+        }
+    }
+}
+
+impl Drop for ReaderGuard {
+    fn drop(&mut self) {
+        self.abort();
+    }
+}
+
+/// Subscribes to websocket channels over a connection kept open by a background read loop
+///
+/// Clones share the same connection. The read loop is aborted, dropping the connection, once
+/// the manager and all its clones are dropped; use `shutdown` to close it cleanly instead.
 #[derive(Clone)]
 pub struct WebsocketManager {
     current_id: Arc<AtomicU64>,
     sub_sender: UnboundedSender<WebsocketOperation>,
+    is_private: bool,
+    deduplicate: bool,
+    runtime: Handle,
+    health: Arc<HealthMonitor>,
+    reader: Arc<ReaderGuard>,
 }
 
-type CallbackFn = Arc<dyn Fn(&Message) + Send + Sync + 'static>;
+/// Callback invoked with every message of a subscription
+pub type CallbackFn = Arc<dyn Fn(&Message) + Send + Sync + 'static>;
+/// Resolved once the server has responded to the subscribe request for a channel
+type Acknowledgement = oneshot::Sender<Result<()>>;
+
+/// How long a shutdown waits for the server to answer the close frame
+const CLOSE_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+type WebsocketConnection = BoxedWebsocketStream;
 
 impl WebsocketManager {
+    /// Start building a WebsocketManager with custom configuration and dependencies
+    pub fn builder(url: URL) -> WebsocketManagerBuilder {
+        WebsocketManagerBuilder::new(url)
+    }
+
     pub async fn new(url: URL, rest_client: Option<Client>) -> Self {
-        let (sub_sender, sub_receiver) =
-            tokio::sync::mpsc::unbounded_channel::<WebsocketOperation>();
-        spawn(Self::_reader(url, rest_client, sub_receiver));
-        Self {
-            current_id: Arc::new(AtomicU64::new(0)),
-            sub_sender,
+        Self::new_with_hooks(url, rest_client, LifecycleHooks::default()).await
+    }
+
+    /// Create a new WebsocketManager with custom ping, reconnect and liveness settings
+    ///
+    /// # Parameters
+    ///
+    /// * `url` - A URL struct representing the websocket endpoint
+    /// * `rest_client` - An optional rest Client used to authenticate private channels
+    /// * `config` - Behaviour settings of the connection
+    ///
+    /// # Errors
+    ///
+    /// `Error::InvalidConfiguration` if the config is rejected by `WebsocketConfig::validate`
+    pub async fn new_with_config(
+        url: URL,
+        rest_client: Option<Client>,
+        config: WebsocketConfig,
+    ) -> Result<Self> {
+        let mut builder = Self::builder(url).config(config);
+        if let Some(rest_client) = rest_client {
+            builder = builder.rest_client(rest_client);
+        }
+        let (manager, _shutdown) = builder.build()?;
+        Ok(manager)
+    }
+
+    /// Create a new WebsocketManager with lifecycle hooks registered before the first connection
+    ///
+    /// # Parameters
+    ///
+    /// * `url` - A URL struct representing the websocket endpoint
+    /// * `rest_client` - An optional rest Client used to authenticate private channels
+    /// * `hooks` - Callbacks invoked on connect, authentication, disconnect and resubscribe
+    pub async fn new_with_hooks(
+        url: URL,
+        rest_client: Option<Client>,
+        hooks: LifecycleHooks,
+    ) -> Self {
+        let mut builder = Self::builder(url).hooks(hooks);
+        if let Some(rest_client) = rest_client {
+            builder = builder.rest_client(rest_client);
         }
+        let (manager, _shutdown) = builder.build().expect("the default config is valid");
+        manager
     }
 
+    /// Suppress `OrderUpdate` and `Fill` events that repeat the previous event of the same
+    /// subscription, keyed on (`id`, `seq_no`, `updated_at`). Applies to subscriptions made
+    /// after this call.
+    pub fn with_deduplication(mut self, enabled: bool) -> Self {
+        self.deduplicate = enabled;
+        self
+    }
+
+    /// Subscribe to a channel, delivering every message to the callback
+    ///
+    /// Each subscription has its own delivery queue, so callbacks run in order for a given
+    /// subscription without blocking the read loop or other subscriptions.
+    ///
+    /// # Errors
+    ///
+    /// * `Error::InvalidSubscription` if the channel parameters are malformed
+    /// * `Error::AuthenticationRequired` if the channel is private and the manager was created without a private rest Client
+    /// * `Error::WebSocketSend` if the websocket read task is no longer running
     pub async fn subscribe(&self, channel: Channel, callback: CallbackFn) -> Result<Identifier> {
-        let identifier = Identifier(
-            self.current_id
-                .fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+        self.send_subscribe(channel, callback, None)
+    }
+
+    /// Subscribe to a channel and wait for the server to accept the subscription
+    ///
+    /// Behaves like `subscribe`, but only returns once the JSON-RPC response to the subscribe
+    /// request has arrived. Returns immediately if the channel is already subscribed.
+    ///
+    /// # Errors
+    ///
+    /// As `subscribe`, and additionally
+    /// * `Error::WebSocketSubscriptionError` if the server rejects the subscription
+    /// * `Error::WebSocketAuthError` if the channel is private and authentication failed
+    /// * `Error::WebSocketRequestTimeout` if the server did not respond in time. The subscription is removed in that case.
+    pub async fn subscribe_acknowledged(
+        &self,
+        channel: Channel,
+        callback: CallbackFn,
+    ) -> Result<Identifier> {
+        let (sender, receiver) = oneshot::channel();
+        let identifier = self.send_subscribe(channel, callback, Some(sender))?;
+        let result = receiver.await.unwrap_or_else(|_| {
+            Err(Error::WebSocketSend(
+                "subscription was dropped before it was acknowledged".into(),
+            ))
+        });
+        if let Err(e) = result {
+            if matches!(e, Error::WebSocketRequestTimeout(_)) {
+                self.unsubscribe(identifier).await?;
+            }
+            return Err(e);
+        }
+        Ok(identifier)
+    }
+
+    pub async fn subscribe_typed<S, F>(&self, spec: S, callback: F) -> Result<Identifier>
+    where
+        S: SubscriptionSpec,
+        F: for<'a> Fn(ChannelEvent<'a, S::Payload>) + Send + Sync + 'static,
+    {
+        let channel = spec.into_channel();
+        self.subscribe(channel, Self::typed_handler::<S, F>(callback))
+            .await
+    }
+
+    /// Typed equivalent of `subscribe_acknowledged`
+    ///
+    /// # Errors
+    ///
+    /// See `subscribe_acknowledged`
+    pub async fn subscribe_typed_acknowledged<S, F>(
+        &self,
+        spec: S,
+        callback: F,
+    ) -> Result<Identifier>
+    where
+        S: SubscriptionSpec,
+        F: for<'a> Fn(ChannelEvent<'a, S::Payload>) + Send + Sync + 'static,
+    {
+        let channel = spec.into_channel();
+        self.subscribe_acknowledged(channel, Self::typed_handler::<S, F>(callback))
+            .await
+    }
+
+    /// Subscribe to a channel, receiving its payloads on a bounded channel instead of a callback
+    ///
+    /// Messages are buffered per subscription, so a slow consumer neither stalls the read loop
+    /// nor other subscriptions. Once `capacity` messages are buffered, `overflow` decides what
+    /// happens to the next one. The receiver holds one more message on top of the buffer, so
+    /// up to `capacity + 1` messages wait for the consumer. Connection events are not
+    /// forwarded, and the receiver is closed when the subscription ends; dropping the receiver
+    /// unsubscribes.
+    ///
+    /// # Parameters
+    ///
+    /// * `spec` - The typed subscription
+    /// * `capacity` - Number of messages buffered for the consumer, at least 1
+    /// * `overflow` - What to do with messages arriving while the buffer is full
+    ///
+    /// # Returns
+    ///
+    /// The identifier of the subscription and the receiver of its payloads
+    ///
+    /// # Errors
+    ///
+    /// See `subscribe`
+    pub async fn subscribe_stream<S>(
+        &self,
+        spec: S,
+        capacity: usize,
+        overflow: OverflowPolicy,
+    ) -> Result<(Identifier, tokio::sync::mpsc::Receiver<S::Payload>)>
+    where
+        S: SubscriptionSpec,
+        S::Payload: Clone,
+    {
+        let channel = spec.into_channel();
+        self.validate(&channel)?;
+        let identifier = self.next_identifier();
+        let (callback, receiver) = stream::spawn_stream::<S>(
+            &self.runtime,
+            capacity,
+            overflow,
+            self.deduplicate,
+            identifier,
+            self.sub_sender.clone(),
         );
         self.sub_sender
-            .send(WebsocketOperation::Subscribe(channel, callback, identifier))
+            .send(WebsocketOperation::Subscribe(
+                channel, callback, identifier, None,
+            ))
+            .map_err(|e| Error::WebSocketSend(e.to_string()))?;
+        Ok((identifier, receiver))
+    }
+
+    /// Call `callback` whenever a socialized loss is applied to a transfer of the account
+    ///
+    /// Subscribes to the private transfers channel and reports each affected transfer once,
+    /// even when later status updates of the transfer carry the same factor again.
+    ///
+    /// # Errors
+    ///
+    /// See `subscribe`
+    pub async fn subscribe_socialized_losses<F>(&self, callback: F) -> Result<Identifier>
+    where
+        F: Fn(&SocializedLoss) + Send + Sync + 'static,
+    {
+        let reported = std::sync::Mutex::new(HashSet::new());
+        self.subscribe_typed(TransfersSubscription, move |event| match event {
+            ChannelEvent::Data(transfer) => {
+                if let Some(loss) = transfer.socialized_loss()
+                    && reported.lock().unwrap().insert(loss.transfer_id.clone())
+                {
+                    warn!("Socialized loss applied to transfer {loss:?}");
+                    callback(&loss);
+                }
+            }
+            ChannelEvent::Error(e) => warn!("Transfers subscription error {e:?}"),
+            _ => {}
+        })
+        .await
+    }
+
+    /// Subscribe to the summaries of every market, only delivering those which changed beyond
+    /// `thresholds` since the last one delivered for their market
+    ///
+    /// Connection events are delivered as they are. After a disconnect the next summary of every
+    /// market is delivered, as moves may have been missed.
+    ///
+    /// # Errors
+    ///
+    /// See `subscribe`
+    pub async fn subscribe_market_summary_changes<F>(
+        &self,
+        thresholds: SummaryThresholds,
+        callback: F,
+    ) -> Result<Identifier>
+    where
+        F: for<'a> Fn(ChannelEvent<'a, MarketSummary>) + Send + Sync + 'static,
+    {
+        let differ = std::sync::Mutex::new(MarketSummaryDiffer::new(thresholds));
+        self.subscribe_typed(MarketSummarySubscription, move |event| match event {
+            ChannelEvent::Data(summary) => {
+                if differ.lock().unwrap().should_forward(summary) {
+                    callback(ChannelEvent::Data(summary));
+                }
+            }
+            ChannelEvent::Disconnected => {
+                differ.lock().unwrap().reset();
+                callback(ChannelEvent::Disconnected);
+            }
+            event => callback(event),
+        })
+        .await
+    }
+
+    fn send_subscribe(
+        &self,
+        channel: Channel,
+        callback: CallbackFn,
+        acknowledgement: Option<Acknowledgement>,
+    ) -> Result<Identifier> {
+        self.validate(&channel)?;
+        let identifier = self.next_identifier();
+        let callback = delivery::spawn_delivery(&self.runtime, callback, self.deduplicate);
+        self.sub_sender
+            .send(WebsocketOperation::Subscribe(
+                channel,
+                callback,
+                identifier,
+                acknowledgement,
+            ))
             .map_err(|e| Error::WebSocketSend(e.to_string()))?;
         Ok(identifier)
     }
 
-    pub async fn subscribe_typed<S, F>(&self, spec: S, callback: F) -> Result<Identifier>
+    fn validate(&self, channel: &Channel) -> Result<()> {
+        channel.validate()?;
+        if channel.requires_auth() && !self.is_private {
+            return Err(Error::AuthenticationRequired(channel.channel_name()));
+        }
+        Ok(())
+    }
+
+    fn next_identifier(&self) -> Identifier {
+        Identifier(
+            self.current_id
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+        )
+    }
+
+    pub(crate) fn typed_handler<S, F>(callback: F) -> CallbackFn
     where
         S: SubscriptionSpec,
         F: for<'a> Fn(ChannelEvent<'a, S::Payload>) + Send + Sync + 'static,
     {
-        let channel = spec.into_channel();
-        let handler: CallbackFn = Arc::new(move |message: &Message| match message {
+        Arc::new(move |message: &Message| match message {
             Message::Connected => callback(ChannelEvent::Connected),
             Message::Disconnected => callback(ChannelEvent::Disconnected),
             Message::Unsubscribed => callback(ChannelEvent::Unsubscribed),
-            Message::Error(err) => callback(ChannelEvent::Error(err)),
+            Message::Error(err) | Message::AuthFailed(err) | Message::ConnectionFailed(err) => {
+                callback(ChannelEvent::Error(err))
+            }
+            Message::AuthRefreshed => trace!("Websocket re-authenticated"),
+            Message::Reconnecting { attempt, backoff } => {
+                trace!("Websocket reconnect attempt {attempt} failed, retrying in {backoff:?}")
+            }
             _ => {
                 if let Some(data) = S::extract(message) {
                     callback(ChannelEvent::Data(data));
@@ -92,9 +420,7 @@ impl WebsocketManager {
                     );
                 }
             }
-        });
-
-        self.subscribe(channel, handler).await
+        })
     }
 
     pub async fn unsubscribe(&self, identifier: Identifier) -> Result<()> {
@@ -104,6 +430,51 @@ impl WebsocketManager {
         Ok(())
     }
 
+    /// Get message statistics for every active channel, keyed by channel name
+    ///
+    /// # Returns
+    ///
+    /// A map of channel name to the SubscriptionStats collected by the read loop
+    ///
+    /// # Errors
+    ///
+    /// If the websocket read task is no longer running
+    pub async fn subscription_stats(&self) -> Result<HashMap<String, SubscriptionStats>> {
+        if self.sub_sender.is_closed() {
+            return Err(Error::WebSocketSend(
+                "websocket read task is no longer running".into(),
+            ));
+        }
+        Ok(self.health.channels())
+    }
+
+    /// Get the state of the connection along with its latency and message counters
+    ///
+    /// The snapshot is kept up to date by the read loop, so it can be taken while the manager
+    /// is reconnecting.
+    pub fn health(&self) -> Health {
+        self.health.health()
+    }
+
+    /// Watch the connection state, e.g. to alert when it stays `Reconnecting`
+    pub fn connection_state(&self) -> tokio::sync::watch::Receiver<ConnectionState> {
+        self.health.subscribe_state()
+    }
+
+    /// Replace the lifecycle hooks of a running manager
+    ///
+    /// Hooks set here will not observe the initial connection, use `new_with_hooks` for that.
+    ///
+    /// # Errors
+    ///
+    /// If the websocket read task is no longer running
+    pub async fn set_lifecycle_hooks(&self, hooks: LifecycleHooks) -> Result<()> {
+        self.sub_sender
+            .send(WebsocketOperation::SetHooks(hooks))
+            .map_err(|e| Error::WebSocketSend(e.to_string()))?;
+        Ok(())
+    }
+
     pub async fn stop(&self) -> Result<()> {
         self.sub_sender
             .send(WebsocketOperation::Stop)
@@ -111,19 +482,64 @@ impl WebsocketManager {
         Ok(())
     }
 
+    /// Unsubscribe every channel, close the connection and wait for the read loop to exit
+    ///
+    /// Subscribers receive `Message::Unsubscribed` and the server a close frame. The read loop
+    /// is aborted if it has not exited within `timeout`, e.g. because it is reconnecting.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout` - How long to wait for the read loop to exit
+    ///
+    /// # Errors
+    ///
+    /// `Error::WebSocketShutdownTimeout` if the read loop had to be aborted
+    pub async fn shutdown(&self, timeout: Duration) -> Result<()> {
+        // The read loop may have exited already, which the state below reports
+        let _ = self.sub_sender.send(WebsocketOperation::Shutdown);
+        let mut state = self.connection_state();
+        let stopped = state.wait_for(|state| *state == ConnectionState::Stopped);
+        if tokio::time::timeout(timeout, stopped).await.is_err() {
+            warn!("Websocket read task did not exit within {timeout:?}, aborting it");
+            self.reader.abort();
+            return Err(Error::WebSocketShutdownTimeout(timeout));
+        }
+        Ok(())
+    }
+
+    /// Open a connection and send the auth request, retrying failed attempts as the reconnect
+    /// policy allows
+    ///
+    /// `on_retry` is called with the number of each failed attempt which will be retried and
+    /// the wait before the next one.
+    ///
+    /// # Returns
+    ///
+    /// The connection along with the number of attempts it took
+    ///
+    /// # Errors
+    ///
+    /// `Error::WebSocketConnectError` once the policy gave up
     async fn _connect(
-        url: URL,
+        url: &URL,
+        config: &WebsocketConfig,
+        connector: &dyn Connector,
         rest_client: &mut Option<Client>,
-    ) -> WebSocketStream<MaybeTlsStream<TcpStream>> {
+        pending_requests: &mut PendingRequests,
+        mut on_retry: impl FnMut(u32, Duration),
+    ) -> Result<(WebsocketConnection, u32)> {
+        pending_requests.clear();
+        let mut attempts: u32 = 0;
         loop {
-            let request = url
-                .websocket()
-                .parse::<Uri>()
-                .unwrap()
-                .into_client_request()
-                .unwrap();
-            match connect_async_with_config(request, None, true).await {
-                Ok((mut connection, _response)) => {
+            attempts = attempts.saturating_add(1);
+            let connection = tokio::time::timeout(
+                config.connect_timeout,
+                connector.connect(url.websocket().to_string()),
+            )
+            .await
+            .unwrap_or_else(|_| Err(Error::WebSocketConnectError("connection timed out".into())));
+            match connection {
+                Ok(mut connection) => {
                     if let Some(client) = rest_client.as_mut()
                         && client.is_private()
                     {
@@ -131,30 +547,31 @@ impl WebsocketManager {
                             Ok(token) => {
                                 let mut params = ObjectParams::new();
                                 params.insert("bearer", token).unwrap();
-                                let request =
-                                    Self::request("auth", jsonrpsee_types::Id::Number(0), params);
-                                let request_str = serde_json::to_string(&request).unwrap();
-                                if let Err(e) = connection
-                                    .send(tokio_tungstenite::tungstenite::protocol::Message::text(
-                                        request_str,
-                                    ))
-                                    .await
-                                {
-                                    log::error!(
-                                        "Error sending auth request {request:?} error {e:?}"
-                                    );
-                                }
+                                Self::send_request(
+                                    &mut connection,
+                                    pending_requests,
+                                    RequestKind::Auth,
+                                    params,
+                                )
+                                .await;
                             }
                             Err(e) => {
                                 log::error!("Could not retrieve jwt auth token {}", e);
                             }
                         }
                     }
-                    return connection;
+                    return Ok((connection, attempts));
                 }
                 Err(e) => {
                     warn!("Error connecting to websocket {e:?}");
-                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    if config.reconnect_policy.exhausted(attempts) {
+                        return Err(Error::WebSocketConnectError(format!(
+                            "gave up after {attempts} attempts, the last failing with {e}"
+                        )));
+                    }
+                    let backoff = config.reconnect_policy.backoff(attempts);
+                    on_retry(attempts, backoff);
+                    tokio::time::sleep(backoff).await;
                 }
             }
         }
@@ -172,20 +589,133 @@ impl WebsocketManager {
         )
     }
 
-    fn request_channel(
-        method: &'static str,
-        channel_name: String,
-        identifier: Identifier,
-    ) -> jsonrpsee_types::RequestSer<'static> {
+    /// Register a request with the pending request map and send it over the connection
+    async fn send_request(
+        connection: &mut WebsocketConnection,
+        pending_requests: &mut PendingRequests,
+        kind: RequestKind,
+        params: ObjectParams,
+    ) {
+        let method = kind.method();
+        let id = pending_requests.register(kind);
+        let request = Self::request(method, jsonrpsee_types::Id::Number(id), params);
+        if let Err(e) = connection
+            .send(tokio_tungstenite::tungstenite::protocol::Message::text(
+                serde_json::to_string(&request).unwrap(),
+            ))
+            .await
+        {
+            log::error!("Error sending {method} request {request:?} error {e:?}");
+        }
+    }
+
+    async fn send_channel_request(
+        connection: &mut WebsocketConnection,
+        pending_requests: &mut PendingRequests,
+        kind: RequestKind,
+    ) {
+        let channel_name = match &kind {
+            RequestKind::Subscribe(channel_name) | RequestKind::Unsubscribe(channel_name) => {
+                channel_name.clone()
+            }
+            RequestKind::Auth | RequestKind::AuthRefresh => return,
+        };
         let mut params = ObjectParams::new();
         params.insert("channel", channel_name).unwrap();
-        Self::request(method, jsonrpsee_types::Id::Number(identifier.0), params)
+        Self::send_request(connection, pending_requests, kind, params).await;
+    }
+
+    /// Notify subscribers of private channels still waiting on auth that it did not succeed
+    #[allow(clippy::type_complexity)]
+    fn fail_deferred(
+        deferred_channels: &mut Vec<String>,
+        subscriptions_by_channel: &HashMap<
+            Cow<'_, str>,
+            (bool, Vec<(Channel, Identifier, CallbackFn)>),
+        >,
+        acknowledgements: &mut HashMap<String, Vec<Acknowledgement>>,
+        reason: &str,
+    ) {
+        let error = Message::Error(Error::WebSocketAuthError(reason.to_string()));
+        for channel_name in deferred_channels.drain(..) {
+            Self::acknowledge(
+                acknowledgements,
+                &channel_name,
+                Err(Error::WebSocketAuthError(reason.to_string())),
+            );
+            if let Some((_, vec)) = subscriptions_by_channel.get(&Cow::Owned(channel_name)) {
+                for (_channel, _id, callback) in vec {
+                    callback(&error);
+                }
+            }
+        }
+    }
+
+    /// Deliver a control message to every subscriber
+    #[allow(clippy::type_complexity)]
+    fn notify_all(
+        subscriptions_by_channel: &HashMap<
+            Cow<'_, str>,
+            (bool, Vec<(Channel, Identifier, CallbackFn)>),
+        >,
+        message: &Message,
+    ) {
+        for (_, vec) in subscriptions_by_channel.values() {
+            for (_channel, _id, callback) in vec {
+                callback(message);
+            }
+        }
+    }
+
+    /// Deliver a message to every subscriber of a private channel
+    #[allow(clippy::type_complexity)]
+    fn notify_private(
+        subscriptions_by_channel: &HashMap<
+            Cow<'_, str>,
+            (bool, Vec<(Channel, Identifier, CallbackFn)>),
+        >,
+        message: &Message,
+    ) {
+        for (_, vec) in subscriptions_by_channel.values() {
+            if vec
+                .first()
+                .is_some_and(|(channel, _, _)| channel.requires_auth())
+            {
+                for (_channel, _id, callback) in vec {
+                    callback(message);
+                }
+            }
+        }
+    }
+
+    /// Resolve every acknowledgement waiting on a channel's subscribe request
+    fn acknowledge(
+        acknowledgements: &mut HashMap<String, Vec<Acknowledgement>>,
+        channel_name: &str,
+        result: Result<()>,
+    ) {
+        for acknowledgement in acknowledgements.remove(channel_name).unwrap_or_default() {
+            // The subscriber may have stopped waiting, which is fine
+            let _ = acknowledgement.send(result.clone());
+        }
+    }
+
+    /// Complete once `timeout` has passed since `last_data_at`, never without a timeout
+    async fn heartbeat_expired(last_data_at: tokio::time::Instant, timeout: Option<Duration>) {
+        match timeout {
+            Some(timeout) => tokio::time::sleep_until(last_data_at + timeout).await,
+            None => std::future::pending().await,
+        }
     }
 
     #[allow(clippy::type_complexity)]
     async fn _reader(
         url: URL,
+        config: WebsocketConfig,
         mut rest_client: Option<Client>,
+        mut hooks: LifecycleHooks,
+        connector: Arc<dyn Connector>,
+        health: Arc<HealthMonitor>,
         mut receiver: UnboundedReceiver<WebsocketOperation>,
     ) {
         let mut subscriptions_by_id: HashMap<Identifier, Cow<'_, str>> = HashMap::new();
@@ -193,15 +723,51 @@ impl WebsocketManager {
             Cow<'_, str>,
             (bool, Vec<(Channel, Identifier, CallbackFn)>),
         > = HashMap::new();
-        let mut connection = Self::_connect(url, &mut rest_client).await;
-
-        // Ping/pong configuration (hard-coded for now)
-        // Change these constants here to adjust behavior.
-        const PING_INTERVAL: Duration = Duration::from_secs(30);
-        const MAX_MISSED_PONGS: u32 = 3;
+        let mut acknowledgements: HashMap<String, Vec<Acknowledgement>> = HashMap::new();
+        let mut pending_requests = PendingRequests::new(config.request_timeout);
+        let connected = Self::_connect(
+            &url,
+            &config,
+            connector.as_ref(),
+            &mut rest_client,
+            &mut pending_requests,
+            |attempts, _| {
+                hooks.reconnect_attempt(&ConnectionInfo {
+                    connection_count: 0,
+                    attempts,
+                })
+            },
+        )
+        .await;
+        let (mut connection, attempts) = match connected {
+            Ok(connected) => connected,
+            Err(e) => {
+                warn!("Could not connect to websocket: {e}");
+                hooks.connection_failed(&ConnectionInfo::default());
+                health.set_state(ConnectionState::Stopped);
+                return;
+            }
+        };
+        health.set_state(ConnectionState::Connected);
+        let mut connection_info = ConnectionInfo {
+            connection_count: 1,
+            attempts,
+        };
+        hooks.connected(&connection_info);
+        // Private channel subscriptions are held back until the server confirms the auth request
+        let mut authenticating = pending_requests.is_pending(&RequestKind::Auth);
+        let mut deferred_channels: Vec<String> = Vec::new();
+        let mut resubscribe_pending = false;
 
         let mut missed_pongs: u32 = 0;
-        let mut ping_ticker = tokio::time::interval(PING_INTERVAL);
+        let mut ping_timer = PingTimer::default();
+        let mut ping_ticker = tokio::time::interval(config.ping_interval);
+        let mut last_data_at = tokio::time::Instant::now();
+        let mut request_timeout_ticker = tokio::time::interval(Duration::from_secs(1));
+        let mut auth_refresh_ticker = tokio::time::interval_at(
+            tokio::time::Instant::now() + config.auth_refresh_interval,
+            config.auth_refresh_interval,
+        );
 
         loop {
             tokio::select! {
@@ -214,12 +780,14 @@ impl WebsocketManager {
                                 trace!("Received websocket message {valid_message:?}");
                                 match valid_message {
                                     tokio_tungstenite::tungstenite::Message::Text(text) => {
+                                        last_data_at = tokio::time::Instant::now();
                                         if let Ok(notification) = serde_json::from_str::<Notification<Value>>(text.as_str()) {
                                             if let Some(channel_entry) = notification.params.get("channel")
                                                 && let Some(channel_name) = channel_entry.as_str()
                                                     && let Some( (_connected, data) ) = subscriptions_by_channel.get(&Cow::Borrowed(channel_name))
                                                         && let Some( (channel, _, _) ) = data.first() {
                                                             let channel_message = channel.to_message(notification.clone());
+                                                            health.record(channel_name, text.len(), matches!(channel_message, Message::Error(_)));
                                                             for (_,_,callback) in data.iter() {
                                                                 callback(&channel_message)
                                                             }
@@ -227,19 +795,69 @@ impl WebsocketManager {
 
                                         }
                                         else if let Ok(response) = serde_json::from_str::<Response<Value>>(text.as_str()) {
-                                            match response.payload {
-                                                ResponsePayload::Success(result) => {
-                                                    if let Some(channel_object) = result.get("channel")
-                                                        && let Some(channel_name) = channel_object.as_str()
-                                                            && let Some(value) = subscriptions_by_channel.get_mut(&Cow::Owned(channel_name.to_string())) {
-                                                                value.0=true;
-                                                                for (_channel, _id, callback) in &value.1 {
-                                                                    callback(&Message::Connected);
+                                            let request_kind = pending_requests.resolve(&response.id);
+                                            match (response.payload, request_kind) {
+                                                (ResponsePayload::Success(_), Some(RequestKind::Subscribe(channel_name))) => {
+                                                    Self::acknowledge(&mut acknowledgements, &channel_name, Ok(()));
+                                                    if let Some(value) = subscriptions_by_channel.get_mut(&Cow::Owned(channel_name)) {
+                                                        value.0=true;
+                                                        for (_channel, _id, callback) in &value.1 {
+                                                            callback(&Message::Connected);
+                                                        }
+                                                    }
+                                                }
+                                                (ResponsePayload::Success(_), Some(RequestKind::Auth)) => {
+                                                    info!("Websocket authenticated");
+                                                    authenticating = false;
+                                                    hooks.authenticated(&connection_info);
+                                                    for channel_name in deferred_channels.drain(..) {
+                                                        Self::send_channel_request(&mut connection, &mut pending_requests, RequestKind::Subscribe(channel_name)).await;
+                                                    }
+                                                    if resubscribe_pending {
+                                                        resubscribe_pending = false;
+                                                        hooks.resubscribed(&connection_info);
+                                                    }
+                                                }
+                                                (ResponsePayload::Success(_), Some(RequestKind::AuthRefresh)) => {
+                                                    info!("Websocket re-authenticated");
+                                                    Self::notify_private(&subscriptions_by_channel, &Message::AuthRefreshed);
+                                                }
+                                                (ResponsePayload::Success(_), Some(RequestKind::Unsubscribe(channel_name))) => {
+                                                    trace!("Unsubscribed from {channel_name}");
+                                                }
+                                                (ResponsePayload::Success(_), None) => {
+                                                    warn!("Received response for unknown request {text:?}");
+                                                }
+                                                (ResponsePayload::Error(e), request_kind) => {
+                                                    warn!("Received error response {e:?} for request {request_kind:?} message {text:?} ");
+                                                    match request_kind {
+                                                        Some(RequestKind::Auth) => {
+                                                            authenticating = false;
+                                                            Self::fail_deferred(&mut deferred_channels, &subscriptions_by_channel, &mut acknowledgements, e.message());
+                                                        }
+                                                        Some(RequestKind::Subscribe(channel_name)) => {
+                                                            // The server will never deliver data for a rejected subscription so drop it
+                                                            subscriptions_by_id.retain(|_, name| *name != channel_name);
+                                                            health.remove_channel(&channel_name);
+                                                            let error = Error::WebSocketSubscriptionError {
+                                                                channel: channel_name.clone(),
+                                                                code: e.code(),
+                                                                message: e.message().to_string(),
+                                                            };
+                                                            Self::acknowledge(&mut acknowledgements, &channel_name, Err(error.clone()));
+                                                            if let Some((_, vec)) = subscriptions_by_channel.remove(&Cow::Owned(channel_name)) {
+                                                                let error = Message::Error(error);
+                                                                for (_channel, _id, callback) in vec {
+                                                                    callback(&error);
                                                                 }
                                                             }
-                                                }
-                                                ResponsePayload::Error(e) => {
-                                                    warn!("Received error response {e:?} message {text:?} ");
+                                                        }
+                                                        Some(RequestKind::AuthRefresh) => {
+                                                            let error = Error::WebSocketAuthError(e.message().to_string());
+                                                            Self::notify_private(&subscriptions_by_channel, &Message::AuthFailed(error));
+                                                        }
+                                                        Some(RequestKind::Unsubscribe(_)) | None => {}
+                                                    }
                                                 }
                                             }
                                         }
@@ -251,9 +869,12 @@ impl WebsocketManager {
                                         // incoming ping from server - respond is automatic at tungstenite level, or ignore
                                         trace!("Received ping from server");
                                     },
-                                    tokio_tungstenite::tungstenite::Message::Pong(_) => {
+                                    tokio_tungstenite::tungstenite::Message::Pong(payload) => {
                                         // received pong from server -> reset missed pong counter
                                         missed_pongs = 0;
+                                        if let Some(latency) = ping_timer.answered(&payload, tokio::time::Instant::now()) {
+                                            health.record_ping_latency(latency);
+                                        }
                                         info!("Received pong from server, resetting missed_pongs to 0");
                                     }
                                     _ => {warn!("Unexpected websocket message {valid_message}")},
@@ -276,15 +897,47 @@ impl WebsocketManager {
                             }
                         }
 
+                        hooks.disconnected(&connection_info);
+                        health.set_state(ConnectionState::Reconnecting);
+
                         missed_pongs = 0;
-                        connection = Self::_connect(url, &mut rest_client).await;
-                        let requests : Vec<jsonrpsee_types::RequestSer<'static>> = subscriptions_by_channel.iter()
-                            .filter_map( |entry| if let Some( (_, identifier, _)) = entry.1.1.first() { Some(Self::request_channel("subscribe", entry.0.to_string(), *identifier))} else {None})
-                            .collect();
-                        for request in requests {
-                            if let Err(e) = connection.send(tokio_tungstenite::tungstenite::protocol::Message::text(serde_json::to_string(&request).unwrap())).await {
-                                log::error!("Error sending resubscribe request {e:?}");
+                        ping_timer.clear();
+                        // The new connection authenticates afresh
+                        auth_refresh_ticker.reset();
+                        let connected = Self::_connect(&url, &config, connector.as_ref(), &mut rest_client, &mut pending_requests, |attempt, backoff| {
+                            hooks.reconnect_attempt(&ConnectionInfo { attempts: attempt, ..connection_info });
+                            Self::notify_all(&subscriptions_by_channel, &Message::Reconnecting { attempt, backoff });
+                        }).await;
+                        let (new_connection, attempts) = match connected {
+                            Ok(connected) => connected,
+                            Err(e) => {
+                                warn!("Could not reconnect to websocket: {e}");
+                                Self::notify_all(&subscriptions_by_channel, &Message::ConnectionFailed(e));
+                                hooks.connection_failed(&connection_info);
+                                break;
                             }
+                        };
+                        connection = new_connection;
+                        last_data_at = tokio::time::Instant::now();
+                        health.set_state(ConnectionState::Connected);
+                        connection_info = ConnectionInfo {
+                            connection_count: connection_info.connection_count + 1,
+                            attempts,
+                        };
+                        hooks.connected(&connection_info);
+                        authenticating = pending_requests.is_pending(&RequestKind::Auth);
+                        deferred_channels.clear();
+                        for (channel_name, (_, vec)) in subscriptions_by_channel.iter() {
+                            if authenticating && vec.first().is_some_and(|(channel, _, _)| channel.requires_auth()) {
+                                deferred_channels.push(channel_name.to_string());
+                            } else {
+                                Self::send_channel_request(&mut connection, &mut pending_requests, RequestKind::Subscribe(channel_name.to_string())).await;
+                            }
+                        }
+                        if deferred_channels.is_empty() {
+                            hooks.resubscribed(&connection_info);
+                        } else {
+                            resubscribe_pending = true;
                         }
                     }
                 }
@@ -292,8 +945,16 @@ impl WebsocketManager {
                 operation = receiver.recv() => {
                     if let Some(action) = operation {
                         match action {
-                            WebsocketOperation::Subscribe(channel, callback, identifier) => {
+                            WebsocketOperation::Subscribe(channel, callback, identifier, acknowledgement) => {
                                 let channel_name = channel.channel_name();
+                                let acknowledged = subscriptions_by_channel.get(&Cow::Owned(channel_name.clone())).is_some_and(|value| value.0);
+                                if let Some(acknowledgement) = acknowledgement {
+                                    if acknowledged {
+                                        let _ = acknowledgement.send(Ok(()));
+                                    } else {
+                                        acknowledgements.entry(channel_name.clone()).or_default().push(acknowledgement);
+                                    }
+                                }
 
                                 subscriptions_by_id.insert(identifier, Cow::Owned(channel_name.clone()));
                                 let entry = subscriptions_by_channel.entry(Cow::Owned(channel_name.clone()));
@@ -306,11 +967,13 @@ impl WebsocketManager {
                                         value.1.push( (channel, identifier, Arc::clone(&callback)) );
                                     }
                                     Entry::Vacant(vacant_entry) => {
-                                        let request = Self::request_channel("subscribe", channel_name.clone(), identifier);
-                                        if let Err(e) = connection.send(tokio_tungstenite::tungstenite::protocol::Message::text(serde_json::to_string(&request).unwrap())).await {
-                                            log::error!("Error sending subscription request {request:?} error {e:?}");
+                                        if authenticating && channel.requires_auth() {
+                                            deferred_channels.push(channel_name.clone());
+                                        } else {
+                                            Self::send_channel_request(&mut connection, &mut pending_requests, RequestKind::Subscribe(channel_name.clone())).await;
                                         }
                                         vacant_entry.insert( (false, vec![(channel, identifier, callback)]) );
+                                        health.add_channel(channel_name);
                                     }
                                 }
                             },
@@ -327,11 +990,14 @@ impl WebsocketManager {
                                         if let Some(idx) = elem_index {
                                             let (_, _, callback) = vec.remove(idx);
                                             if vec.is_empty() {
-                                                let request = Self::request_channel("unsubscribe", channel_name.to_string(), identifier);
-                                                if let Err(e) = connection.send(tokio_tungstenite::tungstenite::protocol::Message::text(serde_json::to_string(&request).unwrap())).await {
-                                                    log::error!("Error sending unsubscribe request {request:?} error {e:?}");
+                                                if let Some(position) = deferred_channels.iter().position(|deferred| *deferred == channel_name) {
+                                                    deferred_channels.remove(position);
+                                                } else {
+                                                    Self::send_channel_request(&mut connection, &mut pending_requests, RequestKind::Unsubscribe(channel_name.to_string())).await;
                                                 }
                                                 subscriptions_by_channel.remove(&channel_name);
+                                                health.remove_channel(channel_name.as_ref());
+                                                acknowledgements.remove(channel_name.as_ref());
                                             }
                                             callback(&Message::Unsubscribed);
                                         }
@@ -348,41 +1014,128 @@ impl WebsocketManager {
                                     warn!("Received unsubscribe request for {identifier:?} but could not locate subscription");
                                 }
                             }
+                            WebsocketOperation::SetHooks(new_hooks) => {
+                                hooks = new_hooks;
+                            }
                             WebsocketOperation::Stop => {
                                 warn!("Received websocket stop request. Stopping websocket read task");
                                 break;
                             },
+                            WebsocketOperation::Shutdown => {
+                                info!("Received websocket shutdown request. Unsubscribing and closing connection");
+                                for (channel_name, (_, vec)) in subscriptions_by_channel.drain() {
+                                    if !deferred_channels.contains(&channel_name.to_string()) {
+                                        Self::send_channel_request(&mut connection, &mut pending_requests, RequestKind::Unsubscribe(channel_name.to_string())).await;
+                                    }
+                                    health.remove_channel(channel_name.as_ref());
+                                    for (_channel, _id, callback) in vec {
+                                        callback(&Message::Unsubscribed);
+                                    }
+                                }
+                                if let Err(e) = connection.close().await {
+                                    warn!("Error closing websocket on shutdown: {e:?}");
+                                }
+                                // Complete the close handshake, so that the server reads everything sent before it
+                                let handshake = async {
+                                    while let Some(Ok(message)) = connection.next().await {
+                                        if matches!(message, tokio_tungstenite::tungstenite::Message::Close(_)) {
+                                            break;
+                                        }
+                                    }
+                                };
+                                let _ = tokio::time::timeout(CLOSE_HANDSHAKE_TIMEOUT, handshake).await;
+                                break;
+                            },
                         }
                     }
                     else { //senders closed. Should we exit?
                     }
                 }
 
+                _ = request_timeout_ticker.tick() => {
+                    for request_kind in pending_requests.expire() {
+                        warn!("Timed out waiting for response to {request_kind:?}");
+                        if request_kind == RequestKind::Auth {
+                            authenticating = false;
+                            Self::fail_deferred(&mut deferred_channels, &subscriptions_by_channel, &mut acknowledgements, "auth request timed out");
+                        }
+                        if request_kind == RequestKind::AuthRefresh {
+                            let error = Error::WebSocketAuthError("auth refresh request timed out".into());
+                            Self::notify_private(&subscriptions_by_channel, &Message::AuthFailed(error));
+                        }
+                        if let RequestKind::Subscribe(channel_name) = &request_kind {
+                            Self::acknowledge(&mut acknowledgements, channel_name, Err(Error::WebSocketRequestTimeout(channel_name.clone())));
+                            if let Some((_, vec)) = subscriptions_by_channel.get(&Cow::Borrowed(channel_name.as_str())) {
+                                let error = Message::Error(Error::WebSocketRequestTimeout(channel_name.clone()));
+                                for (_channel, _id, callback) in vec {
+                                    callback(&error);
+                                }
+                            }
+                        }
+                    }
+                }
+
+                _ = auth_refresh_ticker.tick() => {
+                    // Re-authenticate before the JWT the connection authenticated with expires
+                    if authenticating || pending_requests.is_pending(&RequestKind::AuthRefresh) {
+                        continue;
+                    }
+                    let Some(client) = rest_client.as_ref().filter(|client| client.is_private()) else {
+                        continue;
+                    };
+                    let token = match client.refresh_jwt(true).await {
+                        Ok(()) => client.jwt().await,
+                        Err(e) => Err(e),
+                    };
+                    match token {
+                        Ok(token) => {
+                            let mut params = ObjectParams::new();
+                            params.insert("bearer", token).unwrap();
+                            Self::send_request(&mut connection, &mut pending_requests, RequestKind::AuthRefresh, params).await;
+                        }
+                        Err(e) => {
+                            warn!("Could not refresh jwt for websocket auth {e:?}");
+                            Self::notify_private(&subscriptions_by_channel, &Message::AuthFailed(e));
+                        }
+                    }
+                }
+
+                _ = Self::heartbeat_expired(last_data_at, config.heartbeat_timeout) => {
+                    warn!("No message received for {:?}, closing connection to reconnect", last_data_at.elapsed());
+                    // Wait for the disconnect rather than firing again
+                    last_data_at = tokio::time::Instant::now();
+                    if let Err(e) = connection.close().await {
+                        warn!("Error closing websocket after heartbeat timeout: {:?}", e);
+                    }
+                }
+
                 _ = ping_ticker.tick() => {
                     // Send a ping periodically. If we already missed too many pongs, force a reconnect by closing.
-                    if missed_pongs >= MAX_MISSED_PONGS {
-                        warn!("Missed {} pongs (threshold {}), closing connection to reconnect", missed_pongs, MAX_MISSED_PONGS);
-                        if let Err(e) = connection.close(None).await {
+                    if missed_pongs >= config.max_missed_pongs {
+                        warn!("Missed {} pongs (threshold {}), closing connection to reconnect", missed_pongs, config.max_missed_pongs);
+                        if let Err(e) = connection.close().await {
                             warn!("Error closing websocket after missed pongs: {:?}", e);
                         }
                         // let the connection drop and the existing reconnection logic handle resubscribe
                         continue;
                     }
 
-                    match connection.send(tokio_tungstenite::tungstenite::protocol::Message::Ping(Vec::new().into())).await {
+                    let payload = ping_timer.sent(tokio::time::Instant::now());
+                    match connection.send(tokio_tungstenite::tungstenite::protocol::Message::Ping(payload.into())).await {
                         Ok(_) => {
                             missed_pongs = missed_pongs.saturating_add(1);
                             info!("Sent ping to websocket; missed_pongs={}", missed_pongs);
                         }
                         Err(e) => {
                             warn!("Error sending ping: {:?}. Closing connection to reconnect", e);
-                            let _ = connection.close(None).await;
+                            let _ = connection.close().await;
                         }
                     }
                 }
 
             }
         }
+        health.set_state(ConnectionState::Stopped);
         info!("Exiting websocket read loop");
     }
 }
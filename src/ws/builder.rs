@@ -0,0 +1,718 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+
+use super::connector::{Connector, TungsteniteConnector};
+use super::health::HealthMonitor;
+use super::hooks::LifecycleHooks;
+use super::reconnect::ReconnectPolicy;
+use super::{ReaderGuard, WebsocketManager, WebsocketOperation};
+use crate::error::{Error, Result};
+use crate::rest::Client;
+use crate::url::URL;
+
+/// Behaviour settings for a WebsocketManager
+#[derive(Debug, Clone)]
+pub struct WebsocketConfig {
+    /// How long to wait for the server to respond to a JSON-RPC request before giving up on it
+    pub request_timeout: Duration,
+    /// Suppress back-to-back duplicate `OrderUpdate` and `Fill` events per subscription
+    pub deduplicate: bool,
+    /// How often an authenticated connection re-sends `auth` with a fresh JWT, which must be
+    /// shorter than the JWT lifetime for private channels to keep flowing
+    pub auth_refresh_interval: Duration,
+    /// How often to ping the server
+    pub ping_interval: Duration,
+    /// Pings left unanswered before the connection is considered dead and re-established
+    pub max_missed_pongs: u32,
+    /// How failed connection attempts are retried
+    pub reconnect_policy: ReconnectPolicy,
+    /// How long a connection attempt may take before it counts as failed
+    pub connect_timeout: Duration,
+    /// Re-establish the connection when no message arrived for this long, even though the
+    /// server still answers pings, None to rely on pings alone
+    pub heartbeat_timeout: Option<Duration>,
+}
+
+impl Default for WebsocketConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(10),
+            deduplicate: false,
+            auth_refresh_interval: Duration::from_secs(240),
+            ping_interval: Duration::from_secs(30),
+            max_missed_pongs: 3,
+            reconnect_policy: ReconnectPolicy::default(),
+            connect_timeout: Duration::from_secs(10),
+            heartbeat_timeout: None,
+        }
+    }
+}
+
+impl WebsocketConfig {
+    /// Check the settings the read loop cannot run with
+    ///
+    /// # Errors
+    ///
+    /// `Error::InvalidConfiguration` if the ping or auth refresh interval is zero, or if
+    /// `max_missed_pongs` is zero
+    pub fn validate(&self) -> Result<()> {
+        if self.ping_interval.is_zero() {
+            return Err(Error::InvalidConfiguration(
+                "websocket ping interval must not be zero".into(),
+            ));
+        }
+        if self.auth_refresh_interval.is_zero() {
+            return Err(Error::InvalidConfiguration(
+                "websocket auth refresh interval must not be zero".into(),
+            ));
+        }
+        if self.max_missed_pongs == 0 {
+            return Err(Error::InvalidConfiguration(
+                "websocket max missed pongs must be at least 1".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Builder for a WebsocketManager, allowing its dependencies to be injected
+pub struct WebsocketManagerBuilder {
+    url: URL,
+    config: WebsocketConfig,
+    rest_client: Option<Client>,
+    hooks: LifecycleHooks,
+    connector: Arc<dyn Connector>,
+    runtime: Option<Handle>,
+}
+
+impl WebsocketManagerBuilder {
+    pub(crate) fn new(url: URL) -> Self {
+        Self {
+            url,
+            config: WebsocketConfig::default(),
+            rest_client: None,
+            hooks: LifecycleHooks::default(),
+            connector: Arc::new(TungsteniteConnector),
+            runtime: None,
+        }
+    }
+
+    pub fn config(mut self, config: WebsocketConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Rest client used to authenticate private channels
+    pub fn rest_client(mut self, rest_client: Client) -> Self {
+        self.rest_client = Some(rest_client);
+        self
+    }
+
+    /// Hooks registered before the first connection
+    pub fn hooks(mut self, hooks: LifecycleHooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Connector used to open websocket connections, e.g. an in-memory mock in tests
+    pub fn connector(mut self, connector: impl Connector) -> Self {
+        self.connector = Arc::new(connector);
+        self
+    }
+
+    /// Runtime the read loop and subscriber delivery tasks are spawned on
+    pub fn runtime(mut self, runtime: Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Spawn the read loop and return the manager along with a future completing when it exits
+    ///
+    /// # Errors
+    ///
+    /// `Error::InvalidConfiguration` if the config is rejected by `WebsocketConfig::validate`
+    ///
+    /// # Panics
+    ///
+    /// If no runtime was provided and this is not called from within a tokio runtime
+    pub fn build(self) -> Result<(WebsocketManager, Shutdown)> {
+        self.config.validate()?;
+        let (sub_sender, sub_receiver) =
+            tokio::sync::mpsc::unbounded_channel::<WebsocketOperation>();
+        let is_private = self.rest_client.as_ref().is_some_and(Client::is_private);
+        let runtime = self.runtime.unwrap_or_else(Handle::current);
+        let health = Arc::new(HealthMonitor::new());
+        let reader = runtime.spawn(WebsocketManager::_reader(
+            self.url,
+            self.config.clone(),
+            self.rest_client,
+            self.hooks,
+            self.connector,
+            Arc::clone(&health),
+            sub_receiver,
+        ));
+        let manager = WebsocketManager {
+            current_id: Arc::new(AtomicU64::new(0)),
+            sub_sender,
+            is_private,
+            deduplicate: self.config.deduplicate,
+            runtime,
+            reader: Arc::new(ReaderGuard {
+                abort: reader.abort_handle(),
+                health: Arc::clone(&health),
+            }),
+            health,
+        };
+        Ok((manager, Shutdown(reader)))
+    }
+}
+
+/// Completes once the websocket read loop has exited, e.g. after `WebsocketManager::stop`
+pub struct Shutdown(JoinHandle<()>);
+
+impl Shutdown {
+    /// Handle of the read loop task, e.g. to supervise it alongside other tasks
+    pub fn join_handle(&self) -> &JoinHandle<()> {
+        &self.0
+    }
+
+    pub fn into_join_handle(self) -> JoinHandle<()> {
+        self.0
+    }
+}
+
+impl Future for Shutdown {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        Pin::new(&mut self.0).poll(cx).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ws::connector::BoxedWebsocketStream;
+    use crate::ws::{BboSubscription, ChannelEvent};
+    use futures_util::future::BoxFuture;
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::{Value, json};
+    use tokio::io::DuplexStream;
+    use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+    use tokio_tungstenite::WebSocketStream;
+    use tokio_tungstenite::tungstenite::{Message, protocol::Role};
+
+    /// Connects to an in-memory server, handing the server end of each connection to the test
+    struct MockConnector(UnboundedSender<WebSocketStream<DuplexStream>>);
+
+    impl Connector for MockConnector {
+        fn connect(
+            &self,
+            _url: String,
+        ) -> BoxFuture<'static, crate::error::Result<BoxedWebsocketStream>> {
+            let servers = self.0.clone();
+            Box::pin(async move {
+                let (client, server) = tokio::io::duplex(64 * 1024);
+                let server = WebSocketStream::from_raw_socket(server, Role::Server, None).await;
+                servers.send(server).unwrap();
+                let client = WebSocketStream::from_raw_socket(client, Role::Client, None).await;
+                Ok(Box::pin(client) as BoxedWebsocketStream)
+            })
+        }
+    }
+
+    /// Read the next JSON-RPC request the client sent
+    async fn next_request(server: &mut WebSocketStream<DuplexStream>) -> Value {
+        loop {
+            // Skip the pings the client sends on its own schedule
+            if let Message::Text(text) = server.next().await.unwrap().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    /// Read the next request the client sent and reply to it with `payload`
+    async fn respond(server: &mut WebSocketStream<DuplexStream>, payload: Value) -> Value {
+        let request = next_request(server).await;
+        let mut response = json!({"jsonrpc": "2.0", "id": request["id"]});
+        response
+            .as_object_mut()
+            .unwrap()
+            .extend(payload.as_object().unwrap().clone());
+        server
+            .send(Message::text(response.to_string()))
+            .await
+            .unwrap();
+        request
+    }
+
+    #[tokio::test]
+    async fn mock_connector_round_trip() {
+        let (servers, mut server_receiver) = unbounded_channel();
+        let (manager, shutdown) = WebsocketManager::builder(URL::Testnet)
+            .connector(MockConnector(servers))
+            .build()
+            .unwrap();
+        let mut server = server_receiver.recv().await.unwrap();
+
+        let (events, mut event_receiver) = unbounded_channel();
+        manager
+            .subscribe_typed(BboSubscription::new("BTC-USD-PERP"), move |event| {
+                let event = match event {
+                    ChannelEvent::Connected => "connected".to_string(),
+                    ChannelEvent::Data(bbo) => bbo.market.clone(),
+                    _ => "other".to_string(),
+                };
+                events.send(event).unwrap();
+            })
+            .await
+            .unwrap();
+
+        let request: Value = next_request(&mut server).await;
+        assert_eq!(request["method"], "subscribe");
+        assert_eq!(request["params"]["channel"], "bbo.BTC-USD-PERP");
+        let response = json!({"jsonrpc": "2.0", "id": request["id"], "result": {}});
+        server
+            .send(Message::text(response.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(event_receiver.recv().await.unwrap(), "connected");
+
+        let data: Value =
+            serde_json::from_str(include_str!("../../tests/fixtures/bbo.json")).unwrap();
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "subscription",
+            "params": {"channel": "bbo.BTC-USD-PERP", "data": data},
+        });
+        server
+            .send(Message::text(notification.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(event_receiver.recv().await.unwrap(), "BTC-USD-PERP");
+        let health = manager.health();
+        assert_eq!(health.state, crate::ws::ConnectionState::Connected);
+        assert_eq!(health.reconnects, 0);
+        assert!(health.last_message_at.is_some());
+        assert_eq!(health.channels["bbo.BTC-USD-PERP"].messages, 1);
+
+        let state = manager.connection_state();
+        manager.stop().await.unwrap();
+        shutdown.await;
+        assert_eq!(*state.borrow(), crate::ws::ConnectionState::Stopped);
+    }
+
+    #[tokio::test]
+    async fn graceful_shutdown() {
+        let (servers, mut server_receiver) = unbounded_channel();
+        let (manager, shutdown) = WebsocketManager::builder(URL::Testnet)
+            .connector(MockConnector(servers))
+            .build()
+            .unwrap();
+        let mut server = server_receiver.recv().await.unwrap();
+        let (events, mut event_receiver) = unbounded_channel();
+        let callback: crate::ws::CallbackFn = Arc::new(move |message| {
+            if matches!(message, crate::ws::Message::Unsubscribed) {
+                events.send(()).unwrap();
+            }
+        });
+        manager
+            .subscribe(
+                crate::ws::Channel::BBO {
+                    market_symbol: "BTC-USD-PERP".into(),
+                },
+                callback,
+            )
+            .await
+            .unwrap();
+        respond(&mut server, json!({"result": {}})).await;
+
+        let closing = manager.clone();
+        let closed = tokio::spawn(async move { closing.shutdown(Duration::from_secs(5)).await });
+        let request = next_request(&mut server).await;
+        assert_eq!(request["method"], "unsubscribe");
+        assert_eq!(request["params"]["channel"], "bbo.BTC-USD-PERP");
+        loop {
+            match server.next().await {
+                Some(Ok(Message::Close(_))) | None => break,
+                _ => {}
+            }
+        }
+        closed.await.unwrap().unwrap();
+        event_receiver.recv().await.unwrap();
+        shutdown.await;
+        assert_eq!(manager.health().state, crate::ws::ConnectionState::Stopped);
+
+        // Dropping the last clone of a manager aborts its read loop
+        let (servers, _server_receiver) = unbounded_channel();
+        let (manager, shutdown) = WebsocketManager::builder(URL::Testnet)
+            .connector(MockConnector(servers))
+            .build()
+            .unwrap();
+        let state = manager.connection_state();
+        drop(manager);
+        assert!(
+            shutdown
+                .into_join_handle()
+                .await
+                .unwrap_err()
+                .is_cancelled()
+        );
+        assert_eq!(*state.borrow(), crate::ws::ConnectionState::Stopped);
+    }
+
+    #[tokio::test]
+    async fn shutdown_timeout() {
+        /// Never completes a connection attempt
+        struct HangingConnector;
+
+        impl Connector for HangingConnector {
+            fn connect(
+                &self,
+                _url: String,
+            ) -> BoxFuture<'static, crate::error::Result<BoxedWebsocketStream>> {
+                Box::pin(std::future::pending())
+            }
+        }
+
+        let (manager, shutdown) = WebsocketManager::builder(URL::Testnet)
+            .connector(HangingConnector)
+            .build()
+            .unwrap();
+        let timeout = Duration::from_millis(50);
+        assert!(matches!(
+            manager.shutdown(timeout).await,
+            Err(crate::error::Error::WebSocketShutdownTimeout(waited)) if waited == timeout
+        ));
+        assert!(
+            shutdown
+                .into_join_handle()
+                .await
+                .unwrap_err()
+                .is_cancelled()
+        );
+    }
+
+    #[tokio::test]
+    async fn heartbeat_timeout() {
+        let (servers, mut server_receiver) = unbounded_channel();
+        let (manager, shutdown) = WebsocketManager::builder(URL::Testnet)
+            .config(WebsocketConfig {
+                heartbeat_timeout: Some(Duration::from_millis(200)),
+                ..WebsocketConfig::default()
+            })
+            .connector(MockConnector(servers))
+            .build()
+            .unwrap();
+        let mut server = server_receiver.recv().await.unwrap();
+        manager
+            .subscribe_typed(BboSubscription::new("BTC-USD-PERP"), |_| {})
+            .await
+            .unwrap();
+        respond(&mut server, json!({"result": {}})).await;
+
+        // Messages keep the connection alive
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "subscription",
+                "params": {"channel": "bbo.BTC-USD-PERP", "data": {}},
+            });
+            server
+                .send(Message::text(notification.to_string()))
+                .await
+                .unwrap();
+        }
+        assert!(server_receiver.try_recv().is_err());
+
+        // Silence closes it, after which the client reconnects and resubscribes
+        loop {
+            match server.next().await {
+                Some(Ok(Message::Close(_))) | None => break,
+                _ => {}
+            }
+        }
+        drop(server);
+        let mut server = server_receiver.recv().await.unwrap();
+        let request = next_request(&mut server).await;
+        assert_eq!(request["params"]["channel"], "bbo.BTC-USD-PERP");
+
+        manager.stop().await.unwrap();
+        shutdown.await;
+    }
+
+    #[tokio::test]
+    async fn reconnect_policy() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        /// Connects once, then fails every attempt
+        struct FlakyConnector(MockConnector, AtomicU32);
+
+        impl Connector for FlakyConnector {
+            fn connect(
+                &self,
+                url: String,
+            ) -> BoxFuture<'static, crate::error::Result<BoxedWebsocketStream>> {
+                if self.1.fetch_add(1, Ordering::SeqCst) == 0 {
+                    self.0.connect(url)
+                } else {
+                    Box::pin(async {
+                        Err(crate::error::Error::WebSocketConnectError("refused".into()))
+                    })
+                }
+            }
+        }
+
+        let (servers, mut server_receiver) = unbounded_channel();
+        let (attempts, mut attempt_receiver) = unbounded_channel();
+        let (failures, mut failure_receiver) = unbounded_channel();
+        let hooks = LifecycleHooks::new()
+            .on_reconnect_attempt(move |info| attempts.send(info.attempts).unwrap())
+            .on_connection_failed(move |info| failures.send(info.connection_count).unwrap());
+        let (manager, shutdown) = WebsocketManager::builder(URL::Testnet)
+            .config(WebsocketConfig {
+                reconnect_policy: ReconnectPolicy {
+                    initial_backoff: Duration::from_millis(10),
+                    max_attempts: Some(3),
+                    ..ReconnectPolicy::default()
+                },
+                ..WebsocketConfig::default()
+            })
+            .hooks(hooks)
+            .connector(FlakyConnector(MockConnector(servers), AtomicU32::new(0)))
+            .build()
+            .unwrap();
+        let mut server = server_receiver.recv().await.unwrap();
+
+        let (events, mut event_receiver) = unbounded_channel();
+        let callback: crate::ws::CallbackFn = Arc::new(move |message| {
+            let event = match message {
+                crate::ws::Message::Reconnecting { attempt, backoff } => {
+                    assert!(*backoff <= Duration::from_millis(10) * 2u32.pow(attempt - 1));
+                    format!("reconnecting {attempt}")
+                }
+                crate::ws::Message::ConnectionFailed(_) => "failed".to_string(),
+                _ => return,
+            };
+            events.send(event).unwrap();
+        });
+        manager
+            .subscribe(
+                crate::ws::Channel::BBO {
+                    market_symbol: "BTC-USD-PERP".into(),
+                },
+                callback,
+            )
+            .await
+            .unwrap();
+        respond(&mut server, json!({"result": {}})).await;
+        drop(server);
+
+        // Two failed attempts are retried, the third one exhausts the policy
+        shutdown.await;
+        let mut received = Vec::new();
+        while let Ok(event) = event_receiver.try_recv() {
+            received.push(event);
+        }
+        assert_eq!(received, ["reconnecting 1", "reconnecting 2", "failed"]);
+        assert_eq!(attempt_receiver.recv().await, Some(1));
+        assert_eq!(attempt_receiver.recv().await, Some(2));
+        assert_eq!(failure_receiver.recv().await, Some(1));
+        assert_eq!(manager.health().state, crate::ws::ConnectionState::Stopped);
+    }
+
+    #[tokio::test]
+    async fn auth_refresh() {
+        let (servers, mut server_receiver) = unbounded_channel();
+        let client = Client::with_api_token(reqwest::Client::new(), URL::Testnet, "token".into());
+        let (manager, shutdown) = WebsocketManager::builder(URL::Testnet)
+            .rest_client(client)
+            .config(WebsocketConfig {
+                auth_refresh_interval: Duration::from_millis(100),
+                ..WebsocketConfig::default()
+            })
+            .connector(MockConnector(servers))
+            .build()
+            .unwrap();
+        let mut server = server_receiver.recv().await.unwrap();
+        let auth = respond(&mut server, json!({"result": {}})).await;
+        assert_eq!(auth["method"], "auth");
+        assert_eq!(auth["params"]["bearer"], "token");
+
+        let (events, mut event_receiver) = unbounded_channel();
+        let callback: crate::ws::CallbackFn = Arc::new(move |message| {
+            let event = match message {
+                crate::ws::Message::AuthRefreshed => "refreshed",
+                crate::ws::Message::AuthFailed(_) => "failed",
+                _ => return,
+            };
+            events.send(event).unwrap();
+        });
+        manager
+            .subscribe(
+                crate::ws::Channel::Orders {
+                    market_symbol: None,
+                },
+                callback,
+            )
+            .await
+            .unwrap();
+        respond(&mut server, json!({"result": {}})).await;
+
+        let refresh = respond(&mut server, json!({"result": {}})).await;
+        assert_eq!(refresh["method"], "auth");
+        assert_eq!(event_receiver.recv().await.unwrap(), "refreshed");
+
+        respond(
+            &mut server,
+            json!({"error": {"code": 40110, "message": "invalid bearer jwt"}}),
+        )
+        .await;
+        assert_eq!(event_receiver.recv().await.unwrap(), "failed");
+
+        manager.stop().await.unwrap();
+        shutdown.await;
+    }
+
+    #[tokio::test]
+    async fn subscription_acknowledgements() {
+        let (servers, mut server_receiver) = unbounded_channel();
+        let (manager, shutdown) = WebsocketManager::builder(URL::Testnet)
+            .connector(MockConnector(servers))
+            .build()
+            .unwrap();
+        let mut server = server_receiver.recv().await.unwrap();
+
+        for (market, accept) in [("BTC-USD-PERP", true), ("ETH-USD-PERP", false)] {
+            let subscriber = manager.clone();
+            let acknowledged = tokio::spawn(async move {
+                subscriber
+                    .subscribe_typed_acknowledged(BboSubscription::new(market), |_| {})
+                    .await
+            });
+            let request: Value = next_request(&mut server).await;
+            let response = if accept {
+                json!({"jsonrpc": "2.0", "id": request["id"], "result": {}})
+            } else {
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "error": {"code": -32602, "message": "invalid channel"},
+                })
+            };
+            server
+                .send(Message::text(response.to_string()))
+                .await
+                .unwrap();
+            let result = acknowledged.await.unwrap();
+            if accept {
+                assert!(result.is_ok());
+            } else {
+                assert!(matches!(
+                    result,
+                    Err(crate::error::Error::WebSocketSubscriptionError { code: -32602, .. })
+                ));
+            }
+        }
+
+        // Already acknowledged channels resolve without another round trip
+        manager
+            .subscribe_typed_acknowledged(BboSubscription::new("BTC-USD-PERP"), |_| {})
+            .await
+            .unwrap();
+
+        manager.stop().await.unwrap();
+        shutdown.await;
+    }
+
+    #[tokio::test]
+    async fn socialized_losses() {
+        let (servers, mut server_receiver) = unbounded_channel();
+        let client = Client::with_api_token(reqwest::Client::new(), URL::Testnet, "token".into());
+        let (manager, shutdown) = WebsocketManager::builder(URL::Testnet)
+            .rest_client(client)
+            .connector(MockConnector(servers))
+            .build()
+            .unwrap();
+        let mut server = server_receiver.recv().await.unwrap();
+        respond(&mut server, json!({"result": {}})).await;
+
+        let (losses, mut loss_receiver) = unbounded_channel();
+        manager
+            .subscribe_socialized_losses(move |loss| losses.send(loss.clone()).unwrap())
+            .await
+            .unwrap();
+        let request = respond(&mut server, json!({"result": {}})).await;
+        assert_eq!(request["params"]["channel"], "transfers");
+
+        let mut transfer: Value =
+            serde_json::from_str(include_str!("../../tests/fixtures/transfer.json")).unwrap();
+        for (id, factor, status) in [
+            ("1", "0", "COMPLETED"),
+            ("2", "0.05", "PENDING"),
+            ("2", "0.05", "COMPLETED"),
+            ("3", "0.1", "COMPLETED"),
+        ] {
+            transfer["id"] = id.into();
+            transfer["socialized_loss_factor"] = factor.into();
+            transfer["status"] = status.into();
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "subscription",
+                "params": {"channel": "transfers", "data": transfer},
+            });
+            server
+                .send(Message::text(notification.to_string()))
+                .await
+                .unwrap();
+        }
+
+        let loss = loss_receiver.recv().await.unwrap();
+        assert_eq!(loss.transfer_id, "2");
+        assert_eq!(loss.loss, 5.);
+        assert_eq!(loss_receiver.recv().await.unwrap().transfer_id, "3");
+
+        manager.stop().await.unwrap();
+        shutdown.await;
+        assert!(loss_receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn invalid_config() {
+        let configs = [
+            WebsocketConfig {
+                ping_interval: Duration::ZERO,
+                ..WebsocketConfig::default()
+            },
+            WebsocketConfig {
+                auth_refresh_interval: Duration::ZERO,
+                ..WebsocketConfig::default()
+            },
+            WebsocketConfig {
+                max_missed_pongs: 0,
+                ..WebsocketConfig::default()
+            },
+        ];
+        for config in configs {
+            assert!(matches!(
+                WebsocketManager::builder(URL::Testnet)
+                    .config(config.clone())
+                    .build(),
+                Err(Error::InvalidConfiguration(_))
+            ));
+            assert!(matches!(
+                WebsocketManager::new_with_config(URL::Testnet, None, config).await,
+                Err(Error::InvalidConfiguration(_))
+            ));
+        }
+        assert!(WebsocketConfig::default().validate().is_ok());
+    }
+}
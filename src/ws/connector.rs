@@ -0,0 +1,51 @@
+use std::pin::Pin;
+
+use futures_util::future::BoxFuture;
+use futures_util::{Sink, Stream};
+use tokio_tungstenite::connect_async_with_config;
+use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest};
+
+use crate::error::{Error, Result};
+
+/// A bidirectional websocket message stream, as produced by a `Connector`
+pub trait WebsocketStream:
+    Stream<Item = std::result::Result<tungstenite::Message, tungstenite::Error>>
+    + Sink<tungstenite::Message, Error = tungstenite::Error>
+    + Send
+{
+}
+
+impl<T> WebsocketStream for T where
+    T: Stream<Item = std::result::Result<tungstenite::Message, tungstenite::Error>>
+        + Sink<tungstenite::Message, Error = tungstenite::Error>
+        + Send
+{
+}
+
+pub type BoxedWebsocketStream = Pin<Box<dyn WebsocketStream>>;
+
+/// Opens websocket connections for the WebsocketManager.
+///
+/// The default `TungsteniteConnector` connects over the network. Tests can supply their own
+/// implementation returning in-memory streams to drive the manager against a mock server.
+pub trait Connector: Send + Sync + 'static {
+    fn connect(&self, url: String) -> BoxFuture<'static, Result<BoxedWebsocketStream>>;
+}
+
+/// Connects with tokio-tungstenite, using TLS for `wss` urls
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TungsteniteConnector;
+
+impl Connector for TungsteniteConnector {
+    fn connect(&self, url: String) -> BoxFuture<'static, Result<BoxedWebsocketStream>> {
+        Box::pin(async move {
+            let request = url
+                .into_client_request()
+                .map_err(|e| Error::WebSocketConnectError(e.to_string()))?;
+            let (connection, _response) = connect_async_with_config(request, None, true)
+                .await
+                .map_err(|e| Error::WebSocketConnectError(e.to_string()))?;
+            Ok(Box::pin(connection) as BoxedWebsocketStream)
+        })
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use log::trace;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::unbounded_channel;
+
+use super::CallbackFn;
+use super::types::Message;
+
+/// Suppresses order and fill events that exactly repeat the previously delivered one.
+///
+/// The server occasionally emits the same `OrderUpdate` or `Fill` twice back-to-back, which is
+/// detected by comparing (`id`, `seq_no`, `updated_at`) against the last delivered event.
+#[derive(Debug, Default)]
+pub(crate) struct Deduplicator {
+    last: Option<(String, u64, u64)>,
+}
+
+impl Deduplicator {
+    /// Whether the message duplicates the previous order or fill event
+    pub(crate) fn is_duplicate(&mut self, message: &Message) -> bool {
+        let key = match message {
+            Message::Orders(order) => (order.id.as_str(), order.seq_no, order.last_updated_at),
+            Message::Fills(fill) => (fill.id.as_str(), 0, fill.created_at),
+            _ => return false,
+        };
+        if self
+            .last
+            .as_ref()
+            .is_some_and(|(id, seq_no, updated_at)| (id.as_str(), *seq_no, *updated_at) == key)
+        {
+            return true;
+        }
+        self.last = Some((key.0.to_string(), key.1, key.2));
+        false
+    }
+}
+
+/// Give a subscription callback its own delivery queue drained by a dedicated task.
+///
+/// The returned callback only enqueues, so a slow subscriber neither blocks the read loop nor
+/// other subscribers of the same channel, while its own messages keep their order. The task
+/// exits once the subscription is dropped from the read loop or after `Message::Unsubscribed`.
+pub(crate) fn spawn_delivery(
+    runtime: &Handle,
+    callback: CallbackFn,
+    deduplicate: bool,
+) -> CallbackFn {
+    let (sender, mut receiver) = unbounded_channel::<Message>();
+    runtime.spawn(async move {
+        let mut deduplicator = deduplicate.then(Deduplicator::default);
+        while let Some(message) = receiver.recv().await {
+            if deduplicator
+                .as_mut()
+                .is_some_and(|deduplicator| deduplicator.is_duplicate(&message))
+            {
+                trace!("Suppressed duplicate websocket event {message:?}");
+                continue;
+            }
+            callback(&message);
+            if matches!(message, Message::Unsubscribed) {
+                break;
+            }
+        }
+    });
+    Arc::new(move |message: &Message| {
+        if sender.send(message.clone()).is_err() {
+            trace!("Dropping websocket message for finished subscriber {message:?}");
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use std::sync::Mutex;
+
+    fn fill(id: &str, created_at: u64) -> Message {
+        Message::Fills(testing::fill(id, created_at))
+    }
+
+    #[test]
+    fn back_to_back_duplicates() {
+        let mut deduplicator = Deduplicator::default();
+        assert!(!deduplicator.is_duplicate(&fill("a", 1)));
+        assert!(deduplicator.is_duplicate(&fill("a", 1)));
+        assert!(!deduplicator.is_duplicate(&fill("a", 2)));
+        assert!(!deduplicator.is_duplicate(&fill("b", 2)));
+        assert!(!deduplicator.is_duplicate(&fill("a", 2)));
+        assert!(!deduplicator.is_duplicate(&Message::Connected));
+        assert!(!deduplicator.is_duplicate(&Message::Connected));
+    }
+
+    #[tokio::test]
+    async fn isolated_ordered_delivery() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let callback: CallbackFn = Arc::new(move |message: &Message| {
+            if let Message::Fills(fill) = message {
+                sink.lock().unwrap().push(fill.id.clone());
+            }
+        });
+        let enqueue = spawn_delivery(&Handle::current(), callback, true);
+        for message in [
+            fill("a", 1),
+            fill("a", 1),
+            fill("b", 1),
+            Message::Unsubscribed,
+        ] {
+            enqueue(&message);
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(*received.lock().unwrap(), vec!["a", "b"]);
+
+        // the delivery task has exited after Unsubscribed
+        enqueue(&fill("c", 1));
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert_eq!(received.lock().unwrap().len(), 2);
+    }
+}
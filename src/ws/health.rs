@@ -0,0 +1,214 @@
+//! Connection health of a WebsocketManager.
+//!
+//! The read loop reports connection state changes, notifications and ping round trips to a
+//! [`HealthMonitor`], which `WebsocketManager::health` snapshots and the connection state watch
+//! channel publishes.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+use super::types::SubscriptionStats;
+
+/// State of the websocket connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectionState {
+    /// Opening the first connection
+    Connecting,
+    Connected,
+    /// The connection dropped and a new one is being opened
+    Reconnecting,
+    /// The read loop exited, e.g. after `WebsocketManager::stop`
+    Stopped,
+}
+
+/// Snapshot of the health of a WebsocketManager's connection
+#[derive(Debug, Clone)]
+pub struct Health {
+    pub state: ConnectionState,
+    /// Time the current connection was established
+    pub connected_at: Option<SystemTime>,
+    /// Time the last notification was received on any channel
+    pub last_message_at: Option<SystemTime>,
+    /// Round trip time of the last ping answered by the server
+    pub ping_latency: Option<Duration>,
+    /// Number of times the connection was re-established
+    pub reconnects: u64,
+    /// Counters of every active channel, keyed by channel name
+    pub channels: HashMap<String, SubscriptionStats>,
+}
+
+/// Health shared between the read loop, which updates it, and the manager's clones
+pub(crate) struct HealthMonitor {
+    health: Mutex<Health>,
+    state: watch::Sender<ConnectionState>,
+}
+
+impl HealthMonitor {
+    pub(crate) fn new() -> Self {
+        Self {
+            health: Mutex::new(Health {
+                state: ConnectionState::Connecting,
+                connected_at: None,
+                last_message_at: None,
+                ping_latency: None,
+                reconnects: 0,
+                channels: HashMap::new(),
+            }),
+            state: watch::Sender::new(ConnectionState::Connecting),
+        }
+    }
+
+    pub(crate) fn health(&self) -> Health {
+        self.health.lock().unwrap().clone()
+    }
+
+    pub(crate) fn channels(&self) -> HashMap<String, SubscriptionStats> {
+        self.health.lock().unwrap().channels.clone()
+    }
+
+    pub(crate) fn subscribe_state(&self) -> watch::Receiver<ConnectionState> {
+        self.state.subscribe()
+    }
+
+    pub(crate) fn set_state(&self, state: ConnectionState) {
+        {
+            let mut health = self.health.lock().unwrap();
+            if state == ConnectionState::Connected {
+                if health.connected_at.is_some() {
+                    health.reconnects += 1;
+                }
+                health.connected_at = Some(SystemTime::now());
+            }
+            health.state = state;
+        }
+        self.state.send_replace(state);
+    }
+
+    pub(crate) fn add_channel(&self, channel_name: String) {
+        self.health
+            .lock()
+            .unwrap()
+            .channels
+            .insert(channel_name, SubscriptionStats::default());
+    }
+
+    pub(crate) fn remove_channel(&self, channel_name: &str) {
+        self.health.lock().unwrap().channels.remove(channel_name);
+    }
+
+    /// Count a notification received on a channel
+    pub(crate) fn record(&self, channel_name: &str, bytes: usize, parse_error: bool) {
+        let mut health = self.health.lock().unwrap();
+        health.last_message_at = Some(SystemTime::now());
+        if let Some(stats) = health.channels.get_mut(channel_name) {
+            stats.record(bytes, parse_error);
+        }
+    }
+
+    pub(crate) fn record_ping_latency(&self, latency: Duration) {
+        self.health.lock().unwrap().ping_latency = Some(latency);
+    }
+}
+
+/// Number of unanswered pings remembered, beyond which the oldest is forgotten
+const MAX_PENDING_PINGS: usize = 16;
+
+/// Send times of the pings awaiting a pong, keyed by the ping payload
+///
+/// A late pong is matched with the ping it answers rather than the latest one, so the measured
+/// latency includes the whole delay.
+#[derive(Default)]
+pub(crate) struct PingTimer {
+    next: u64,
+    pending: VecDeque<(u64, Instant)>,
+}
+
+impl PingTimer {
+    /// Remember a ping sent at `now`, returning the payload to send with it
+    pub(crate) fn sent(&mut self, now: Instant) -> Vec<u8> {
+        let id = self.next;
+        self.next = self.next.wrapping_add(1);
+        if self.pending.len() == MAX_PENDING_PINGS {
+            self.pending.pop_front();
+        }
+        self.pending.push_back((id, now));
+        id.to_be_bytes().to_vec()
+    }
+
+    /// Round trip time of the ping answered by a pong with `payload`, None for an unknown one
+    ///
+    /// Pongs answer pings in order, so older pings still awaiting a pong are forgotten.
+    pub(crate) fn answered(&mut self, payload: &[u8], now: Instant) -> Option<Duration> {
+        let id = u64::from_be_bytes(payload.try_into().ok()?);
+        let index = self.pending.iter().position(|(sent, _)| *sent == id)?;
+        let (_, sent_at) = self.pending.drain(..=index).next_back()?;
+        Some(now.duration_since(sent_at))
+    }
+
+    /// Forget the pings of a closed connection
+    pub(crate) fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn late_pongs() {
+        let mut timer = PingTimer::default();
+        let start = Instant::now();
+        let first = timer.sent(start);
+        let second = timer.sent(start + Duration::from_secs(1));
+        let third = timer.sent(start + Duration::from_secs(2));
+
+        // The pong of the first ping arrives after the third ping was sent
+        assert_eq!(
+            timer.answered(&first, start + Duration::from_millis(2500)),
+            Some(Duration::from_millis(2500))
+        );
+        assert_eq!(
+            timer.answered(&third, start + Duration::from_millis(2600)),
+            Some(Duration::from_millis(600))
+        );
+        // Forgotten along with the pings before the third one
+        assert_eq!(
+            timer.answered(&second, start + Duration::from_secs(3)),
+            None
+        );
+        assert_eq!(timer.answered(b"unsolicited", start), None);
+
+        let fourth = timer.sent(start + Duration::from_secs(4));
+        timer.clear();
+        assert_eq!(
+            timer.answered(&fourth, start + Duration::from_secs(5)),
+            None
+        );
+    }
+
+    #[test]
+    fn transitions() {
+        let monitor = HealthMonitor::new();
+        let mut states = monitor.subscribe_state();
+        monitor.add_channel("bbo.BTC-USD-PERP".into());
+        monitor.record("bbo.BTC-USD-PERP", 10, false);
+        monitor.record("trades.BTC-USD-PERP", 10, false);
+        monitor.set_state(ConnectionState::Connected);
+        assert!(states.has_changed().unwrap());
+        assert_eq!(*states.borrow_and_update(), ConnectionState::Connected);
+        monitor.set_state(ConnectionState::Reconnecting);
+        monitor.set_state(ConnectionState::Connected);
+
+        let health = monitor.health();
+        assert_eq!(health.state, ConnectionState::Connected);
+        assert_eq!(health.reconnects, 1);
+        assert!(health.last_message_at.is_some());
+        assert_eq!(health.channels.len(), 1);
+        assert_eq!(health.channels["bbo.BTC-USD-PERP"].messages, 1);
+    }
+}
@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+/// Details about the websocket connection passed to lifecycle hooks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// Number of successful connections made so far, including the current one
+    pub connection_count: u64,
+    /// Number of attempts it took to establish the current connection
+    pub attempts: u32,
+}
+
+pub type HookFn = Arc<dyn Fn(&ConnectionInfo) + Send + Sync + 'static>;
+
+/// User supplied callbacks invoked on websocket connection lifecycle transitions.
+#[derive(Clone, Default)]
+pub struct LifecycleHooks {
+    pub(crate) on_connect: Option<HookFn>,
+    pub(crate) on_authenticated: Option<HookFn>,
+    pub(crate) on_disconnect: Option<HookFn>,
+    pub(crate) on_resubscribed: Option<HookFn>,
+    pub(crate) on_reconnect_attempt: Option<HookFn>,
+    pub(crate) on_connection_failed: Option<HookFn>,
+}
+
+impl LifecycleHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Called each time the socket connects, including reconnects
+    pub fn on_connect(mut self, hook: impl Fn(&ConnectionInfo) + Send + Sync + 'static) -> Self {
+        self.on_connect = Some(Arc::new(hook));
+        self
+    }
+
+    /// Called when the server accepts the auth request sent after connecting
+    pub fn on_authenticated(
+        mut self,
+        hook: impl Fn(&ConnectionInfo) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_authenticated = Some(Arc::new(hook));
+        self
+    }
+
+    /// Called when the socket disconnects, before reconnecting
+    pub fn on_disconnect(mut self, hook: impl Fn(&ConnectionInfo) + Send + Sync + 'static) -> Self {
+        self.on_disconnect = Some(Arc::new(hook));
+        self
+    }
+
+    /// Called after existing subscriptions have been re-sent on a new connection
+    pub fn on_resubscribed(
+        mut self,
+        hook: impl Fn(&ConnectionInfo) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_resubscribed = Some(Arc::new(hook));
+        self
+    }
+
+    /// Called after each failed connection attempt which will be retried, with the number of
+    /// attempts which failed so far
+    pub fn on_reconnect_attempt(
+        mut self,
+        hook: impl Fn(&ConnectionInfo) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_reconnect_attempt = Some(Arc::new(hook));
+        self
+    }
+
+    /// Called when the reconnect policy gives up, after which the read loop exits
+    pub fn on_connection_failed(
+        mut self,
+        hook: impl Fn(&ConnectionInfo) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_connection_failed = Some(Arc::new(hook));
+        self
+    }
+
+    pub(crate) fn connected(&self, info: &ConnectionInfo) {
+        if let Some(hook) = &self.on_connect {
+            hook(info)
+        }
+    }
+
+    pub(crate) fn authenticated(&self, info: &ConnectionInfo) {
+        if let Some(hook) = &self.on_authenticated {
+            hook(info)
+        }
+    }
+
+    pub(crate) fn disconnected(&self, info: &ConnectionInfo) {
+        if let Some(hook) = &self.on_disconnect {
+            hook(info)
+        }
+    }
+
+    pub(crate) fn resubscribed(&self, info: &ConnectionInfo) {
+        if let Some(hook) = &self.on_resubscribed {
+            hook(info)
+        }
+    }
+
+    pub(crate) fn reconnect_attempt(&self, info: &ConnectionInfo) {
+        if let Some(hook) = &self.on_reconnect_attempt {
+            hook(info)
+        }
+    }
+
+    pub(crate) fn connection_failed(&self, info: &ConnectionInfo) {
+        if let Some(hook) = &self.on_connection_failed {
+            hook(info)
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// How a WebsocketManager re-establishes its connection
+///
+/// Failed connection attempts are retried after an exponentially growing wait, part of which
+/// is random so that clients dropped at the same time do not reconnect in lockstep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectPolicy {
+    /// Wait after the first failed attempt, doubled for every failed attempt after it
+    pub initial_backoff: Duration,
+    /// Upper bound of the wait between attempts
+    pub max_backoff: Duration,
+    /// Fraction of each wait which is random, between 0 and 1
+    pub jitter: f64,
+    /// Consecutive failed attempts after which the manager gives up and delivers
+    /// `Message::ConnectionFailed`, None to retry forever
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            jitter: 0.2,
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Wait after failed attempt number `attempt`, counted from 1
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        self.backoff_with(attempt, random_fraction())
+    }
+
+    /// Whether to give up after `attempts` consecutive failed attempts
+    pub(crate) fn exhausted(&self, attempts: u32) -> bool {
+        self.max_attempts.is_some_and(|max| attempts >= max)
+    }
+
+    fn backoff_with(&self, attempt: u32, random: f64) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff);
+        backoff.mul_f64(1. - self.jitter.clamp(0., 1.) * random)
+    }
+}
+
+/// A number in `[0, 1)`, drawn from the randomly keyed std hasher
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u8(0);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff() {
+        let policy = ReconnectPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            jitter: 0.5,
+            max_attempts: Some(5),
+        };
+        assert_eq!(policy.backoff_with(1, 0.), Duration::from_millis(100));
+        assert_eq!(policy.backoff_with(3, 0.), Duration::from_millis(400));
+        assert_eq!(policy.backoff_with(3, 1.), Duration::from_millis(200));
+        assert_eq!(policy.backoff_with(30, 0.), Duration::from_secs(1));
+        for attempt in 1..10 {
+            let backoff = policy.backoff(attempt);
+            assert!(backoff <= policy.backoff_with(attempt, 0.));
+            assert!(backoff >= policy.backoff_with(attempt, 1.));
+        }
+        assert!(!policy.exhausted(4));
+        assert!(policy.exhausted(5));
+        assert!(!ReconnectPolicy::default().exhausted(u32::MAX));
+    }
+}
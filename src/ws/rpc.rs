@@ -0,0 +1,116 @@
+use jsonrpsee_types::Id;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// The kind of JSON-RPC request awaiting a response from the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RequestKind {
+    Auth,
+    /// Re-authentication of an already authenticated connection with a fresh JWT
+    AuthRefresh,
+    Subscribe(String),
+    Unsubscribe(String),
+}
+
+impl RequestKind {
+    pub(crate) fn method(&self) -> &'static str {
+        match self {
+            RequestKind::Auth | RequestKind::AuthRefresh => "auth",
+            RequestKind::Subscribe(_) => "subscribe",
+            RequestKind::Unsubscribe(_) => "unsubscribe",
+        }
+    }
+}
+
+/// Tracks outstanding JSON-RPC requests so responses can be correlated by request id.
+pub(crate) struct PendingRequests {
+    next_id: u64,
+    timeout: Duration,
+    pending: HashMap<u64, (RequestKind, Instant)>,
+}
+
+impl PendingRequests {
+    pub(crate) fn new(timeout: Duration) -> Self {
+        Self {
+            next_id: 0,
+            timeout,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Register a new request and return the id it should be sent with
+    pub(crate) fn register(&mut self, kind: RequestKind) -> u64 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.pending.insert(id, (kind, Instant::now()));
+        id
+    }
+
+    /// Remove and return the request matching a response id
+    pub(crate) fn resolve(&mut self, id: &Id<'_>) -> Option<RequestKind> {
+        match id {
+            Id::Number(number) => self.pending.remove(number).map(|(kind, _)| kind),
+            _ => None,
+        }
+    }
+
+    /// Whether a request of the given kind is still awaiting a response
+    pub(crate) fn is_pending(&self, kind: &RequestKind) -> bool {
+        self.pending
+            .values()
+            .any(|(pending_kind, _)| pending_kind == kind)
+    }
+
+    /// Remove and return all requests which have been waiting longer than the timeout
+    pub(crate) fn expire(&mut self) -> Vec<RequestKind> {
+        let now = Instant::now();
+        let expired: Vec<u64> = self
+            .pending
+            .iter()
+            .filter(|(_, (_, sent_at))| now.duration_since(*sent_at) >= self.timeout)
+            .map(|(id, _)| *id)
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|id| self.pending.remove(&id).map(|(kind, _)| kind))
+            .collect()
+    }
+
+    /// Drop all outstanding requests, e.g. when the connection they were sent on is gone
+    pub(crate) fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_and_resolve() {
+        let mut pending = PendingRequests::new(Duration::from_secs(10));
+        let auth = pending.register(RequestKind::Auth);
+        let subscribe = pending.register(RequestKind::Subscribe("bbo.BTC-USD-PERP".into()));
+        assert_ne!(auth, subscribe);
+
+        assert_eq!(
+            pending.resolve(&Id::Number(subscribe)),
+            Some(RequestKind::Subscribe("bbo.BTC-USD-PERP".into()))
+        );
+        assert_eq!(pending.resolve(&Id::Number(subscribe)), None);
+        assert_eq!(pending.resolve(&Id::Number(auth)), Some(RequestKind::Auth));
+    }
+
+    #[test]
+    fn expire_timed_out_requests() {
+        let mut pending = PendingRequests::new(Duration::ZERO);
+        pending.register(RequestKind::Unsubscribe("trades.ETH-USD-PERP".into()));
+        assert_eq!(
+            pending.expire(),
+            vec![RequestKind::Unsubscribe("trades.ETH-USD-PERP".into())]
+        );
+        assert!(pending.expire().is_empty());
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use log::{trace, warn};
+use tokio::runtime::Handle;
+use tokio::sync::Notify;
+use tokio::sync::mpsc::{Receiver, Sender, UnboundedSender, channel};
+
+use super::delivery::Deduplicator;
+use super::subscription::SubscriptionSpec;
+use super::types::{Identifier, Message};
+use super::{CallbackFn, WebsocketOperation};
+
+/// What a stream subscription does with a message while its buffer is full
+///
+/// Both policies drop messages: the read loop is shared by every subscription of the
+/// connection, so waiting for a slow consumer would stall all of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discard the oldest buffered message to make room, keeping the stream current
+    #[default]
+    DropOldest,
+    /// Discard the incoming message, keeping the buffered ones
+    DropNewest,
+}
+
+/// Messages received by the read loop which the forwarding task has not yet handed over
+struct Buffer<T> {
+    queue: VecDeque<T>,
+    /// Set once the read loop dropped the subscription
+    closed: bool,
+}
+
+struct Shared<T> {
+    buffer: Mutex<Buffer<T>>,
+    ready: Notify,
+}
+
+impl<T> Shared<T> {
+    fn close(&self) {
+        self.buffer.lock().unwrap().closed = true;
+        self.ready.notify_one();
+    }
+}
+
+/// Owned by the read loop's callback, closing the stream when the subscription is dropped
+struct Feed<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Drop for Feed<T> {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+/// Create the callback feeding a stream subscription along with its receiver
+///
+/// The callback runs on the read loop and only buffers, applying `overflow` once `capacity`
+/// messages are waiting. A forwarding task hands them to the receiver in order, and
+/// unsubscribes `identifier` once the receiver is dropped. The receiver's channel holds the
+/// message being handed over on top of the buffer, so up to `capacity + 1` messages wait for
+/// the consumer.
+pub(crate) fn spawn_stream<S>(
+    runtime: &Handle,
+    capacity: usize,
+    overflow: OverflowPolicy,
+    deduplicate: bool,
+    identifier: Identifier,
+    operations: UnboundedSender<WebsocketOperation>,
+) -> (CallbackFn, Receiver<S::Payload>)
+where
+    S: SubscriptionSpec,
+    S::Payload: Clone,
+{
+    let capacity = capacity.max(1);
+    let shared = Arc::new(Shared {
+        buffer: Mutex::new(Buffer {
+            queue: VecDeque::new(),
+            closed: false,
+        }),
+        ready: Notify::new(),
+    });
+    // The buffer holds the backlog, the channel only the message being handed over
+    let (sender, receiver) = channel(1);
+    runtime.spawn(forward(Arc::clone(&shared), sender, identifier, operations));
+
+    let feed = Feed { shared };
+    let deduplicator = Mutex::new(deduplicate.then(Deduplicator::default));
+    let callback: CallbackFn = Arc::new(move |message: &Message| {
+        let payload = match message {
+            Message::Unsubscribed => {
+                feed.shared.close();
+                return;
+            }
+            Message::ConnectionFailed(e) => {
+                warn!("Stream subscription {identifier:?} connection failed {e:?}");
+                feed.shared.close();
+                return;
+            }
+            Message::Error(e) | Message::AuthFailed(e) => {
+                warn!("Stream subscription {identifier:?} error {e:?}");
+                return;
+            }
+            _ => match S::extract(message) {
+                Some(payload) => payload,
+                None => return,
+            },
+        };
+        if deduplicator
+            .lock()
+            .unwrap()
+            .as_mut()
+            .is_some_and(|deduplicator| deduplicator.is_duplicate(message))
+        {
+            trace!("Suppressed duplicate websocket event {message:?}");
+            return;
+        }
+        {
+            let mut buffer = feed.shared.buffer.lock().unwrap();
+            if buffer.queue.len() >= capacity {
+                match overflow {
+                    OverflowPolicy::DropOldest => {
+                        buffer.queue.pop_front();
+                    }
+                    OverflowPolicy::DropNewest => {
+                        trace!("Stream subscription {identifier:?} is full, dropping message");
+                        return;
+                    }
+                }
+            }
+            buffer.queue.push_back(payload.clone());
+        }
+        feed.shared.ready.notify_one();
+    });
+    (callback, receiver)
+}
+
+/// Hand buffered messages to the receiver until either side goes away
+async fn forward<T: Send + 'static>(
+    shared: Arc<Shared<T>>,
+    sender: Sender<T>,
+    identifier: Identifier,
+    operations: UnboundedSender<WebsocketOperation>,
+) {
+    loop {
+        let Ok(permit) = sender.reserve().await else {
+            break;
+        };
+        let next = loop {
+            {
+                let mut buffer = shared.buffer.lock().unwrap();
+                if let Some(next) = buffer.queue.pop_front() {
+                    break Some(next);
+                }
+                if buffer.closed {
+                    break None;
+                }
+            }
+            tokio::select! {
+                _ = shared.ready.notified() => {}
+                _ = sender.closed() => break None,
+            }
+        };
+        match next {
+            Some(next) => permit.send(next),
+            None => break,
+        }
+    }
+    if !shared.buffer.lock().unwrap().closed {
+        trace!("Stream receiver of {identifier:?} dropped, unsubscribing");
+        let _ = operations.send(WebsocketOperation::Unsubscribe(identifier));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::BBO;
+    use crate::ws::BboSubscription;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn bbo(last_updated_at: u64) -> Message {
+        let mut bbo: BBO =
+            serde_json::from_str(include_str!("../../tests/fixtures/bbo.json")).unwrap();
+        bbo.last_updated_at = last_updated_at;
+        Message::BBO(bbo)
+    }
+
+    async fn drain(receiver: &mut Receiver<BBO>) -> Vec<u64> {
+        let mut received = Vec::new();
+        while let Some(bbo) = receiver.recv().await {
+            received.push(bbo.last_updated_at);
+        }
+        received
+    }
+
+    #[tokio::test]
+    async fn overflow_policies() {
+        for (overflow, expected) in [
+            (OverflowPolicy::DropOldest, vec![1, 4, 5]),
+            (OverflowPolicy::DropNewest, vec![1, 2, 3]),
+        ] {
+            let (operations, _) = unbounded_channel();
+            let (callback, mut receiver) = spawn_stream::<BboSubscription>(
+                &Handle::current(),
+                2,
+                overflow,
+                false,
+                Identifier(1),
+                operations,
+            );
+            callback(&bbo(1));
+            // The first message is handed over, the rest is buffered
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            for last_updated_at in 2..=5 {
+                callback(&bbo(last_updated_at));
+            }
+            callback(&Message::Unsubscribed);
+            assert_eq!(drain(&mut receiver).await, expected, "{overflow:?}");
+        }
+
+        // Dropping the receiver unsubscribes
+        let (operations, mut unsubscribed) = unbounded_channel();
+        let (callback, receiver) = spawn_stream::<BboSubscription>(
+            &Handle::current(),
+            2,
+            OverflowPolicy::DropOldest,
+            false,
+            Identifier(7),
+            operations,
+        );
+        drop(receiver);
+        assert!(matches!(
+            unsubscribed.recv().await,
+            Some(WebsocketOperation::Unsubscribe(Identifier(7)))
+        ));
+        drop(callback);
+    }
+}
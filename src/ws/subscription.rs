@@ -1,8 +1,8 @@
-use super::types::{Channel, Message};
+use super::types::{Channel, Message, RefreshRate};
 use crate::error;
 use crate::structs::{
-    AccountInformation, BBO, BalanceEvent, Fill, FundingData, FundingPayment, MarketSummary,
-    OrderBook, OrderUpdate, Position, Trade,
+    AccountInformation, BBO, BalanceEvent, Fill, FundingData, FundingPayment, Kline,
+    KlineResolution, MarketSummary, OrderBook, OrderUpdate, Position, Trade, Transfer,
 };
 
 /// High-level events surfaced to typed websocket callbacks.
@@ -22,7 +22,7 @@ pub trait SubscriptionSpec: Send + 'static {
     fn into_channel(self) -> Channel;
 
     /// Extract a typed payload from a raw message when it matches this subscription.
-    fn extract<'a>(message: &'a Message) -> Option<&'a Self::Payload>;
+    fn extract(message: &Message) -> Option<&Self::Payload>;
 }
 
 #[derive(Debug, Clone, Default)]
@@ -35,7 +35,7 @@ impl SubscriptionSpec for MarketSummarySubscription {
         Channel::MarketSummary
     }
 
-    fn extract<'a>(message: &'a Message) -> Option<&'a Self::Payload> {
+    fn extract(message: &Message) -> Option<&Self::Payload> {
         if let Message::MarketSummary(data) = message {
             Some(data)
         } else {
@@ -66,7 +66,7 @@ impl SubscriptionSpec for BboSubscription {
         }
     }
 
-    fn extract<'a>(message: &'a Message) -> Option<&'a Self::Payload> {
+    fn extract(message: &Message) -> Option<&Self::Payload> {
         if let Message::BBO(data) = message {
             Some(data)
         } else {
@@ -97,7 +97,7 @@ impl SubscriptionSpec for TradesSubscription {
         }
     }
 
-    fn extract<'a>(message: &'a Message) -> Option<&'a Self::Payload> {
+    fn extract(message: &Message) -> Option<&Self::Payload> {
         if let Message::Trades(data) = message {
             Some(data)
         } else {
@@ -106,12 +106,58 @@ impl SubscriptionSpec for TradesSubscription {
     }
 }
 
+/// Candles of a market, sent as they update
+#[derive(Debug, Clone)]
+pub struct KlinesSubscription {
+    pub market_symbol: String,
+    pub resolution: KlineResolution,
+}
+
+impl KlinesSubscription {
+    pub fn new(symbol: impl Into<String>, resolution: KlineResolution) -> Self {
+        Self {
+            market_symbol: symbol.into(),
+            resolution,
+        }
+    }
+}
+
+impl SubscriptionSpec for KlinesSubscription {
+    type Payload = Kline;
+
+    fn into_channel(self) -> Channel {
+        Channel::Klines {
+            market_symbol: self.market_symbol,
+            resolution: self.resolution,
+        }
+    }
+
+    fn extract(message: &Message) -> Option<&Self::Payload> {
+        if let Message::Kline(data) = message {
+            Some(data)
+        } else {
+            None
+        }
+    }
+}
+
+/// Order book snapshot subscription, configured with builder methods.
+///
+/// ```
+/// use paradex::ws::{OrderBookSubscription, RefreshRate};
+///
+/// let spec = OrderBookSubscription::new("BTC-USD-PERP")
+///     .refresh_rate(RefreshRate::Ms100)
+///     .price_tick("0.5")
+///     .depth(30);
+/// ```
 #[derive(Debug, Clone)]
 pub struct OrderBookSubscription {
     pub market_symbol: String,
     pub channel_name: Option<String>,
-    pub refresh_rate: String,
+    pub refresh_rate: RefreshRate,
     pub price_tick: Option<String>,
+    pub depth: u16,
 }
 
 impl OrderBookSubscription {
@@ -119,10 +165,35 @@ impl OrderBookSubscription {
         Self {
             market_symbol: symbol.into(),
             channel_name: None,
-            refresh_rate: "50ms".into(),
+            refresh_rate: RefreshRate::default(),
             price_tick: None,
+            depth: Channel::ORDER_BOOK_DEFAULT_DEPTH,
         }
     }
+
+    /// How often the server sends a snapshot
+    pub fn refresh_rate(mut self, refresh_rate: RefreshRate) -> Self {
+        self.refresh_rate = refresh_rate;
+        self
+    }
+
+    /// Aggregate levels into buckets of this price increment
+    pub fn price_tick(mut self, price_tick: impl Into<String>) -> Self {
+        self.price_tick = Some(price_tick.into());
+        self
+    }
+
+    /// Number of levels per side, between 1 and `Channel::ORDER_BOOK_MAX_DEPTH`
+    pub fn depth(mut self, depth: u16) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Feed name of the channel, defaults to `snapshot`
+    pub fn channel_name(mut self, channel_name: impl Into<String>) -> Self {
+        self.channel_name = Some(channel_name.into());
+        self
+    }
 }
 
 impl SubscriptionSpec for OrderBookSubscription {
@@ -134,10 +205,11 @@ impl SubscriptionSpec for OrderBookSubscription {
             channel_name: self.channel_name,
             refresh_rate: self.refresh_rate,
             price_tick: self.price_tick,
+            depth: self.depth,
         }
     }
 
-    fn extract<'a>(message: &'a Message) -> Option<&'a Self::Payload> {
+    fn extract(message: &Message) -> Option<&Self::Payload> {
         if let Message::OrderBook(data) = message {
             Some(data)
         } else {
@@ -146,6 +218,67 @@ impl SubscriptionSpec for OrderBookSubscription {
     }
 }
 
+/// Order book snapshots including RPI orders, with the same options as the snapshot feed
+///
+/// Comparing it with `OrderBookSubscription` shows how much of the book is RPI liquidity.
+#[derive(Debug, Clone)]
+pub struct OrderBookInteractiveSubscription {
+    pub market_symbol: String,
+    pub refresh_rate: RefreshRate,
+    pub price_tick: Option<String>,
+    pub depth: u16,
+}
+
+impl OrderBookInteractiveSubscription {
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self {
+            market_symbol: symbol.into(),
+            refresh_rate: RefreshRate::default(),
+            price_tick: None,
+            depth: Channel::ORDER_BOOK_DEFAULT_DEPTH,
+        }
+    }
+
+    /// How often the server sends a snapshot
+    pub fn refresh_rate(mut self, refresh_rate: RefreshRate) -> Self {
+        self.refresh_rate = refresh_rate;
+        self
+    }
+
+    /// Aggregate levels into buckets of this price increment
+    pub fn price_tick(mut self, price_tick: impl Into<String>) -> Self {
+        self.price_tick = Some(price_tick.into());
+        self
+    }
+
+    /// Number of levels per side, between 1 and `Channel::ORDER_BOOK_MAX_DEPTH`
+    pub fn depth(mut self, depth: u16) -> Self {
+        self.depth = depth;
+        self
+    }
+}
+
+impl SubscriptionSpec for OrderBookInteractiveSubscription {
+    type Payload = OrderBook;
+
+    fn into_channel(self) -> Channel {
+        Channel::OrderBookInteractive {
+            market_symbol: self.market_symbol,
+            refresh_rate: self.refresh_rate,
+            price_tick: self.price_tick,
+            depth: self.depth,
+        }
+    }
+
+    fn extract(message: &Message) -> Option<&Self::Payload> {
+        if let Message::OrderBookInteractive(data) = message {
+            Some(data)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct OrderBookDeltasSubscription {
     pub market_symbol: String,
@@ -168,7 +301,7 @@ impl SubscriptionSpec for OrderBookDeltasSubscription {
         }
     }
 
-    fn extract<'a>(message: &'a Message) -> Option<&'a Self::Payload> {
+    fn extract(message: &Message) -> Option<&Self::Payload> {
         if let Message::OrderBookDeltas(data) = message {
             Some(data)
         } else {
@@ -205,7 +338,7 @@ impl SubscriptionSpec for FundingDataSubscription {
         }
     }
 
-    fn extract<'a>(message: &'a Message) -> Option<&'a Self::Payload> {
+    fn extract(message: &Message) -> Option<&Self::Payload> {
         if let Message::FundingData(data) = message {
             Some(data)
         } else {
@@ -242,7 +375,7 @@ impl SubscriptionSpec for OrdersSubscription {
         }
     }
 
-    fn extract<'a>(message: &'a Message) -> Option<&'a Self::Payload> {
+    fn extract(message: &Message) -> Option<&Self::Payload> {
         if let Message::Orders(data) = message {
             Some(data)
         } else {
@@ -279,7 +412,7 @@ impl SubscriptionSpec for FillsSubscription {
         }
     }
 
-    fn extract<'a>(message: &'a Message) -> Option<&'a Self::Payload> {
+    fn extract(message: &Message) -> Option<&Self::Payload> {
         if let Message::Fills(data) = message {
             Some(data)
         } else {
@@ -298,7 +431,7 @@ impl SubscriptionSpec for PositionSubscription {
         Channel::Position
     }
 
-    fn extract<'a>(message: &'a Message) -> Option<&'a Self::Payload> {
+    fn extract(message: &Message) -> Option<&Self::Payload> {
         if let Message::Position(data) = message {
             Some(data)
         } else {
@@ -317,7 +450,7 @@ impl SubscriptionSpec for AccountSubscription {
         Channel::Account
     }
 
-    fn extract<'a>(message: &'a Message) -> Option<&'a Self::Payload> {
+    fn extract(message: &Message) -> Option<&Self::Payload> {
         if let Message::Account(data) = message {
             Some(data)
         } else {
@@ -336,7 +469,7 @@ impl SubscriptionSpec for BalanceEventsSubscription {
         Channel::BalanceEvents
     }
 
-    fn extract<'a>(message: &'a Message) -> Option<&'a Self::Payload> {
+    fn extract(message: &Message) -> Option<&Self::Payload> {
         if let Message::BalanceEvent(data) = message {
             Some(data)
         } else {
@@ -373,7 +506,7 @@ impl SubscriptionSpec for FundingPaymentsSubscription {
         }
     }
 
-    fn extract<'a>(message: &'a Message) -> Option<&'a Self::Payload> {
+    fn extract(message: &Message) -> Option<&Self::Payload> {
         if let Message::FundingPayments(data) = message {
             Some(data)
         } else {
@@ -381,3 +514,22 @@ impl SubscriptionSpec for FundingPaymentsSubscription {
         }
     }
 }
+
+#[derive(Debug, Clone, Default)]
+pub struct TransfersSubscription;
+
+impl SubscriptionSpec for TransfersSubscription {
+    type Payload = Transfer;
+
+    fn into_channel(self) -> Channel {
+        Channel::Transfers
+    }
+
+    fn extract(message: &Message) -> Option<&Self::Payload> {
+        if let Message::Transfers(data) = message {
+            Some(data)
+        } else {
+            None
+        }
+    }
+}
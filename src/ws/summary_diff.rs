@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::structs::MarketSummary;
+
+/// Changes of a market summary beyond which `MarketSummaryDiffer` forwards it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryThresholds {
+    /// Move of the mark, last traded, bid or ask price since the last forwarded summary of the
+    /// market, in basis points
+    pub price_bps: f64,
+    /// Absolute change of the funding rate, None to ignore funding
+    pub funding_rate: Option<f64>,
+    /// Forward the summary of an unchanged market once this long after its last forwarded one,
+    /// None to only forward changes
+    pub max_interval: Option<Duration>,
+}
+
+impl Default for SummaryThresholds {
+    fn default() -> Self {
+        Self {
+            price_bps: 1.,
+            funding_rate: None,
+            max_interval: None,
+        }
+    }
+}
+
+/// Drops market summaries which changed too little since the last one forwarded for their
+/// market, so consumers of every market only handle meaningful moves
+///
+/// The first summary of each market is always forwarded. Changes are measured against the last
+/// forwarded summary rather than the last received one, so slow drifts are reported once they
+/// add up to a threshold.
+#[derive(Debug, Clone)]
+pub struct MarketSummaryDiffer {
+    thresholds: SummaryThresholds,
+    forwarded: HashMap<String, (MarketSummary, Instant)>,
+}
+
+impl MarketSummaryDiffer {
+    pub fn new(thresholds: SummaryThresholds) -> Self {
+        Self {
+            thresholds,
+            forwarded: HashMap::new(),
+        }
+    }
+
+    /// Whether to forward `summary`, remembering it as the last forwarded one if so
+    pub fn should_forward(&mut self, summary: &MarketSummary) -> bool {
+        self.should_forward_at(summary, Instant::now())
+    }
+
+    /// Forget the forwarded summaries, so the next summary of every market is forwarded
+    pub fn reset(&mut self) {
+        self.forwarded.clear();
+    }
+
+    fn should_forward_at(&mut self, summary: &MarketSummary, now: Instant) -> bool {
+        let forward = match self.forwarded.get(&summary.symbol) {
+            None => true,
+            Some((previous, forwarded_at)) => {
+                let thresholds = &self.thresholds;
+                [
+                    (previous.mark_price, summary.mark_price),
+                    (previous.last_traded_price, summary.last_traded_price),
+                    (previous.bid, summary.bid),
+                    (previous.ask, summary.ask),
+                ]
+                .into_iter()
+                .any(|(previous, current)| moved_bps(previous, current) > thresholds.price_bps)
+                    || thresholds.funding_rate.is_some_and(|threshold| {
+                        (summary.funding_rate - previous.funding_rate).abs() > threshold
+                    })
+                    || thresholds
+                        .max_interval
+                        .is_some_and(|interval| now.duration_since(*forwarded_at) >= interval)
+            }
+        };
+        if forward {
+            self.forwarded
+                .insert(summary.symbol.clone(), (summary.clone(), now));
+        }
+        forward
+    }
+}
+
+/// Relative move from `previous` to `current` in basis points, infinite when a price appears
+/// or disappears
+fn moved_bps(previous: f64, current: f64) -> f64 {
+    if previous == current || (previous.is_nan() && current.is_nan()) {
+        0.
+    } else if previous == 0. || !previous.is_finite() || !current.is_finite() {
+        f64::INFINITY
+    } else {
+        ((current - previous) / previous).abs() * 10_000.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn differ() {
+        let summary: MarketSummary =
+            serde_json::from_str(include_str!("../../tests/fixtures/market_summary.json")).unwrap();
+        let mut differ = MarketSummaryDiffer::new(SummaryThresholds {
+            price_bps: 5.,
+            funding_rate: Some(0.01),
+            max_interval: Some(Duration::from_secs(10)),
+        });
+        let start = Instant::now();
+        assert!(differ.should_forward_at(&summary, start));
+
+        // Moves add up against the last forwarded summary
+        let mut next = summary.clone();
+        next.mark_price = summary.mark_price * 1.0003;
+        assert!(!differ.should_forward_at(&next, start));
+        next.mark_price = summary.mark_price * 1.0006;
+        assert!(differ.should_forward_at(&next, start));
+        next.bid = f64::NAN;
+        assert!(differ.should_forward_at(&next, start));
+        assert!(!differ.should_forward_at(&next, start));
+        next.funding_rate += 0.02;
+        assert!(differ.should_forward_at(&next, start));
+
+        let mut other = summary.clone();
+        other.symbol = "ETH-USD-PERP".to_string();
+        assert!(differ.should_forward_at(&other, start));
+        assert!(!differ.should_forward_at(&other, start + Duration::from_secs(9)));
+        assert!(differ.should_forward_at(&other, start + Duration::from_secs(10)));
+
+        differ.reset();
+        assert!(differ.should_forward_at(&next, start));
+    }
+}
@@ -1,15 +1,43 @@
+use crate::decimal::parse_decimal;
 use crate::error;
 use crate::structs::{
-    AccountInformation, BBO, BalanceEvent, Fill, FundingData, FundingPayment, MarketSummary,
-    OrderBook, OrderUpdate, Position, Trade,
+    AccountInformation, BBO, BalanceEvent, Fill, FundingData, FundingPayment, Kline,
+    KlineResolution, MarketSummary, OrderBook, OrderUpdate, Position, Trade, Transfer,
 };
 use jsonrpsee_types::Notification;
+use rust_decimal::Decimal;
 use serde_json::Value;
+use std::str::FromStr;
 use std::string::String;
+use std::time::{Duration, SystemTime};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Identifier(pub(crate) u64);
 
+/// Per-channel counters collected by the websocket read loop.
+#[derive(Debug, Clone, Default)]
+pub struct SubscriptionStats {
+    /// Number of notifications received on the channel
+    pub messages: u64,
+    /// Total size in bytes of the raw notification text
+    pub bytes: u64,
+    /// Number of notifications whose payload could not be parsed
+    pub parse_errors: u64,
+    /// Time the last notification was received
+    pub last_message_at: Option<SystemTime>,
+}
+
+impl SubscriptionStats {
+    pub(crate) fn record(&mut self, bytes: usize, parse_error: bool) {
+        self.messages += 1;
+        self.bytes += bytes as u64;
+        if parse_error {
+            self.parse_errors += 1;
+        }
+        self.last_message_at = Some(SystemTime::now());
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Message {
     //Control Messages
@@ -17,14 +45,30 @@ pub enum Message {
     Disconnected,
     Unsubscribed,
     Error(error::Error),
+    /// Private channels only: the connection re-authenticated with a fresh JWT
+    AuthRefreshed,
+    /// Private channels only: re-authenticating the connection failed, so the server may stop
+    /// delivering messages once the current JWT expires
+    AuthFailed(error::Error),
+    /// Connection attempt number `attempt` failed and the next one starts after `backoff`
+    Reconnecting {
+        attempt: u32,
+        backoff: Duration,
+    },
+    /// The reconnect policy gave up on re-establishing the connection, so no more messages
+    /// will be delivered
+    ConnectionFailed(error::Error),
 
     //Public Channels
     BBO(BBO),
     MarketSummary(MarketSummary),
     OrderBook(OrderBook),
     OrderBookDeltas(OrderBook),
+    /// Order book including RPI liquidity
+    OrderBookInteractive(OrderBook),
     Trades(Trade),
     FundingData(FundingData),
+    Kline(Kline),
 
     //Private Channels
     Orders(OrderUpdate),
@@ -33,6 +77,46 @@ pub enum Message {
     Account(AccountInformation),
     BalanceEvent(BalanceEvent),
     FundingPayments(FundingPayment),
+    Transfers(Transfer),
+}
+
+/// Refresh rates supported by the order book snapshot channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RefreshRate {
+    #[default]
+    Ms50,
+    Ms100,
+}
+
+impl RefreshRate {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            RefreshRate::Ms50 => "50ms",
+            RefreshRate::Ms100 => "100ms",
+        }
+    }
+}
+
+impl std::fmt::Display for RefreshRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RefreshRate {
+    type Err = error::Error;
+
+    fn from_str(value: &str) -> error::Result<Self> {
+        [RefreshRate::Ms50, RefreshRate::Ms100]
+            .into_iter()
+            .find(|rate| rate.as_str() == value)
+            .ok_or_else(|| {
+                error::Error::InvalidSubscription(format!(
+                    "unsupported refresh rate {value:?}, expected one of {:?}",
+                    Channel::ORDER_BOOK_REFRESH_RATES
+                ))
+            })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -42,12 +126,20 @@ pub enum Channel {
     OrderBook {
         market_symbol: String,
         channel_name: Option<String>,
-        refresh_rate: String,
+        refresh_rate: RefreshRate,
         price_tick: Option<String>,
+        depth: u16,
     },
     OrderBookDeltas {
         market_symbol: String,
     },
+    /// Order book snapshots including RPI orders, which the `snapshot` feed leaves out
+    OrderBookInteractive {
+        market_symbol: String,
+        refresh_rate: RefreshRate,
+        price_tick: Option<String>,
+        depth: u16,
+    },
     BBO {
         market_symbol: String,
     },
@@ -57,6 +149,12 @@ pub enum Channel {
     FundingData {
         market_symbol: Option<String>,
     },
+    /// Candles of `klines.{market_symbol}.{resolution}`, the resolution in minutes as in the
+    /// `resolution` parameter of the REST klines endpoint
+    Klines {
+        market_symbol: String,
+        resolution: KlineResolution,
+    },
 
     //Private Channels
     Orders {
@@ -71,9 +169,137 @@ pub enum Channel {
     FundingPayments {
         market_symbol: Option<String>,
     },
+    Transfers,
 }
 
 impl Channel {
+    /// Whether the channel is private and needs an authenticated connection
+    pub fn requires_auth(&self) -> bool {
+        match self {
+            Channel::MarketSummary
+            | Channel::OrderBook { .. }
+            | Channel::OrderBookDeltas { .. }
+            | Channel::OrderBookInteractive { .. }
+            | Channel::BBO { .. }
+            | Channel::Trades { .. }
+            | Channel::FundingData { .. }
+            | Channel::Klines { .. } => false,
+            Channel::Orders { .. }
+            | Channel::Fills { .. }
+            | Channel::Position
+            | Channel::Account
+            | Channel::BalanceEvents
+            | Channel::FundingPayments { .. }
+            | Channel::Transfers => true,
+        }
+    }
+
+    /// Refresh rates accepted by the order book snapshot channel
+    pub const ORDER_BOOK_REFRESH_RATES: [&'static str; 2] =
+        [RefreshRate::Ms50.as_str(), RefreshRate::Ms100.as_str()];
+
+    /// Default number of levels per side sent by the order book snapshot channel
+    pub const ORDER_BOOK_DEFAULT_DEPTH: u16 = 15;
+
+    /// Maximum number of levels per side which can be requested from the order book snapshot channel
+    pub const ORDER_BOOK_MAX_DEPTH: u16 = 50;
+
+    /// Check the channel parameters are well formed before subscribing
+    ///
+    /// # Errors
+    ///
+    /// `Error::InvalidSubscription` describing the first invalid parameter
+    pub fn validate(&self) -> error::Result<()> {
+        match self {
+            Channel::MarketSummary
+            | Channel::Position
+            | Channel::Account
+            | Channel::BalanceEvents
+            | Channel::Transfers => Ok(()),
+            Channel::OrderBook {
+                market_symbol,
+                channel_name,
+                refresh_rate,
+                price_tick,
+                depth,
+            } => {
+                if let Some(name) = channel_name
+                    && (name.is_empty()
+                        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+                {
+                    return Err(error::Error::InvalidSubscription(format!(
+                        "invalid order book channel name {name:?}"
+                    )));
+                }
+                Self::validate_order_book(market_symbol, refresh_rate.as_str(), price_tick, *depth)
+            }
+            Channel::OrderBookInteractive {
+                market_symbol,
+                refresh_rate,
+                price_tick,
+                depth,
+            } => {
+                Self::validate_order_book(market_symbol, refresh_rate.as_str(), price_tick, *depth)
+            }
+            Channel::OrderBookDeltas { market_symbol }
+            | Channel::BBO { market_symbol }
+            | Channel::Trades { market_symbol }
+            | Channel::Klines { market_symbol, .. } => Self::validate_market_symbol(market_symbol),
+            Channel::FundingData { market_symbol }
+            | Channel::Orders { market_symbol }
+            | Channel::Fills { market_symbol }
+            | Channel::FundingPayments { market_symbol } => market_symbol
+                .as_deref()
+                .map_or(Ok(()), Self::validate_market_symbol),
+        }
+    }
+
+    /// Check the parameters shared by the order book snapshot feeds
+    fn validate_order_book(
+        market_symbol: &str,
+        refresh_rate: &str,
+        price_tick: &Option<String>,
+        depth: u16,
+    ) -> error::Result<()> {
+        Self::validate_market_symbol(market_symbol)?;
+        if depth == 0 || depth > Self::ORDER_BOOK_MAX_DEPTH {
+            return Err(error::Error::InvalidSubscription(format!(
+                "order book depth {depth} must be between 1 and {}",
+                Self::ORDER_BOOK_MAX_DEPTH
+            )));
+        }
+        if !Self::ORDER_BOOK_REFRESH_RATES.contains(&refresh_rate) {
+            return Err(error::Error::InvalidSubscription(format!(
+                "unsupported refresh rate {refresh_rate:?}, expected one of {:?}",
+                Self::ORDER_BOOK_REFRESH_RATES
+            )));
+        }
+        if let Some(tick) = price_tick
+            && !parse_decimal(tick).is_ok_and(|tick| tick > Decimal::ZERO)
+        {
+            return Err(error::Error::InvalidSubscription(format!(
+                "price tick {tick:?} must be a positive decimal"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Market symbols are dash separated alphanumeric segments e.g. BTC-USD-PERP, with lower
+    /// case letters in scaled markets such as kBONK-USD-PERP
+    fn validate_market_symbol(market_symbol: &str) -> error::Result<()> {
+        let well_formed = !market_symbol.is_empty()
+            && market_symbol.split('-').all(|segment| {
+                !segment.is_empty() && segment.chars().all(|c| c.is_ascii_alphanumeric())
+            });
+        if well_formed {
+            Ok(())
+        } else {
+            Err(error::Error::InvalidSubscription(format!(
+                "malformed market symbol {market_symbol:?}"
+            )))
+        }
+    }
+
     pub fn channel_name(&self) -> String {
         match self {
             Channel::MarketSummary => "markets_summary".into(),
@@ -84,13 +310,15 @@ impl Channel {
                 channel_name,
                 refresh_rate,
                 price_tick,
+                depth,
             } => format!(
-                "order_book.{}.{}@15@{}{}",
+                "order_book.{}.{}@{}@{}{}",
                 market_symbol,
                 channel_name
                     .as_ref()
                     .map(|s| s.as_str())
                     .unwrap_or("snapshot"),
+                depth,
                 refresh_rate,
                 if let Some(tick) = price_tick {
                     format!("@{tick}")
@@ -101,6 +329,23 @@ impl Channel {
             Channel::OrderBookDeltas { market_symbol } => {
                 format!("order_book.{market_symbol}.deltas")
             }
+            Channel::OrderBookInteractive {
+                market_symbol,
+                refresh_rate,
+                price_tick,
+                depth,
+            } => format!(
+                "order_book.{market_symbol}.interactive@{depth}@{refresh_rate}{}",
+                if let Some(tick) = price_tick {
+                    format!("@{tick}")
+                } else {
+                    String::new()
+                }
+            ),
+            Channel::Klines {
+                market_symbol,
+                resolution,
+            } => format!("klines.{market_symbol}.{}", u32::from(*resolution)),
             Channel::FundingData { market_symbol } => format!(
                 "funding_data.{}",
                 if let Some(s) = market_symbol {
@@ -129,6 +374,7 @@ impl Channel {
             Channel::Position => "positions".into(),
             Channel::Account => "account".into(),
             Channel::BalanceEvents => "balance_events".into(),
+            Channel::Transfers => "transfers".into(),
             Channel::FundingPayments { market_symbol } => {
                 format!(
                     "funding_payments.{}",
@@ -174,9 +420,15 @@ impl Channel {
             Channel::OrderBookDeltas { .. } => {
                 Self::parse_notification::<OrderBook>(notification, Message::OrderBookDeltas)
             }
+            Channel::OrderBookInteractive { .. } => {
+                Self::parse_notification::<OrderBook>(notification, Message::OrderBookInteractive)
+            }
             Channel::FundingData { .. } => {
                 Self::parse_notification::<FundingData>(notification, Message::FundingData)
             }
+            Channel::Klines { .. } => {
+                Self::parse_notification::<Kline>(notification, Message::Kline)
+            }
 
             Channel::Orders { .. } => {
                 Self::parse_notification::<OrderUpdate>(notification, Message::Orders)
@@ -194,6 +446,280 @@ impl Channel {
             Channel::FundingPayments { .. } => {
                 Self::parse_notification::<FundingPayment>(notification, Message::FundingPayments)
             }
+            Channel::Transfers => {
+                Self::parse_notification::<Transfer>(notification, Message::Transfers)
+            }
         }
     }
 }
+
+impl std::fmt::Display for Channel {
+    /// Formats the channel as its exact wire name
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.channel_name())
+    }
+}
+
+impl FromStr for Channel {
+    type Err = error::Error;
+
+    /// Parse a wire channel name, e.g. `bbo.BTC-USD-PERP`, back into a Channel
+    fn from_str(s: &str) -> error::Result<Self> {
+        let invalid = || error::Error::InvalidSubscription(format!("unknown channel {s:?}"));
+        let optional_market = |market: &str| {
+            if market == "ALL" {
+                None
+            } else {
+                Some(market.to_string())
+            }
+        };
+
+        let channel = match s {
+            "markets_summary" => Channel::MarketSummary,
+            "positions" => Channel::Position,
+            "account" => Channel::Account,
+            "balance_events" => Channel::BalanceEvents,
+            "transfers" => Channel::Transfers,
+            _ => {
+                let (prefix, rest) = s.split_once('.').ok_or_else(invalid)?;
+                match prefix {
+                    "bbo" => Channel::BBO {
+                        market_symbol: rest.to_string(),
+                    },
+                    "trades" => Channel::Trades {
+                        market_symbol: rest.to_string(),
+                    },
+                    "funding_data" => Channel::FundingData {
+                        market_symbol: optional_market(rest),
+                    },
+                    "orders" => Channel::Orders {
+                        market_symbol: optional_market(rest),
+                    },
+                    "fills" => Channel::Fills {
+                        market_symbol: optional_market(rest),
+                    },
+                    "funding_payments" => Channel::FundingPayments {
+                        market_symbol: optional_market(rest),
+                    },
+                    "klines" => {
+                        let (market_symbol, resolution) =
+                            rest.rsplit_once('.').ok_or_else(invalid)?;
+                        Channel::Klines {
+                            market_symbol: market_symbol.to_string(),
+                            resolution: resolution
+                                .parse::<u32>()
+                                .ok()
+                                .and_then(|minutes| KlineResolution::try_from(minutes).ok())
+                                .ok_or_else(invalid)?,
+                        }
+                    }
+                    "order_book" => {
+                        // price ticks may contain '.', so only split off the market symbol
+                        let (market_symbol, feed) = rest.split_once('.').ok_or_else(invalid)?;
+                        if feed == "deltas" {
+                            Channel::OrderBookDeltas {
+                                market_symbol: market_symbol.to_string(),
+                            }
+                        } else {
+                            let mut parts = feed.split('@');
+                            let name = parts.next().ok_or_else(invalid)?;
+                            let depth = parts
+                                .next()
+                                .and_then(|depth| depth.parse::<u16>().ok())
+                                .ok_or_else(invalid)?;
+                            let refresh_rate = parts.next().ok_or_else(invalid)?;
+                            let price_tick = parts.next().map(String::from);
+                            if parts.next().is_some() {
+                                return Err(invalid());
+                            }
+                            if name == "interactive" {
+                                Channel::OrderBookInteractive {
+                                    market_symbol: market_symbol.to_string(),
+                                    refresh_rate: refresh_rate.parse().map_err(|_| invalid())?,
+                                    price_tick,
+                                    depth,
+                                }
+                            } else {
+                                Channel::OrderBook {
+                                    market_symbol: market_symbol.to_string(),
+                                    channel_name: (name != "snapshot").then(|| name.to_string()),
+                                    refresh_rate: refresh_rate.parse().map_err(|_| invalid())?,
+                                    price_tick,
+                                    depth,
+                                }
+                            }
+                        }
+                    }
+                    _ => return Err(invalid()),
+                }
+            }
+        };
+        Ok(channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn private_channels_require_auth() {
+        assert!(!Channel::MarketSummary.requires_auth());
+        assert!(
+            !Channel::BBO {
+                market_symbol: "BTC-USD-PERP".into()
+            }
+            .requires_auth()
+        );
+        assert!(
+            Channel::Orders {
+                market_symbol: None
+            }
+            .requires_auth()
+        );
+        assert!(Channel::Account.requires_auth());
+    }
+
+    #[test]
+    fn validate_channel_parameters() {
+        let order_book = |refresh_rate, price_tick: Option<&str>| Channel::OrderBook {
+            market_symbol: "BTC-USD-PERP".into(),
+            channel_name: None,
+            refresh_rate,
+            price_tick: price_tick.map(String::from),
+            depth: Channel::ORDER_BOOK_DEFAULT_DEPTH,
+        };
+        assert!(order_book(RefreshRate::Ms50, None).validate().is_ok());
+        assert!(
+            order_book(RefreshRate::Ms100, Some("0.5"))
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            order_book(RefreshRate::Ms50, Some("-1"))
+                .validate()
+                .is_err()
+        );
+        assert_eq!("100ms".parse::<RefreshRate>().unwrap(), RefreshRate::Ms100);
+        assert!("10ms".parse::<RefreshRate>().is_err());
+
+        for symbol in ["ETH-USD-3000-C", "kBONK-USD-PERP"] {
+            assert!(
+                Channel::BBO {
+                    market_symbol: symbol.into()
+                }
+                .validate()
+                .is_ok(),
+                "{symbol:?} should be accepted"
+            );
+        }
+        for symbol in ["", "BTC..PERP", "BTC-", "BTC USD", "BTC_USD"] {
+            assert!(
+                Channel::Trades {
+                    market_symbol: symbol.into()
+                }
+                .validate()
+                .is_err(),
+                "{symbol:?} should be rejected"
+            );
+        }
+        assert!(
+            Channel::Fills {
+                market_symbol: None
+            }
+            .validate()
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn channel_name_round_trip() {
+        let channels = [
+            Channel::MarketSummary,
+            Channel::BBO {
+                market_symbol: "BTC-USD-PERP".into(),
+            },
+            Channel::Trades {
+                market_symbol: "ETH-USD-PERP".into(),
+            },
+            Channel::OrderBook {
+                market_symbol: "BTC-USD-PERP".into(),
+                channel_name: None,
+                refresh_rate: RefreshRate::Ms100,
+                price_tick: Some("0.5".into()),
+                depth: 15,
+            },
+            Channel::OrderBookDeltas {
+                market_symbol: "BTC-USD-PERP".into(),
+            },
+            Channel::OrderBookInteractive {
+                market_symbol: "BTC-USD-PERP".into(),
+                refresh_rate: RefreshRate::Ms50,
+                price_tick: None,
+                depth: 15,
+            },
+            Channel::FundingData {
+                market_symbol: None,
+            },
+            Channel::Klines {
+                market_symbol: "BTC-USD-PERP".into(),
+                resolution: KlineResolution::Min5,
+            },
+            Channel::Orders {
+                market_symbol: Some("BTC-USD-PERP".into()),
+            },
+            Channel::Fills {
+                market_symbol: None,
+            },
+            Channel::Position,
+            Channel::Account,
+            Channel::BalanceEvents,
+            Channel::FundingPayments {
+                market_symbol: Some("SOL-USD-PERP".into()),
+            },
+            Channel::Transfers,
+        ];
+        for channel in channels {
+            let name = channel.to_string();
+            assert_eq!(name, channel.channel_name());
+            assert_eq!(name.parse::<Channel>().unwrap(), channel);
+        }
+        assert!("order_book.BTC-USD-PERP".parse::<Channel>().is_err());
+        assert!("unknown.BTC-USD-PERP".parse::<Channel>().is_err());
+        assert!("klines.BTC-USD-PERP.2".parse::<Channel>().is_err());
+        assert!("klines.BTC-USD-PERP".parse::<Channel>().is_err());
+        assert!(
+            "order_book.BTC-USD-PERP.snapshot@15@10ms"
+                .parse::<Channel>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn kline_notification() {
+        let notification: Notification<Value> =
+            serde_json::from_str(include_str!("../../tests/fixtures/kline_notification.json"))
+                .unwrap();
+        let channel: Channel = notification.params["channel"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            channel,
+            Channel::Klines {
+                market_symbol: "BTC-USD-PERP".into(),
+                resolution: KlineResolution::Min5,
+            }
+        );
+        let Message::Kline(kline) = channel.to_message(notification) else {
+            panic!("expected a kline");
+        };
+        assert_eq!(kline.timestamp_ms, 1681493400000);
+        assert_eq!(kline.open, 30101.1);
+        assert_eq!(kline.high, 30150.5);
+        assert_eq!(kline.low, 30090.2);
+        assert_eq!(kline.close, 30120.7);
+        assert_eq!(kline.volume, 12.345);
+    }
+}
@@ -0,0 +1,274 @@
+//! Deserializes captured API payloads from `tests/fixtures` into the SDK structs and checks
+//! that re-serializing them reproduces every field of the payload.
+
+use paradex::exact;
+use paradex::structs::{
+    AccountInfo, AccountInformation, AccountMarginConfigurations, AccountMarginUpdateResponse,
+    AccountProfile, BBO, BalanceEvent, Balances, CancelByMarketResponse, CursorResult, Fill,
+    FundingData, FundingPayment, JWTToken, Kline, MarketSummary, MarketSummaryStatic, OpenOrders,
+    OrderBook, OrderBookInteractiveResponse, OrderBookResponse, OrderBookUpdateType, OrderStatus,
+    OrderUpdate, OrderUpdates, Positions, ReferralSummary, ResultsContainer, SystemConfig,
+    SystemState, SystemStatus, SystemTimeResponse, Trade, Transfer, VaultHistory, VaultSummary,
+};
+use rust_decimal::Decimal;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+fn fixture(name: &str) -> String {
+    let path = format!("{}/tests/fixtures/{name}.json", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("could not read {path}: {e}"))
+}
+
+/// Deserialize a fixture, then assert serializing it again reproduces the fixture
+fn round_trip<T: DeserializeOwned + Serialize>(name: &str) -> T {
+    let text = fixture(name);
+    let value: T = serde_json::from_str(&text)
+        .unwrap_or_else(|e| panic!("could not deserialize fixture {name}: {e}"));
+    let original: Value = serde_json::from_str(&text).unwrap();
+    let serialized = serde_json::to_value(&value).unwrap();
+    assert_same_payload(&original, &serialized, name);
+    value
+}
+
+/// Assert every field of `original` is reproduced in `serialized`
+///
+/// Numbers are compared by value whether sent as strings or not, since e.g. `"0.10"` is
+/// re-serialized as `"0.1"`. Null fields may be left out, and the empty string the API sends
+/// for a missing value may come back as null.
+fn assert_same_payload(original: &Value, serialized: &Value, path: &str) {
+    let number = |value: &Value| match value {
+        Value::String(text) => text.parse::<f64>().ok(),
+        value => value.as_f64(),
+    };
+    match (original, serialized) {
+        (Value::Object(original), Value::Object(serialized)) => {
+            for (key, value) in original {
+                let path = format!("{path}.{key}");
+                match serialized.get(key) {
+                    Some(serialized) => assert_same_payload(value, serialized, &path),
+                    None => assert!(value.is_null(), "{path} was dropped"),
+                }
+            }
+        }
+        (Value::Array(original), Value::Array(serialized)) => {
+            assert_eq!(original.len(), serialized.len(), "{path} changed length");
+            for (index, (original, serialized)) in original.iter().zip(serialized).enumerate() {
+                assert_same_payload(original, serialized, &format!("{path}[{index}]"));
+            }
+        }
+        (Value::String(empty), Value::Null) if empty.is_empty() => {}
+        (Value::String(_) | Value::Number(_), Value::String(_) | Value::Number(_))
+            if original != serialized =>
+        {
+            assert!(
+                number(original).is_some() && number(original) == number(serialized),
+                "{path} changed from {original} to {serialized}"
+            );
+        }
+        _ => assert_eq!(original, serialized, "{path} changed"),
+    }
+}
+
+#[test]
+fn system() {
+    let config: SystemConfig = round_trip("system_config");
+    assert_eq!(config.paraclear_decimals, 8);
+    assert_eq!(config.bridged_tokens[0].symbol, "USDC");
+    assert!(config.bridged_token("usdc").is_some());
+    assert!(config.bridged_token("BTC").is_none());
+
+    let state: SystemState = round_trip("system_state");
+    assert_eq!(state.status, SystemStatus::CancelOnly);
+
+    let time: SystemTimeResponse = round_trip("system_time");
+    assert_eq!(time.server_time, 1681493415023);
+
+    let jwt: JWTToken = round_trip("jwt_token");
+    assert!(jwt.jwt_token.starts_with("eyJ"));
+}
+
+#[test]
+fn markets() {
+    let summary: MarketSummary = round_trip("market_summary");
+    assert_eq!(summary.volume_24, Some(47041.0424));
+    assert_eq!(summary.bid_iv, None);
+
+    let option_summary: MarketSummary = round_trip("market_summary_option");
+    assert_eq!(option_summary.delta, Some(0.55));
+
+    let markets: ResultsContainer<Vec<MarketSummaryStatic>> = round_trip("markets");
+    assert_eq!(markets.results.len(), 2);
+    assert_eq!(markets.results[0].price_tick_size, 0.1);
+    assert_eq!(markets.results[0].strike_price, None);
+    assert_eq!(markets.results[1].strike_price, Some(3000.));
+
+    let klines: ResultsContainer<Vec<Kline>> = round_trip("klines");
+    assert_eq!(klines.results[1].timestamp_ms, 1681493460000);
+}
+
+#[test]
+fn market_data() {
+    let bbo: BBO = round_trip("bbo");
+    assert_eq!(bbo.bid_size, 0.025);
+
+    let trade: Trade = round_trip("trade");
+    assert_eq!(trade.price, 30001.2);
+
+    let snapshot: OrderBook = round_trip("order_book_snapshot");
+    assert_eq!(snapshot.update_type, OrderBookUpdateType::Snapshot);
+    assert_eq!(snapshot.inserts.len(), 2);
+
+    let delta: OrderBook = round_trip("order_book_delta");
+    assert_eq!(delta.update_type, OrderBookUpdateType::Delta);
+
+    let book: OrderBookResponse = round_trip("orderbook_response");
+    assert_eq!(book.bids[0], ("1908.18".to_string(), "0.5".to_string()));
+
+    let interactive: OrderBookInteractiveResponse = round_trip("orderbook_interactive_response");
+    assert_eq!(interactive.seq_no, 20784);
+    assert_eq!(
+        interactive.rpi_best_bid_size(),
+        Some(Decimal::from_str_exact("0.3").unwrap())
+    );
+    assert_eq!(
+        interactive.rpi_best_ask_size(),
+        Some(Decimal::from_str_exact("1.2").unwrap())
+    );
+    // Without API orders on a side, all of its interactive best level is RPI
+    let one_sided = OrderBookInteractiveResponse {
+        best_bid_api: None,
+        ..interactive
+    };
+    assert_eq!(
+        one_sided.rpi_best_bid_size(),
+        one_sided
+            .best_bid_interactive
+            .as_ref()
+            .map(|(_, size)| Decimal::from_str_exact(size).unwrap())
+    );
+
+    let funding: FundingData = round_trip("funding_data");
+    assert_eq!(funding.funding_rate, 0.00034);
+}
+
+#[test]
+fn orders_and_fills() {
+    let order: OrderUpdate = round_trip("order_update");
+    assert_eq!(order.status, OrderStatus::CLOSED);
+    assert_eq!(order.seq_no, 1681471234972000000);
+
+    let open_orders: OrderUpdates = round_trip("open_orders");
+    assert!(open_orders.results[0].avg_fill_price.is_nan());
+    let open_orders = OpenOrders::from(open_orders);
+    assert_eq!(open_orders.resting.len(), 1);
+    assert_eq!(open_orders.conditional[0].id, "123458");
+
+    let fill: Fill = round_trip("fill");
+    assert_eq!(fill.realized_pnl, -12.5);
+
+    let page: CursorResult<Fill> = round_trip("fills_page");
+    assert!(page.next.is_some());
+    assert!(page.prev.is_none());
+
+    let cancel: CancelByMarketResponse = round_trip("cancel_by_market_response");
+    assert_eq!(cancel.market, "BTC-USD-PERP");
+}
+
+#[test]
+fn account() {
+    let account: AccountInformation = round_trip("account_information");
+    assert_eq!(account.settlement_asset, "USDC");
+
+    let margin: AccountMarginConfigurations = round_trip("account_margin_configurations");
+    assert_eq!(margin.configs[0].isolated_margin_leverage, Some(10));
+    assert_eq!(margin.configs[1].isolated_margin_leverage, None);
+
+    let margin_update: AccountMarginUpdateResponse = round_trip("account_margin_update_response");
+    assert_eq!(margin_update.leverage, 10);
+
+    let balance_event: BalanceEvent = round_trip("balance_event");
+    assert_eq!(balance_event.fees, 0.15);
+
+    let balances: Balances = round_trip("balances");
+    assert_eq!(balances.results[0].token, "USDC");
+
+    let positions: Positions = round_trip("positions");
+    assert!(positions.results[0].liquidation_price.is_nan());
+
+    let transfer: Transfer = round_trip("transfer");
+    assert_eq!(transfer.amount, 100.);
+
+    let funding_payment: FundingPayment = round_trip("funding_payment");
+    assert_eq!(funding_payment.payment, 34.4490622);
+
+    let profile: AccountProfile = round_trip("account_profile");
+    assert_eq!(
+        profile.market_max_slippage["BTC-USD-PERP"],
+        Decimal::new(5, 2)
+    );
+    assert_eq!(
+        profile.referral.unwrap().discount_rate,
+        Some(Decimal::new(5, 2))
+    );
+
+    let info: AccountInfo = round_trip("account_info");
+    assert_eq!(info.fees["taker_fee"], Decimal::new(2, 4));
+
+    let referrals: ResultsContainer<Vec<ReferralSummary>> = round_trip("referrals_summary");
+    assert_eq!(referrals.results[0].referral_code, "cryptofox8");
+}
+
+#[test]
+fn exact() {
+    let bbo: exact::BBO = round_trip("bbo");
+    assert_eq!(bbo.bid_size, Decimal::new(25, 3));
+    assert_eq!(bbo.mid(), Decimal::new(3055617, 2));
+    assert_eq!(BBO::from(bbo).bid_size, 0.025);
+
+    let trade: exact::Trade = round_trip("trade");
+    assert_eq!(trade.notional(), Decimal::new(300012, 3));
+
+    let fill: exact::Fill = round_trip("fill");
+    assert_eq!(fill.fee, Decimal::new(756, 2));
+    assert_eq!(fill.realized_pnl.to_string(), "-12.5");
+
+    let page: CursorResult<exact::Fill> = round_trip("fills_page");
+    assert!(page.next.is_some());
+
+    let balances: ResultsContainer<Vec<exact::Balance>> = round_trip("balances");
+    assert_eq!(balances.results[0].size.to_string(), "123003.620");
+
+    let positions: ResultsContainer<Vec<exact::Position>> = round_trip("positions");
+    let position = positions.results[0].clone();
+    assert_eq!(position.liquidation_price, None);
+    assert_eq!(position.cost, Decimal::new(-100054623, 4));
+    assert!(
+        paradex::structs::Position::from(position)
+            .liquidation_price
+            .is_nan()
+    );
+}
+
+#[test]
+fn vaults() {
+    let summary: ResultsContainer<Vec<VaultSummary>> = round_trip("vault_summary");
+    assert_eq!(summary.results[0].vtoken_price, 1.0832);
+
+    let history: ResultsContainer<Vec<VaultHistory>> = round_trip("vault_history");
+    let points: Vec<(u64, f64)> = history.results[0].points().collect();
+    assert_eq!(points[1], (1681579815023, 1.0412));
+    assert_eq!(points.len(), 3);
+}
+
+#[test]
+fn fixtures_are_valid_json() {
+    let directory = format!("{}/tests/fixtures", env!("CARGO_MANIFEST_DIR"));
+    for entry in std::fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            serde_json::from_str::<Value>(&text).is_ok(),
+            "{path:?} is not valid json"
+        );
+    }
+}